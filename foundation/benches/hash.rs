@@ -0,0 +1,18 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use foundation::hash::{hash_password, verify_password};
+
+fn bench_hash_password(c: &mut Criterion) {
+    c.bench_function("hash_password", |b| {
+        b.iter(|| hash_password("correct horse battery staple").unwrap())
+    });
+}
+
+fn bench_verify_password(c: &mut Criterion) {
+    let hash = hash_password("correct horse battery staple").unwrap();
+    c.bench_function("verify_password", |b| {
+        b.iter(|| verify_password("correct horse battery staple", &hash).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_hash_password, bench_verify_password);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use foundation::key::Key;
+
+fn bench_sign(c: &mut Criterion) {
+    let key = Key::generate();
+    let payload = b"session-payload-of-realistic-length-for-benchmarking";
+    c.bench_function("key_sign", |b| b.iter(|| key.sign(payload)));
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let key = Key::generate();
+    let payload = b"session-payload-of-realistic-length-for-benchmarking";
+    let signature = key.sign(payload);
+    c.bench_function("key_verify", |b| b.iter(|| key.verify(payload, &signature)));
+}
+
+criterion_group!(benches, bench_sign, bench_verify);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+//! Parsing for the `Authorization: Basic` scheme ([RFC 7617]).
+//!
+//! Nothing in this workspace currently authenticates requests this way
+//! (services here use `libsvc::session`), but the wire format shows up
+//! often enough in internal tooling and third-party integrations that it's
+//! worth having a single, correctly-implemented parser rather than an
+//! ad-hoc `split(':')` wherever it's next needed.
+//!
+//! [RFC 7617]: https://www.rfc-editor.org/rfc/rfc7617
+
+use base64::Engine;
+use thiserror::Error;
+
+/// A header value failed to parse as RFC 7617 `Basic` credentials.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BasicAuthError {
+    #[error("not a Basic auth scheme")]
+    NotBasicScheme,
+    #[error("credentials are not valid base64")]
+    InvalidBase64,
+    #[error("decoded credentials are not valid UTF-8")]
+    InvalidUtf8,
+    #[error("decoded credentials have no user-id:password separator")]
+    MissingSeparator,
+}
+
+/// Decodes the value of an `Authorization` header as `Basic` credentials,
+/// returning `(user_id, password)`.
+///
+/// Per RFC 7617, the credentials are split on the *first* colon only, so a
+/// password is free to contain colons itself, and the decoded bytes are
+/// treated as UTF-8 rather than restricted to a narrower charset.
+pub fn decode_basic_auth(header_value: &str) -> Result<(String, String), BasicAuthError> {
+    let encoded = header_value
+        .strip_prefix("Basic ")
+        .ok_or(BasicAuthError::NotBasicScheme)?;
+
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| BasicAuthError::InvalidBase64)?;
+    let credentials = String::from_utf8(decoded).map_err(|_| BasicAuthError::InvalidUtf8)?;
+
+    let (user_id, password) = credentials
+        .split_once(':')
+        .ok_or(BasicAuthError::MissingSeparator)?;
+    Ok((user_id.to_string(), password.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(credentials: &str) -> String {
+        format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        )
+    }
+
+    #[test]
+    fn decodes_simple_credentials() {
+        let header = encode("alice:password123");
+        assert_eq!(
+            decode_basic_auth(&header),
+            Ok(("alice".to_string(), "password123".to_string()))
+        );
+    }
+
+    #[test]
+    fn passwords_may_contain_colons() {
+        let header = encode("alice:pa:ss:word");
+        assert_eq!(
+            decode_basic_auth(&header),
+            Ok(("alice".to_string(), "pa:ss:word".to_string()))
+        );
+    }
+
+    #[test]
+    fn supports_utf8_passwords() {
+        let header = encode("alice:pāssw🔒rd");
+        assert_eq!(
+            decode_basic_auth(&header),
+            Ok(("alice".to_string(), "pāssw🔒rd".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_non_basic_schemes() {
+        assert_eq!(
+            decode_basic_auth("Bearer sometoken"),
+            Err(BasicAuthError::NotBasicScheme)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert_eq!(
+            decode_basic_auth("Basic not-valid-base64!!"),
+            Err(BasicAuthError::InvalidBase64)
+        );
+    }
+
+    #[test]
+    fn rejects_non_utf8_credentials() {
+        let header = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode([0xff, 0xfe])
+        );
+        assert_eq!(decode_basic_auth(&header), Err(BasicAuthError::InvalidUtf8));
+    }
+
+    #[test]
+    fn rejects_credentials_with_no_separator() {
+        let header = encode("alicepassword123");
+        assert_eq!(
+            decode_basic_auth(&header),
+            Err(BasicAuthError::MissingSeparator)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Binary object storage for user-uploaded content such as avatars.
+//!
+//! [`BlobStorage`] is implemented here for the local filesystem, since
+//! that has no dependencies beyond the standard library. Backends that
+//! pull in a cloud SDK (e.g. S3) live in the service crate that needs
+//! them, gated behind a feature flag, so this crate's dependency
+//! footprint stays small for services that never upload anything.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+/// Errors that can occur while storing or retrieving a blob.
+#[derive(Debug, Error)]
+pub enum BlobError {
+    #[error("blob not found: {0}")]
+    NotFound(String),
+    #[error("blob storage backend error: {0}")]
+    Backend(String),
+}
+
+pub type Result<T> = std::result::Result<T, BlobError>;
+
+/// Storage for opaque byte blobs, keyed by an opaque `key` chosen by the
+/// caller. Each blob carries the `content_type` it was stored with, so
+/// callers can serve it back without guessing its format.
+#[async_trait]
+pub trait BlobStorage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<()>;
+    async fn get(&self, key: &str) -> Result<(String, Vec<u8>)>;
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// A [`BlobStorage`] that writes each blob under `root` as two files: the
+/// raw bytes at `<key>`, and its content type at `<key>.content-type`, so
+/// the type survives a restart without a side database.
+pub struct FilesystemBlobStorage {
+    root: PathBuf,
+}
+
+impl FilesystemBlobStorage {
+    /// Opens (or creates) a blob store rooted at `root`.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| BlobError::Backend(e.to_string()))?;
+        Ok(Self { root })
+    }
+
+    fn data_path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn content_type_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{key}.content-type"))
+    }
+}
+
+#[async_trait]
+impl BlobStorage for FilesystemBlobStorage {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<()> {
+        fs::write(self.data_path(key), data).map_err(|e| BlobError::Backend(e.to_string()))?;
+        fs::write(self.content_type_path(key), content_type)
+            .map_err(|e| BlobError::Backend(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<(String, Vec<u8>)> {
+        let data = fs::read(self.data_path(key)).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => BlobError::NotFound(key.to_string()),
+            _ => BlobError::Backend(e.to_string()),
+        })?;
+        let content_type = fs::read_to_string(self.content_type_path(key))
+            .unwrap_or_else(|_| "application/octet-stream".to_string());
+        Ok((content_type, data))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.data_path(key)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(BlobError::NotFound(key.to_string()))
+            }
+            Err(e) => return Err(BlobError::Backend(e.to_string())),
+        }
+        let _ = fs::remove_file(self.content_type_path(key));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_then_get_round_trips_bytes_and_content_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FilesystemBlobStorage::open(dir.path()).unwrap();
+
+        store
+            .put("avatar-1", "image/png", vec![1, 2, 3])
+            .await
+            .unwrap();
+        let (content_type, data) = store.get("avatar-1").await.unwrap();
+
+        assert_eq!(content_type, "image/png");
+        assert_eq!(data, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn get_of_missing_key_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FilesystemBlobStorage::open(dir.path()).unwrap();
+
+        assert!(matches!(
+            store.get("missing").await,
+            Err(BlobError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_removes_a_stored_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FilesystemBlobStorage::open(dir.path()).unwrap();
+
+        store.put("avatar-1", "image/png", vec![1]).await.unwrap();
+        store.delete("avatar-1").await.unwrap();
+
+        assert!(matches!(
+            store.get("avatar-1").await,
+            Err(BlobError::NotFound(_))
+        ));
+    }
+}
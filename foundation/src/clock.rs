@@ -0,0 +1,81 @@
+//! An injectable source of the current time, so domain logic that deals in
+//! expiry or daily buckets (session expiry, usage accounting) can be
+//! exercised deterministically in tests instead of racing the real clock.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real wall clock. The default [`Clock`] everywhere one is needed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A [`Clock`] that only advances when told to, for tests that need to
+/// assert on behavior at or around a specific instant (e.g. a session just
+/// past its expiry).
+pub struct TestClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl TestClock {
+    /// Starts the clock at `now`.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    /// Moves the clock to `now`.
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("test clock lock poisoned") = now;
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: chrono::Duration) {
+        let mut now = self.now.lock().expect("test clock lock poisoned");
+        *now += duration;
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("test clock lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_starts_at_the_given_instant() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().into();
+        let clock = TestClock::new(start);
+        assert_eq!(clock.now(), start);
+    }
+
+    #[test]
+    fn test_clock_advances_by_the_given_duration() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().into();
+        let clock = TestClock::new(start);
+        clock.advance(chrono::Duration::seconds(30));
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(30));
+    }
+
+    #[test]
+    fn test_clock_set_overrides_the_current_instant() {
+        let clock = TestClock::new(DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().into());
+        let later = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().into();
+        clock.set(later);
+        assert_eq!(clock.now(), later);
+    }
+}
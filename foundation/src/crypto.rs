@@ -0,0 +1,102 @@
+//! Symmetric authenticated encryption for data at rest, such as the
+//! admin backup archives in `users::backup`.
+//!
+//! Unlike [`crate::key::Key`], which signs a payload that travels
+//! alongside its plaintext (a session cookie), this is for payloads that
+//! must not be readable at all without the key, so it encrypts rather
+//! than just authenticates. AES-256-GCM bundles both into one primitive:
+//! [`EncryptionKey::seal`] ties the ciphertext to the key, and
+//! [`EncryptionKey::open`] fails the moment either was tampered with.
+
+use aes_gcm::aead::{Aead, Generate, Key as AesKey, KeyInit, Nonce};
+use aes_gcm::Aes256Gcm;
+use thiserror::Error;
+
+/// Errors that can occur while sealing or opening a payload.
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("sealed payload is truncated or otherwise malformed")]
+    Malformed,
+    #[error("sealed payload failed authentication (wrong key, or it was tampered with)")]
+    Unauthenticated,
+}
+
+/// A 256-bit AES-GCM key.
+///
+/// Each [`EncryptionKey::seal`] call generates a fresh random nonce and
+/// prepends it to the returned ciphertext, so the same key can seal many
+/// payloads without the caller having to track nonces itself.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptionKey {
+    /// Generates a new random key.
+    pub fn generate() -> Self {
+        Self { cipher: Aes256Gcm::new(&AesKey::<Aes256Gcm>::generate()) }
+    }
+
+    /// Builds a key from existing secret bytes, e.g. loaded from config.
+    pub fn from_bytes(secret: [u8; 32]) -> Self {
+        Self { cipher: Aes256Gcm::new(&secret.into()) }
+    }
+
+    /// Encrypts `plaintext`, returning `nonce || ciphertext`.
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Nonce::<Aes256Gcm>::generate();
+        // Only fails if `plaintext` exceeds AES-GCM's ~64 GiB limit, far
+        // beyond anything this crate encrypts in one call.
+        let ciphertext =
+            self.cipher.encrypt(&nonce, plaintext).expect("plaintext too large to seal");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypts a payload produced by [`EncryptionKey::seal`].
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if sealed.len() < 12 {
+            return Err(CryptoError::Malformed);
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let nonce = Nonce::<Aes256Gcm>::try_from(nonce).map_err(|_| CryptoError::Malformed)?;
+        self.cipher.decrypt(&nonce, ciphertext).map_err(|_| CryptoError::Unauthenticated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_its_own_seal() {
+        let key = EncryptionKey::generate();
+        let sealed = key.seal(b"top secret");
+        assert_eq!(key.open(&sealed).unwrap(), b"top secret");
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let key = EncryptionKey::generate();
+        let mut sealed = key.seal(b"top secret");
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0xff;
+        assert!(matches!(key.open(&sealed), Err(CryptoError::Unauthenticated)));
+    }
+
+    #[test]
+    fn rejects_the_wrong_key() {
+        let sealed = EncryptionKey::generate().seal(b"top secret");
+        assert!(matches!(
+            EncryptionKey::generate().open(&sealed),
+            Err(CryptoError::Unauthenticated)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_truncated_payload() {
+        let key = EncryptionKey::generate();
+        assert!(matches!(key.open(&[0u8; 4]), Err(CryptoError::Malformed)));
+    }
+}
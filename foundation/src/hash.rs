@@ -0,0 +1,43 @@
+//! Password hashing and verification using Argon2id.
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use thiserror::Error;
+
+/// Errors that can occur while hashing or verifying a password.
+#[derive(Debug, Error)]
+pub enum HashError {
+    #[error("failed to hash password: {0}")]
+    Hash(String),
+    #[error("failed to parse password hash: {0}")]
+    InvalidHash(String),
+}
+
+/// Hashes `password` using Argon2id with a freshly generated salt.
+pub fn hash_password(password: &str) -> Result<String, HashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| HashError::Hash(e.to_string()))
+}
+
+/// Verifies `password` against a previously produced `hash`.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, HashError> {
+    let parsed = PasswordHash::new(hash).map_err(|e| HashError::InvalidHash(e.to_string()))?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_and_verifies_roundtrip() {
+        let hash = hash_password("correct horse battery staple").unwrap();
+        assert!(verify_password("correct horse battery staple", &hash).unwrap());
+        assert!(!verify_password("wrong password", &hash).unwrap());
+    }
+}
@@ -0,0 +1,198 @@
+//! Opaque, parse-validated identifiers used for domain entities.
+//!
+//! An [`Id`] is backed by a 128-bit value, generated either as a random
+//! UUIDv4 or, when sortable ids are wanted, a ULID. Both encode to and from
+//! [`uuid::Uuid`], so storage and comparisons are format-agnostic; only the
+//! string representation differs.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// The id generation scheme to use. Selected via service configuration;
+/// [`IdFormat::Uuid4`] is the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IdFormat {
+    /// Random, non-sortable UUIDv4. The default.
+    #[default]
+    Uuid4,
+    /// Lexicographically sortable by creation time (Crockford base32
+    /// encoding of a ULID).
+    Ulid,
+}
+
+/// An id failed to parse as either a UUID or a ULID.
+#[derive(Debug, Error)]
+#[error("invalid id: {0:?}")]
+pub struct IdError(String);
+
+/// An opaque, unique identifier for a domain entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Id {
+    value: Uuid,
+    format: IdFormat,
+}
+
+impl Id {
+    /// Generates a new random UUIDv4 identifier.
+    pub fn new() -> Self {
+        Self::generate(IdFormat::Uuid4)
+    }
+
+    /// Generates a new identifier using the given `format`.
+    pub fn generate(format: IdFormat) -> Self {
+        let value = match format {
+            IdFormat::Uuid4 => Uuid::new_v4(),
+            IdFormat::Ulid => Uuid::from_u128(ulid::Ulid::new().0),
+        };
+        Self { value, format }
+    }
+}
+
+impl Default for Id {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            IdFormat::Uuid4 => write!(f, "{}", self.value),
+            IdFormat::Ulid => write!(f, "{}", ulid::Ulid(self.value.as_u128())),
+        }
+    }
+}
+
+impl TryFrom<&str> for Id {
+    type Error = IdError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if let Ok(uuid) = Uuid::parse_str(value) {
+            return Ok(Self {
+                value: uuid,
+                format: IdFormat::Uuid4,
+            });
+        }
+        if let Ok(ulid) = value.parse::<ulid::Ulid>() {
+            return Ok(Self {
+                value: Uuid::from_u128(ulid.0),
+                format: IdFormat::Ulid,
+            });
+        }
+        Err(IdError(value.to_string()))
+    }
+}
+
+impl TryFrom<String> for Id {
+    type Error = IdError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+impl Serialize for Id {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Id::try_from(s.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A source of fresh [`Id`]s, so code that mints entity ids (a `register`, a
+/// `create`) can be exercised with predictable ids in tests and swap in a
+/// different scheme (ULIDs, a Snowflake generator) per deployment without
+/// touching the call sites.
+pub trait IdGenerator: Send + Sync {
+    fn generate(&self) -> Id;
+}
+
+/// Generates ids in the given [`IdFormat`]. The default [`IdGenerator`]
+/// everywhere one is needed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultIdGenerator {
+    pub format: IdFormat,
+}
+
+impl IdGenerator for DefaultIdGenerator {
+    fn generate(&self) -> Id {
+        Id::generate(self.format)
+    }
+}
+
+/// An [`IdGenerator`] that hands out ids derived from a monotonically
+/// increasing counter instead of random ones, for tests that need to assert
+/// on a specific id.
+#[derive(Debug, Default)]
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Starts counting from `1`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn generate(&self) -> Id {
+        let n = self.next.fetch_add(1, Ordering::Relaxed) + 1;
+        Id {
+            value: Uuid::from_u128(n as u128),
+            format: IdFormat::Uuid4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_ids_are_unique() {
+        assert_ne!(Id::new(), Id::new());
+    }
+
+    #[test]
+    fn displays_as_inner_string() {
+        let id = Id::new();
+        assert_eq!(Id::try_from(id.to_string().as_str()).unwrap(), id);
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        assert!(Id::try_from("not-an-id").is_err());
+    }
+
+    #[test]
+    fn ulid_round_trips_and_sorts_lexicographically() {
+        let first = Id::generate(IdFormat::Ulid);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = Id::generate(IdFormat::Ulid);
+        assert!(first.to_string() < second.to_string());
+        assert_eq!(Id::try_from(first.to_string().as_str()).unwrap(), first);
+    }
+
+    #[test]
+    fn default_id_generator_produces_unique_ids() {
+        let generator = DefaultIdGenerator::default();
+        assert_ne!(generator.generate(), generator.generate());
+    }
+
+    #[test]
+    fn sequential_id_generator_counts_up_from_one() {
+        let generator = SequentialIdGenerator::new();
+        assert_eq!(generator.generate().to_string(), Id { value: Uuid::from_u128(1), format: IdFormat::Uuid4 }.to_string());
+        assert_eq!(generator.generate().to_string(), Id { value: Uuid::from_u128(2), format: IdFormat::Uuid4 }.to_string());
+    }
+}
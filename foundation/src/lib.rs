@@ -0,0 +1,13 @@
+//! Foundation provides low-level, reusable building blocks shared across
+//! Sonemas services: identifiers, cryptographic key handling, password
+//! hashing, and blob storage. Higher-level service wiring lives in
+//! `libsvc`.
+
+pub mod basic_auth;
+pub mod blob;
+pub mod clock;
+pub mod crypto;
+pub mod hash;
+pub mod id;
+pub mod key;
+pub mod timezone;
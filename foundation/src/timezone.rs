@@ -0,0 +1,56 @@
+//! Rendering a UTC instant in a caller's preferred IANA timezone, for
+//! responses that want to show users local times without the stored
+//! timestamp itself ever leaving UTC.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+/// A UTC instant alongside its rendering in `timezone`, so a response can
+/// carry both without the caller having to convert anything client-side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocalizedTimestamp {
+    pub utc: DateTime<Utc>,
+    pub local: DateTime<FixedOffset>,
+    pub timezone: String,
+}
+
+impl LocalizedTimestamp {
+    /// Renders `utc` in `timezone` (an IANA name, e.g. `"America/New_York"`).
+    /// An unrecognized name falls back to UTC rather than failing to build a
+    /// response over a bad preference value.
+    pub fn new(utc: DateTime<Utc>, timezone: &str) -> Self {
+        let tz: Tz = timezone.parse().unwrap_or(Tz::UTC);
+        Self {
+            utc,
+            local: utc.with_timezone(&tz).fixed_offset(),
+            timezone: timezone.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_requested_timezone() {
+        let utc = DateTime::parse_from_rfc3339("2026-01-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let rendered = LocalizedTimestamp::new(utc, "America/New_York");
+        assert_eq!(rendered.utc, utc);
+        assert_eq!(rendered.timezone, "America/New_York");
+        assert_eq!(rendered.local.to_rfc3339(), "2026-01-15T07:00:00-05:00");
+    }
+
+    #[test]
+    fn falls_back_to_utc_for_an_unrecognized_timezone() {
+        let utc = DateTime::parse_from_rfc3339("2026-01-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let rendered = LocalizedTimestamp::new(utc, "Not/A_Zone");
+        assert_eq!(rendered.local, utc.fixed_offset());
+        assert_eq!(rendered.timezone, "Not/A_Zone");
+    }
+}
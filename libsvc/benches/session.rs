@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use foundation::id::Id;
+use foundation::key::Key;
+use libsvc::session::{SessionBuilder, SessionManager};
+
+fn bench_session_sign(c: &mut Criterion) {
+    let key = Key::generate();
+    c.bench_function("session_sign", |b| {
+        b.iter(|| SessionBuilder::new(Id::new(), 3600).finish(&key))
+    });
+}
+
+fn bench_session_verify(c: &mut Criterion) {
+    let key = Key::generate();
+    let session = SessionBuilder::new(Id::new(), 3600).finish(&key);
+    c.bench_function("session_verify", |b| b.iter(|| session.verify(&key)));
+}
+
+/// Benchmarks [`SessionManager::get`] as contended by several threads
+/// reading the same sessions concurrently, the access pattern an
+/// authentication middleware produces under load.
+fn bench_session_manager_get_under_contention(c: &mut Criterion) {
+    let key = Key::generate();
+    let manager = Arc::new(SessionManager::new());
+    let ids: Vec<String> = (0..8)
+        .map(|_| {
+            let session = SessionBuilder::new(Id::new(), 3600).finish(&key);
+            let id = session.id.clone();
+            manager.insert(session).unwrap();
+            id
+        })
+        .collect();
+
+    c.bench_function("session_manager_get_under_contention", |b| {
+        b.iter(|| {
+            thread::scope(|scope| {
+                for id in &ids {
+                    let manager = &manager;
+                    scope.spawn(move || manager.get(id).unwrap());
+                }
+            });
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_session_sign,
+    bench_session_verify,
+    bench_session_manager_get_under_contention
+);
+criterion_main!(benches);
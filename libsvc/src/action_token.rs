@@ -0,0 +1,181 @@
+//! Narrowly scoped, minutes-lived tokens authorizing exactly one action on
+//! one resource — "download export 123", "confirm this email change" —
+//! rather than a full [`crate::session::Session`]. A link mailed to a
+//! user only needs to prove that *this specific action* was
+//! pre-approved, not who's clicking it; handing out a session instead
+//! would let that link (sitting in an inbox, forwarded, cached by a link
+//! scanner) authenticate as the user for every route and however long
+//! the session lasts. An [`ActionToken`] narrows both dimensions: it
+//! carries nothing but the action, the resource, and an expiry a few
+//! minutes out.
+//!
+//! Every signing primitive in this crate is symmetric HMAC-SHA256 (see
+//! [`foundation::key::Key`] and [`crate::request_signing`], which made the
+//! same call for service-to-service signing), so this reuses that rather
+//! than introducing a second scheme for one more caller.
+
+use base64::Engine;
+use foundation::key::Key;
+use thiserror::Error;
+
+/// The current action-token payload format: a version byte followed by
+/// length-prefixed fields, matching [`crate::session`]'s encoding so that
+/// formatting changes can't silently change what gets signed.
+const PAYLOAD_VERSION: u8 = 1;
+
+/// [`mint`] or [`verify`] failed.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ActionTokenError {
+    #[error("token is not a validly formed action token")]
+    Malformed,
+    #[error("token's signature does not match")]
+    Mismatch,
+    #[error("token has expired")]
+    Expired,
+    #[error("token does not authorize this action")]
+    WrongAction,
+    #[error("token does not authorize this resource")]
+    WrongResource,
+}
+
+fn encode_field(buf: &mut Vec<u8>, field: &[u8]) {
+    buf.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    buf.extend_from_slice(field);
+}
+
+fn payload(action: &str, resource: &str, expires_at: u64) -> Vec<u8> {
+    let mut buf = vec![PAYLOAD_VERSION];
+    encode_field(&mut buf, action.as_bytes());
+    encode_field(&mut buf, resource.as_bytes());
+    buf.extend_from_slice(&expires_at.to_be_bytes());
+    buf
+}
+
+/// Mints a token authorizing exactly `action` on `resource`, valid from
+/// `now` until `now + ttl_secs`. The returned string is safe to embed in
+/// a URL query parameter.
+pub fn mint(key: &Key, action: &str, resource: &str, now: u64, ttl_secs: u64) -> String {
+    let payload = payload(action, resource, now + ttl_secs);
+    let signature = key.sign(&payload);
+    let mut token = payload;
+    token.extend(signature);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token)
+}
+
+/// Verifies that `token` was minted by [`mint`] for `action`, and that it
+/// hasn't expired as of `now`. Returns the resource it authorizes so the
+/// caller can check it matches whatever the request is trying to act on.
+pub fn verify(key: &Key, token: &str, action: &str, now: u64) -> Result<String, ActionTokenError> {
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(token)
+        .map_err(|_| ActionTokenError::Malformed)?;
+    // HMAC-SHA256 signatures are always 32 bytes.
+    if decoded.len() < 32 {
+        return Err(ActionTokenError::Malformed);
+    }
+    let (payload, signature) = decoded.split_at(decoded.len() - 32);
+    if !key.verify(payload, signature) {
+        return Err(ActionTokenError::Mismatch);
+    }
+
+    let (&version, rest) = payload.split_first().ok_or(ActionTokenError::Malformed)?;
+    if version != PAYLOAD_VERSION {
+        return Err(ActionTokenError::Malformed);
+    }
+    let (token_action, rest) = decode_field(rest)?;
+    let (token_resource, rest) = decode_field(rest)?;
+    let expires_at_bytes: [u8; 8] = rest.try_into().map_err(|_| ActionTokenError::Malformed)?;
+    let expires_at = u64::from_be_bytes(expires_at_bytes);
+
+    if now >= expires_at {
+        return Err(ActionTokenError::Expired);
+    }
+    if token_action != action {
+        return Err(ActionTokenError::WrongAction);
+    }
+    Ok(token_resource)
+}
+
+fn decode_field(buf: &[u8]) -> Result<(String, &[u8]), ActionTokenError> {
+    if buf.len() < 4 {
+        return Err(ActionTokenError::Malformed);
+    }
+    let (len_bytes, rest) = buf.split_at(4);
+    let len = u32::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+    if rest.len() < len {
+        return Err(ActionTokenError::Malformed);
+    }
+    let (field, rest) = rest.split_at(len);
+    let field = String::from_utf8(field.to_vec()).map_err(|_| ActionTokenError::Malformed)?;
+    Ok((field, rest))
+}
+
+/// Convenience for callers that also need to check the resource: like
+/// [`verify`], but rejects the token outright unless it authorizes
+/// `resource` too.
+pub fn verify_for_resource(
+    key: &Key,
+    token: &str,
+    action: &str,
+    resource: &str,
+    now: u64,
+) -> Result<(), ActionTokenError> {
+    if verify(key, token, action, now)? != resource {
+        return Err(ActionTokenError::WrongResource);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_freshly_minted_token_verifies_for_its_own_action_and_resource() {
+        let key = Key::generate();
+        let token = mint(&key, "download_export", "user-1", 1_000, 300);
+        assert_eq!(verify(&key, &token, "download_export", 1_100).unwrap(), "user-1");
+        assert!(verify_for_resource(&key, &token, "download_export", "user-1", 1_100).is_ok());
+    }
+
+    #[test]
+    fn an_expired_token_is_rejected() {
+        let key = Key::generate();
+        let token = mint(&key, "download_export", "user-1", 1_000, 300);
+        assert_eq!(verify(&key, &token, "download_export", 1_300).unwrap_err(), ActionTokenError::Expired);
+    }
+
+    #[test]
+    fn a_token_is_rejected_for_the_wrong_action() {
+        let key = Key::generate();
+        let token = mint(&key, "download_export", "user-1", 1_000, 300);
+        assert_eq!(
+            verify(&key, &token, "confirm_email_change", 1_100).unwrap_err(),
+            ActionTokenError::WrongAction
+        );
+    }
+
+    #[test]
+    fn a_token_is_rejected_for_the_wrong_resource() {
+        let key = Key::generate();
+        let token = mint(&key, "download_export", "user-1", 1_000, 300);
+        assert_eq!(
+            verify_for_resource(&key, &token, "download_export", "user-2", 1_100).unwrap_err(),
+            ActionTokenError::WrongResource
+        );
+    }
+
+    #[test]
+    fn a_token_signed_under_a_different_key_is_rejected() {
+        let key = Key::generate();
+        let other = Key::generate();
+        let token = mint(&key, "download_export", "user-1", 1_000, 300);
+        assert_eq!(verify(&other, &token, "download_export", 1_100).unwrap_err(), ActionTokenError::Mismatch);
+    }
+
+    #[test]
+    fn a_malformed_token_is_rejected_rather_than_panicking() {
+        let key = Key::generate();
+        assert_eq!(verify(&key, "not-a-token", "download_export", 1_100).unwrap_err(), ActionTokenError::Malformed);
+    }
+}
@@ -0,0 +1,182 @@
+//! Verifying CAPTCHA challenge responses.
+//!
+//! hCaptcha, reCAPTCHA, and Cloudflare Turnstile all expose the same
+//! "siteverify" shape: POST a secret key and the token the client got back
+//! from solving the widget, receive a JSON `success` flag. [`CaptchaVerifier`]
+//! abstracts over which of those (or some other provider) a deployment uses,
+//! so the call sites that gate registration and repeated failed logins don't
+//! need to know.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::http_client::RetryingHttpClient;
+
+/// A [`CaptchaVerifier`] operation failed.
+#[derive(Debug, Error)]
+pub enum CaptchaError {
+    #[error("the captcha challenge response was rejected")]
+    ChallengeFailed,
+    #[error("the captcha provider could not be reached: {0}")]
+    ProviderUnavailable(String),
+}
+
+/// Verifies a solved CAPTCHA challenge.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    /// Checks `response_token` — the value the client obtained by solving
+    /// the challenge widget — with the configured provider.
+    async fn verify(&self, response_token: &str) -> Result<(), CaptchaError>;
+}
+
+/// A [`CaptchaVerifier`] that accepts every token, for local development
+/// and deployments that haven't configured a provider.
+#[derive(Default)]
+pub struct DisabledCaptchaVerifier;
+
+impl DisabledCaptchaVerifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for DisabledCaptchaVerifier {
+    async fn verify(&self, _response_token: &str) -> Result<(), CaptchaError> {
+        Ok(())
+    }
+}
+
+/// Where to verify challenge responses, and the secret key to verify them
+/// with. `verify_url` is provider-specific:
+/// `https://hcaptcha.com/siteverify`, `https://www.google.com/recaptcha/api/siteverify`,
+/// and `https://challenges.cloudflare.com/turnstile/v0/siteverify` all
+/// speak the same request/response shape [`HttpCaptchaVerifier`] expects.
+#[derive(Debug, Clone)]
+pub struct HttpCaptchaConfig {
+    pub verify_url: String,
+    pub secret_key: String,
+}
+
+/// A [`CaptchaVerifier`] backed by a provider's HTTP siteverify endpoint.
+/// Uses a [`RetryingHttpClient`] rather than a bare `reqwest::Client`, so a
+/// provider blip during a registration burst doesn't fail every request
+/// behind it.
+pub struct HttpCaptchaVerifier {
+    config: HttpCaptchaConfig,
+    client: RetryingHttpClient,
+}
+
+impl HttpCaptchaVerifier {
+    pub fn new(config: HttpCaptchaConfig) -> Self {
+        Self {
+            config,
+            client: RetryingHttpClient::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SiteverifyResponse {
+    success: bool,
+}
+
+#[async_trait]
+impl CaptchaVerifier for HttpCaptchaVerifier {
+    async fn verify(&self, response_token: &str) -> Result<(), CaptchaError> {
+        let response = self
+            .client
+            .send_with_retry(|| {
+                self.client.client().post(&self.config.verify_url).form(&[
+                    ("secret", self.config.secret_key.as_str()),
+                    ("response", response_token),
+                ])
+            })
+            .await
+            .map_err(|e| CaptchaError::ProviderUnavailable(e.to_string()))?;
+
+        let body: SiteverifyResponse = response
+            .json()
+            .await
+            .map_err(|e| CaptchaError::ProviderUnavailable(e.to_string()))?;
+
+        if body.success {
+            Ok(())
+        } else {
+            Err(CaptchaError::ChallengeFailed)
+        }
+    }
+}
+
+/// Counts consecutive failed logins per identifier, so a caller can decide
+/// when to start requiring a solved CAPTCHA before trying another one. A
+/// success clears the identifier's count rather than letting it decay over
+/// time, since the thing being guarded against is a sustained guessing
+/// attempt, not an occasional typo.
+#[derive(Default)]
+pub struct FailedLoginTracker {
+    counts: std::sync::Mutex<std::collections::HashMap<String, u32>>,
+}
+
+impl FailedLoginTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a failed login attempt for `identifier` and returns the
+    /// updated count.
+    pub fn record_failure(&self, identifier: &str) -> u32 {
+        let mut counts = self.counts.lock().expect("failed-login tracker lock poisoned");
+        let count = counts.entry(identifier.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Clears `identifier`'s failure count after a successful login.
+    pub fn record_success(&self, identifier: &str) {
+        self.counts
+            .lock()
+            .expect("failed-login tracker lock poisoned")
+            .remove(identifier);
+    }
+
+    /// The number of consecutive failed logins recorded for `identifier`.
+    pub fn count(&self, identifier: &str) -> u32 {
+        self.counts
+            .lock()
+            .expect("failed-login tracker lock poisoned")
+            .get(identifier)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled_verifier_accepts_any_token() {
+        let verifier = DisabledCaptchaVerifier::new();
+        assert!(verifier.verify("anything").await.is_ok());
+    }
+
+    #[test]
+    fn tracker_counts_consecutive_failures_and_resets_on_success() {
+        let tracker = FailedLoginTracker::new();
+        assert_eq!(tracker.record_failure("a@example.com"), 1);
+        assert_eq!(tracker.record_failure("a@example.com"), 2);
+        assert_eq!(tracker.count("a@example.com"), 2);
+
+        tracker.record_success("a@example.com");
+        assert_eq!(tracker.count("a@example.com"), 0);
+    }
+
+    #[test]
+    fn tracker_tracks_identifiers_independently() {
+        let tracker = FailedLoginTracker::new();
+        tracker.record_failure("a@example.com");
+        assert_eq!(tracker.count("b@example.com"), 0);
+    }
+}
@@ -0,0 +1,77 @@
+//! Sparse-fieldset support for read endpoints: `?fields=a,b,c` projects a
+//! JSON response down to just the requested top-level fields, so a client
+//! that only needs a few of them doesn't pay for the rest.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Serializes `value` to JSON and, when `fields` is `Some`, keeps only the
+/// requested top-level keys (a comma-separated list, e.g. `"id,email"`).
+/// Unrecognized field names are silently dropped, the same way an unknown
+/// query parameter would be. `fields: None` returns `value` untouched.
+pub fn project(value: &impl Serialize, fields: Option<&str>) -> Value {
+    let json = serde_json::to_value(value).expect("value must serialize to JSON");
+
+    let Some(fields) = fields else {
+        return json;
+    };
+    let Value::Object(map) = json else {
+        return json;
+    };
+
+    let wanted: HashSet<&str> = fields.split(',').map(str::trim).collect();
+    let filtered: Map<String, Value> =
+        map.into_iter().filter(|(key, _)| wanted.contains(key.as_str())).collect();
+    Value::Object(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Example {
+        id: &'static str,
+        email: &'static str,
+        date_created: &'static str,
+    }
+
+    fn example() -> Example {
+        Example {
+            id: "1",
+            email: "a@example.com",
+            date_created: "2024-01-01",
+        }
+    }
+
+    #[test]
+    fn no_fields_query_returns_the_full_object() {
+        let value = project(&example(), None);
+        assert_eq!(value["id"], "1");
+        assert_eq!(value["email"], "a@example.com");
+        assert_eq!(value["date_created"], "2024-01-01");
+    }
+
+    #[test]
+    fn fields_query_keeps_only_the_requested_keys() {
+        let value = project(&example(), Some("id,email"));
+        assert_eq!(value.as_object().unwrap().len(), 2);
+        assert_eq!(value["id"], "1");
+        assert_eq!(value["email"], "a@example.com");
+    }
+
+    #[test]
+    fn whitespace_around_field_names_is_ignored() {
+        let value = project(&example(), Some(" id , email "));
+        assert_eq!(value.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn unknown_field_names_are_dropped_silently() {
+        let value = project(&example(), Some("id,nonexistent"));
+        assert_eq!(value.as_object().unwrap().len(), 1);
+        assert_eq!(value["id"], "1");
+    }
+}
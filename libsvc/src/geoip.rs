@@ -0,0 +1,39 @@
+//! Country lookups for GeoIP-based access control.
+//!
+//! This crate embeds no GeoIP database of its own — [`GeoIpLookup`] is the
+//! extension point a deployment backs with a real one (most commonly a
+//! MaxMind GeoLite2/GeoIP2 database), so [`crate::ip_access`]'s country
+//! blocking doesn't need to know which one is plugged in.
+//! [`NoopGeoIpLookup`] is the default until one is configured, and never
+//! resolves a country, so country blocking has no effect until then.
+
+use std::net::IpAddr;
+
+/// Resolves the country an IP address is geolocated to.
+pub trait GeoIpLookup: Send + Sync {
+    /// Returns the looked-up country as an ISO 3166-1 alpha-2 code (e.g.
+    /// `"US"`), or `None` if it can't be determined.
+    fn country_for(&self, ip: IpAddr) -> Option<String>;
+}
+
+/// A [`GeoIpLookup`] that never resolves a country, for deployments that
+/// haven't wired up a real GeoIP database.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopGeoIpLookup;
+
+impl GeoIpLookup for NoopGeoIpLookup {
+    fn country_for(&self, _ip: IpAddr) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noop_lookup_never_resolves_a_country() {
+        let lookup = NoopGeoIpLookup;
+        assert_eq!(lookup.country_for("203.0.113.1".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! A small outbound HTTP client helper: retries transient failures with
+//! jittered backoff and traces every attempt, so call sites like
+//! [`crate::captcha::HttpCaptchaVerifier`] don't each hand-roll their own
+//! retry loop around a bare [`reqwest::Client`].
+//!
+//! This mirrors `users::repository::resilient::ResilientRepository`'s
+//! backoff shape, but without a circuit breaker — an outbound call here is
+//! already one-shot per request rather than a hot path worth protecting
+//! with one, and a breaker tripped by one caller would wrongly block
+//! every other caller sharing the client.
+
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use thiserror::Error;
+use tokio::time::sleep;
+
+/// Tunables for [`RetryingHttpClient`].
+#[derive(Debug, Clone)]
+pub struct HttpClientConfig {
+    /// Maximum number of attempts per call, including the first.
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubles on each subsequent retry.
+    pub base_backoff: Duration,
+    /// Upper bound on backoff, before jitter is added.
+    pub max_backoff: Duration,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A [`RetryingHttpClient::send_with_retry`] call failed even after
+/// exhausting its retries.
+#[derive(Debug, Error)]
+pub enum HttpClientError {
+    #[error("the request could not be sent: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("the server returned {status} after {attempts} attempt(s)")]
+    ServerError { status: StatusCode, attempts: u32 },
+}
+
+/// Wraps a [`reqwest::Client`] with retries and tracing for transient
+/// failures — connection errors, timeouts, and `5xx` responses.
+pub struct RetryingHttpClient {
+    client: reqwest::Client,
+    config: HttpClientConfig,
+}
+
+impl Default for RetryingHttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryingHttpClient {
+    /// Wraps a default [`reqwest::Client`] with the default retry tunables.
+    pub fn new() -> Self {
+        Self::with_config(reqwest::Client::new(), HttpClientConfig::default())
+    }
+
+    /// Wraps `client` with custom retry tunables.
+    pub fn with_config(client: reqwest::Client, config: HttpClientConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// The wrapped client, for callers that need to build requests with it
+    /// directly (e.g. to pass into [`Self::send_with_retry`]).
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .config
+            .base_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = exponential.min(self.config.max_backoff);
+        let jitter_ms = rand::thread_rng().gen_range(0..=(capped.as_millis() as u64 / 2).max(1));
+        capped + Duration::from_millis(jitter_ms)
+    }
+
+    /// Sends the request `build_request` produces, retrying connection
+    /// errors, timeouts, and `5xx` responses with jittered backoff until
+    /// [`HttpClientConfig::max_attempts`] is reached. `build_request` is
+    /// called fresh on every attempt, since a sent [`RequestBuilder`]
+    /// can't be replayed.
+    pub async fn send_with_retry(
+        &self,
+        mut build_request: impl FnMut() -> RequestBuilder,
+    ) -> Result<Response, HttpClientError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            tracing::debug!(attempt, "sending outbound HTTP request");
+            match build_request().send().await {
+                Ok(response) if response.status().is_server_error() => {
+                    let status = response.status();
+                    if attempt < self.config.max_attempts {
+                        tracing::warn!(attempt, %status, "retrying after server error response");
+                        sleep(self.backoff_for(attempt)).await;
+                        continue;
+                    }
+                    return Err(HttpClientError::ServerError { status, attempts: attempt });
+                }
+                Ok(response) => return Ok(response),
+                Err(err) if is_retryable(&err) && attempt < self.config.max_attempts => {
+                    tracing::warn!(attempt, %err, "retrying after transport error");
+                    sleep(self.backoff_for(attempt)).await;
+                }
+                Err(err) => return Err(HttpClientError::Transport(err)),
+            }
+        }
+    }
+}
+
+fn is_retryable(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves exactly one canned raw HTTP response per accepted
+    /// connection, pulled from `responses` in order, and returns how many
+    /// connections it accepted.
+    async fn serve_responses(responses: Vec<&'static str>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let accepted_counter = accepted.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                accepted_counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{addr}"), accepted)
+    }
+
+    const OK_RESPONSE: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+    const SERVER_ERROR_RESPONSE: &str = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n";
+
+    fn fast_retry_config() -> HttpClientConfig {
+        HttpClientConfig {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_successful_request_is_not_retried() {
+        let (url, accepted) = serve_responses(vec![OK_RESPONSE]).await;
+        let client = RetryingHttpClient::with_config(reqwest::Client::new(), fast_retry_config());
+        let response = client
+            .send_with_retry(|| client.client().get(&url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn a_server_error_is_retried_until_it_succeeds() {
+        let (url, accepted) =
+            serve_responses(vec![SERVER_ERROR_RESPONSE, SERVER_ERROR_RESPONSE, OK_RESPONSE]).await;
+        let client = RetryingHttpClient::with_config(reqwest::Client::new(), fast_retry_config());
+        let response = client
+            .send_with_retry(|| client.client().get(&url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn server_errors_past_max_attempts_are_reported_as_an_error() {
+        let (url, accepted) = serve_responses(vec![
+            SERVER_ERROR_RESPONSE,
+            SERVER_ERROR_RESPONSE,
+            SERVER_ERROR_RESPONSE,
+        ])
+        .await;
+        let client = RetryingHttpClient::with_config(reqwest::Client::new(), fast_retry_config());
+        let err = client
+            .send_with_retry(|| client.client().get(&url))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            HttpClientError::ServerError { status: StatusCode::SERVICE_UNAVAILABLE, attempts: 3 }
+        ));
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,278 @@
+//! Localizing user-facing API error messages, and negotiating which
+//! locale to render them in from a request's `Accept-Language` header.
+//!
+//! Mirrors [`crate::mail_templates`]'s tera-backed catalog, but the two
+//! are kept separate: an API error and a notification email read
+//! differently even when describing the same condition, and the call
+//! sites (an HTTP error response vs. a [`crate::mailer::Mailer`] send)
+//! have nothing else in common.
+//!
+//! [`MessageCode`] is the identifier clients should branch on — it's
+//! stable across locales and across wording changes to the message
+//! text, which [`message`] renders separately per [`MessageCode::as_str`].
+
+use std::sync::OnceLock;
+
+use tera::{Context, Tera};
+
+/// The locale every message is guaranteed to exist in, used when a
+/// negotiated locale isn't registered.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Stable, machine-readable identifiers for user-facing API errors.
+/// Clients should match on [`MessageCode::as_str`] (sent as a response's
+/// `code` field), not on [`LocalizedMessage::message`], which changes
+/// with locale and may be reworded over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCode {
+    InvalidCredentials,
+    ValidationFailed,
+    EmailAlreadyRegistered,
+    UsernameAlreadyTaken,
+    CaptchaRequired,
+    CaptchaFailed,
+    AccountDeactivated,
+    MfaEnrollmentRequired,
+    LoginDenied,
+    InternalError,
+}
+
+impl MessageCode {
+    /// The stable identifier sent to clients as `code`, and the catalog
+    /// key [`message`] looks templates up by.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageCode::InvalidCredentials => "invalid_credentials",
+            MessageCode::ValidationFailed => "validation_failed",
+            MessageCode::EmailAlreadyRegistered => "email_already_registered",
+            MessageCode::UsernameAlreadyTaken => "username_already_taken",
+            MessageCode::CaptchaRequired => "captcha_required",
+            MessageCode::CaptchaFailed => "captcha_failed",
+            MessageCode::AccountDeactivated => "account_deactivated",
+            MessageCode::MfaEnrollmentRequired => "mfa_enrollment_required",
+            MessageCode::LoginDenied => "login_denied",
+            MessageCode::InternalError => "internal_error",
+        }
+    }
+}
+
+/// A [`MessageCode`] rendered for a particular locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedMessage {
+    pub code: &'static str,
+    pub message: String,
+}
+
+fn tera() -> &'static Tera {
+    static TERA: OnceLock<Tera> = OnceLock::new();
+    TERA.get_or_init(|| {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(RAW_MESSAGES.iter().copied())
+            .expect("i18n messages are valid tera syntax");
+        tera
+    })
+}
+
+/// Renders `code` for `locale`, falling back to [`DEFAULT_LOCALE`] if
+/// `locale` has no catalog of its own. Never fails: a missing or broken
+/// template would otherwise turn a localization gap into a 500 on every
+/// request that hits it, so this falls back to the English text rather
+/// than propagating a render error.
+pub fn message(code: MessageCode, locale: &str) -> LocalizedMessage {
+    let locale = if has_locale(locale) { locale } else { DEFAULT_LOCALE };
+    let name = format!("{locale}/{}", code.as_str());
+    let rendered = tera()
+        .render(&name, &Context::new())
+        .or_else(|_| tera().render(&format!("{DEFAULT_LOCALE}/{}", code.as_str()), &Context::new()))
+        .unwrap_or_else(|_| code.as_str().to_string());
+    LocalizedMessage { code: code.as_str(), message: rendered }
+}
+
+fn has_locale(locale: &str) -> bool {
+    tera()
+        .get_template_names()
+        .any(|name| name.starts_with(&format!("{locale}/")))
+}
+
+/// Picks the best locale for `accept_language` (an HTTP `Accept-Language`
+/// header value, e.g. `"es-ES,es;q=0.9,en;q=0.8"`) out of `supported`,
+/// preferring an exact match, then a shared primary subtag (`es` for
+/// `es-MX` when only `es-ES` is supported), in the header's stated
+/// preference order. Falls back to [`DEFAULT_LOCALE`] if nothing in the
+/// header matches, or the header is missing or unparseable.
+pub fn negotiate_locale(accept_language: Option<&str>, supported: &[&str]) -> String {
+    let Some(header) = accept_language else {
+        return DEFAULT_LOCALE.to_string();
+    };
+
+    let mut candidates: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim();
+            if tag.is_empty() || tag == "*" {
+                return None;
+            }
+            let quality = parts
+                .next()
+                .and_then(|q| q.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((tag.to_string(), quality))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (tag, _) in &candidates {
+        if let Some(exact) = supported.iter().find(|s| s.eq_ignore_ascii_case(tag)) {
+            return exact.to_string();
+        }
+    }
+    let primary_subtag = |tag: &str| tag.split('-').next().unwrap_or(tag).to_ascii_lowercase();
+    for (tag, _) in &candidates {
+        let primary = primary_subtag(tag);
+        if let Some(matched) = supported
+            .iter()
+            .find(|s| primary_subtag(s) == primary)
+        {
+            return matched.to_string();
+        }
+    }
+    DEFAULT_LOCALE.to_string()
+}
+
+const RAW_MESSAGES: &[(&str, &str)] = &[
+    (
+        "en-US/invalid_credentials",
+        "The email/username or password you entered is incorrect.",
+    ),
+    (
+        "en-US/validation_failed",
+        "The request could not be validated.",
+    ),
+    (
+        "en-US/email_already_registered",
+        "An account with that email address already exists.",
+    ),
+    (
+        "en-US/username_already_taken",
+        "That username is already taken.",
+    ),
+    (
+        "en-US/captcha_required",
+        "Please complete the captcha challenge to continue.",
+    ),
+    (
+        "en-US/captcha_failed",
+        "The captcha challenge could not be verified.",
+    ),
+    (
+        "en-US/account_deactivated",
+        "This account is deactivated. Contact support if you believe this is a mistake.",
+    ),
+    (
+        "en-US/mfa_enrollment_required",
+        "Multi-factor authentication is required for this account. Please enroll before signing in.",
+    ),
+    (
+        "en-US/login_denied",
+        "This sign-in attempt was denied. Contact support if you believe this is a mistake.",
+    ),
+    (
+        "en-US/internal_error",
+        "Something went wrong on our end. Please try again.",
+    ),
+    (
+        "es-ES/invalid_credentials",
+        "El correo electrónico, nombre de usuario o contraseña que ingresaste es incorrecto.",
+    ),
+    (
+        "es-ES/validation_failed",
+        "No se pudo validar la solicitud.",
+    ),
+    (
+        "es-ES/email_already_registered",
+        "Ya existe una cuenta con esa dirección de correo electrónico.",
+    ),
+    (
+        "es-ES/username_already_taken",
+        "Ese nombre de usuario ya está en uso.",
+    ),
+    (
+        "es-ES/captcha_required",
+        "Completa el desafío captcha para continuar.",
+    ),
+    (
+        "es-ES/captcha_failed",
+        "No se pudo verificar el desafío captcha.",
+    ),
+    (
+        "es-ES/account_deactivated",
+        "Esta cuenta está desactivada. Contacta con soporte si crees que es un error.",
+    ),
+    (
+        "es-ES/mfa_enrollment_required",
+        "Se requiere autenticación multifactor para esta cuenta. Regístrate antes de iniciar sesión.",
+    ),
+    (
+        "es-ES/login_denied",
+        "Este intento de inicio de sesión fue denegado. Contacta con soporte si crees que es un error.",
+    ),
+    (
+        "es-ES/internal_error",
+        "Ocurrió un error de nuestro lado. Por favor, inténtalo de nuevo.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_default_locale() {
+        let rendered = message(MessageCode::InvalidCredentials, DEFAULT_LOCALE);
+        assert_eq!(rendered.code, "invalid_credentials");
+        assert_eq!(
+            rendered.message,
+            "The email/username or password you entered is incorrect."
+        );
+    }
+
+    #[test]
+    fn renders_a_registered_non_default_locale() {
+        let rendered = message(MessageCode::CaptchaRequired, "es-ES");
+        assert_eq!(
+            rendered.message,
+            "Completa el desafío captcha para continuar."
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_default_locale_when_unregistered() {
+        let rendered = message(MessageCode::ValidationFailed, "fr-FR");
+        assert_eq!(rendered.message, "The request could not be validated.");
+    }
+
+    #[test]
+    fn negotiate_picks_the_highest_quality_exact_match() {
+        let locale = negotiate_locale(Some("fr;q=0.9,es-ES;q=0.95,en-US;q=0.5"), &["en-US", "es-ES"]);
+        assert_eq!(locale, "es-ES");
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_a_shared_primary_subtag() {
+        let locale = negotiate_locale(Some("es-MX,en;q=0.5"), &["en-US", "es-ES"]);
+        assert_eq!(locale, "es-ES");
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_default_when_nothing_matches() {
+        let locale = negotiate_locale(Some("fr-FR,de-DE;q=0.8"), &["en-US", "es-ES"]);
+        assert_eq!(locale, DEFAULT_LOCALE);
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_default_when_header_is_missing() {
+        assert_eq!(negotiate_locale(None, &["en-US", "es-ES"]), DEFAULT_LOCALE);
+    }
+}
@@ -0,0 +1,182 @@
+//! ID tokens: OpenID Connect's proof of who authenticated, returned
+//! alongside an access session from [`crate::oidc`]'s token endpoint.
+//!
+//! A real OIDC ID token is a JWT, asymmetrically signed so any relying
+//! party can verify one without sharing a secret with the issuer. Every
+//! signing primitive in this crate is symmetric HMAC-SHA256 instead (see
+//! [`foundation::key::Key`], [`crate::action_token`], which made the same
+//! call for action tokens), and this service's authorization code flow is
+//! scoped to "a minimal IdP for internal apps" rather than arbitrary
+//! third-party relying parties — so an ID token here is signed the same
+//! way everything else in this service is, with the same key that signs
+//! sessions, rather than standing up a second, asymmetric signing scheme
+//! for the one caller that would use it.
+
+use base64::Engine;
+use foundation::key::Key;
+use thiserror::Error;
+
+/// The current ID token payload format, matching [`crate::action_token`]'s
+/// and [`crate::session`]'s length-prefixed-field encoding.
+const PAYLOAD_VERSION: u8 = 1;
+
+/// [`mint`] or [`verify`] failed.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdTokenError {
+    #[error("token is not a validly formed id token")]
+    Malformed,
+    #[error("token's signature does not match")]
+    Mismatch,
+    #[error("token has expired")]
+    Expired,
+}
+
+/// The OIDC claims an ID token carries. Scoped to the core claims every
+/// relying party needs (RFC 7519 / OpenID Connect Core §2); this IdP
+/// doesn't yet support requesting additional claims via `scope`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdTokenClaims {
+    /// `iss`: this provider's issuer, matching [`crate::oidc::OidcProviderConfig::issuer`].
+    pub issuer: String,
+    /// `sub`: the authenticated user's id.
+    pub subject: String,
+    /// `aud`: the client id the token was minted for.
+    pub audience: String,
+    /// `iat`: when the token was minted, unix seconds.
+    pub issued_at: u64,
+    /// `exp`: when the token stops being valid, unix seconds.
+    pub expires_at: u64,
+    /// `nonce`: echoes the value the client sent at `/v1/oidc/authorize`,
+    /// if any, binding the token to that specific authorization request.
+    pub nonce: Option<String>,
+}
+
+fn encode_field(buf: &mut Vec<u8>, field: &[u8]) {
+    buf.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    buf.extend_from_slice(field);
+}
+
+fn decode_field(buf: &[u8]) -> Result<(String, &[u8]), IdTokenError> {
+    if buf.len() < 4 {
+        return Err(IdTokenError::Malformed);
+    }
+    let (len_bytes, rest) = buf.split_at(4);
+    let len = u32::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+    if rest.len() < len {
+        return Err(IdTokenError::Malformed);
+    }
+    let (field, rest) = rest.split_at(len);
+    let field = String::from_utf8(field.to_vec()).map_err(|_| IdTokenError::Malformed)?;
+    Ok((field, rest))
+}
+
+fn payload(claims: &IdTokenClaims) -> Vec<u8> {
+    let mut buf = vec![PAYLOAD_VERSION];
+    encode_field(&mut buf, claims.issuer.as_bytes());
+    encode_field(&mut buf, claims.subject.as_bytes());
+    encode_field(&mut buf, claims.audience.as_bytes());
+    buf.extend_from_slice(&claims.issued_at.to_be_bytes());
+    buf.extend_from_slice(&claims.expires_at.to_be_bytes());
+    encode_field(&mut buf, claims.nonce.as_deref().unwrap_or_default().as_bytes());
+    buf
+}
+
+/// Mints an ID token carrying `claims`, signed with `key`. The returned
+/// string is safe to hand back in a token endpoint's JSON response body.
+pub fn mint(key: &Key, claims: &IdTokenClaims) -> String {
+    let payload = payload(claims);
+    let signature = key.sign(&payload);
+    let mut token = payload;
+    token.extend(signature);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token)
+}
+
+/// Verifies that `token` was minted by [`mint`] with `key`, and that it
+/// hasn't expired as of `now`.
+pub fn verify(key: &Key, token: &str, now: u64) -> Result<IdTokenClaims, IdTokenError> {
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(token)
+        .map_err(|_| IdTokenError::Malformed)?;
+    // HMAC-SHA256 signatures are always 32 bytes.
+    if decoded.len() < 32 {
+        return Err(IdTokenError::Malformed);
+    }
+    let (payload, signature) = decoded.split_at(decoded.len() - 32);
+    if !key.verify(payload, signature) {
+        return Err(IdTokenError::Mismatch);
+    }
+
+    let (&version, rest) = payload.split_first().ok_or(IdTokenError::Malformed)?;
+    if version != PAYLOAD_VERSION {
+        return Err(IdTokenError::Malformed);
+    }
+    let (issuer, rest) = decode_field(rest)?;
+    let (subject, rest) = decode_field(rest)?;
+    let (audience, rest) = decode_field(rest)?;
+    if rest.len() < 16 {
+        return Err(IdTokenError::Malformed);
+    }
+    let (issued_at_bytes, rest) = rest.split_at(8);
+    let issued_at = u64::from_be_bytes(issued_at_bytes.try_into().unwrap());
+    let (expires_at_bytes, rest) = rest.split_at(8);
+    let expires_at = u64::from_be_bytes(expires_at_bytes.try_into().unwrap());
+    let (nonce, _rest) = decode_field(rest)?;
+
+    if now >= expires_at {
+        return Err(IdTokenError::Expired);
+    }
+
+    Ok(IdTokenClaims {
+        issuer,
+        subject,
+        audience,
+        issued_at,
+        expires_at,
+        nonce: (!nonce.is_empty()).then_some(nonce),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> IdTokenClaims {
+        IdTokenClaims {
+            issuer: "https://accounts.example.com".to_string(),
+            subject: "user-1".to_string(),
+            audience: "client-1".to_string(),
+            issued_at: 1_000,
+            expires_at: 1_300,
+            nonce: Some("abc123".to_string()),
+        }
+    }
+
+    #[test]
+    fn a_freshly_minted_token_verifies_and_round_trips_its_claims() {
+        let key = Key::generate();
+        let token = mint(&key, &claims());
+        assert_eq!(verify(&key, &token, 1_100).unwrap(), claims());
+    }
+
+    #[test]
+    fn an_expired_token_is_rejected() {
+        let key = Key::generate();
+        let token = mint(&key, &claims());
+        assert_eq!(verify(&key, &token, 1_300).unwrap_err(), IdTokenError::Expired);
+    }
+
+    #[test]
+    fn a_token_signed_by_a_different_key_is_rejected() {
+        let token = mint(&Key::generate(), &claims());
+        assert_eq!(verify(&Key::generate(), &token, 1_100).unwrap_err(), IdTokenError::Mismatch);
+    }
+
+    #[test]
+    fn a_token_with_no_nonce_round_trips_as_none() {
+        let key = Key::generate();
+        let mut without_nonce = claims();
+        without_nonce.nonce = None;
+        let token = mint(&key, &without_nonce);
+        assert_eq!(verify(&key, &token, 1_100).unwrap().nonce, None);
+    }
+}
@@ -0,0 +1,149 @@
+//! Cross-replica cache invalidation, so a cache decorator (such as
+//! `users`' `CachedRepository`) on one instance evicts entries that
+//! changed on another instead of serving them stale until TTL expiry.
+//!
+//! This crate has no embedded pub/sub broker: [`CacheInvalidator`] is the
+//! extension point a deployment backs with a real channel — most
+//! commonly Redis pub/sub or NATS — so cache decorators that publish and
+//! subscribe to invalidations don't need to know which one is plugged
+//! in. [`NoopCacheInvalidator`] is the default until one is configured
+//! (a single instance has nothing to invalidate remotely);
+//! [`LocalCacheInvalidator`] fans events out to in-process subscribers,
+//! for tests and single-process deployments that run more than one
+//! cache instance.
+
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// The default capacity of a [`LocalCacheInvalidator`]'s broadcast
+/// channel. Past this, a lagging subscriber misses the oldest events
+/// rather than blocking publishers — acceptable here, since a missed
+/// eviction only costs a cache hit on stale data until TTL expiry.
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Error)]
+pub enum InvalidationError {
+    #[error("the invalidation channel could not be reached: {0}")]
+    Unavailable(String),
+}
+
+/// A cache key invalidated on some replica, broadcast so others can
+/// evict their own copy of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidationEvent {
+    pub key: String,
+}
+
+/// Publishes and subscribes to cache invalidation events across
+/// replicas.
+#[async_trait]
+pub trait CacheInvalidator: Send + Sync {
+    /// Announces that `key` changed, so other replicas evict it.
+    async fn publish(&self, key: &str) -> Result<(), InvalidationError>;
+
+    /// Subscribes to invalidation events published by any replica.
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationEvent>;
+}
+
+/// A [`CacheInvalidator`] that does nothing, for single-instance
+/// deployments where there is nothing to invalidate remotely.
+pub struct NoopCacheInvalidator {
+    // Kept so `subscribe` can hand back a receiver that simply never
+    // fires, rather than every caller special-casing "no invalidator".
+    sender: broadcast::Sender<InvalidationEvent>,
+}
+
+impl Default for NoopCacheInvalidator {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(1);
+        Self { sender }
+    }
+}
+
+impl NoopCacheInvalidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheInvalidator for NoopCacheInvalidator {
+    async fn publish(&self, _key: &str) -> Result<(), InvalidationError> {
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// A [`CacheInvalidator`] backed by an in-process broadcast channel.
+/// Every [`LocalCacheInvalidator`] cloned from the same original shares
+/// the same channel, so it stands in for a real pub/sub broker in tests
+/// and in deployments that run several cache instances in one process.
+#[derive(Clone)]
+pub struct LocalCacheInvalidator {
+    sender: broadcast::Sender<InvalidationEvent>,
+}
+
+impl Default for LocalCacheInvalidator {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl LocalCacheInvalidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheInvalidator for LocalCacheInvalidator {
+    async fn publish(&self, key: &str) -> Result<(), InvalidationError> {
+        // An error here only means there are currently no subscribers,
+        // which isn't a failure: nobody has stale data to evict yet.
+        let _ = self.sender.send(InvalidationEvent { key: key.to_string() });
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn published_events_reach_every_subscriber() {
+        let invalidator = LocalCacheInvalidator::new();
+        let mut a = invalidator.subscribe();
+        let mut b = invalidator.subscribe();
+
+        invalidator.publish("user:1").await.unwrap();
+
+        assert_eq!(a.recv().await.unwrap().key, "user:1");
+        assert_eq!(b.recv().await.unwrap().key, "user:1");
+    }
+
+    #[tokio::test]
+    async fn publishing_with_no_subscribers_is_not_an_error() {
+        let invalidator = LocalCacheInvalidator::new();
+        assert!(invalidator.publish("user:1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn noop_invalidator_never_fires() {
+        let invalidator = NoopCacheInvalidator::new();
+        let mut events = invalidator.subscribe();
+        invalidator.publish("user:1").await.unwrap();
+
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(10), events.recv())
+            .await
+            .is_err());
+    }
+}
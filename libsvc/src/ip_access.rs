@@ -0,0 +1,166 @@
+//! IP allowlist/denylist and GeoIP country blocking, decided independently
+//! of authentication — a request from a blocked address is rejected before
+//! it ever reaches a handler, let alone [`crate::session`] verification.
+//! See `users::http::ip_filter` for where this is layered onto a router.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+
+use crate::geoip::GeoIpLookup;
+
+/// Tunables for [`IpAccessControl`].
+#[derive(Debug, Clone, Default)]
+pub struct IpAccessConfig {
+    /// If non-empty, only addresses matching one of these ranges are let
+    /// through — everything else is denied, even an address that also
+    /// isn't in `denylist`.
+    pub allowlist: Vec<IpNet>,
+    /// Addresses matching one of these ranges are denied outright,
+    /// checked before `allowlist`.
+    pub denylist: Vec<IpNet>,
+    /// ISO 3166-1 alpha-2 country codes to block, resolved through
+    /// whatever [`GeoIpLookup`] [`IpAccessControl::check`] is given. Has
+    /// no effect paired with [`crate::geoip::NoopGeoIpLookup`], since it
+    /// never resolves a country.
+    pub blocked_countries: HashSet<String>,
+}
+
+/// Why [`IpAccessControl::check`] denied a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DenyReason {
+    Denylisted,
+    NotAllowlisted,
+    BlockedCountry(String),
+}
+
+impl std::fmt::Display for DenyReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DenyReason::Denylisted => write!(f, "address is denylisted"),
+            DenyReason::NotAllowlisted => write!(f, "address is not allowlisted"),
+            DenyReason::BlockedCountry(country) => write!(f, "country {country} is blocked"),
+        }
+    }
+}
+
+/// Decides whether a connecting address should be let through, by CIDR
+/// allow/deny ranges and optionally by GeoIP country.
+#[derive(Debug, Clone, Default)]
+pub struct IpAccessControl {
+    config: IpAccessConfig,
+}
+
+impl IpAccessControl {
+    pub fn new(config: IpAccessConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns `Ok(())` if `ip` should be let through, or the first
+    /// [`DenyReason`] that applies otherwise. `geoip` is only consulted
+    /// when `blocked_countries` is non-empty.
+    pub fn check(&self, ip: IpAddr, geoip: &dyn GeoIpLookup) -> Result<(), DenyReason> {
+        if self.config.denylist.iter().any(|net| net.contains(&ip)) {
+            return Err(DenyReason::Denylisted);
+        }
+        if !self.config.allowlist.is_empty()
+            && !self.config.allowlist.iter().any(|net| net.contains(&ip))
+        {
+            return Err(DenyReason::NotAllowlisted);
+        }
+        if !self.config.blocked_countries.is_empty() {
+            if let Some(country) = geoip.country_for(ip) {
+                if self.config.blocked_countries.contains(&country) {
+                    return Err(DenyReason::BlockedCountry(country));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geoip::NoopGeoIpLookup;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    fn net(cidr: &str) -> IpNet {
+        cidr.parse().unwrap()
+    }
+
+    #[test]
+    fn an_empty_config_allows_everything() {
+        let control = IpAccessControl::new(IpAccessConfig::default());
+        assert!(control.check(ip("203.0.113.1"), &NoopGeoIpLookup).is_ok());
+    }
+
+    #[test]
+    fn a_denylisted_range_is_rejected() {
+        let control = IpAccessControl::new(IpAccessConfig {
+            denylist: vec![net("203.0.113.0/24")],
+            ..Default::default()
+        });
+        assert_eq!(
+            control.check(ip("203.0.113.1"), &NoopGeoIpLookup),
+            Err(DenyReason::Denylisted)
+        );
+    }
+
+    #[test]
+    fn a_nonempty_allowlist_rejects_everything_not_in_it() {
+        let control = IpAccessControl::new(IpAccessConfig {
+            allowlist: vec![net("10.0.0.0/8")],
+            ..Default::default()
+        });
+        assert_eq!(
+            control.check(ip("203.0.113.1"), &NoopGeoIpLookup),
+            Err(DenyReason::NotAllowlisted)
+        );
+        assert!(control.check(ip("10.1.2.3"), &NoopGeoIpLookup).is_ok());
+    }
+
+    #[test]
+    fn denylist_is_checked_before_allowlist() {
+        let control = IpAccessControl::new(IpAccessConfig {
+            allowlist: vec![net("10.0.0.0/8")],
+            denylist: vec![net("10.1.2.0/24")],
+            ..Default::default()
+        });
+        assert_eq!(
+            control.check(ip("10.1.2.3"), &NoopGeoIpLookup),
+            Err(DenyReason::Denylisted)
+        );
+    }
+
+    #[test]
+    fn a_blocked_country_is_rejected_when_the_geoip_lookup_resolves_it() {
+        struct AlwaysFr;
+        impl GeoIpLookup for AlwaysFr {
+            fn country_for(&self, _ip: IpAddr) -> Option<String> {
+                Some("FR".to_string())
+            }
+        }
+        let control = IpAccessControl::new(IpAccessConfig {
+            blocked_countries: ["FR".to_string()].into_iter().collect(),
+            ..Default::default()
+        });
+        assert_eq!(
+            control.check(ip("203.0.113.1"), &AlwaysFr),
+            Err(DenyReason::BlockedCountry("FR".to_string()))
+        );
+    }
+
+    #[test]
+    fn country_blocking_has_no_effect_with_a_noop_lookup() {
+        let control = IpAccessControl::new(IpAccessConfig {
+            blocked_countries: ["FR".to_string()].into_iter().collect(),
+            ..Default::default()
+        });
+        assert!(control.check(ip("203.0.113.1"), &NoopGeoIpLookup).is_ok());
+    }
+}
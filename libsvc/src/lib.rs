@@ -0,0 +1,34 @@
+//! Shared service-layer scaffolding: sessions, repository errors, and
+//! audit logging. Individual services (such as `users`) build their domain
+//! logic on top of these primitives.
+
+pub mod action_token;
+pub mod audit;
+pub mod captcha;
+pub mod dpop;
+pub mod fields;
+pub mod geoip;
+pub mod i18n;
+pub mod http_client;
+pub mod id_token;
+pub mod invalidation;
+pub mod ip_access;
+pub mod mail_templates;
+pub mod mailer;
+pub mod metrics;
+pub mod oidc;
+pub mod pool_metrics;
+pub mod pusher;
+pub mod rate_limit;
+pub mod repository;
+pub mod request_signing;
+pub mod risk;
+pub mod saml;
+pub mod search_index;
+pub mod security_signal;
+pub mod service_account;
+pub mod session;
+pub mod sms;
+pub mod telemetry;
+pub mod unit_of_work;
+pub mod verification_cache;
@@ -0,0 +1,194 @@
+//! Localizable templates for the transactional emails this service sends
+//! (email verification, password reset, and login alerts), rendered with
+//! [`tera`] so subject/body text isn't hand-formatted in English at each
+//! call site. A caller renders a template for a user's
+//! `UserPreferences::locale`, then hands the resulting subject and body
+//! to a [`crate::mailer::Mailer`] exactly as it would any other message —
+//! this module only produces text, it doesn't send anything.
+//!
+//! Falls back to [`DEFAULT_LOCALE`] for a locale with no templates of its
+//! own, so an unrecognized or partially-translated locale still gets a
+//! usable email rather than a render error.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use tera::{Context, Tera};
+use thiserror::Error;
+
+/// The locale every template is guaranteed to exist in, used when a
+/// requested locale isn't registered.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// A transactional email this service knows how to render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    /// Confirm a new or changed email address.
+    Verification,
+    /// Redeem a password reset token.
+    PasswordReset,
+    /// Notify the account owner of a new sign-in.
+    LoginAlert,
+}
+
+impl EmailTemplate {
+    fn name(self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => "verification",
+            EmailTemplate::PasswordReset => "password_reset",
+            EmailTemplate::LoginAlert => "login_alert",
+        }
+    }
+}
+
+/// A [`render`] failure.
+#[derive(Debug, Error)]
+pub enum MailTemplateError {
+    #[error("template could not be rendered: {0}")]
+    Render(#[from] tera::Error),
+}
+
+/// A rendered email, ready to hand to a [`crate::mailer::Mailer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+fn tera() -> &'static Tera {
+    static TERA: OnceLock<Tera> = OnceLock::new();
+    TERA.get_or_init(|| {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(RAW_TEMPLATES.iter().copied())
+            .expect("mail templates are valid tera syntax");
+        tera
+    })
+}
+
+/// Renders `template` for `locale` (falling back to [`DEFAULT_LOCALE`] if
+/// `locale` has no templates registered) with `vars` bound into the
+/// template context.
+pub fn render(
+    template: EmailTemplate,
+    locale: &str,
+    vars: &HashMap<&str, &str>,
+) -> Result<RenderedEmail, MailTemplateError> {
+    let locale = if has_locale(locale) { locale } else { DEFAULT_LOCALE };
+    let mut context = Context::new();
+    for (key, value) in vars {
+        context.insert(key.to_string(), value);
+    }
+    let subject = tera().render(&format!("{locale}/{}.subject", template.name()), &context)?;
+    let body = tera().render(&format!("{locale}/{}.body", template.name()), &context)?;
+    Ok(RenderedEmail { subject, body })
+}
+
+fn has_locale(locale: &str) -> bool {
+    tera()
+        .get_template_names()
+        .any(|name| name.starts_with(&format!("{locale}/")))
+}
+
+const RAW_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "en-US/verification.subject",
+        "Confirm your email address",
+    ),
+    (
+        "en-US/verification.body",
+        "Use this code to confirm your new email address: {{ token }}",
+    ),
+    ("en-US/password_reset.subject", "Reset your password"),
+    (
+        "en-US/password_reset.body",
+        "Use this code to reset your password: {{ token }}",
+    ),
+    ("en-US/login_alert.subject", "New sign-in to your account"),
+    (
+        "en-US/login_alert.body",
+        "We noticed a new sign-in to your account{% if ip %} from {{ ip }}{% endif %}. \
+         If this wasn't you, reset your password immediately.",
+    ),
+    (
+        "es-ES/verification.subject",
+        "Confirma tu dirección de correo electrónico",
+    ),
+    (
+        "es-ES/verification.body",
+        "Usa este código para confirmar tu nueva dirección de correo electrónico: {{ token }}",
+    ),
+    (
+        "es-ES/password_reset.subject",
+        "Restablece tu contraseña",
+    ),
+    (
+        "es-ES/password_reset.body",
+        "Usa este código para restablecer tu contraseña: {{ token }}",
+    ),
+    (
+        "es-ES/login_alert.subject",
+        "Nuevo inicio de sesión en tu cuenta",
+    ),
+    (
+        "es-ES/login_alert.body",
+        "Detectamos un nuevo inicio de sesión en tu cuenta{% if ip %} desde {{ ip }}{% endif %}. \
+         Si no fuiste tú, restablece tu contraseña de inmediato.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_default_locale() {
+        let mut vars = HashMap::new();
+        vars.insert("token", "abc123");
+        let email = render(EmailTemplate::Verification, DEFAULT_LOCALE, &vars).unwrap();
+        assert_eq!(email.subject, "Confirm your email address");
+        assert_eq!(
+            email.body,
+            "Use this code to confirm your new email address: abc123"
+        );
+    }
+
+    #[test]
+    fn renders_a_registered_non_default_locale() {
+        let mut vars = HashMap::new();
+        vars.insert("token", "abc123");
+        let email = render(EmailTemplate::PasswordReset, "es-ES", &vars).unwrap();
+        assert_eq!(email.subject, "Restablece tu contraseña");
+        assert_eq!(
+            email.body,
+            "Usa este código para restablecer tu contraseña: abc123"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_default_locale_when_unregistered() {
+        let mut vars = HashMap::new();
+        vars.insert("token", "abc123");
+        let email = render(EmailTemplate::Verification, "fr-FR", &vars).unwrap();
+        assert_eq!(email.subject, "Confirm your email address");
+    }
+
+    #[test]
+    fn login_alert_omits_the_ip_clause_when_not_provided() {
+        let email = render(EmailTemplate::LoginAlert, DEFAULT_LOCALE, &HashMap::new()).unwrap();
+        assert_eq!(
+            email.body,
+            "We noticed a new sign-in to your account. If this wasn't you, reset your password immediately."
+        );
+    }
+
+    #[test]
+    fn login_alert_includes_the_ip_clause_when_provided() {
+        let mut vars = HashMap::new();
+        vars.insert("ip", "203.0.113.5");
+        let email = render(EmailTemplate::LoginAlert, DEFAULT_LOCALE, &vars).unwrap();
+        assert_eq!(
+            email.body,
+            "We noticed a new sign-in to your account from 203.0.113.5. If this wasn't you, reset your password immediately."
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! Outbound transactional email, abstracted so the domain layer does not
+//! depend on a particular delivery provider.
+
+/// Sends plain-text notification emails.
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str);
+}
+
+/// A [`Mailer`] that logs messages instead of delivering them, for local
+/// development and tests.
+#[derive(Default)]
+pub struct LoggingMailer;
+
+impl LoggingMailer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Mailer for LoggingMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) {
+        tracing::info!(%to, %subject, %body, "would send email");
+    }
+}
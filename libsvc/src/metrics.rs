@@ -0,0 +1,70 @@
+//! A hook for exporting the counters and observations behind core service
+//! activity — logins, login failures, sessions issued/verified/rejected —
+//! to whatever metrics backend a deployment runs (Prometheus, StatsD,
+//! ...). A user's login attempt and a session being issued or checked are
+//! the choke points every sign-in and every authenticated request passes
+//! through, so that's where [`Metrics::increment`] is called from.
+//! [`Metrics`] only defines the interface one plugs in through; the
+//! default [`NoopMetrics`] discards everything, for deployments that
+//! haven't wired one up.
+
+/// A sink for counters and observations.
+pub trait Metrics: Send + Sync {
+    /// Increments the counter named `name` by 1.
+    fn increment(&self, name: &str);
+    /// Records an observation (e.g. a duration) against the named
+    /// histogram or summary.
+    fn observe(&self, name: &str, value: f64);
+}
+
+/// A [`Metrics`] that discards everything. The default everywhere one is
+/// needed.
+#[derive(Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+    fn increment(&self, _name: &str) {}
+    fn observe(&self, _name: &str, _value: f64) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingMetrics {
+        counters: Mutex<Vec<String>>,
+        observations: Mutex<Vec<(String, f64)>>,
+    }
+
+    impl Metrics for RecordingMetrics {
+        fn increment(&self, name: &str) {
+            self.counters.lock().unwrap().push(name.to_string());
+        }
+
+        fn observe(&self, name: &str, value: f64) {
+            self.observations.lock().unwrap().push((name.to_string(), value));
+        }
+    }
+
+    #[test]
+    fn a_custom_implementation_receives_increments_and_observations() {
+        let metrics = RecordingMetrics::default();
+        metrics.increment("logins_total");
+        metrics.observe("session_ttl_seconds", 3600.0);
+        assert_eq!(metrics.counters.lock().unwrap().as_slice(), ["logins_total"]);
+        assert_eq!(
+            metrics.observations.lock().unwrap().as_slice(),
+            [("session_ttl_seconds".to_string(), 3600.0)]
+        );
+    }
+
+    #[test]
+    fn noop_implementation_does_not_panic() {
+        let metrics = NoopMetrics;
+        metrics.increment("logins_total");
+        metrics.observe("session_ttl_seconds", 3600.0);
+    }
+}
@@ -0,0 +1,306 @@
+//! Configuration and server-side state for this service's (optional) role
+//! as an OpenID Connect provider: the [`OidcProviderConfig`] a
+//! `/.well-known/openid-configuration` document is built from, the
+//! registered [`OidcClient`]s allowed to request sessions, and the
+//! short-lived [`AuthorizationCode`]s the authorization code flow hands
+//! out between `/v1/oidc/authorize` and `/v1/oidc/token`. ID token
+//! issuance itself lives in [`crate::id_token`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use foundation::hash::verify_password;
+
+/// An OIDC provider's own identity: the `issuer` claim every other URL in
+/// its discovery document is built by appending a path to.
+pub struct OidcProviderConfig {
+    /// This provider's `issuer`, e.g. `https://accounts.example.com` — no
+    /// trailing slash, matching how it appears in an issued ID token's
+    /// `iss` claim.
+    pub issuer: String,
+}
+
+/// A client application registered to request sessions through the
+/// authorization code flow, analogous to [`crate::service_account::ServiceAccount`]
+/// on the token-exchange side.
+#[derive(Debug, Clone)]
+pub struct OidcClient {
+    pub client_id: String,
+    /// An Argon2id hash of the client's secret (see
+    /// [`foundation::hash::hash_password`]), or `None` for a public client
+    /// (e.g. a mobile app or SPA that can't keep a secret) that must rely
+    /// on PKCE alone to prove it's the party that started the flow.
+    pub client_secret_hash: Option<String>,
+    /// Exact-match redirect URIs this client may request a code be
+    /// returned to, checked the same way at `/v1/oidc/authorize` and
+    /// `/v1/oidc/token` per RFC 6749 §10.6 so a stolen code can't be
+    /// redeemed against a different redirect than the one it was issued
+    /// for.
+    pub redirect_uris: Vec<String>,
+    /// The widest set of scopes this client may request.
+    pub allowed_scopes: Vec<String>,
+}
+
+/// Looks up registered [`OidcClient`]s by client id.
+pub trait OidcClientRegistry: Send + Sync {
+    /// Returns the named client, regardless of whether it has a secret —
+    /// used at `/v1/oidc/authorize`, which only needs to validate the
+    /// redirect URI and scope, not authenticate the caller.
+    fn get(&self, client_id: &str) -> Option<OidcClient>;
+
+    /// Returns the named client if `client_secret` verifies against its
+    /// stored hash. A public client (no stored hash) never authenticates
+    /// this way, even with an empty secret.
+    fn authenticate(&self, client_id: &str, client_secret: &str) -> Option<OidcClient> {
+        let client = self.get(client_id)?;
+        let hash = client.client_secret_hash.as_deref()?;
+        verify_password(client_secret, hash).unwrap_or(false).then_some(client)
+    }
+}
+
+/// An [`OidcClientRegistry`] backed by a fixed set of clients, configured
+/// up front rather than looked up from a repository.
+#[derive(Default)]
+pub struct InMemoryOidcClientRegistry {
+    clients: HashMap<String, OidcClient>,
+}
+
+impl InMemoryOidcClientRegistry {
+    pub fn new(clients: Vec<OidcClient>) -> Self {
+        Self {
+            clients: clients.into_iter().map(|client| (client.client_id.clone(), client)).collect(),
+        }
+    }
+}
+
+impl OidcClientRegistry for InMemoryOidcClientRegistry {
+    fn get(&self, client_id: &str) -> Option<OidcClient> {
+        self.clients.get(client_id).cloned()
+    }
+}
+
+/// A pending grant recorded when `/v1/oidc/authorize` (or the consent
+/// screen it defers to) approves a request, and redeemed exactly once by
+/// `/v1/oidc/token`.
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub user_id: String,
+    pub scope: String,
+    /// The PKCE challenge from the authorize request (RFC 7636). Only
+    /// `S256` is supported — the whole point of PKCE is that the verifier
+    /// sent to `/v1/oidc/token` is checked against a value that can't be
+    /// reversed back into it, and `plain` defeats that.
+    pub code_challenge: String,
+    /// The `nonce` from the authorize request, if any, carried through to
+    /// the ID token [`crate::id_token::IdTokenClaims::nonce`] minted when
+    /// this code is redeemed.
+    pub nonce: Option<String>,
+    pub expires_at: u64,
+}
+
+/// Stores [`AuthorizationCode`]s between issuance and redemption.
+pub trait AuthorizationCodeStore: Send + Sync {
+    fn insert(&self, code: String, grant: AuthorizationCode);
+
+    /// Removes and returns the grant for `code`, since an authorization
+    /// code is valid for exactly one token exchange (RFC 6749 §4.1.2) —
+    /// a second redemption, even of a still-unexpired code, must fail.
+    fn consume(&self, code: &str) -> Option<AuthorizationCode>;
+}
+
+/// An [`AuthorizationCodeStore`] backed by an in-memory map, for
+/// deployments that haven't wired up a shared one (and for tests).
+#[derive(Default)]
+pub struct InMemoryAuthorizationCodeStore {
+    codes: Mutex<HashMap<String, AuthorizationCode>>,
+}
+
+impl InMemoryAuthorizationCodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AuthorizationCodeStore for InMemoryAuthorizationCodeStore {
+    fn insert(&self, code: String, grant: AuthorizationCode) {
+        self.codes.lock().unwrap_or_else(|p| p.into_inner()).insert(code, grant);
+    }
+
+    fn consume(&self, code: &str) -> Option<AuthorizationCode> {
+        self.codes.lock().unwrap_or_else(|p| p.into_inner()).remove(code)
+    }
+}
+
+/// A single (client, scope) grant a user has approved, as returned by
+/// [`ConsentStore::list_for_user`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentGrant {
+    pub client_id: String,
+    pub scope: String,
+}
+
+/// Remembers which (user, client, scope) combinations a user has already
+/// approved, so `/v1/oidc/authorize` can skip the consent screen on a
+/// returning visit, and backs the `/v1/users/me/consents` endpoints a user
+/// reviews and revokes those grants through.
+pub trait ConsentStore: Send + Sync {
+    fn has_consented(&self, user_id: &str, client_id: &str, scope: &str) -> bool;
+    fn record(&self, user_id: String, client_id: String, scope: String);
+
+    /// Every grant `user_id` has approved, across all clients, for
+    /// `GET /v1/users/me/consents`.
+    fn list_for_user(&self, user_id: &str) -> Vec<ConsentGrant>;
+
+    /// Removes every grant `user_id` has approved for `client_id`,
+    /// regardless of scope, for `DELETE /v1/users/me/consents/:client_id`.
+    /// A user revoking a client's access revokes all of it, the same way a
+    /// relying party's own "disconnect this app" control would, rather
+    /// than requiring a separate call per previously-approved scope.
+    fn revoke(&self, user_id: &str, client_id: &str);
+}
+
+/// A [`ConsentStore`] backed by an in-memory set, for deployments that
+/// haven't wired up a shared one (and for tests).
+#[derive(Default)]
+pub struct InMemoryConsentStore {
+    granted: Mutex<std::collections::HashSet<(String, String, String)>>,
+}
+
+impl InMemoryConsentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConsentStore for InMemoryConsentStore {
+    fn has_consented(&self, user_id: &str, client_id: &str, scope: &str) -> bool {
+        self.granted
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .contains(&(user_id.to_string(), client_id.to_string(), scope.to_string()))
+    }
+
+    fn record(&self, user_id: String, client_id: String, scope: String) {
+        self.granted.lock().unwrap_or_else(|p| p.into_inner()).insert((user_id, client_id, scope));
+    }
+
+    fn list_for_user(&self, user_id: &str) -> Vec<ConsentGrant> {
+        self.granted
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .iter()
+            .filter(|(granted_user, _, _)| granted_user == user_id)
+            .map(|(_, client_id, scope)| ConsentGrant { client_id: client_id.clone(), scope: scope.clone() })
+            .collect()
+    }
+
+    fn revoke(&self, user_id: &str, client_id: &str) {
+        self.granted
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .retain(|(granted_user, granted_client, _)| granted_user != user_id || granted_client != client_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use foundation::hash::hash_password;
+
+    use super::*;
+
+    fn registry() -> InMemoryOidcClientRegistry {
+        InMemoryOidcClientRegistry::new(vec![
+            OidcClient {
+                client_id: "confidential-app".to_string(),
+                client_secret_hash: Some(hash_password("s3cret").unwrap()),
+                redirect_uris: vec!["https://app.example.com/callback".to_string()],
+                allowed_scopes: vec!["openid".to_string(), "profile".to_string()],
+            },
+            OidcClient {
+                client_id: "public-app".to_string(),
+                client_secret_hash: None,
+                redirect_uris: vec!["https://spa.example.com/callback".to_string()],
+                allowed_scopes: vec!["openid".to_string()],
+            },
+        ])
+    }
+
+    #[test]
+    fn gets_a_registered_client_by_id() {
+        assert_eq!(registry().get("confidential-app").unwrap().client_id, "confidential-app");
+        assert!(registry().get("no-such-client").is_none());
+    }
+
+    #[test]
+    fn authenticates_a_confidential_client_with_the_right_secret() {
+        assert!(registry().authenticate("confidential-app", "s3cret").is_some());
+        assert!(registry().authenticate("confidential-app", "wrong").is_none());
+    }
+
+    #[test]
+    fn a_public_client_never_authenticates_even_with_an_empty_secret() {
+        assert!(registry().authenticate("public-app", "").is_none());
+    }
+
+    #[test]
+    fn an_authorization_code_can_only_be_consumed_once() {
+        let store = InMemoryAuthorizationCodeStore::new();
+        store.insert(
+            "code-1".to_string(),
+            AuthorizationCode {
+                client_id: "confidential-app".to_string(),
+                redirect_uri: "https://app.example.com/callback".to_string(),
+                user_id: "user-1".to_string(),
+                scope: "openid".to_string(),
+                code_challenge: "challenge".to_string(),
+                nonce: None,
+                expires_at: 1_000,
+            },
+        );
+        assert!(store.consume("code-1").is_some());
+        assert!(store.consume("code-1").is_none());
+    }
+
+    #[test]
+    fn consent_is_remembered_per_user_client_and_scope() {
+        let store = InMemoryConsentStore::new();
+        assert!(!store.has_consented("user-1", "confidential-app", "openid"));
+        store.record("user-1".to_string(), "confidential-app".to_string(), "openid".to_string());
+        assert!(store.has_consented("user-1", "confidential-app", "openid"));
+        assert!(!store.has_consented("user-1", "confidential-app", "openid profile"));
+    }
+
+    #[test]
+    fn lists_only_the_named_users_grants() {
+        let store = InMemoryConsentStore::new();
+        store.record("user-1".to_string(), "confidential-app".to_string(), "openid".to_string());
+        store.record("user-1".to_string(), "public-app".to_string(), "openid".to_string());
+        store.record("user-2".to_string(), "confidential-app".to_string(), "openid".to_string());
+
+        let mut grants = store.list_for_user("user-1");
+        grants.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        assert_eq!(
+            grants,
+            vec![
+                ConsentGrant { client_id: "confidential-app".to_string(), scope: "openid".to_string() },
+                ConsentGrant { client_id: "public-app".to_string(), scope: "openid".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn revoking_a_client_clears_every_scope_granted_to_it_without_touching_others() {
+        let store = InMemoryConsentStore::new();
+        store.record("user-1".to_string(), "confidential-app".to_string(), "openid".to_string());
+        store.record("user-1".to_string(), "confidential-app".to_string(), "profile".to_string());
+        store.record("user-1".to_string(), "public-app".to_string(), "openid".to_string());
+
+        store.revoke("user-1", "confidential-app");
+
+        assert!(!store.has_consented("user-1", "confidential-app", "openid"));
+        assert!(!store.has_consented("user-1", "confidential-app", "profile"));
+        assert!(store.has_consented("user-1", "public-app", "openid"));
+    }
+}
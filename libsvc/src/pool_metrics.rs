@@ -0,0 +1,114 @@
+//! Instrumentation for a pooled backend connection — pool utilization
+//! and slow-query logging — for a SQL-backed repository to report
+//! through, without every such repository reinventing the threshold
+//! check and the metric names.
+//!
+//! This crate has no SQL-backed repository yet (see
+//! `users::repository::read_replica`'s doc comment for the current
+//! state of that work), so nothing constructs a [`PoolSample`] or calls
+//! [`QueryLogger::record_query`] today; this module is the extension
+//! point such a repository would use once one exists, the same way
+//! [`crate::search_index::SearchIndex`] is the extension point for a
+//! real search engine.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::metrics::Metrics;
+
+/// A point-in-time read of a connection pool's state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolSample {
+    /// Connections currently checked out and in use.
+    pub in_use: u32,
+    /// Connections sitting idle, ready to be checked out.
+    pub idle: u32,
+    /// The pool's configured maximum size.
+    pub max_size: u32,
+    /// How long the caller waited to acquire this connection.
+    pub acquire_wait: Duration,
+}
+
+/// Reports pool utilization and query durations to a [`Metrics`] sink,
+/// and logs any query slower than `slow_query_threshold` so operators
+/// can diagnose saturation without reaching for external tooling.
+pub struct QueryLogger {
+    metrics: Arc<dyn Metrics>,
+    slow_query_threshold: Duration,
+}
+
+impl QueryLogger {
+    pub fn new(metrics: Arc<dyn Metrics>, slow_query_threshold: Duration) -> Self {
+        Self { metrics, slow_query_threshold }
+    }
+
+    /// Records a snapshot of pool utilization, taken e.g. on a timer or
+    /// before each checkout.
+    pub fn record_pool_sample(&self, sample: PoolSample) {
+        self.metrics.observe("pool_connections_in_use", sample.in_use as f64);
+        self.metrics.observe("pool_connections_idle", sample.idle as f64);
+        self.metrics.observe("pool_connections_max", sample.max_size as f64);
+        self.metrics.observe("pool_acquire_wait_seconds", sample.acquire_wait.as_secs_f64());
+    }
+
+    /// Records how long a query labeled `label` (e.g. its statement name)
+    /// took, and logs it as a slow query if it crossed the threshold.
+    pub fn record_query(&self, label: &str, elapsed: Duration) {
+        self.metrics.observe("query_duration_seconds", elapsed.as_secs_f64());
+        if elapsed >= self.slow_query_threshold {
+            tracing::warn!(
+                query = %label,
+                elapsed_ms = elapsed.as_millis(),
+                threshold_ms = self.slow_query_threshold.as_millis(),
+                "slow query"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingMetrics {
+        observations: Mutex<Vec<(String, f64)>>,
+    }
+
+    impl Metrics for RecordingMetrics {
+        fn increment(&self, _name: &str) {}
+
+        fn observe(&self, name: &str, value: f64) {
+            self.observations.lock().unwrap().push((name.to_string(), value));
+        }
+    }
+
+    #[test]
+    fn a_pool_sample_reports_all_four_gauges() {
+        let metrics = Arc::new(RecordingMetrics::default());
+        let logger = QueryLogger::new(metrics.clone(), Duration::from_millis(100));
+
+        logger.record_pool_sample(PoolSample {
+            in_use: 3,
+            idle: 2,
+            max_size: 10,
+            acquire_wait: Duration::from_millis(5),
+        });
+
+        let observations = metrics.observations.lock().unwrap();
+        assert_eq!(observations.len(), 4);
+    }
+
+    #[test]
+    fn a_query_under_the_threshold_is_recorded_but_not_logged_as_slow() {
+        let metrics = Arc::new(RecordingMetrics::default());
+        let logger = QueryLogger::new(metrics.clone(), Duration::from_millis(100));
+
+        logger.record_query("get_user_by_id", Duration::from_millis(10));
+
+        let observations = metrics.observations.lock().unwrap();
+        assert_eq!(observations.as_slice(), [("query_duration_seconds".to_string(), 0.01)]);
+    }
+}
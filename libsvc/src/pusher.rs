@@ -0,0 +1,118 @@
+//! Outbound mobile push notifications, abstracted so the domain layer does
+//! not depend on a particular provider — mirrors [`crate::mailer::Mailer`]'s
+//! split. Unlike [`crate::mailer::Mailer::send`], [`Pusher::push`] returns a
+//! `Result`: a push provider can reject a device token outright, and a
+//! caller needs to distinguish that from a transient outage so it can
+//! forget the token rather than keep retrying it forever.
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::http_client::RetryingHttpClient;
+
+/// A [`Pusher::push`] call failed.
+#[derive(Debug, Error)]
+pub enum PushError {
+    /// The provider rejected the device token as invalid, unregistered, or
+    /// uninstalled. The caller should stop pushing to it.
+    #[error("the device token was rejected as invalid or unregistered")]
+    InvalidToken,
+    #[error("the push provider could not be reached: {0}")]
+    ProviderUnavailable(String),
+}
+
+/// Sends a push notification to a single device token.
+#[async_trait]
+pub trait Pusher: Send + Sync {
+    async fn push(&self, token: &str, subject: &str, body: &str) -> Result<(), PushError>;
+}
+
+/// A [`Pusher`] that logs messages instead of delivering them, for local
+/// development and tests.
+#[derive(Default)]
+pub struct LoggingPusher;
+
+impl LoggingPusher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Pusher for LoggingPusher {
+    async fn push(&self, token: &str, subject: &str, body: &str) -> Result<(), PushError> {
+        tracing::info!(%token, %subject, %body, "would send push notification");
+        Ok(())
+    }
+}
+
+/// Where to reach Firebase Cloud Messaging's HTTP v1 send endpoint, and the
+/// bearer token to authenticate with.
+#[derive(Debug, Clone)]
+pub struct FcmConfig {
+    /// `https://fcm.googleapis.com/v1/projects/<project-id>/messages:send`
+    pub endpoint: String,
+    pub access_token: String,
+}
+
+/// A [`Pusher`] backed by FCM's HTTP v1 API. Uses a [`RetryingHttpClient`]
+/// rather than a bare `reqwest::Client`, the same way
+/// [`crate::captcha::HttpCaptchaVerifier`] does, so a provider blip doesn't
+/// fail every push behind it.
+pub struct FcmPusher {
+    config: FcmConfig,
+    client: RetryingHttpClient,
+}
+
+impl FcmPusher {
+    pub fn new(config: FcmConfig) -> Self {
+        Self { config, client: RetryingHttpClient::new() }
+    }
+}
+
+#[async_trait]
+impl Pusher for FcmPusher {
+    async fn push(&self, token: &str, subject: &str, body: &str) -> Result<(), PushError> {
+        let response = self
+            .client
+            .send_with_retry(|| {
+                self.client
+                    .client()
+                    .post(&self.config.endpoint)
+                    .bearer_auth(&self.config.access_token)
+                    .json(&serde_json::json!({
+                        "message": {
+                            "token": token,
+                            "notification": { "title": subject, "body": body },
+                        },
+                    }))
+            })
+            .await
+            .map_err(|e| PushError::ProviderUnavailable(e.to_string()))?;
+
+        // FCM returns 404 for an unregistered token and 400 for a
+        // malformed one (e.g. belonging to a different Firebase project) —
+        // both mean the token is permanently unusable, not that the
+        // request should be retried.
+        match response.status() {
+            status if status == reqwest::StatusCode::NOT_FOUND
+                || status == reqwest::StatusCode::BAD_REQUEST =>
+            {
+                Err(PushError::InvalidToken)
+            }
+            status if status.is_success() => Ok(()),
+            status => Err(PushError::ProviderUnavailable(format!("unexpected status {status}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn logging_pusher_always_succeeds() {
+        let pusher = LoggingPusher::new();
+        assert!(pusher.push("token", "subject", "body").await.is_ok());
+    }
+}
@@ -0,0 +1,181 @@
+//! A generic sliding-window rate limiter keyed by an arbitrary string, for
+//! throttling repeated attempts (e.g. failed logins) per identifier.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// A [`SlidingWindowRateLimiter`] operation failed.
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    #[error("rate limiter lock was poisoned by a panicked holder")]
+    LockPoisoned,
+}
+
+/// Tunables for [`SlidingWindowRateLimiter`].
+#[derive(Debug, Clone)]
+pub struct RateLimiterConfig {
+    /// Maximum attempts a single key may make within `window`.
+    pub max_attempts: u32,
+    /// The sliding window over which attempts are counted.
+    pub window: Duration,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Counts attempts per key within a trailing time window, so a key that
+/// exceeds `max_attempts` is throttled until old attempts age out rather
+/// than until some fixed reset time.
+///
+/// `key` comes straight from the caller — e.g. the login identifier on an
+/// unauthenticated `authenticate()` call — so it's never trustworthy. Left
+/// unchecked, a map entry is created for every distinct key ever seen and
+/// never removed, letting an attacker grow this map without bound just by
+/// varying the identifier on each failed attempt. [`Self::check`] sweeps
+/// the whole map for keys with no attempts left in the window once per
+/// `config.window`, so a key that's stopped attempting is forgotten
+/// rather than held onto forever.
+pub struct SlidingWindowRateLimiter {
+    config: RateLimiterConfig,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    attempts: HashMap<String, VecDeque<Instant>>,
+    last_swept: Instant,
+}
+
+impl SlidingWindowRateLimiter {
+    pub fn new(config: RateLimiterConfig) -> Self {
+        let last_swept = Instant::now();
+        Self {
+            config,
+            state: Mutex::new(RateLimiterState { attempts: HashMap::new(), last_swept }),
+        }
+    }
+
+    /// Records an attempt for `key` and reports whether it is allowed.
+    /// Attempts older than the configured window are dropped first, so a
+    /// key that stops attempting eventually recovers on its own. The
+    /// attempt is still recorded even when rejected, so a key hammering
+    /// the limiter doesn't get a free pass once it's back under the
+    /// threshold.
+    pub fn check(&self, key: &str) -> Result<bool, RateLimitError> {
+        let now = Instant::now();
+        let mut state = self.state.lock().map_err(|_| RateLimitError::LockPoisoned)?;
+
+        if now.duration_since(state.last_swept) > self.config.window {
+            let window = self.config.window;
+            state.attempts.retain(|_, entry| {
+                while let Some(&oldest) = entry.front() {
+                    if now.duration_since(oldest) > window {
+                        entry.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+                !entry.is_empty()
+            });
+            state.last_swept = now;
+        }
+
+        let entry = state.attempts.entry(key.to_string()).or_default();
+        while let Some(&oldest) = entry.front() {
+            if now.duration_since(oldest) > self.config.window {
+                entry.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let allowed = entry.len() < self.config.max_attempts as usize;
+        entry.push_back(now);
+        Ok(allowed)
+    }
+
+    /// How many distinct keys this limiter is currently tracking, for
+    /// tests asserting that [`Self::check`]'s sweep actually evicts keys
+    /// rather than retaining them forever.
+    #[cfg(test)]
+    fn tracked_key_count(&self) -> usize {
+        self.state.lock().unwrap().attempts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_attempts_under_the_threshold() {
+        let limiter = SlidingWindowRateLimiter::new(RateLimiterConfig {
+            max_attempts: 3,
+            window: Duration::from_secs(60),
+        });
+        assert!(limiter.check("a").unwrap());
+        assert!(limiter.check("a").unwrap());
+        assert!(limiter.check("a").unwrap());
+    }
+
+    #[test]
+    fn rejects_attempts_once_the_threshold_is_exceeded() {
+        let limiter = SlidingWindowRateLimiter::new(RateLimiterConfig {
+            max_attempts: 2,
+            window: Duration::from_secs(60),
+        });
+        assert!(limiter.check("a").unwrap());
+        assert!(limiter.check("a").unwrap());
+        assert!(!limiter.check("a").unwrap());
+    }
+
+    #[test]
+    fn keys_are_tracked_independently() {
+        let limiter = SlidingWindowRateLimiter::new(RateLimiterConfig {
+            max_attempts: 1,
+            window: Duration::from_secs(60),
+        });
+        assert!(limiter.check("a").unwrap());
+        assert!(limiter.check("b").unwrap());
+        assert!(!limiter.check("a").unwrap());
+    }
+
+    #[test]
+    fn attempts_outside_the_window_are_forgotten() {
+        let limiter = SlidingWindowRateLimiter::new(RateLimiterConfig {
+            max_attempts: 1,
+            window: Duration::from_millis(20),
+        });
+        assert!(limiter.check("a").unwrap());
+        assert!(!limiter.check("a").unwrap());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(limiter.check("a").unwrap());
+    }
+
+    #[test]
+    fn stale_keys_are_evicted_instead_of_growing_the_map_forever() {
+        let limiter = SlidingWindowRateLimiter::new(RateLimiterConfig {
+            max_attempts: 1,
+            window: Duration::from_millis(20),
+        });
+        limiter.check("a").unwrap();
+        limiter.check("b").unwrap();
+        limiter.check("c").unwrap();
+        assert_eq!(limiter.tracked_key_count(), 3);
+
+        // Past the window, and past the sweep interval (also the
+        // window), so the next check sweeps every stale key away before
+        // adding its own.
+        std::thread::sleep(Duration::from_millis(30));
+        limiter.check("d").unwrap();
+        assert_eq!(limiter.tracked_key_count(), 1);
+    }
+}
@@ -0,0 +1,86 @@
+//! Shared repository error type used by every backend implementation.
+
+use std::error::Error as StdError;
+
+use thiserror::Error;
+
+/// Errors a repository can return. Backend-specific failures should be
+/// mapped into one of these variants rather than leaking driver types, so
+/// that callers can branch on [`Error::is_retryable`] instead of matching
+/// backend-specific error types.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("entity not found")]
+    NotFound,
+    #[error("duplicate entity: {0}")]
+    Duplicate(String),
+    #[error("duplicate username: {0}")]
+    DuplicateUsername(String),
+    #[error("constraint violation: {0}")]
+    ConstraintViolation(String),
+    #[error("backend connection error: {source}")]
+    ConnectionError {
+        #[source]
+        source: Box<dyn StdError + Send + Sync>,
+    },
+    #[error("backend operation timed out")]
+    Timeout,
+    #[error("backend error: {0}")]
+    Backend(String),
+    #[error("rate limit exceeded for {0}")]
+    RateLimited(String),
+    #[error("account {0} is not active")]
+    AccountNotActive(String),
+    #[error("account {0} is under legal hold")]
+    LegalHold(String),
+    #[error("account {0} is a service account and has no password to authenticate with")]
+    PasswordLoginDisabled(String),
+}
+
+impl Error {
+    /// Wraps `source` as a [`Error::ConnectionError`].
+    pub fn connection(source: impl StdError + Send + Sync + 'static) -> Self {
+        Error::ConnectionError {
+            source: Box::new(source),
+        }
+    }
+
+    /// Whether the operation that produced this error is worth retrying,
+    /// e.g. after a backoff. Not-found and constraint failures are not:
+    /// retrying them would just fail again in the same way.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::ConnectionError { .. } | Error::Timeout)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct DriverError;
+
+    impl fmt::Display for DriverError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("connection refused")
+        }
+    }
+
+    impl StdError for DriverError {}
+
+    #[test]
+    fn connection_and_timeout_errors_are_retryable() {
+        assert!(Error::connection(DriverError).is_retryable());
+        assert!(Error::Timeout.is_retryable());
+    }
+
+    #[test]
+    fn not_found_and_constraint_errors_are_not_retryable() {
+        assert!(!Error::NotFound.is_retryable());
+        assert!(!Error::Duplicate("a@example.com".to_string()).is_retryable());
+        assert!(!Error::ConstraintViolation("fk violation".to_string()).is_retryable());
+    }
+}
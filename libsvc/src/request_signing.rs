@@ -0,0 +1,175 @@
+//! HMAC request signing for service-to-service calls that skip bearer
+//! tokens entirely: each request carries its own signature over the
+//! method, path, a hash of the body, and a timestamp, checked against a
+//! shared key looked up by client id.
+//!
+//! Ed25519 would let a verifier hold a key that can only check signatures,
+//! never forge them, but every signing primitive already in this crate is
+//! symmetric HMAC-SHA256 (see [`foundation::key::Key`]), so this reuses
+//! that rather than introducing a second, asymmetric scheme for one
+//! caller. See `users::http::request_signature` for the verification
+//! middleware built on top of [`verify`].
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use foundation::key::Key;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// A signed request's transport-level fields, exchanged as HTTP headers
+/// by convention (`x-service-id`, `x-signature`, `x-signature-timestamp`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSignature {
+    pub client_id: String,
+    /// Base64-encoded HMAC-SHA256 signature.
+    pub signature: String,
+    /// Unix timestamp (seconds) the signature was produced at.
+    pub timestamp: u64,
+}
+
+/// Looks up the shared signing key for a trusted caller by client id.
+pub trait RequestSigningKeyStore: Send + Sync {
+    fn key_for(&self, client_id: &str) -> Option<Key>;
+}
+
+/// A [`RequestSigningKeyStore`] backed by a fixed set of keys, configured
+/// up front rather than looked up from a repository.
+#[derive(Default)]
+pub struct InMemoryRequestSigningKeyStore {
+    keys: HashMap<String, Key>,
+}
+
+impl InMemoryRequestSigningKeyStore {
+    pub fn new(keys: impl IntoIterator<Item = (String, Key)>) -> Self {
+        Self { keys: keys.into_iter().collect() }
+    }
+}
+
+impl RequestSigningKeyStore for InMemoryRequestSigningKeyStore {
+    fn key_for(&self, client_id: &str) -> Option<Key> {
+        self.keys.get(client_id).cloned()
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RequestSignatureError {
+    #[error("no signing key registered for client {0}")]
+    UnknownClient(String),
+    #[error("timestamp is outside the allowed clock skew")]
+    Stale,
+    #[error("signature does not match the request")]
+    Mismatch,
+}
+
+fn canonical_message(method: &str, path: &str, body: &[u8], timestamp: u64) -> Vec<u8> {
+    let body_hash = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(body));
+    format!("{method}\n{path}\n{body_hash}\n{timestamp}").into_bytes()
+}
+
+/// Signs a request on the calling side — the small client helper internal
+/// callers use to attach [`RequestSignature`] headers to an outbound call.
+pub fn sign(
+    client_id: impl Into<String>,
+    key: &Key,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    timestamp: u64,
+) -> RequestSignature {
+    let message = canonical_message(method, path, body, timestamp);
+    RequestSignature {
+        client_id: client_id.into(),
+        signature: base64::engine::general_purpose::STANDARD.encode(key.sign(&message)),
+        timestamp,
+    }
+}
+
+/// Verifies that `signature` was produced by `signature.client_id`'s
+/// registered key over `method`, `path`, and `body`, and that its
+/// timestamp is within `max_skew_secs` of `now` — rejecting both forged
+/// and replayed-after-the-fact requests.
+pub fn verify(
+    keys: &dyn RequestSigningKeyStore,
+    signature: &RequestSignature,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    now: u64,
+    max_skew_secs: u64,
+) -> Result<(), RequestSignatureError> {
+    if now.abs_diff(signature.timestamp) > max_skew_secs {
+        return Err(RequestSignatureError::Stale);
+    }
+    let key = keys
+        .key_for(&signature.client_id)
+        .ok_or_else(|| RequestSignatureError::UnknownClient(signature.client_id.clone()))?;
+    let expected = base64::engine::general_purpose::STANDARD
+        .decode(&signature.signature)
+        .map_err(|_| RequestSignatureError::Mismatch)?;
+    let message = canonical_message(method, path, body, signature.timestamp);
+    if key.verify(&message, &expected) {
+        Ok(())
+    } else {
+        Err(RequestSignatureError::Mismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_signature_verifies_against_the_same_request_it_was_made_for() {
+        let key = Key::generate();
+        let keys = InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())]);
+        let signature = sign("svc-a", &key, "POST", "/v1/users/register", b"{}", 1_000);
+        assert_eq!(
+            verify(&keys, &signature, "POST", "/v1/users/register", b"{}", 1_000, 60),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn a_tampered_body_fails_verification() {
+        let key = Key::generate();
+        let keys = InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())]);
+        let signature = sign("svc-a", &key, "POST", "/v1/users/register", b"{}", 1_000);
+        assert_eq!(
+            verify(&keys, &signature, "POST", "/v1/users/register", b"{\"x\":1}", 1_000, 60),
+            Err(RequestSignatureError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn a_tampered_path_fails_verification() {
+        let key = Key::generate();
+        let keys = InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())]);
+        let signature = sign("svc-a", &key, "POST", "/v1/users/register", b"{}", 1_000);
+        assert_eq!(
+            verify(&keys, &signature, "POST", "/v1/admin/users/search", b"{}", 1_000, 60),
+            Err(RequestSignatureError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn an_unregistered_client_is_rejected() {
+        let keys = InMemoryRequestSigningKeyStore::new([]);
+        let signature = sign("svc-unknown", &Key::generate(), "GET", "/v1/users/me", b"", 1_000);
+        assert_eq!(
+            verify(&keys, &signature, "GET", "/v1/users/me", b"", 1_000, 60),
+            Err(RequestSignatureError::UnknownClient("svc-unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn a_timestamp_outside_the_allowed_skew_is_rejected() {
+        let key = Key::generate();
+        let keys = InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())]);
+        let signature = sign("svc-a", &key, "GET", "/v1/users/me", b"", 1_000);
+        assert_eq!(
+            verify(&keys, &signature, "GET", "/v1/users/me", b"", 1_100, 60),
+            Err(RequestSignatureError::Stale)
+        );
+    }
+}
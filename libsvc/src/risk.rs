@@ -0,0 +1,286 @@
+//! A small rules engine evaluated at login: [`RiskPolicy`] matches a login
+//! attempt's [`RiskContext`] against configurable [`RiskRule`]s and returns
+//! the first one whose conditions all hold, so a deployment can require
+//! MFA, deny, or allow a login based on where and when it's coming from.
+//! Rules are data, not code — `users::risk_policy` loads a [`RiskPolicy`]
+//! from a YAML file, the same way `users::seed` loads fixtures from JSON.
+//! See `users::http::handlers::authenticate` for where a policy is
+//! consulted and its decision logged to [`crate::security_signal`].
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Timelike, Utc};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+
+/// What a login attempt looks like to [`RiskPolicy::evaluate`].
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    pub ip: IpAddr,
+    /// Resolved via [`crate::geoip::GeoIpLookup`]. `None` if it can't be
+    /// determined (e.g. a [`crate::geoip::NoopGeoIpLookup`]), in which
+    /// case [`RiskCondition::Country`] never matches.
+    pub country: Option<String>,
+    /// Whether the caller's device (see [`KnownDeviceTracker`]) has never
+    /// been seen for this account before. Always `false` when the caller
+    /// didn't present a device id at all, since there's nothing to call
+    /// novel.
+    pub new_device: bool,
+    pub at: DateTime<Utc>,
+}
+
+/// A single condition [`RiskRule::conditions`] checks. A rule matches a
+/// [`RiskContext`] when every one of its conditions does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RiskCondition {
+    /// Matches when the login's IP falls in one of `ranges`.
+    IpRange { ranges: Vec<IpNet> },
+    /// Matches when the login's resolved country is one of `countries`
+    /// (ISO 3166-1 alpha-2 codes).
+    Country { countries: HashSet<String> },
+    /// Matches when the login comes from a device not previously seen
+    /// for this account.
+    NewDevice,
+    /// Matches when the login falls in `start_hour..end_hour` UTC
+    /// (0-23), wrapping past midnight when `start_hour > end_hour` (e.g.
+    /// `22..6` covers 10pm through 6am).
+    TimeOfDay { start_hour: u32, end_hour: u32 },
+}
+
+impl RiskCondition {
+    fn matches(&self, context: &RiskContext) -> bool {
+        match self {
+            RiskCondition::IpRange { ranges } => ranges.iter().any(|net| net.contains(&context.ip)),
+            RiskCondition::Country { countries } => {
+                context.country.as_ref().is_some_and(|country| countries.contains(country))
+            }
+            RiskCondition::NewDevice => context.new_device,
+            RiskCondition::TimeOfDay { start_hour, end_hour } => {
+                let hour = context.at.hour();
+                if start_hour <= end_hour {
+                    (*start_hour..*end_hour).contains(&hour)
+                } else {
+                    hour >= *start_hour || hour < *end_hour
+                }
+            }
+        }
+    }
+}
+
+/// What a matching [`RiskRule::action`] decides for a login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskAction {
+    Allow,
+    Deny,
+    RequireMfa,
+}
+
+impl std::fmt::Display for RiskAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RiskAction::Allow => "allow",
+            RiskAction::Deny => "deny",
+            RiskAction::RequireMfa => "require_mfa",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One entry in a [`RiskPolicy`]. `name` identifies the rule in the
+/// [`crate::security_signal::SecurityEvent::RiskRuleMatched`] event
+/// [`RiskPolicy::evaluate`]'s caller logs, so an operator can trace a
+/// decision back to the config that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskRule {
+    pub name: String,
+    pub conditions: Vec<RiskCondition>,
+    pub action: RiskAction,
+}
+
+/// A configurable, ordered set of [`RiskRule`]s. [`RiskPolicy::evaluate`]
+/// returns the first whose conditions all hold; a login matching none of
+/// them is implicitly allowed, so a deployment only has to write rules
+/// for the exceptions it cares about.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskPolicy {
+    pub rules: Vec<RiskRule>,
+}
+
+impl RiskPolicy {
+    /// The first rule every one of whose conditions matches `context`, or
+    /// `None` if none do.
+    pub fn evaluate(&self, context: &RiskContext) -> Option<&RiskRule> {
+        self.rules.iter().find(|rule| rule.conditions.iter().all(|condition| condition.matches(context)))
+    }
+}
+
+/// Tracks which device ids have been seen for each account, so
+/// [`RiskCondition::NewDevice`] can tell a first-time login from a
+/// familiar one. A device is identified by whatever opaque id the caller
+/// presents (e.g. an `x-device-id` header) — there's no fingerprinting
+/// beyond trusting that value.
+#[derive(Default)]
+pub struct KnownDeviceTracker {
+    by_user_id: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl KnownDeviceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `device_id` has been recorded for `user_id` before.
+    pub fn is_known(&self, user_id: &str, device_id: &str) -> bool {
+        self.by_user_id
+            .lock()
+            .expect("known-device tracker lock poisoned")
+            .get(user_id)
+            .is_some_and(|devices| devices.contains(device_id))
+    }
+
+    /// Remembers `device_id` as seen for `user_id`.
+    pub fn record(&self, user_id: &str, device_id: &str) {
+        self.by_user_id
+            .lock()
+            .expect("known-device tracker lock poisoned")
+            .entry(user_id.to_string())
+            .or_default()
+            .insert(device_id.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    fn context(ip: IpAddr) -> RiskContext {
+        RiskContext {
+            ip,
+            country: None,
+            new_device: false,
+            at: DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z").unwrap().with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn a_policy_with_no_rules_matches_nothing() {
+        let policy = RiskPolicy::default();
+        assert!(policy.evaluate(&context(ip("203.0.113.1"))).is_none());
+    }
+
+    #[test]
+    fn an_ip_range_condition_matches_an_address_inside_it() {
+        let policy = RiskPolicy {
+            rules: vec![RiskRule {
+                name: "blocked-range".to_string(),
+                conditions: vec![RiskCondition::IpRange { ranges: vec!["203.0.113.0/24".parse().unwrap()] }],
+                action: RiskAction::Deny,
+            }],
+        };
+        let matched = policy.evaluate(&context(ip("203.0.113.5"))).unwrap();
+        assert_eq!(matched.name, "blocked-range");
+        assert!(policy.evaluate(&context(ip("198.51.100.1"))).is_none());
+    }
+
+    #[test]
+    fn a_country_condition_matches_the_resolved_country() {
+        let policy = RiskPolicy {
+            rules: vec![RiskRule {
+                name: "blocked-country".to_string(),
+                conditions: vec![RiskCondition::Country { countries: ["FR".to_string()].into_iter().collect() }],
+                action: RiskAction::RequireMfa,
+            }],
+        };
+        let mut with_country = context(ip("203.0.113.1"));
+        with_country.country = Some("FR".to_string());
+        assert!(policy.evaluate(&with_country).is_some());
+        assert!(policy.evaluate(&context(ip("203.0.113.1"))).is_none());
+    }
+
+    #[test]
+    fn a_new_device_condition_matches_only_a_novel_device() {
+        let policy = RiskPolicy {
+            rules: vec![RiskRule {
+                name: "new-device".to_string(),
+                conditions: vec![RiskCondition::NewDevice],
+                action: RiskAction::RequireMfa,
+            }],
+        };
+        let mut novel = context(ip("203.0.113.1"));
+        novel.new_device = true;
+        assert!(policy.evaluate(&novel).is_some());
+        assert!(policy.evaluate(&context(ip("203.0.113.1"))).is_none());
+    }
+
+    #[test]
+    fn a_time_of_day_condition_matches_within_and_wrapping_past_midnight() {
+        let daytime = RiskRule {
+            name: "business-hours".to_string(),
+            conditions: vec![RiskCondition::TimeOfDay { start_hour: 9, end_hour: 17 }],
+            action: RiskAction::Allow,
+        };
+        let overnight = RiskRule {
+            name: "overnight".to_string(),
+            conditions: vec![RiskCondition::TimeOfDay { start_hour: 22, end_hour: 6 }],
+            action: RiskAction::RequireMfa,
+        };
+
+        let mut at = context(ip("203.0.113.1"));
+        at.at = DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert!(daytime.conditions[0].matches(&at));
+        assert!(!overnight.conditions[0].matches(&at));
+
+        at.at = DateTime::parse_from_rfc3339("2026-01-01T23:00:00Z").unwrap().with_timezone(&Utc);
+        assert!(!daytime.conditions[0].matches(&at));
+        assert!(overnight.conditions[0].matches(&at));
+    }
+
+    #[test]
+    fn only_a_rule_whose_every_condition_matches_is_returned() {
+        let policy = RiskPolicy {
+            rules: vec![RiskRule {
+                name: "foreign-new-device".to_string(),
+                conditions: vec![
+                    RiskCondition::Country { countries: ["FR".to_string()].into_iter().collect() },
+                    RiskCondition::NewDevice,
+                ],
+                action: RiskAction::Deny,
+            }],
+        };
+        let mut partial = context(ip("203.0.113.1"));
+        partial.country = Some("FR".to_string());
+        assert!(policy.evaluate(&partial).is_none());
+
+        partial.new_device = true;
+        assert!(policy.evaluate(&partial).is_some());
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let policy = RiskPolicy {
+            rules: vec![
+                RiskRule { name: "first".to_string(), conditions: vec![], action: RiskAction::Deny },
+                RiskRule { name: "second".to_string(), conditions: vec![], action: RiskAction::Allow },
+            ],
+        };
+        assert_eq!(policy.evaluate(&context(ip("203.0.113.1"))).unwrap().name, "first");
+    }
+
+    #[test]
+    fn a_device_is_unknown_until_recorded() {
+        let tracker = KnownDeviceTracker::new();
+        assert!(!tracker.is_known("user-1", "device-a"));
+        tracker.record("user-1", "device-a");
+        assert!(tracker.is_known("user-1", "device-a"));
+        assert!(!tracker.is_known("user-1", "device-b"));
+        assert!(!tracker.is_known("user-2", "device-a"));
+    }
+}
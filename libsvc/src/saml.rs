@@ -0,0 +1,60 @@
+//! Minimal service-provider (SP) support for SAML 2.0 SSO.
+//!
+//! This module owns the protocol-agnostic parts of the SP role: the
+//! assertion shape a service actually needs ([`SamlAssertion`]) and the
+//! extension point for checking that an IdP really signed the response it
+//! claims to ([`SamlAssertionVerifier`]). Verifying an XML signature
+//! correctly means reproducing the IdP's exact canonicalization and
+//! checking it against their certificate; getting that subtly wrong is a
+//! signature bypass waiting to happen, and this crate has no XML-dsig or
+//! X.509 machinery to do it safely. Deployments that need real enterprise
+//! SSO should back [`SamlAssertionVerifier`] with a dedicated SAML
+//! library rather than a hand-rolled one — the trait exists so the rest
+//! of the stack (the ACS endpoint, the mapping onto a local user, issuing
+//! a session) doesn't need to know which one is plugged in.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SamlError {
+    #[error("the assertion's signature did not validate")]
+    SignatureInvalid,
+    #[error("the assertion is not valid at the current time")]
+    Expired,
+    #[error("the SAML response could not be parsed: {0}")]
+    Malformed(String),
+}
+
+/// The subset of an IdP's `<Assertion>` a relying party needs: who the
+/// user is, and whatever attributes the IdP chose to release about them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlAssertion {
+    pub subject: String,
+    pub attributes: HashMap<String, String>,
+}
+
+impl SamlAssertion {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+}
+
+/// Checks that a raw `SAMLResponse` (already base64-decoded into XML) was
+/// issued by a trusted IdP, and extracts the assertion it carries.
+pub trait SamlAssertionVerifier: Send + Sync {
+    fn verify(&self, raw_response_xml: &str) -> Result<SamlAssertion, SamlError>;
+}
+
+/// An SP's configuration for a single trusted IdP: how it's known to
+/// that IdP, and how to verify what it sends back.
+pub struct SamlSpConfig {
+    /// This SP's `entityID`, as published in its metadata.
+    pub entity_id: String,
+    /// The externally-reachable URL of the assertion consumer service
+    /// endpoint, published in metadata so the IdP knows where to POST.
+    pub acs_url: String,
+    pub verifier: Arc<dyn SamlAssertionVerifier>,
+}
@@ -0,0 +1,183 @@
+//! Pluggable full-text search over domain records, for fuzzy lookups a
+//! plain field-equality filter can't do — for example, an admin searching
+//! users by a partial or misspelled email or display name.
+//!
+//! This crate has no embedded search engine of its own: good fuzzy
+//! matching (tokenization, stemming, relevance ranking) is an entire
+//! project, not something to hand-roll behind a trait. [`SearchIndex`]
+//! is the extension point a deployment backs with a real engine — most
+//! commonly an embedded Tantivy index or a Meilisearch client — so the
+//! call sites that keep it in sync and query it don't need to know
+//! which one is plugged in. [`NoopSearchIndex`] is the default until one
+//! is configured; [`InMemorySearchIndex`] is a naive substring-matching
+//! stand-in for tests and small deployments, not a replacement for a
+//! real engine's ranking.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SearchIndexError {
+    #[error("the search index could not be reached: {0}")]
+    Unavailable(String),
+}
+
+/// A record submitted to a [`SearchIndex`]: an opaque `id` (the caller's
+/// own identifier, e.g. a user id) plus whatever text fields should be
+/// searchable against it.
+#[derive(Debug, Clone, Default)]
+pub struct SearchDocument {
+    pub id: String,
+    pub fields: HashMap<String, String>,
+}
+
+impl SearchDocument {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// Keeps a search engine's copy of a record set in sync with the
+/// source of truth, and answers fuzzy text queries against it.
+#[async_trait]
+pub trait SearchIndex: Send + Sync {
+    /// Indexes (or re-indexes) `document`, replacing any existing entry
+    /// for the same id.
+    async fn index(&self, document: SearchDocument) -> Result<(), SearchIndexError>;
+
+    /// Removes `id` from the index, if present.
+    async fn remove(&self, id: &str) -> Result<(), SearchIndexError>;
+
+    /// Returns up to `limit` ids ranked by relevance to `query`.
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<String>, SearchIndexError>;
+}
+
+/// A [`SearchIndex`] that indexes nothing and always reports no matches,
+/// for deployments that haven't configured a real engine.
+#[derive(Debug, Default)]
+pub struct NoopSearchIndex;
+
+#[async_trait]
+impl SearchIndex for NoopSearchIndex {
+    async fn index(&self, _document: SearchDocument) -> Result<(), SearchIndexError> {
+        Ok(())
+    }
+
+    async fn remove(&self, _id: &str) -> Result<(), SearchIndexError> {
+        Ok(())
+    }
+
+    async fn search(&self, _query: &str, _limit: usize) -> Result<Vec<String>, SearchIndexError> {
+        Ok(Vec::new())
+    }
+}
+
+/// A [`SearchIndex`] backed by naive, case-insensitive substring matching
+/// over indexed field values, held entirely in memory.
+#[derive(Default)]
+pub struct InMemorySearchIndex {
+    documents: Mutex<HashMap<String, SearchDocument>>,
+}
+
+impl InMemorySearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, SearchDocument>>, SearchIndexError> {
+        self.documents
+            .lock()
+            .map_err(|_| SearchIndexError::Unavailable("index lock poisoned".to_string()))
+    }
+}
+
+#[async_trait]
+impl SearchIndex for InMemorySearchIndex {
+    async fn index(&self, document: SearchDocument) -> Result<(), SearchIndexError> {
+        self.lock()?.insert(document.id.clone(), document);
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), SearchIndexError> {
+        self.lock()?.remove(id);
+        Ok(())
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<String>, SearchIndexError> {
+        let needle = query.to_lowercase();
+        let documents = self.lock()?;
+        let mut matches: Vec<&str> = documents
+            .values()
+            .filter(|doc| doc.fields.values().any(|v| v.to_lowercase().contains(&needle)))
+            .map(|doc| doc.id.as_str())
+            .collect();
+        matches.sort_unstable();
+        Ok(matches.into_iter().take(limit).map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn noop_index_never_matches() {
+        let index = NoopSearchIndex;
+        index
+            .index(SearchDocument::new("1").with_field("email", "a@example.com"))
+            .await
+            .unwrap();
+        assert!(index.search("a@example.com", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn in_memory_index_matches_on_substring_case_insensitively() {
+        let index = InMemorySearchIndex::new();
+        index
+            .index(SearchDocument::new("1").with_field("email", "Alice@Example.com"))
+            .await
+            .unwrap();
+        index
+            .index(SearchDocument::new("2").with_field("email", "bob@example.com"))
+            .await
+            .unwrap();
+
+        let results = index.search("alice", 10).await.unwrap();
+        assert_eq!(results, vec!["1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn removed_documents_stop_matching() {
+        let index = InMemorySearchIndex::new();
+        index
+            .index(SearchDocument::new("1").with_field("email", "a@example.com"))
+            .await
+            .unwrap();
+        index.remove("1").await.unwrap();
+
+        assert!(index.search("a@example.com", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_results_are_capped_at_the_requested_limit() {
+        let index = InMemorySearchIndex::new();
+        for i in 0..5 {
+            index
+                .index(SearchDocument::new(i.to_string()).with_field("email", "shared@example.com"))
+                .await
+                .unwrap();
+        }
+        assert_eq!(index.search("shared", 2).await.unwrap().len(), 2);
+    }
+}
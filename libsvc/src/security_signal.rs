@@ -0,0 +1,284 @@
+//! Hooks for surfacing suspicious-activity signals to a risk engine.
+//!
+//! A user's login attempt and the verification of their session on every
+//! later request are the two choke points every sign-in and every
+//! authenticated request passes through, so that's where
+//! [`SecuritySignal::observe`] is called from. What counts as suspicious
+//! — a login from a new country, impossible travel between two logins, a
+//! burst of invalid signatures — and how to react to it is a risk
+//! engine's job, not this crate's; [`SecuritySignal`] only defines the
+//! interface one plugs in through. The default [`LoggingSecuritySignal`]
+//! just logs, for deployments that haven't wired one up. See
+//! [`RetainingSecuritySignal`] for a decorator that also keeps a queryable
+//! history, for a security event log distinct from
+//! [`crate::audit::AuditLog`]'s record of ordinary CRUD actions.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A suspicious-activity signal worth surfacing to a risk engine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind")]
+pub enum SecurityEvent {
+    /// `identifier` (the email or username presented) failed to
+    /// authenticate with an unknown login or a wrong password.
+    InvalidCredentials { identifier: String },
+    /// A session that exists in the store failed signature verification —
+    /// forged, tampered with, or signed by a key we no longer trust.
+    InvalidSessionSignature { session_id: String },
+    /// `identifier` crossed [`crate::captcha`]'s configured failed-login
+    /// threshold and now must solve a CAPTCHA before authenticating again
+    /// — the closest thing this service has to an account lockout.
+    LoginLockout { identifier: String },
+    /// `admin_id` started a session impersonating `target_user_id` (see
+    /// `impersonated_by` on `libsvc::session::Session`).
+    Impersonation { admin_id: String, target_user_id: String },
+    /// The session-signing key was rotated, invalidating every session
+    /// signed by a key that's no longer trusted.
+    SessionKeyRotated,
+    /// `admin_id` changed `target_user_id`'s account status to `status`
+    /// (see `users::domain::UserStatus`), such as deactivating or banning
+    /// an account.
+    AccountStatusChanged {
+        admin_id: String,
+        target_user_id: String,
+        status: String,
+    },
+    /// A session bound to a client certificate (see
+    /// `libsvc::session::Session::cert_thumbprint`) was presented over a
+    /// connection with a different certificate, or none at all — the
+    /// token may have been stolen and replayed from elsewhere.
+    CertBindingMismatch { session_id: String },
+    /// A DPoP-bound session (see `libsvc::session::Session::dpop_thumbprint`)
+    /// was presented without a valid proof of possession — missing,
+    /// stale, replayed, or signed by the wrong key.
+    DPoPProofInvalid { session_id: String },
+    /// `crate::risk::RiskRule` named `rule` matched `identifier`'s login
+    /// attempt and decided `action`.
+    RiskRuleMatched { identifier: String, rule: String, action: String },
+}
+
+/// Observes [`SecurityEvent`]s as they occur.
+pub trait SecuritySignal: Send + Sync {
+    fn observe(&self, event: SecurityEvent);
+}
+
+/// A [`SecuritySignal`] that logs events instead of forwarding them to a
+/// risk engine, for local development and deployments that haven't wired
+/// one up.
+#[derive(Default)]
+pub struct LoggingSecuritySignal;
+
+impl LoggingSecuritySignal {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SecuritySignal for LoggingSecuritySignal {
+    fn observe(&self, event: SecurityEvent) {
+        match event {
+            SecurityEvent::InvalidCredentials { identifier } => {
+                tracing::warn!(%identifier, "invalid credentials presented");
+            }
+            SecurityEvent::InvalidSessionSignature { session_id } => {
+                tracing::warn!(%session_id, "session failed signature verification");
+            }
+            SecurityEvent::LoginLockout { identifier } => {
+                tracing::warn!(%identifier, "login locked out pending a solved captcha");
+            }
+            SecurityEvent::Impersonation { admin_id, target_user_id } => {
+                tracing::warn!(%admin_id, %target_user_id, "admin started an impersonation session");
+            }
+            SecurityEvent::SessionKeyRotated => {
+                tracing::warn!("session-signing key rotated");
+            }
+            SecurityEvent::AccountStatusChanged { admin_id, target_user_id, status } => {
+                tracing::warn!(%admin_id, %target_user_id, %status, "admin changed account status");
+            }
+            SecurityEvent::CertBindingMismatch { session_id } => {
+                tracing::warn!(%session_id, "session presented over a connection with a different client certificate");
+            }
+            SecurityEvent::DPoPProofInvalid { session_id } => {
+                tracing::warn!(%session_id, "DPoP-bound session presented without a valid proof of possession");
+            }
+            SecurityEvent::RiskRuleMatched { identifier, rule, action } => {
+                tracing::warn!(%identifier, %rule, %action, "risk rule matched at login");
+            }
+        }
+    }
+}
+
+/// A [`SecurityEvent`] plus when [`RetainingSecuritySignal`] observed it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedSecurityEvent {
+    pub event: SecurityEvent,
+    pub at: DateTime<Utc>,
+}
+
+/// Default for [`RetainingSecuritySignal::new`] when no other capacity is
+/// configured.
+pub const DEFAULT_SECURITY_EVENT_LOG_CAPACITY: usize = 1000;
+
+/// Wraps an inner [`SecuritySignal`] and retains the most recently
+/// observed events, oldest dropped first once `capacity` is reached, so
+/// an operator investigating an incident can query recent security
+/// activity even when the inner signal only forwards to a risk engine and
+/// doesn't retain anything itself (see [`LoggingSecuritySignal`]). This is
+/// its own fixed-size retention policy, independent of
+/// [`crate::audit::AuditLog`]'s unbounded record of ordinary CRUD
+/// actions — a responder shouldn't have to wade through profile updates,
+/// or worry about the log ever filling disk, to find what they need.
+pub struct RetainingSecuritySignal {
+    inner: Arc<dyn SecuritySignal>,
+    capacity: usize,
+    events: Mutex<VecDeque<RecordedSecurityEvent>>,
+}
+
+impl RetainingSecuritySignal {
+    pub fn new(inner: Arc<dyn SecuritySignal>, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// The most recently observed events, newest first, capped at
+    /// `limit`.
+    pub fn recent(&self, limit: usize) -> Vec<RecordedSecurityEvent> {
+        let events = self.events.lock().expect("security event log lock poisoned");
+        events.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Discards every retained event observed at or before `cutoff`,
+    /// returning how many were removed. Independent of the
+    /// capacity-based eviction [`SecuritySignal::observe`] already does —
+    /// this enforces a retention *age* (see `crate::retention`) rather
+    /// than a retention *count*.
+    ///
+    /// Unlike [`crate::audit::AuditLog::purge_older_than`], this has no
+    /// way to exempt a held account: [`SecurityEvent`] variants identify
+    /// their subject by whatever the caller presented (an `identifier`
+    /// that may be an email, not a user id), not a canonical user id, so
+    /// there's nothing reliable to match a legal hold against. A held
+    /// account's security events are purged on the same schedule as
+    /// everyone else's.
+    pub fn purge_older_than(&self, cutoff: DateTime<Utc>) -> usize {
+        let mut events = self.events.lock().expect("security event log lock poisoned");
+        let before = events.len();
+        events.retain(|recorded| recorded.at > cutoff);
+        before - events.len()
+    }
+}
+
+impl SecuritySignal for RetainingSecuritySignal {
+    fn observe(&self, event: SecurityEvent) {
+        self.inner.observe(event.clone());
+        let mut events = self.events.lock().expect("security event log lock poisoned");
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(RecordedSecurityEvent { event, at: Utc::now() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSecuritySignal {
+        events: Mutex<Vec<SecurityEvent>>,
+    }
+
+    impl SecuritySignal for RecordingSecuritySignal {
+        fn observe(&self, event: SecurityEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn a_custom_implementation_receives_observed_events() {
+        let signal = RecordingSecuritySignal::default();
+        signal.observe(SecurityEvent::InvalidCredentials {
+            identifier: "user@example.com".to_string(),
+        });
+        assert_eq!(signal.events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn logging_implementation_does_not_panic_on_either_event() {
+        let signal = LoggingSecuritySignal::new();
+        signal.observe(SecurityEvent::InvalidCredentials {
+            identifier: "user@example.com".to_string(),
+        });
+        signal.observe(SecurityEvent::InvalidSessionSignature {
+            session_id: "sess-1".to_string(),
+        });
+    }
+
+    #[test]
+    fn retaining_signal_forwards_to_its_inner_signal() {
+        let inner = Arc::new(RecordingSecuritySignal::default());
+        let signal = RetainingSecuritySignal::new(inner.clone(), 10);
+        signal.observe(SecurityEvent::InvalidCredentials {
+            identifier: "user@example.com".to_string(),
+        });
+        assert_eq!(inner.events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn retaining_signal_returns_recent_events_newest_first() {
+        let signal = RetainingSecuritySignal::new(Arc::new(LoggingSecuritySignal::new()), 10);
+        signal.observe(SecurityEvent::InvalidCredentials {
+            identifier: "first@example.com".to_string(),
+        });
+        signal.observe(SecurityEvent::InvalidCredentials {
+            identifier: "second@example.com".to_string(),
+        });
+
+        let recent = signal.recent(10);
+        assert_eq!(
+            recent[0].event,
+            SecurityEvent::InvalidCredentials { identifier: "second@example.com".to_string() }
+        );
+        assert_eq!(
+            recent[1].event,
+            SecurityEvent::InvalidCredentials { identifier: "first@example.com".to_string() }
+        );
+    }
+
+    #[test]
+    fn retaining_signal_drops_the_oldest_event_past_capacity() {
+        let signal = RetainingSecuritySignal::new(Arc::new(LoggingSecuritySignal::new()), 2);
+        signal.observe(SecurityEvent::LoginLockout { identifier: "a".to_string() });
+        signal.observe(SecurityEvent::LoginLockout { identifier: "b".to_string() });
+        signal.observe(SecurityEvent::LoginLockout { identifier: "c".to_string() });
+
+        let recent = signal.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].event, SecurityEvent::LoginLockout { identifier: "c".to_string() });
+        assert_eq!(recent[1].event, SecurityEvent::LoginLockout { identifier: "b".to_string() });
+    }
+
+    #[test]
+    fn purge_older_than_drops_only_events_at_or_before_the_cutoff() {
+        let signal = RetainingSecuritySignal::new(Arc::new(LoggingSecuritySignal::new()), 10);
+        signal.observe(SecurityEvent::LoginLockout { identifier: "a".to_string() });
+        let cutoff = Utc::now();
+        signal.observe(SecurityEvent::LoginLockout { identifier: "b".to_string() });
+
+        let purged = signal.purge_older_than(cutoff);
+
+        assert_eq!(purged, 1);
+        let recent = signal.recent(10);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].event, SecurityEvent::LoginLockout { identifier: "b".to_string() });
+    }
+}
@@ -0,0 +1,94 @@
+//! Trusted service accounts for server-to-server token exchange.
+//!
+//! [`crate::session`] already lets one party act on behalf of another —
+//! an admin impersonating a user — by recording who's really behind the
+//! wheel in the session claims. Token exchange is the same idea from a
+//! different direction: a trusted service presents its own credential,
+//! rather than a human admin's, to obtain a session acting as a user, and
+//! [`crate::session::Session::exchanged_by`] records which service asked
+//! for it.
+
+use std::collections::HashMap;
+
+use foundation::hash::verify_password;
+
+use crate::session::Role;
+
+/// A service trusted to exchange its own API key for a session acting as
+/// some user, limited to `allowed_roles`.
+#[derive(Debug, Clone)]
+pub struct ServiceAccount {
+    pub client_id: String,
+    /// An Argon2id hash of the service's API key, produced the same way a
+    /// user's password is (see [`foundation::hash::hash_password`]).
+    pub key_hash: String,
+    /// The widest set of roles this service may request a session with.
+    pub allowed_roles: Vec<Role>,
+}
+
+/// Looks up and authenticates [`ServiceAccount`]s by client id.
+pub trait ServiceAccountRegistry: Send + Sync {
+    /// Returns the named service account if `client_id` is known and
+    /// `api_key` verifies against its stored hash.
+    fn authenticate(&self, client_id: &str, api_key: &str) -> Option<ServiceAccount>;
+}
+
+/// A [`ServiceAccountRegistry`] backed by a fixed set of accounts,
+/// configured up front rather than looked up from a repository.
+#[derive(Default)]
+pub struct InMemoryServiceAccountRegistry {
+    accounts: HashMap<String, ServiceAccount>,
+}
+
+impl InMemoryServiceAccountRegistry {
+    pub fn new(accounts: Vec<ServiceAccount>) -> Self {
+        Self {
+            accounts: accounts
+                .into_iter()
+                .map(|account| (account.client_id.clone(), account))
+                .collect(),
+        }
+    }
+}
+
+impl ServiceAccountRegistry for InMemoryServiceAccountRegistry {
+    fn authenticate(&self, client_id: &str, api_key: &str) -> Option<ServiceAccount> {
+        let account = self.accounts.get(client_id)?;
+        verify_password(api_key, &account.key_hash)
+            .unwrap_or(false)
+            .then(|| account.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use foundation::hash::hash_password;
+
+    use super::*;
+
+    fn registry() -> InMemoryServiceAccountRegistry {
+        InMemoryServiceAccountRegistry::new(vec![ServiceAccount {
+            client_id: "billing-service".to_string(),
+            key_hash: hash_password("s3cret-api-key").unwrap(),
+            allowed_roles: vec![Role::User],
+        }])
+    }
+
+    #[test]
+    fn authenticates_a_known_client_with_the_right_key() {
+        let account = registry()
+            .authenticate("billing-service", "s3cret-api-key")
+            .expect("should authenticate");
+        assert_eq!(account.client_id, "billing-service");
+    }
+
+    #[test]
+    fn rejects_an_unknown_client() {
+        assert!(registry().authenticate("no-such-service", "s3cret-api-key").is_none());
+    }
+
+    #[test]
+    fn rejects_a_known_client_with_the_wrong_key() {
+        assert!(registry().authenticate("billing-service", "wrong-key").is_none());
+    }
+}
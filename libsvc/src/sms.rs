@@ -0,0 +1,230 @@
+//! Outbound SMS delivery, abstracted the same way [`crate::mailer::Mailer`]
+//! and [`crate::pusher::Pusher`] are — so call sites (SMS-based OTP, as an
+//! alternative second factor to [`crate::mailer::Mailer`]-delivered magic
+//! links) don't depend on a particular carrier gateway.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::http_client::RetryingHttpClient;
+use crate::rate_limit::{RateLimiterConfig, SlidingWindowRateLimiter};
+
+/// An [`SmsSender::send`] call failed.
+#[derive(Debug, Error)]
+pub enum SmsError {
+    #[error("the destination number was rejected as invalid")]
+    InvalidNumber,
+    #[error("the sms provider could not be reached: {0}")]
+    ProviderUnavailable(String),
+    /// Raised by [`RateLimitedSmsSender`] rather than the underlying
+    /// provider — kept as a variant here (rather than its own error type)
+    /// so callers handle it the same way they'd handle any other delivery
+    /// failure.
+    #[error("sms sends to {0} are rate limited")]
+    RateLimited(String),
+}
+
+/// Sends a single SMS message.
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError>;
+}
+
+/// An [`SmsSender`] that logs messages instead of delivering them, for
+/// local development and tests.
+#[derive(Default)]
+pub struct LoggingSmsSender;
+
+impl LoggingSmsSender {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl SmsSender for LoggingSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        tracing::info!(%to, %body, "would send sms");
+        Ok(())
+    }
+}
+
+/// Credentials and sender number for Twilio's Messages API.
+#[derive(Debug, Clone)]
+pub struct TwilioConfig {
+    pub account_sid: String,
+    pub auth_token: String,
+    pub from_number: String,
+}
+
+/// An [`SmsSender`] backed by Twilio's Messages API. Uses a
+/// [`RetryingHttpClient`] rather than a bare `reqwest::Client`, the same
+/// way [`crate::captcha::HttpCaptchaVerifier`] and
+/// [`crate::pusher::FcmPusher`] do, so a provider blip doesn't fail every
+/// send behind it.
+pub struct TwilioSmsSender {
+    config: TwilioConfig,
+    client: RetryingHttpClient,
+}
+
+impl TwilioSmsSender {
+    pub fn new(config: TwilioConfig) -> Self {
+        Self { config, client: RetryingHttpClient::new() }
+    }
+}
+
+#[async_trait]
+impl SmsSender for TwilioSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+            self.config.account_sid
+        );
+        let response = self
+            .client
+            .send_with_retry(|| {
+                self.client
+                    .client()
+                    .post(&url)
+                    .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+                    .form(&[("To", to), ("From", self.config.from_number.as_str()), ("Body", body)])
+            })
+            .await
+            .map_err(|e| SmsError::ProviderUnavailable(e.to_string()))?;
+
+        // Twilio returns 400 for a malformed/invalid "To" number.
+        match response.status() {
+            status if status == reqwest::StatusCode::BAD_REQUEST => Err(SmsError::InvalidNumber),
+            status if status.is_success() => Ok(()),
+            status => Err(SmsError::ProviderUnavailable(format!("unexpected status {status}"))),
+        }
+    }
+}
+
+/// Where to reach AWS SNS's `Publish` action, and the bearer token to
+/// authenticate with. Hits a plain HTTP endpoint rather than signing
+/// requests with the full AWS SDK, mirroring how [`crate::pusher::FcmPusher`]
+/// speaks FCM's HTTP v1 API directly instead of pulling in a Google Cloud
+/// SDK.
+#[derive(Debug, Clone)]
+pub struct SnsConfig {
+    pub endpoint: String,
+    pub access_token: String,
+}
+
+/// An [`SmsSender`] backed by AWS SNS.
+pub struct SnsSmsSender {
+    config: SnsConfig,
+    client: RetryingHttpClient,
+}
+
+impl SnsSmsSender {
+    pub fn new(config: SnsConfig) -> Self {
+        Self { config, client: RetryingHttpClient::new() }
+    }
+}
+
+#[async_trait]
+impl SmsSender for SnsSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        let response = self
+            .client
+            .send_with_retry(|| {
+                self.client
+                    .client()
+                    .post(&self.config.endpoint)
+                    .bearer_auth(&self.config.access_token)
+                    .json(&serde_json::json!({ "PhoneNumber": to, "Message": body }))
+            })
+            .await
+            .map_err(|e| SmsError::ProviderUnavailable(e.to_string()))?;
+
+        match response.status() {
+            status if status == reqwest::StatusCode::BAD_REQUEST => Err(SmsError::InvalidNumber),
+            status if status.is_success() => Ok(()),
+            status => Err(SmsError::ProviderUnavailable(format!("unexpected status {status}"))),
+        }
+    }
+}
+
+/// Tunables for [`RateLimitedSmsSender`], separate from
+/// [`RateLimiterConfig`]'s login-attempt defaults: SMS costs real money
+/// per message, so the default window is wider and the allowance tighter,
+/// to guard against "SMS pumping" (driving up a victim's or a tenant's
+/// carrier bill by triggering sends to premium-rate or attacker-controlled
+/// numbers) rather than just against brute-forcing a code.
+pub fn default_sms_cost_guard_config() -> RateLimiterConfig {
+    RateLimiterConfig { max_attempts: 3, window: Duration::from_secs(3600) }
+}
+
+/// Wraps an [`SmsSender`] with a [`SlidingWindowRateLimiter`] keyed by
+/// destination number, so a burst of requests for the same number (an OTP
+/// "resend" button mashed, or an attacker pumping a premium-rate number to
+/// run up cost) is throttled before it reaches the underlying provider.
+pub struct RateLimitedSmsSender {
+    inner: std::sync::Arc<dyn SmsSender>,
+    limiter: SlidingWindowRateLimiter,
+}
+
+impl RateLimitedSmsSender {
+    pub fn new(inner: std::sync::Arc<dyn SmsSender>, cost_guard: RateLimiterConfig) -> Self {
+        Self { inner, limiter: SlidingWindowRateLimiter::new(cost_guard) }
+    }
+}
+
+#[async_trait]
+impl SmsSender for RateLimitedSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        let allowed = self
+            .limiter
+            .check(to)
+            .map_err(|e| SmsError::ProviderUnavailable(e.to_string()))?;
+        if !allowed {
+            return Err(SmsError::RateLimited(to.to_string()));
+        }
+        self.inner.send(to, body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn logging_sender_always_succeeds() {
+        let sender = LoggingSmsSender::new();
+        assert!(sender.send("+15555550100", "body").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_sender_allows_sends_under_the_threshold() {
+        let sender = RateLimitedSmsSender::new(
+            std::sync::Arc::new(LoggingSmsSender::new()),
+            RateLimiterConfig { max_attempts: 2, window: Duration::from_secs(60) },
+        );
+        assert!(sender.send("+15555550100", "one").await.is_ok());
+        assert!(sender.send("+15555550100", "two").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_sender_rejects_sends_once_the_threshold_is_exceeded() {
+        let sender = RateLimitedSmsSender::new(
+            std::sync::Arc::new(LoggingSmsSender::new()),
+            RateLimiterConfig { max_attempts: 1, window: Duration::from_secs(60) },
+        );
+        assert!(sender.send("+15555550100", "one").await.is_ok());
+        assert!(matches!(sender.send("+15555550100", "two").await, Err(SmsError::RateLimited(_))));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_sender_tracks_numbers_independently() {
+        let sender = RateLimitedSmsSender::new(
+            std::sync::Arc::new(LoggingSmsSender::new()),
+            RateLimiterConfig { max_attempts: 1, window: Duration::from_secs(60) },
+        );
+        assert!(sender.send("+15555550100", "one").await.is_ok());
+        assert!(sender.send("+15555550199", "one").await.is_ok());
+    }
+}
@@ -0,0 +1,128 @@
+//! Tracing setup with a runtime-adjustable log filter and a choice of
+//! human-readable or structured JSON output, so operators can pick the
+//! format their log pipeline expects (JSON for shipping to a log
+//! aggregator, pretty for a developer's terminal) without code changes.
+//! See [`TelemetryConfig`] for the fields every record is tagged with.
+
+use std::sync::OnceLock;
+
+use thiserror::Error;
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Registry;
+
+/// Handle to the active [`EnvFilter`], usable from anywhere the
+/// subscriber was installed from.
+pub type LogLevelHandle = reload::Handle<EnvFilter, Registry>;
+
+#[derive(Debug, Error)]
+pub enum TelemetryError {
+    #[error("invalid log directives: {0}")]
+    InvalidDirectives(String),
+    #[error("failed to reload tracing filter: {0}")]
+    ReloadFailed(String),
+}
+
+/// Output format for log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable, multi-line — meant for a developer's terminal.
+    Pretty,
+    /// One structured JSON object per record — meant for a log
+    /// aggregator. Events emitted inside a request span (see
+    /// `users::http::request_id`) carry that span's fields, including
+    /// `request_id`, in their `spans` array.
+    Json,
+}
+
+/// Identity and format settings a deployment boots its logging with.
+/// [`crate::telemetry`] itself only consumes `default_directives` and
+/// `format`; the rest are handed to each request's span (see
+/// `users::http::request_id::track_request`) so they end up on every
+/// record produced while handling that request.
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    pub service_name: String,
+    pub service_version: String,
+    pub environment: String,
+    pub default_directives: String,
+    pub format: LogFormat,
+}
+
+static HANDLE: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// Installs the global `tracing` subscriber per `config` (filter seeded
+/// from `RUST_LOG`, falling back to `config.default_directives` if
+/// unset), and returns a handle that can change that filter later.
+///
+/// Safe to call more than once within a process (as in tests that each
+/// spawn their own app): only the first call installs the subscriber and
+/// creates the handle, every later call returns a clone of that same
+/// handle rather than one pointing at a filter nothing is using.
+pub fn init(config: &TelemetryConfig) -> LogLevelHandle {
+    HANDLE
+        .get_or_init(|| {
+            let filter = EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| EnvFilter::new(&config.default_directives));
+            let (filter, handle) = reload::Layer::new(filter);
+            let registry = Registry::default().with(filter);
+            match config.format {
+                LogFormat::Json => {
+                    let _ = registry.with(tracing_subscriber::fmt::layer().json()).try_init();
+                }
+                LogFormat::Pretty => {
+                    let _ = registry
+                        .with(tracing_subscriber::fmt::layer().pretty())
+                        .try_init();
+                }
+            }
+            handle
+        })
+        .clone()
+}
+
+/// Replaces the active filter directives (e.g. `"users=debug,info"`).
+pub fn set_directives(handle: &LogLevelHandle, directives: &str) -> Result<(), TelemetryError> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| TelemetryError::InvalidDirectives(e.to_string()))?;
+    handle
+        .reload(filter)
+        .map_err(|e| TelemetryError::ReloadFailed(e.to_string()))
+}
+
+/// Returns the currently active filter directives as a string.
+pub fn current_directives(handle: &LogLevelHandle) -> Result<String, TelemetryError> {
+    handle
+        .with_current(|filter| filter.to_string())
+        .map_err(|e| TelemetryError::ReloadFailed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> TelemetryConfig {
+        TelemetryConfig {
+            service_name: "users".to_string(),
+            service_version: "0.0.0".to_string(),
+            environment: "test".to_string(),
+            default_directives: "info".to_string(),
+            format: LogFormat::Pretty,
+        }
+    }
+
+    // `init` shares one process-wide handle (see its doc comment), so
+    // these share a single test to avoid racing on global state.
+    #[test]
+    fn set_directives_validates_and_reports_back_the_active_filter() {
+        let handle = init(&test_config());
+
+        let err = set_directives(&handle, "users=not_a_real_level").unwrap_err();
+        assert!(matches!(err, TelemetryError::InvalidDirectives(_)));
+
+        set_directives(&handle, "users=debug,warn").unwrap();
+        assert_eq!(current_directives(&handle).unwrap(), "users=debug,warn");
+    }
+}
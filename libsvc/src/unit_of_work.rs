@@ -0,0 +1,60 @@
+//! Transactional boundary for multi-step writes that must either all land
+//! or all roll back together, e.g. creating a user and recording the audit
+//! event for it. Memory-backed services have nothing to roll back; a
+//! SQL-backed implementation would open a real database transaction in
+//! [`UnitOfWorkFactory::begin`] and commit or roll it back accordingly.
+
+use async_trait::async_trait;
+
+use crate::repository::Result;
+
+/// An open transaction. Exactly one of [`commit`](UnitOfWork::commit) or
+/// [`rollback`](UnitOfWork::rollback) must be called to close it.
+#[async_trait]
+pub trait UnitOfWork: Send + Sync {
+    async fn commit(self: Box<Self>) -> Result<()>;
+    async fn rollback(self: Box<Self>) -> Result<()>;
+}
+
+/// Opens [`UnitOfWork`]s.
+#[async_trait]
+pub trait UnitOfWorkFactory: Send + Sync {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>>;
+}
+
+struct NoopUnitOfWork;
+
+#[async_trait]
+impl UnitOfWork for NoopUnitOfWork {
+    async fn commit(self: Box<Self>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`UnitOfWorkFactory`] for backends with no real transactions, such as
+/// the in-memory repository: every unit commits and rolls back as no-ops.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopUnitOfWorkFactory;
+
+#[async_trait]
+impl UnitOfWorkFactory for NoopUnitOfWorkFactory {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>> {
+        Ok(Box::new(NoopUnitOfWork))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn noop_unit_commits_and_rolls_back_without_error() {
+        let factory = NoopUnitOfWorkFactory;
+        factory.begin().await.unwrap().commit().await.unwrap();
+        factory.begin().await.unwrap().rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+//! A tiny cache of recently-verified session signatures, so a burst of
+//! requests carrying the same session within a short window doesn't
+//! redo [`crate::session::Session::verify_with`]'s signature check on
+//! every one of them. A cache hit only excuses the caller from
+//! re-verifying the signature itself — expiry, `not_before`, and
+//! `issuer`/`audience` (see [`crate::session::Session::verify_claims_with`])
+//! still have to be checked on every request, since those can flip from
+//! passing to failing within the cache's TTL even though the signature
+//! can't.
+//!
+//! Disabled by default: one verification per request is already cheap,
+//! and skipping it trades a small amount of CPU for the cache itself
+//! plus a (short, bounded) window in which a revoked key could still be
+//! accepted for a session this process already saw verify. Callers that
+//! want the trade-off turn it on via [`VerificationCacheConfig::enabled`].
+//! Only successful verifications are cached — caching failures would
+//! also suppress the security signal raised for each rejected attempt.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+/// Tunables for [`VerificationCache`].
+#[derive(Debug, Clone)]
+pub struct VerificationCacheConfig {
+    /// Whether lookups consult and populate the cache at all. Off by
+    /// default.
+    pub enabled: bool,
+    /// How long a cached verification stays valid.
+    pub ttl: Duration,
+}
+
+impl Default for VerificationCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Caches that `(session_id, signature)` pairs verified successfully,
+/// for [`VerificationCacheConfig::ttl`].
+pub struct VerificationCache {
+    config: VerificationCacheConfig,
+    verified_at: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+fn cache_key(session_id: &str, signature: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(session_id.as_bytes());
+    hasher.update(signature);
+    hasher.finalize().into()
+}
+
+impl VerificationCache {
+    pub fn new(config: VerificationCacheConfig) -> Self {
+        Self {
+            config,
+            verified_at: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<[u8; 32], Instant>> {
+        self.verified_at.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns `true` if `(session_id, signature)` was recorded as
+    /// verified within the TTL, so the caller can skip re-verifying it.
+    /// Always `false` while disabled.
+    pub fn is_recently_verified(&self, session_id: &str, signature: &[u8]) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let key = cache_key(session_id, signature);
+        let mut verified_at = self.lock();
+        match verified_at.get(&key) {
+            Some(at) if at.elapsed() < self.config.ttl => true,
+            Some(_) => {
+                verified_at.remove(&key);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Records that `(session_id, signature)` just verified successfully.
+    /// A no-op while disabled.
+    pub fn record_verified(&self, session_id: &str, signature: &[u8]) {
+        if !self.config.enabled {
+            return;
+        }
+        let key = cache_key(session_id, signature);
+        self.lock().insert(key, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_recorded_verification_is_recalled_within_the_ttl() {
+        let cache = VerificationCache::new(VerificationCacheConfig {
+            enabled: true,
+            ttl: Duration::from_secs(60),
+        });
+
+        assert!(!cache.is_recently_verified("session-1", b"sig"));
+        cache.record_verified("session-1", b"sig");
+        assert!(cache.is_recently_verified("session-1", b"sig"));
+    }
+
+    #[test]
+    fn a_different_signature_for_the_same_session_is_not_a_hit() {
+        let cache = VerificationCache::new(VerificationCacheConfig {
+            enabled: true,
+            ttl: Duration::from_secs(60),
+        });
+
+        cache.record_verified("session-1", b"sig-a");
+        assert!(!cache.is_recently_verified("session-1", b"sig-b"));
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let cache = VerificationCache::new(VerificationCacheConfig {
+            enabled: true,
+            ttl: Duration::from_millis(1),
+        });
+
+        cache.record_verified("session-1", b"sig");
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(!cache.is_recently_verified("session-1", b"sig"));
+    }
+
+    #[test]
+    fn disabled_cache_never_reports_a_hit() {
+        let cache = VerificationCache::new(VerificationCacheConfig::default());
+
+        cache.record_verified("session-1", b"sig");
+        assert!(!cache.is_recently_verified("session-1", b"sig"));
+    }
+}
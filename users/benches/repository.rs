@@ -0,0 +1,70 @@
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+use users::domain::{User, UserKind, UserRepository, UserStatus};
+use users::repository::memory::MemoryUserRepository;
+
+fn sample_user(id: &str) -> User {
+    User {
+        id: id.to_string(),
+        email: format!("{id}@example.com"),
+        username: Some(format!("user-{id}")),
+        created_at: Utc::now(),
+        pending_email: None,
+        avatar_url: None,
+        status: UserStatus::Active,
+        legal_hold: false,
+        kind: UserKind::Human,
+        organization_id: None,
+        custom_attributes: Default::default(),
+        tags: Default::default(),
+    }
+}
+
+fn bench_create(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut counter = 0u64;
+    c.bench_function("memory_repository_create", |b| {
+        b.iter(|| {
+            counter += 1;
+            let repo = MemoryUserRepository::new();
+            rt.block_on(repo.create(sample_user(&counter.to_string())))
+                .unwrap()
+        })
+    });
+}
+
+fn bench_get(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let repo = MemoryUserRepository::new();
+    rt.block_on(repo.create(sample_user("1"))).unwrap();
+    c.bench_function("memory_repository_get", |b| {
+        b.iter(|| rt.block_on(repo.get("1")).unwrap())
+    });
+}
+
+fn bench_update(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let repo = MemoryUserRepository::new();
+    let user = rt.block_on(repo.create(sample_user("1"))).unwrap();
+    c.bench_function("memory_repository_update", |b| {
+        b.iter(|| rt.block_on(repo.update(user.clone())).unwrap())
+    });
+}
+
+fn bench_delete(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut counter = 0u64;
+    c.bench_function("memory_repository_delete", |b| {
+        b.iter(|| {
+            counter += 1;
+            let id = counter.to_string();
+            let repo = MemoryUserRepository::new();
+            rt.block_on(repo.create(sample_user(&id))).unwrap();
+            rt.block_on(repo.delete(&id)).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_create, bench_get, bench_update, bench_delete);
+criterion_main!(benches);
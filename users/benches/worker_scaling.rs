@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::{Builder, Runtime};
+use users::domain::{User, UserKind, UserRepository, UserStatus};
+use users::repository::memory::MemoryUserRepository;
+
+/// Concurrent `create` calls issued per benchmark iteration. Large enough
+/// that a single-worker runtime visibly queues behind the others.
+const CONCURRENT_REQUESTS: usize = 64;
+
+fn sample_user(id: &str) -> User {
+    User {
+        id: id.to_string(),
+        email: format!("{id}@example.com"),
+        username: Some(format!("user-{id}")),
+        created_at: Utc::now(),
+        pending_email: None,
+        avatar_url: None,
+        status: UserStatus::Active,
+        legal_hold: false,
+        kind: UserKind::Human,
+        organization_id: None,
+        custom_attributes: Default::default(),
+        tags: Default::default(),
+    }
+}
+
+fn runtime_with_workers(worker_threads: usize) -> Runtime {
+    Builder::new_multi_thread()
+        .worker_threads(worker_threads)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Fans `CONCURRENT_REQUESTS` creates out across the repository as
+/// separate tasks and waits for all of them, the same shape as a burst of
+/// simultaneous HTTP requests hitting the service.
+async fn create_burst(repo: Arc<MemoryUserRepository>, generation: u64) {
+    let handles: Vec<_> = (0..CONCURRENT_REQUESTS)
+        .map(|i| {
+            let repo = repo.clone();
+            let id = format!("{generation}-{i}");
+            tokio::spawn(async move { repo.create(sample_user(&id)).await })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+}
+
+/// Demonstrates `WORKER_THREADS`' effect on throughput (see
+/// `src/main.rs`): a burst of concurrent requests completes faster with
+/// more worker threads to run them on, up to the point the work saturates
+/// available CPUs. Compare `worker_scaling/1_worker` against
+/// `worker_scaling/4_workers` in the criterion report.
+fn bench_worker_counts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("worker_scaling");
+    let mut generation = 0u64;
+    for worker_threads in [1, 2, 4] {
+        let rt = runtime_with_workers(worker_threads);
+        let repo = Arc::new(MemoryUserRepository::new());
+        let label = format!(
+            "{worker_threads}_worker{}",
+            if worker_threads == 1 { "" } else { "s" }
+        );
+        group.bench_function(label, |b| {
+            b.iter(|| {
+                generation += 1;
+                rt.block_on(create_burst(repo.clone(), generation));
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_worker_counts);
+criterion_main!(benches);
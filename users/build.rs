@@ -0,0 +1,28 @@
+//! Captures build-time provenance (`GET /debug/build` reads these back)
+//! so a deployed binary can be matched to the commit and toolchain that
+//! produced it without needing to track that out of band.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok().map(|s| s.trim().to_string())
+}
+
+fn main() {
+    let git_sha = command_output("git", &["rev-parse", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BUILD_GIT_SHA={git_sha}");
+
+    let rustc_version = command_output("rustc", &["--version"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BUILD_RUSTC_VERSION={rustc_version}");
+
+    let built_at_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs().to_string())
+        .unwrap_or_else(|_| "0".to_string());
+    println!("cargo:rustc-env=BUILD_TIMESTAMP_UNIX={built_at_unix}");
+}
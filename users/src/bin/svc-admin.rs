@@ -0,0 +1,132 @@
+//! Administrative commands for the `users` service.
+//!
+//! `svc-admin backup` and `svc-admin restore` dump the file-backed user
+//! store to (and load it back from) an encrypted, versioned archive; see
+//! [`users::backup`] for the archive format. `svc-admin migrate-store`
+//! backfills a second file-backed store from a first; see
+//! [`users::repository::migrating`]. Point `--data-dir` at the same
+//! directory a running server was started with `DATA_DIR` set to —
+//! against a store still being written to, these commands may miss
+//! records written during the run, so prefer running them against a
+//! stopped server or a filesystem snapshot of the directory.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use base64::Engine;
+use clap::{Parser, Subcommand};
+
+use foundation::crypto::EncryptionKey;
+use users::backup::{backup, restore};
+use users::repository::credentials::MemoryCredentialsRepository;
+use users::repository::file::FileUserRepository;
+use users::repository::migrating::copy_in_batches;
+
+/// The environment variable holding the base64-encoded 256-bit key used
+/// to encrypt and decrypt backup archives. There is no flag for this, so
+/// it never ends up in a shell history or process listing.
+const BACKUP_KEY_ENV: &str = "BACKUP_ENCRYPTION_KEY";
+
+/// How many users `migrate-store` copies per `search`/`create` round, if
+/// `--batch-size` isn't given.
+const DEFAULT_MIGRATION_BATCH_SIZE: usize = 200;
+
+#[derive(Parser)]
+#[command(name = "svc-admin", about = "Administrative commands for the users service")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Writes every user and their credentials to an encrypted archive.
+    Backup {
+        /// Directory holding the file-backed repository's snapshot and log.
+        #[arg(long, env = "DATA_DIR")]
+        data_dir: PathBuf,
+        /// Path to write the archive to.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Restores users and credentials from a previously written archive.
+    /// Users already present (matched by email) are left untouched.
+    Restore {
+        /// Directory holding the file-backed repository's snapshot and log.
+        #[arg(long, env = "DATA_DIR")]
+        data_dir: PathBuf,
+        /// Path of the archive to read.
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Copies every user from one file-backed store into another, so a
+    /// new backend can be backfilled before traffic cuts over to it.
+    /// Users already present in the destination (matched by email) are
+    /// left untouched, so a failed run can simply be retried.
+    MigrateStore {
+        /// Directory of the store to copy users from.
+        #[arg(long)]
+        from_data_dir: PathBuf,
+        /// Directory of the store to copy users into.
+        #[arg(long)]
+        to_data_dir: PathBuf,
+        /// How many users to copy per batch.
+        #[arg(long, default_value_t = DEFAULT_MIGRATION_BATCH_SIZE)]
+        batch_size: usize,
+    },
+}
+
+fn load_encryption_key() -> EncryptionKey {
+    let encoded = std::env::var(BACKUP_KEY_ENV)
+        .unwrap_or_else(|_| panic!("{BACKUP_KEY_ENV} must be set to a base64-encoded 256-bit key"));
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(&encoded)
+        .unwrap_or_else(|e| panic!("{BACKUP_KEY_ENV} is not valid base64: {e}"));
+    let key: [u8; 32] = decoded.try_into().unwrap_or_else(|bytes: Vec<u8>| {
+        panic!("{BACKUP_KEY_ENV} must decode to 32 bytes, got {}", bytes.len())
+    });
+    EncryptionKey::from_bytes(key)
+}
+
+#[tokio::main]
+async fn main() {
+    match Cli::parse().command {
+        Command::Backup { data_dir, out } => {
+            let key = load_encryption_key();
+            let repository =
+                FileUserRepository::open(&data_dir).expect("failed to open data directory");
+            let credentials = MemoryCredentialsRepository::new();
+            let file = File::create(&out).expect("failed to create backup file");
+            let written =
+                backup(&repository, &credentials, &key, file).await.expect("backup failed");
+            println!("wrote {written} users to {}", out.display());
+        }
+        Command::Restore { data_dir, input } => {
+            let key = load_encryption_key();
+            let repository =
+                FileUserRepository::open(&data_dir).expect("failed to open data directory");
+            let credentials = MemoryCredentialsRepository::new();
+            let file = File::open(&input).expect("failed to open backup file");
+            let summary = restore(&repository, &credentials, &key, BufReader::new(file))
+                .await
+                .expect("restore failed");
+            println!(
+                "restored {} users ({} already existed and were skipped)",
+                summary.restored, summary.skipped_existing
+            );
+        }
+        Command::MigrateStore { from_data_dir, to_data_dir, batch_size } => {
+            let from = FileUserRepository::open(&from_data_dir)
+                .expect("failed to open source data directory");
+            let to = FileUserRepository::open(&to_data_dir)
+                .expect("failed to open destination data directory");
+            let summary =
+                copy_in_batches(&from, &to, batch_size).await.expect("migration failed");
+            println!(
+                "copied {} users ({} already existed and were skipped)",
+                summary.copied, summary.skipped_existing
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Batches notifications queued under [`NotificationCategory::Digest`]
+//! (see [`crate::notifications::NotificationMailer`]) into one email per
+//! user per interval, rather than sending each as it's queued.
+//! [`DigestConfig`] holds the interval; [`run_once`] performs a single
+//! batch-and-send pass and reports how many emails it sent;
+//! [`spawn_periodic`] runs that pass on a fixed interval, the way `main`
+//! wires up [`crate::retention::spawn_periodic`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use libsvc::mailer::Mailer;
+
+use crate::domain::{DigestQueueRepository, UserRepository};
+
+/// How often [`spawn_periodic`] runs a batch-and-send pass.
+#[derive(Debug, Clone, Copy)]
+pub struct DigestConfig {
+    pub interval: Duration,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self { interval: Duration::from_secs(24 * 3600) }
+    }
+}
+
+/// How many digest emails a single [`run_once`] pass sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DigestReport {
+    pub emails_sent: usize,
+}
+
+/// Drains `queue`, and for every user with at least one queued
+/// notification, looks up their current email via `repository` and sends
+/// them one combined digest email through `mailer`. A user whose account
+/// has since been deleted (so the lookup fails) has their queued
+/// notifications silently dropped along with the rest of their data,
+/// rather than retried forever against an account that no longer exists.
+pub async fn run_once(
+    queue: &dyn DigestQueueRepository,
+    repository: &dyn UserRepository,
+    mailer: &dyn Mailer,
+) -> DigestReport {
+    let drained = match queue.drain_all().await {
+        Ok(drained) => drained,
+        Err(err) => {
+            tracing::warn!(%err, "failed to drain digest queue; skipping this pass");
+            return DigestReport::default();
+        }
+    };
+
+    let mut emails_sent = 0;
+    for (user_id, notifications) in drained {
+        if notifications.is_empty() {
+            continue;
+        }
+        let user = match repository.get(&user_id).await {
+            Ok(user) => user,
+            Err(err) => {
+                tracing::warn!(%err, user_id, "failed to look up user for digest email, dropping queued notifications");
+                continue;
+            }
+        };
+        let body = notifications
+            .iter()
+            .map(|notification| format!("{}\n{}", notification.subject, notification.body))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        mailer.send(&user.email, "Your digest", &body);
+        emails_sent += 1;
+    }
+
+    DigestReport { emails_sent }
+}
+
+/// Spawns a task that calls [`run_once`] every `config.interval`, for as
+/// long as the process runs. Intended to be called once at startup,
+/// alongside [`crate::retention::spawn_periodic`].
+pub fn spawn_periodic(
+    config: DigestConfig,
+    queue: Arc<dyn DigestQueueRepository>,
+    repository: Arc<dyn UserRepository>,
+    mailer: Arc<dyn Mailer>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+        loop {
+            ticker.tick().await;
+            let report = run_once(queue.as_ref(), repository.as_ref(), mailer.as_ref()).await;
+            tracing::info!(emails_sent = report.emails_sent, "digest batch pass complete");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{QueuedDigestNotification, User, UserKind, UserStatus};
+    use crate::repository::digest::MemoryDigestQueueRepository;
+    use crate::repository::memory::MemoryUserRepository;
+    use libsvc::mailer::LoggingMailer;
+
+    fn sample_user(id: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: format!("{id}@example.com"),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: UserStatus::Active,
+            legal_hold: false,
+            kind: UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn run_once_is_a_no_op_with_nothing_queued() {
+        let queue = MemoryDigestQueueRepository::new();
+        let repository = MemoryUserRepository::new();
+        let mailer = LoggingMailer::new();
+
+        let report = run_once(&queue, &repository, &mailer).await;
+        assert_eq!(report.emails_sent, 0);
+    }
+
+    #[tokio::test]
+    async fn run_once_sends_one_email_per_user_with_queued_notifications() {
+        use crate::domain::UserRepository as _;
+
+        let queue = MemoryDigestQueueRepository::new();
+        let repository = MemoryUserRepository::new();
+        let mailer = LoggingMailer::new();
+        repository.create(sample_user("user-1")).await.unwrap();
+        queue
+            .enqueue(
+                "user-1",
+                QueuedDigestNotification { subject: "a".to_string(), body: "a body".to_string() },
+            )
+            .await
+            .unwrap();
+        queue
+            .enqueue(
+                "user-1",
+                QueuedDigestNotification { subject: "b".to_string(), body: "b body".to_string() },
+            )
+            .await
+            .unwrap();
+
+        let report = run_once(&queue, &repository, &mailer).await;
+        assert_eq!(report.emails_sent, 1);
+        assert!(queue.drain_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_deleted_user_s_queued_notifications_are_dropped_rather_than_retried() {
+        let queue = MemoryDigestQueueRepository::new();
+        let repository = MemoryUserRepository::new();
+        let mailer = LoggingMailer::new();
+        queue
+            .enqueue(
+                "gone",
+                QueuedDigestNotification { subject: "a".to_string(), body: "a body".to_string() },
+            )
+            .await
+            .unwrap();
+
+        let report = run_once(&queue, &repository, &mailer).await;
+        assert_eq!(report.emails_sent, 0);
+    }
+}
@@ -0,0 +1,1043 @@
+//! The `User` aggregate and the traits that define how it is stored and
+//! operated on.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use foundation::timezone::LocalizedTimestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use libsvc::repository::Result;
+
+/// A registered user's profile. Deliberately carries no credential
+/// material — see [`Credentials`] — so that reading or updating a profile
+/// (including every REST response built from one) never touches secrets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub email: String,
+    /// An optional, unique handle that can be used instead of `email` to
+    /// log in. See [`UserLogic::authenticate`].
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// An email change awaiting confirmation, along with the token that
+    /// must be presented to complete it. `email` is only swapped once the
+    /// token is confirmed; see [`UserLogic::confirm_email_change`].
+    pub pending_email: Option<PendingEmailChange>,
+    /// The URL of the user's uploaded avatar, if any. Set by
+    /// `PUT /v1/users/me/avatar`; `None` until the user uploads one.
+    pub avatar_url: Option<String>,
+    /// Whether this account may currently authenticate. Defaults to
+    /// [`UserStatus::Active`] for any record stored before this field
+    /// existed, so older repository rows keep working without a backfill.
+    #[serde(default)]
+    pub status: UserStatus,
+    /// Whether an admin has placed this account under legal hold, set via
+    /// [`UserLogic::set_legal_hold`]. While `true`, [`UserLogic::erase`]
+    /// refuses to run a GDPR erasure against this account, and the
+    /// retention purge job (`users::retention`) skips its audit trail and
+    /// active sessions, so evidence isn't lost to routine cleanup while a
+    /// hold is in effect. Defaults to `false` for any record stored before
+    /// this field existed.
+    #[serde(default)]
+    pub legal_hold: bool,
+    /// Whether this account belongs to a human or a trusted service. See
+    /// [`UserKind`]. Defaults to [`UserKind::Human`] for any record stored
+    /// before this field existed, so older repository rows keep working
+    /// without a backfill.
+    #[serde(default)]
+    pub kind: UserKind,
+    /// The organization this account belongs to, if any. Lets a
+    /// [`libsvc::session::Role::OrgAdmin`] session (see
+    /// `users::http::admin::authorize_org_scoped`) manage only accounts
+    /// that share its holder's own `organization_id`, rather than every
+    /// account like [`libsvc::session::Role::Admin`] can. `None` for an
+    /// account that belongs to no organization, which no `OrgAdmin` can
+    /// manage.
+    #[serde(default)]
+    pub organization_id: Option<String>,
+    /// Free-form per-account metadata an admin can attach, validated
+    /// against the schema currently configured via
+    /// [`UserLogic::set_custom_attributes_schema`] whenever it's written
+    /// through [`UserLogic::set_custom_attributes`]. Empty for any record
+    /// stored before this field existed, and for any account nobody has
+    /// ever set attributes on.
+    #[serde(default)]
+    pub custom_attributes: Map<String, Value>,
+    /// Free-form labels an admin can attach for staged rollouts and
+    /// support tooling — e.g. `"beta"` or `"flagged-for-review"` — set via
+    /// [`UserLogic::add_tag`] and [`UserLogic::remove_tag`], and matched
+    /// exactly (case-sensitive, no wildcarding) by
+    /// [`UserSearchFilter::tag`]. Empty for any record stored before this
+    /// field existed.
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+}
+
+/// Distinguishes an account a person signs into from one a trusted
+/// service authenticates as. A [`UserKind::Service`] account never has a
+/// password: [`UserLogic::authenticate`] refuses it outright, and it
+/// signs in only through [`UserLogic::authenticate_service_account`],
+/// the same way [`libsvc::service_account`] already lets a *separately*
+/// trusted caller exchange its own credential for a session acting as
+/// some other user — this is the analogous case where the service *is*
+/// the user. It's also left out of [`UserLogic::search`]'s results by
+/// default (see [`UserSearchFilter::kind`]) and skipped by email-based
+/// sign-in flows such as `crate::http::handlers::request_magic_link`,
+/// since there's no human on the other end of that inbox to click one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserKind {
+    #[default]
+    Human,
+    Service,
+}
+
+/// Whether a [`User`] may currently authenticate, set via
+/// [`UserLogic::set_status`] and enforced both at login
+/// ([`UserLogic::authenticate`]) and on every already-issued session (see
+/// `AuthenticatedUser::from_request_parts` in `users::http::auth`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    /// Suspended, typically at the account holder's own request or by an
+    /// admin for a non-punitive reason. Reversible via another
+    /// [`UserLogic::set_status`] call back to [`UserStatus::Active`].
+    Deactivated,
+    /// Suspended by an admin for violating terms of service. Reversible
+    /// the same way as [`UserStatus::Deactivated`], but distinguished from
+    /// it so an admin reviewing the account can see why it was suspended.
+    Banned,
+}
+
+/// Why an admin changed a user's [`UserStatus`], recorded alongside the
+/// change so the audit trail shows more than just the new status. Not
+/// exhaustive of every real-world reason — deployments with more nuanced
+/// policies are expected to fold those details into their own ticketing
+/// system and reference it out of band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusChangeReason {
+    TermsOfServiceViolation,
+    UserRequested,
+    SuspiciousActivity,
+    Other,
+}
+
+impl StatusChangeReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            StatusChangeReason::TermsOfServiceViolation => "terms_of_service_violation",
+            StatusChangeReason::UserRequested => "user_requested",
+            StatusChangeReason::SuspiciousActivity => "suspicious_activity",
+            StatusChangeReason::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for StatusChangeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl UserStatus {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::Deactivated => "deactivated",
+            UserStatus::Banned => "banned",
+        }
+    }
+
+}
+
+impl std::fmt::Display for UserStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for UserStatus {
+    type Err = std::convert::Infallible;
+
+    /// Falls back to [`UserStatus::Active`] for anything unrecognized
+    /// (including a field absent from a record written before this field
+    /// existed) rather than failing to load the user over it.
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match value {
+            "deactivated" => UserStatus::Deactivated,
+            "banned" => UserStatus::Banned,
+            _ => UserStatus::Active,
+        })
+    }
+}
+
+/// A requested-but-unconfirmed change of a user's email address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingEmailChange {
+    pub new_email: String,
+    pub confirmation_token: String,
+}
+
+/// The credential material behind a [`User`], kept in its own entity (and
+/// its own [`CredentialsRepository`]) so a password hash never ends up on
+/// a profile read or REST response. A password hash is the only
+/// credential kind this service issues today, but the split leaves room
+/// for others (a TOTP secret, a WebAuthn public key) to live alongside it
+/// per user without `User` ever growing secret fields of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub user_id: String,
+    pub password_hash: String,
+}
+
+/// Persists and retrieves a user's [`Credentials`], independent of
+/// [`UserRepository`] so the credential store can be secured, rotated, and
+/// scaled separately from the profile store (see [`Credentials`]).
+#[async_trait]
+pub trait CredentialsRepository: Send + Sync {
+    async fn create(&self, credentials: Credentials) -> Result<()>;
+    async fn get(&self, user_id: &str) -> Result<Credentials>;
+    async fn update(&self, credentials: Credentials) -> Result<()>;
+    async fn delete(&self, user_id: &str) -> Result<()>;
+}
+
+/// A [`User`] safe to hand back over the wire: a pending email change is
+/// reduced to the address it would become, dropping the confirmation
+/// token. Every REST response that returns a user's profile returns this
+/// instead of [`User`] itself, whose `Serialize` impl would otherwise
+/// include the token.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserView {
+    pub id: String,
+    pub email: String,
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// `created_at` rendered in the caller's preferred timezone (see
+    /// [`UserPreferences::timezone`]), alongside the UTC value above.
+    /// `None` for call sites that build a [`UserView`] without a timezone
+    /// to render against; see [`UserView::with_timezone`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at_local: Option<LocalizedTimestamp>,
+    pub pending_email: Option<String>,
+    pub avatar_url: Option<String>,
+    pub custom_attributes: Map<String, Value>,
+    pub tags: BTreeSet<String>,
+}
+
+impl From<User> for UserView {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            created_at: user.created_at,
+            created_at_local: None,
+            pending_email: user.pending_email.map(|pending| pending.new_email),
+            avatar_url: user.avatar_url,
+            custom_attributes: user.custom_attributes,
+            tags: user.tags,
+        }
+    }
+}
+
+impl UserView {
+    /// Same as [`From<User>`], but with [`UserView::created_at_local`]
+    /// populated by rendering `created_at` in `timezone`.
+    pub fn with_timezone(user: User, timezone: &str) -> Self {
+        let mut view = Self::from(user);
+        view.created_at_local = Some(LocalizedTimestamp::new(view.created_at, timezone));
+        view
+    }
+}
+
+/// A user's own view of one of their active sessions, as included in
+/// [`UserDataExport::sessions`]. Leaves out the signature, which isn't
+/// part of what a user knows about a session they hold.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionExport {
+    pub id: String,
+    pub kind: libsvc::session::SessionKind,
+    pub roles: Vec<libsvc::session::Role>,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+impl From<libsvc::session::Session<libsvc::session::Signed>> for SessionExport {
+    fn from(session: libsvc::session::Session<libsvc::session::Signed>) -> Self {
+        Self {
+            id: session.id,
+            kind: session.kind,
+            roles: session.roles,
+            issued_at: session.issued_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// Everything known about a user, assembled for export or deletion.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserDataExport {
+    pub profile: UserView,
+    pub sessions: Vec<SessionExport>,
+    pub audit_events: Vec<libsvc::audit::AuditEvent>,
+}
+
+/// A user's notification, locale, and display preferences. Kept as its own
+/// aggregate rather than fields on [`User`], since preferences change for
+/// different reasons than the credential record and have no bearing on
+/// authentication.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPreferences {
+    pub user_id: String,
+    pub notifications_enabled: bool,
+    pub locale: String,
+    /// An IANA timezone name (e.g. `"America/New_York"`) used to render
+    /// timestamps in responses alongside UTC; see
+    /// [`UserView::created_at_local`].
+    pub timezone: String,
+    pub theme: Theme,
+    /// Per-category opt-in, checked by
+    /// [`crate::notifications::NotificationMailer::notify`] before a
+    /// notification is sent (or, for [`NotificationCategory::Digest`],
+    /// queued). Independent of [`UserPreferences::notifications_enabled`],
+    /// which this service's older, all-or-nothing preference predates
+    /// these categories and continues to gate transactional mail sent
+    /// directly through [`libsvc::mailer::Mailer`] rather than through
+    /// `NotificationMailer`.
+    pub notification_categories: NotificationCategoryPreferences,
+    /// The phone number [`UserLogic::request_sms_otp`] sends a one-time
+    /// code to. `None` until the user sets one via
+    /// `PUT /v1/users/me/preferences`.
+    pub phone: Option<String>,
+}
+
+/// Per-[`NotificationCategory`] opt-in. Kept as named fields, matching
+/// this struct's own fields, rather than a map, since the category set is
+/// fixed and known at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationCategoryPreferences {
+    pub security_enabled: bool,
+    pub product_enabled: bool,
+    pub digest_enabled: bool,
+}
+
+impl NotificationCategoryPreferences {
+    pub fn is_enabled(&self, category: NotificationCategory) -> bool {
+        match category {
+            NotificationCategory::Security => self.security_enabled,
+            NotificationCategory::Product => self.product_enabled,
+            NotificationCategory::Digest => self.digest_enabled,
+        }
+    }
+}
+
+impl Default for NotificationCategoryPreferences {
+    fn default() -> Self {
+        Self {
+            security_enabled: true,
+            product_enabled: true,
+            digest_enabled: true,
+        }
+    }
+}
+
+/// A kind of notification a user can opt in or out of independently; see
+/// [`NotificationCategoryPreferences`] and
+/// [`crate::notifications::NotificationMailer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// Unsolicited alerts about the account itself (e.g. a change to its
+    /// login email) — distinct from transactional mail the user directly
+    /// triggered, like a confirmation link, which always sends regardless
+    /// of this preference.
+    Security,
+    /// Announcements and other non-account-critical mail.
+    Product,
+    /// Batched by [`crate::digest`] into one email per interval rather
+    /// than sent immediately.
+    Digest,
+}
+
+impl UserPreferences {
+    /// The preferences a user has before ever setting any explicitly.
+    pub fn defaults(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: user_id.into(),
+            notifications_enabled: true,
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
+            theme: Theme::System,
+            notification_categories: NotificationCategoryPreferences::default(),
+            phone: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+    System,
+}
+
+/// Filters accepted by [`UserRepository::search`]. Every field narrows the
+/// result set when set; `None` leaves it unconstrained.
+#[derive(Debug, Clone, Default)]
+pub struct UserSearchFilter {
+    /// Matches users whose email starts with this prefix.
+    pub email_prefix: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// This service grants roles per session rather than storing them on
+    /// the user (see [`libsvc::session::SessionBuilder::with_roles`]), so
+    /// every account implicitly holds [`libsvc::session::Role::User`] and
+    /// nothing else. Filtering by any other role matches no one.
+    pub role: Option<libsvc::session::Role>,
+    /// Whether the account's current email has no unconfirmed change
+    /// pending (see [`PendingEmailChange`]) — the closest concept this
+    /// service has to "verified", since it has no separate
+    /// email-verification step at registration.
+    pub verified: Option<bool>,
+    /// A fuzzy match against email/username, answered by whatever
+    /// [`libsvc::search_index::SearchIndex`] [`UserLogic::search`] is
+    /// configured with rather than by [`matches_search_filter`] — a
+    /// deployment with no real index configured matches nobody on this
+    /// field. Every other field in this filter still applies on top of
+    /// the index's candidates.
+    pub text_query: Option<String>,
+    /// Matches accounts by their current [`User::legal_hold`] flag. Used
+    /// by `users::retention` to find every account currently under hold,
+    /// so its purge pass can leave them alone.
+    pub legal_hold: Option<bool>,
+    /// Matches accounts by [`User::kind`]. `None` here leaves this filter
+    /// itself unconstrained like every other field, but
+    /// `crate::http::admin::search_users` defaults this to
+    /// `Some(UserKind::Human)` rather than `None` when a caller doesn't
+    /// specify one, so service accounts stay out of ordinary admin
+    /// listings unless asked for explicitly.
+    pub kind: Option<UserKind>,
+    /// Matches accounts by [`User::organization_id`]. Set unconditionally
+    /// by `crate::http::admin::search_users` to the caller's own
+    /// organization when the caller only holds
+    /// [`libsvc::session::Role::OrgAdmin`], so an org admin's search can
+    /// never see accounts outside their own organization regardless of
+    /// what they ask for.
+    pub organization_id: Option<String>,
+    /// Matches accounts whose [`User::custom_attributes`] has `key` set to
+    /// exactly `value`. Unlike every other field here, this isn't a
+    /// structural property of the account — it's whatever shape the
+    /// currently configured schema allows, so only an exact key/value
+    /// equality check is offered rather than anything schema-aware.
+    pub custom_attribute: Option<(String, Value)>,
+    /// Matches accounts with this exact tag in [`User::tags`].
+    pub tag: Option<String>,
+}
+
+/// Pagination for [`UserRepository::search`]: skip `offset` matches, then
+/// return up to `limit`.
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// One page of [`UserRepository::search`] results.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSearchPage {
+    pub users: Vec<User>,
+    /// Whether further matches exist past this page.
+    pub has_more: bool,
+}
+
+/// One page of a user's own [`UserLogic::activity`] timeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityPage {
+    pub events: Vec<libsvc::audit::AuditEvent>,
+    /// Whether further, older events exist past this page.
+    pub has_more: bool,
+}
+
+/// Orders `events` newest first and slices out `pagination`'s page.
+pub(crate) fn paginate_activity(mut events: Vec<libsvc::audit::AuditEvent>, pagination: Pagination) -> ActivityPage {
+    events.sort_by_key(|event| std::cmp::Reverse(event.at));
+    let total = events.len();
+    let page = events.into_iter().skip(pagination.offset).take(pagination.limit).collect();
+    ActivityPage {
+        events: page,
+        has_more: pagination.offset.saturating_add(pagination.limit) < total,
+    }
+}
+
+/// Whether `user` satisfies every constraint in `filter`. Shared by every
+/// [`UserRepository::search`] implementation so the filter DSL behaves
+/// identically regardless of backend.
+pub(crate) fn matches_search_filter(user: &User, filter: &UserSearchFilter) -> bool {
+    if let Some(prefix) = &filter.email_prefix {
+        if !user.email.starts_with(prefix.as_str()) {
+            return false;
+        }
+    }
+    if let Some(after) = filter.created_after {
+        if user.created_at < after {
+            return false;
+        }
+    }
+    if let Some(before) = filter.created_before {
+        if user.created_at > before {
+            return false;
+        }
+    }
+    if let Some(role) = filter.role {
+        if role != libsvc::session::Role::User {
+            return false;
+        }
+    }
+    if let Some(verified) = filter.verified {
+        if user.pending_email.is_none() != verified {
+            return false;
+        }
+    }
+    if let Some(legal_hold) = filter.legal_hold {
+        if user.legal_hold != legal_hold {
+            return false;
+        }
+    }
+    if let Some(kind) = filter.kind {
+        if user.kind != kind {
+            return false;
+        }
+    }
+    if let Some(organization_id) = &filter.organization_id {
+        if user.organization_id.as_deref() != Some(organization_id.as_str()) {
+            return false;
+        }
+    }
+    if let Some((key, value)) = &filter.custom_attribute {
+        if user.custom_attributes.get(key) != Some(value) {
+            return false;
+        }
+    }
+    if let Some(tag) = &filter.tag {
+        if !user.tags.contains(tag) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Orders `users` by `created_at` ascending and slices out `pagination`'s
+/// page, so repeated pages stay stable as long as no new accounts are
+/// created in between. Shared by every in-memory-filtering
+/// [`UserRepository::search`] implementation.
+pub(crate) fn paginate_search_results(mut users: Vec<User>, pagination: Pagination) -> UserSearchPage {
+    users.sort_by_key(|user| user.created_at);
+    let total = users.len();
+    let page = users.into_iter().skip(pagination.offset).take(pagination.limit).collect();
+    UserSearchPage {
+        users: page,
+        has_more: pagination.offset.saturating_add(pagination.limit) < total,
+    }
+}
+
+/// Persists and retrieves [`User`] records.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, user: User) -> Result<User>;
+    async fn get(&self, id: &str) -> Result<User>;
+    async fn get_by_email(&self, email: &str) -> Result<User>;
+    async fn get_by_username(&self, username: &str) -> Result<User>;
+    async fn update(&self, user: User) -> Result<User>;
+    async fn delete(&self, id: &str) -> Result<()>;
+
+    /// Finds users matching `filter`, paginated by `pagination`.
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage>;
+
+    /// Counts users matching `filter`, without fetching the matched
+    /// records themselves.
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64>;
+
+    /// Returns `true` if a user with `email` already exists, without
+    /// fetching the record (and its password hash) to find out.
+    async fn exists_by_email(&self, email: &str) -> Result<bool>;
+
+    /// Reports point-in-time health and storage statistics, for
+    /// `GET /debug/ready` and Prometheus export. The default measures the
+    /// latency of a [`UserRepository::count`] call and reports an
+    /// `"unknown"` backend; an implementation backed by a real store
+    /// should override it to name itself and, where the backend offers a
+    /// cheaper dedicated health check than counting every row, use that
+    /// instead.
+    async fn stats(&self) -> Result<RepositoryStats> {
+        let started = Instant::now();
+        let user_count = self.count(&UserSearchFilter::default()).await?;
+        Ok(RepositoryStats {
+            backend: "unknown".to_string(),
+            user_count,
+            ping_latency: started.elapsed(),
+        })
+    }
+}
+
+/// Point-in-time health and storage statistics for a [`UserRepository`].
+/// See [`UserRepository::stats`].
+#[derive(Debug, Clone)]
+pub struct RepositoryStats {
+    /// A short name for the storage backend (e.g. `"memory"`,
+    /// `"dynamodb"`), for distinguishing deployments in metrics.
+    pub backend: String,
+    pub user_count: u64,
+    /// How long the backend took to answer, used as a coarse readiness
+    /// signal: a healthy backend answers quickly, a struggling one is
+    /// slow before it starts erroring outright.
+    pub ping_latency: Duration,
+}
+
+/// Persists and retrieves [`UserPreferences`], independent of
+/// [`UserRepository`] so the credential store and preference store can be
+/// backed, scaled, and migrated separately.
+#[async_trait]
+pub trait UserPreferencesRepository: Send + Sync {
+    /// Returns `user_id`'s preferences, or [`UserPreferences::defaults`] if
+    /// none have been set yet.
+    async fn get(&self, user_id: &str) -> Result<UserPreferences>;
+    async fn put(&self, preferences: UserPreferences) -> Result<UserPreferences>;
+}
+
+/// Counts API calls per user per UTC calendar day, independent of
+/// [`UserRepository`] since it tracks operational usage rather than part
+/// of a user's stored profile. Backs `GET /v1/users/me/usage` and the
+/// quota-enforcement middleware in [`crate::http::usage`].
+#[async_trait]
+pub trait UsageRepository: Send + Sync {
+    /// Records one API call for `user_id` on `day` and returns the running
+    /// total for that day, including this call.
+    async fn increment(&self, user_id: &str, day: NaiveDate) -> Result<u64>;
+
+    /// Returns `user_id`'s call count for `day`, without recording a call.
+    /// `0` if none have been recorded yet.
+    async fn get(&self, user_id: &str, day: NaiveDate) -> Result<u64>;
+}
+
+/// Persists per-user feature flag overrides, independent of
+/// [`UserRepository`] since flags are operational configuration rather
+/// than part of a user's stored profile. Backs
+/// [`crate::feature_flags::FeatureFlags`].
+#[async_trait]
+pub trait FeatureOverridesRepository: Send + Sync {
+    /// Returns `user_id`'s overrides, keyed by flag name. Empty if none
+    /// have been set.
+    async fn get_overrides(&self, user_id: &str) -> Result<HashMap<String, bool>>;
+
+    /// Sets `user_id`'s override for `flag`, replacing any existing one.
+    async fn set_override(&self, user_id: &str, flag: &str, enabled: bool) -> Result<()>;
+
+    /// Clears `user_id`'s override for `flag`, if any. Idempotent:
+    /// clearing a flag with no override succeeds without changing
+    /// anything.
+    async fn clear_override(&self, user_id: &str, flag: &str) -> Result<()>;
+}
+
+/// A notification queued for a user's next batched digest email rather
+/// than sent immediately; see [`crate::notifications::NotificationMailer`]
+/// and [`crate::digest`].
+#[derive(Debug, Clone)]
+pub struct QueuedDigestNotification {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Persists notifications queued under [`NotificationCategory::Digest`]
+/// until [`crate::digest::run_once`] batches and sends them.
+#[async_trait]
+pub trait DigestQueueRepository: Send + Sync {
+    async fn enqueue(&self, user_id: &str, notification: QueuedDigestNotification) -> Result<()>;
+
+    /// Removes and returns every queued notification, grouped by user id.
+    async fn drain_all(&self) -> Result<HashMap<String, Vec<QueuedDigestNotification>>>;
+}
+
+/// An in-app notification, shown in a user's inbox (`GET
+/// /v1/users/me/notifications`) regardless of whether its
+/// [`NotificationCategory`] is also opted into email delivery — the inbox
+/// is a separate channel from [`crate::notifications::NotificationMailer`]'s
+/// mail/digest delivery, so an account that has opted out of, say,
+/// [`NotificationCategory::Product`] email still sees the event if it
+/// looks. Created alongside every [`crate::notifications::NotificationMailer::notify`]
+/// call, i.e. driven by the same domain events as outbound mail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String,
+    pub category: NotificationCategory,
+    pub subject: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    /// `None` until [`UserLogic::mark_notification_read`] (or
+    /// [`UserLogic::mark_all_notifications_read`]) is called for it.
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// One page of [`UserLogic::notifications`] results.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    /// Whether further, older notifications exist past this page.
+    pub has_more: bool,
+}
+
+/// Orders `notifications` newest first and slices out `pagination`'s page.
+/// Shared by every in-memory-filtering [`NotificationRepository::list`]
+/// implementation, the same way [`paginate_search_results`] is shared by
+/// [`UserRepository::search`] implementations.
+pub(crate) fn paginate_notifications(
+    mut notifications: Vec<Notification>,
+    pagination: Pagination,
+) -> NotificationPage {
+    notifications.sort_by_key(|notification| std::cmp::Reverse(notification.created_at));
+    let total = notifications.len();
+    let page = notifications.into_iter().skip(pagination.offset).take(pagination.limit).collect();
+    NotificationPage {
+        notifications: page,
+        has_more: pagination.offset.saturating_add(pagination.limit) < total,
+    }
+}
+
+/// Persists [`Notification`]s for a user's in-app inbox, independent of
+/// [`UserRepository`] since an inbox entry is operational rather than part
+/// of a user's stored profile. Backs
+/// [`crate::notifications::NotificationMailer`] and
+/// `GET /v1/users/me/notifications`.
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    async fn create(&self, notification: Notification) -> Result<()>;
+
+    /// Returns `user_id`'s notifications, paginated by `pagination`, newest
+    /// first.
+    async fn list(&self, user_id: &str, pagination: Pagination) -> Result<NotificationPage>;
+
+    /// Marks `id` read for `user_id` at `read_at`. Idempotent: marking an
+    /// already-read notification read again just updates `read_at`.
+    async fn mark_read(
+        &self,
+        user_id: &str,
+        id: &str,
+        read_at: DateTime<Utc>,
+    ) -> Result<Notification>;
+
+    /// Marks every one of `user_id`'s unread notifications read at
+    /// `read_at`, returning how many were updated.
+    async fn mark_all_read(&self, user_id: &str, read_at: DateTime<Utc>) -> Result<u64>;
+}
+
+/// Which push provider a registered [`DeviceToken`] is delivered through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DevicePlatform {
+    Fcm,
+    Apns,
+}
+
+/// A mobile device registered to receive push notifications, via
+/// `POST /v1/users/me/devices`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceToken {
+    pub id: String,
+    pub user_id: String,
+    pub platform: DevicePlatform,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Persists [`DeviceToken`]s, independent of [`UserRepository`] the same
+/// way [`NotificationRepository`] is — a device registration is operational
+/// rather than part of a user's stored profile. Backs
+/// [`crate::notifications::NotificationMailer`]'s push delivery and the
+/// `/v1/users/me/devices` endpoints.
+#[async_trait]
+pub trait DeviceRepository: Send + Sync {
+    /// Registers `device`, or replaces an existing registration for the
+    /// same `(user_id, token)` pair — re-registering an app reinstall
+    /// shouldn't accumulate duplicate rows.
+    async fn register(&self, device: DeviceToken) -> Result<DeviceToken>;
+
+    /// Returns every device currently registered to `user_id`.
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<DeviceToken>>;
+
+    /// Unregisters `token` from `user_id`, whether because the user asked
+    /// to or because [`crate::notifications::NotificationMailer`] got an
+    /// invalid-token response pushing to it. A no-op if it wasn't
+    /// registered.
+    async fn remove(&self, user_id: &str, token: &str) -> Result<()>;
+}
+
+/// A one-time SMS code issued by [`UserLogic::request_sms_otp`], stored
+/// hashed (never in the clear) the same way a password never is — see
+/// [`crate::logic`] for how `code_hash` is computed.
+#[derive(Debug, Clone)]
+pub struct SmsOtp {
+    pub phone: String,
+    pub code_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Persists at most one outstanding [`SmsOtp`] per phone number, backing
+/// SMS-based one-time codes as an alternative second factor to
+/// [`UserLogic::authenticate`]'s password check.
+#[async_trait]
+pub trait SmsOtpRepository: Send + Sync {
+    /// Stores `otp`, replacing any code already outstanding for the same
+    /// [`SmsOtp::phone`] — requesting a new code invalidates the old one.
+    async fn store(&self, otp: SmsOtp) -> Result<()>;
+
+    /// Removes and returns the code outstanding for `phone`, if any.
+    /// Consumed unconditionally so a code can't be replayed, whether or
+    /// not the caller's guess turns out to match it.
+    async fn take(&self, phone: &str) -> Result<Option<SmsOtp>>;
+}
+
+/// Persists a user's hashed MFA recovery codes — never the codes
+/// themselves, the same way [`SmsOtpRepository`] never stores an OTP in
+/// the clear. Backs [`UserLogic::generate_backup_codes`] and
+/// [`UserLogic::consume_backup_code`], for recovering access when
+/// [`UserLogic::verify_sms_otp`]'s registered phone is unreachable.
+#[async_trait]
+pub trait BackupCodesRepository: Send + Sync {
+    /// Replaces `user_id`'s backup codes with `code_hashes` wholesale,
+    /// invalidating every previously issued code.
+    async fn store(&self, user_id: &str, code_hashes: Vec<String>) -> Result<()>;
+
+    /// Removes `code_hash` from `user_id`'s stored codes if present,
+    /// returning whether it was there. Single-use: a consumed code can't
+    /// be replayed.
+    async fn consume(&self, user_id: &str, code_hash: &str) -> Result<bool>;
+}
+
+/// Domain operations available on users, independent of transport.
+#[async_trait]
+pub trait UserLogic: Send + Sync {
+    async fn register(&self, email: &str, password: &str) -> Result<User>;
+
+    /// Registers a [`UserKind::Service`] account identified by
+    /// `client_id` (stored in [`User::email`], its only unique handle)
+    /// and authenticated going forward with `api_key` rather than a
+    /// password — see [`UserLogic::authenticate_service_account`].
+    async fn register_service_account(&self, client_id: &str, api_key: &str) -> Result<User>;
+
+    /// Authenticates by either email address or username. Fails with
+    /// [`libsvc::repository::Error::PasswordLoginDisabled`] for a
+    /// [`UserKind::Service`] account, which has no password to check.
+    async fn authenticate(&self, identifier: &str, password: &str) -> Result<User>;
+
+    /// Authenticates a [`UserKind::Service`] account by its API key.
+    /// Fails with [`libsvc::repository::Error::NotFound`] for an
+    /// identifier that isn't a service account, the same way
+    /// [`UserLogic::authenticate`] treats a wrong password — so neither
+    /// endpoint can be used to probe which kind an account is.
+    async fn authenticate_service_account(&self, client_id: &str, api_key: &str) -> Result<User>;
+
+    /// Checks `password` against `id`'s stored credentials, for flows (such
+    /// as confirming a password change) that need to verify it without the
+    /// rate limiting and lockout handling [`UserLogic::authenticate`]
+    /// applies to a login attempt.
+    async fn verify_password(&self, id: &str, password: &str) -> Result<bool>;
+    async fn get(&self, id: &str) -> Result<User>;
+
+    /// Looks a user up by their exact email address, for flows (such as a
+    /// magic-link login) that need to resolve an email to a user without a
+    /// password.
+    async fn find_by_email(&self, email: &str) -> Result<User>;
+
+    /// Updates profile fields. A new `email` does not take effect
+    /// immediately: it starts a [`PendingEmailChange`] that must be
+    /// confirmed via [`UserLogic::confirm_email_change`].
+    async fn update(
+        &self,
+        id: &str,
+        email: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<User>;
+
+    /// Returns `true` if `username` is not already taken.
+    async fn username_available(&self, username: &str) -> Result<bool>;
+
+    /// Confirms a pending email change, swapping `email` for the
+    /// previously requested address.
+    async fn confirm_email_change(&self, id: &str, token: &str) -> Result<User>;
+
+    /// Points `id`'s profile at a newly uploaded avatar. The bytes
+    /// themselves are handled by the HTTP layer's [`foundation::blob`]
+    /// store; this only records where they ended up.
+    async fn update_avatar(&self, id: &str, avatar_url: Option<String>) -> Result<User>;
+
+    /// Finds users matching `filter`, paginated by `pagination`, for the
+    /// admin search endpoint.
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage>;
+
+    /// Fails with [`libsvc::repository::Error::LegalHold`] if `id` is
+    /// currently under [`User::legal_hold`].
+    async fn delete(&self, id: &str) -> Result<()>;
+
+    /// Assembles a complete export of everything the service holds about a
+    /// user, for the right to data portability.
+    async fn export_data(&self, id: &str) -> Result<UserDataExport>;
+
+    /// Erases a user's personal data while anonymizing (rather than
+    /// deleting) audit records that reference them, for the right to
+    /// erasure. Fails with [`libsvc::repository::Error::LegalHold`] if
+    /// `id` is currently under [`User::legal_hold`] — a hold exists
+    /// precisely to prevent this.
+    async fn erase(&self, id: &str) -> Result<()>;
+
+    /// Returns `id`'s notification, locale, and display preferences.
+    async fn get_preferences(&self, id: &str) -> Result<UserPreferences>;
+
+    /// Replaces `id`'s preferences wholesale.
+    async fn update_preferences(&self, preferences: UserPreferences) -> Result<UserPreferences>;
+
+    /// Reports the backing [`UserRepository`]'s health and storage
+    /// statistics, for the readiness endpoint and metrics export.
+    async fn repository_stats(&self) -> Result<RepositoryStats>;
+
+    /// Returns a page of `id`'s own audit timeline (registrations, logins,
+    /// profile changes, sessions issued, and the like), newest first, for
+    /// `GET /v1/users/me/activity`.
+    async fn activity(&self, id: &str, pagination: Pagination) -> Result<ActivityPage>;
+
+    /// Sets `id`'s [`UserStatus`], for admin-initiated deactivation,
+    /// banning, or reactivation. Takes effect immediately: a non-`Active`
+    /// status rejects the next login attempt (see
+    /// [`UserLogic::authenticate`]) and every already-issued session (see
+    /// `AuthenticatedUser::from_request_parts` in `users::http::auth`).
+    /// Recording *why* the status changed is the HTTP layer's job, the
+    /// same way [`crate::http::admin::impersonate`] records its own audit
+    /// event rather than pushing that detail down into this trait.
+    async fn set_status(&self, id: &str, status: UserStatus) -> Result<User>;
+
+    /// Places or lifts a legal hold on `id`'s account. While under hold,
+    /// [`UserLogic::erase`] refuses to run, and the retention purge job
+    /// (`users::retention`) skips the account's audit trail and active
+    /// sessions. Recording who placed or lifted the hold and why is the
+    /// HTTP layer's job, the same way [`UserLogic::set_status`] leaves that
+    /// to [`crate::http::admin::set_user_status`].
+    async fn set_legal_hold(&self, id: &str, hold: bool) -> Result<User>;
+
+    /// Assigns or clears `id`'s [`User::organization_id`], for admin-only
+    /// management of which organization an account belongs to. Unlike
+    /// [`UserLogic::set_status`] and [`UserLogic::set_legal_hold`], this is
+    /// restricted to [`libsvc::session::Role::Admin`] rather than also
+    /// allowing a scoped [`libsvc::session::Role::OrgAdmin`] — an org admin
+    /// reassigning a user's organization could otherwise move accounts in
+    /// or out of their own scope at will. See
+    /// [`crate::http::admin::set_user_organization`].
+    async fn set_organization(&self, id: &str, organization_id: Option<String>) -> Result<User>;
+
+    /// Returns the JSON Schema every [`UserLogic::set_custom_attributes`]
+    /// call is currently validated against, or `None` if none has been
+    /// configured, in which case any attributes are accepted.
+    async fn get_custom_attributes_schema(&self) -> Option<Value>;
+
+    /// Replaces the JSON Schema [`UserLogic::set_custom_attributes`]
+    /// validates against going forward. `None` clears it. Fails with
+    /// [`libsvc::repository::Error::ConstraintViolation`] if `schema` is
+    /// not itself a valid JSON Schema document. Takes effect immediately,
+    /// but — like [`crate::http::admin::publish_next_session_key`]'s key
+    /// ring — only for writes made after it returns; existing
+    /// `custom_attributes` already on file are not retroactively
+    /// re-validated.
+    async fn set_custom_attributes_schema(&self, schema: Option<Value>) -> Result<()>;
+
+    /// Replaces `id`'s [`User::custom_attributes`] wholesale, after
+    /// validating `attributes` against the schema currently configured via
+    /// [`UserLogic::set_custom_attributes_schema`]. Fails with
+    /// [`libsvc::repository::Error::ConstraintViolation`] if validation
+    /// fails, leaving the account's stored attributes unchanged.
+    async fn set_custom_attributes(&self, id: &str, attributes: Map<String, Value>) -> Result<User>;
+
+    /// Adds `tag` to `id`'s [`User::tags`], for admin-only segmentation of
+    /// accounts (staged rollouts, support escalations, and the like).
+    /// Idempotent: adding a tag already present is a no-op.
+    async fn add_tag(&self, id: &str, tag: String) -> Result<User>;
+
+    /// Removes `tag` from `id`'s [`User::tags`], if present. Idempotent:
+    /// removing a tag that isn't there is a no-op.
+    async fn remove_tag(&self, id: &str, tag: &str) -> Result<User>;
+
+    /// Returns a page of `id`'s own in-app notification inbox, newest
+    /// first, for `GET /v1/users/me/notifications`.
+    async fn notifications(&self, id: &str, pagination: Pagination) -> Result<NotificationPage>;
+
+    /// Marks `notification_id` read on `id`'s behalf.
+    async fn mark_notification_read(&self, id: &str, notification_id: &str) -> Result<Notification>;
+
+    /// Marks every one of `id`'s unread notifications read, returning how
+    /// many were updated.
+    async fn mark_all_notifications_read(&self, id: &str) -> Result<u64>;
+
+    /// Registers `platform`/`token` to receive push notifications for
+    /// `id`, via `POST /v1/users/me/devices`.
+    async fn register_device(
+        &self,
+        id: &str,
+        platform: DevicePlatform,
+        token: String,
+    ) -> Result<DeviceToken>;
+
+    /// Unregisters `token` from `id`'s devices, via
+    /// `DELETE /v1/users/me/devices/:token`.
+    async fn unregister_device(&self, id: &str, token: &str) -> Result<()>;
+
+    /// Generates a one-time numeric code, texts it to `id`'s
+    /// [`UserPreferences::phone`] via the configured
+    /// [`libsvc::sms::SmsSender`], and stores it (hashed) for
+    /// [`UserLogic::verify_sms_otp`] to check. Fails with
+    /// [`libsvc::repository::Error::ConstraintViolation`] if `id` has no
+    /// phone number on file.
+    async fn request_sms_otp(&self, id: &str) -> Result<()>;
+
+    /// Verifies `code` against the most recently requested, unexpired SMS
+    /// OTP for `id`, consuming it either way so it can't be replayed.
+    /// Fails with [`libsvc::repository::Error::NotFound`] if the code is
+    /// wrong, expired, or none was requested.
+    async fn verify_sms_otp(&self, id: &str, code: &str) -> Result<()>;
+
+    /// Generates 10 single-use recovery codes for `id`, replacing any
+    /// previously issued set, and returns them in the clear — the only
+    /// time they're ever available unhashed, since
+    /// [`BackupCodesRepository`] stores only their hashes. Used both to
+    /// set up recovery codes for the first time and to regenerate them
+    /// via `POST /v1/users/me/backup-codes`.
+    async fn generate_backup_codes(&self, id: &str) -> Result<Vec<String>>;
+
+    /// Consumes one of `id`'s recovery codes, as a fallback to
+    /// [`UserLogic::verify_sms_otp`] for when the registered phone is
+    /// unavailable. Fails with [`libsvc::repository::Error::NotFound`] if
+    /// `code` doesn't match any outstanding one.
+    async fn consume_backup_code(&self, id: &str, code: &str) -> Result<()>;
+
+    /// Whether `id` has enrolled the second factor
+    /// [`crate::http::state::AppState::mfa_required_roles`] demands before
+    /// a session carrying a covered role is accepted — currently, whether
+    /// they have a phone number on file for [`UserLogic::request_sms_otp`].
+    /// Checked by `AuthenticatedUser::from_request_parts` and by
+    /// `crate::http::handlers::authenticate` before issuing a session in
+    /// the first place.
+    async fn mfa_enrolled(&self, id: &str) -> Result<bool>;
+}
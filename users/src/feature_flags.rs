@@ -0,0 +1,110 @@
+//! Boolean feature flags, each with a service-wide default that can be
+//! overridden per user — for rolling a feature out to specific accounts
+//! (internal testers, a beta cohort) ahead of (or instead of) flipping it
+//! on for everyone.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::FeatureOverridesRepository;
+
+/// Evaluates a flag for a given user: their own override if one is set
+/// via [`FeatureFlags::set_override`], else the flag's service-wide
+/// default, else `false` for a flag nobody has configured at all.
+pub struct FeatureFlags {
+    defaults: RwLock<HashMap<String, bool>>,
+    overrides: Arc<dyn FeatureOverridesRepository>,
+}
+
+impl FeatureFlags {
+    pub fn new(overrides: Arc<dyn FeatureOverridesRepository>) -> Self {
+        Self {
+            defaults: RwLock::new(HashMap::new()),
+            overrides,
+        }
+    }
+
+    /// Sets `flag`'s service-wide default, used for any user without an
+    /// override of their own.
+    pub fn set_default(&self, flag: &str, enabled: bool) -> Result<()> {
+        let mut defaults = self
+            .defaults
+            .write()
+            .map_err(|_| Error::Backend("feature flag defaults lock poisoned".to_string()))?;
+        defaults.insert(flag.to_string(), enabled);
+        Ok(())
+    }
+
+    /// Returns whether `flag` is enabled for `user_id`.
+    pub async fn is_enabled(&self, flag: &str, user_id: &str) -> Result<bool> {
+        let overrides = self.overrides.get_overrides(user_id).await?;
+        if let Some(enabled) = overrides.get(flag) {
+            return Ok(*enabled);
+        }
+        let defaults = self
+            .defaults
+            .read()
+            .map_err(|_| Error::Backend("feature flag defaults lock poisoned".to_string()))?;
+        Ok(defaults.get(flag).copied().unwrap_or(false))
+    }
+
+    /// Sets `user_id`'s override for `flag`, taking precedence over the
+    /// service-wide default regardless of which way it points.
+    pub async fn set_override(&self, user_id: &str, flag: &str, enabled: bool) -> Result<()> {
+        self.overrides.set_override(user_id, flag, enabled).await
+    }
+
+    /// Clears `user_id`'s override for `flag`, if any, falling back to the
+    /// service-wide default again.
+    pub async fn clear_override(&self, user_id: &str, flag: &str) -> Result<()> {
+        self.overrides.clear_override(user_id, flag).await
+    }
+
+    /// Returns `user_id`'s overrides, keyed by flag name.
+    pub async fn overrides_for(&self, user_id: &str) -> Result<HashMap<String, bool>> {
+        self.overrides.get_overrides(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::feature_flags::MemoryFeatureOverridesRepository;
+
+    fn flags() -> FeatureFlags {
+        FeatureFlags::new(Arc::new(MemoryFeatureOverridesRepository::new()))
+    }
+
+    #[tokio::test]
+    async fn an_unconfigured_flag_is_disabled_by_default() {
+        let flags = flags();
+        assert!(!flags.is_enabled("beta-search", "1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn the_service_wide_default_applies_without_an_override() {
+        let flags = flags();
+        flags.set_default("beta-search", true).unwrap();
+        assert!(flags.is_enabled("beta-search", "1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_per_user_override_takes_precedence_over_the_default() {
+        let flags = flags();
+        flags.set_default("beta-search", true).unwrap();
+        flags.set_override("1", "beta-search", false).await.unwrap();
+        assert!(!flags.is_enabled("beta-search", "1").await.unwrap());
+        assert!(flags.is_enabled("beta-search", "2").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn clearing_an_override_falls_back_to_the_default() {
+        let flags = flags();
+        flags.set_default("beta-search", true).unwrap();
+        flags.set_override("1", "beta-search", false).await.unwrap();
+        flags.clear_override("1", "beta-search").await.unwrap();
+        assert!(flags.is_enabled("beta-search", "1").await.unwrap());
+    }
+}
@@ -0,0 +1,832 @@
+//! Endpoints restricted to admins, for acting on another user's behalf.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use foundation::id::Id;
+use foundation::key::Key;
+use libsvc::audit::AuditEvent;
+use libsvc::security_signal::{RecordedSecurityEvent, SecurityEvent};
+use libsvc::session::{Role, SessionBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::auth::AuthenticatedUser;
+use super::handlers::status_for;
+use super::state::AppState;
+use crate::domain::{Pagination, StatusChangeReason, UserSearchFilter, UserStatus};
+
+/// Authorizes `auth` to act on an account belonging to
+/// `target_organization_id`: unrestricted for [`Role::Admin`]; restricted to
+/// accounts sharing `auth`'s own [`crate::domain::User::organization_id`]
+/// for [`Role::OrgAdmin`] (an `OrgAdmin` with no organization of their own,
+/// or a target with none, is authorized for nothing); forbidden otherwise.
+pub(super) fn authorize_org_scoped(
+    auth: &AuthenticatedUser,
+    target_organization_id: Option<&str>,
+) -> Result<(), StatusCode> {
+    if auth.roles.contains(&Role::Admin) {
+        return Ok(());
+    }
+    if auth.roles.contains(&Role::OrgAdmin) {
+        if let (Some(caller_org), Some(target_org)) =
+            (auth.organization_id.as_deref(), target_organization_id)
+        {
+            if caller_org == target_org {
+                return Ok(());
+            }
+        }
+    }
+    Err(StatusCode::FORBIDDEN)
+}
+
+/// Rejects `auth` outright unless it holds [`Role::Admin`] or
+/// [`Role::OrgAdmin`]. Callers that look up the target account before
+/// calling [`authorize_org_scoped`] (to learn its `organization_id`) must
+/// run this check first — otherwise a plain [`Role::User`] could tell
+/// whether a given id exists from a 403-vs-404 response without ever
+/// holding an admin-ish role at all.
+fn require_any_admin_role(auth: &AuthenticatedUser) -> Result<(), StatusCode> {
+    if auth.roles.contains(&Role::Admin) || auth.roles.contains(&Role::OrgAdmin) {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonateResponse {
+    pub session_id: String,
+}
+
+/// `POST /v1/admin/users/{id}/impersonate`
+///
+/// Issues a session for the target user with the calling admin's id
+/// recorded in the session claims (see
+/// [`libsvc::session::Session::impersonated_by`]), and records an audit
+/// event naming both parties, so every later request made on this session
+/// can be traced back to the admin who started it.
+pub async fn impersonate(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+) -> Result<Json<ImpersonateResponse>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let target = state
+        .user_logic
+        .get(&target_id)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let admin_id =
+        Id::try_from(auth.user_id.as_str()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let target_user_id =
+        Id::try_from(target.id.as_str()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let signing_key = state
+        .session_key
+        .active()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let session = SessionBuilder::new(target_user_id, 3600)
+        .with_roles(vec![Role::User])
+        .with_issuer(state.session_validation.issuer.as_str())
+        .with_audience(state.session_validation.audience.as_str())
+        .impersonated_by(admin_id)
+        .finish(&signing_key);
+    let session_id = session.id.clone();
+    state
+        .sessions
+        .insert(session)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target.id,
+        format!("admin {} acting as user {}", auth.user_id, target.id),
+    ));
+    state.security_signal.observe(SecurityEvent::Impersonation {
+        admin_id: auth.user_id.clone(),
+        target_user_id: target.id.clone(),
+    });
+
+    Ok(Json(ImpersonateResponse { session_id }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublishNextSessionKeyRequest {
+    /// The new shared secret, as raw bytes. Operators roll it out to every
+    /// replica with this same call before any replica starts signing with
+    /// it (see `POST /v1/admin/session-key/cutover`).
+    pub secret: String,
+}
+
+/// `POST /v1/admin/session-key/publish`
+///
+/// Publishes the next session-signing key on this replica, so it accepts
+/// sessions signed with it without yet using it to sign new ones. Part of
+/// a zero-downtime key rotation: call this on every replica, then call
+/// `cutover` on every replica once all of them have it published.
+pub async fn publish_next_session_key(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<PublishNextSessionKeyRequest>,
+) -> Result<StatusCode, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    state
+        .session_key
+        .publish_next(Key::from_bytes(body.secret.into_bytes()))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `POST /v1/admin/session-key/cutover`
+///
+/// Promotes this replica's published next key to active, so it starts
+/// signing new sessions with it. Fails with `409 Conflict` if no key has
+/// been published yet, since cutting over with nothing published would
+/// silently do nothing.
+pub async fn cutover_session_key(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    state.session_key.cutover().map_err(|err| match err {
+        libsvc::session::KeyRingError::NoNextKey => StatusCode::CONFLICT,
+        libsvc::session::KeyRingError::LockPoisoned => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    state.security_signal.observe(SecurityEvent::SessionKeyRotated);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserStatusRequest {
+    pub status: UserStatus,
+    /// Why the status is changing, recorded in the audit event and
+    /// [`SecurityEvent::AccountStatusChanged`] alongside the new status,
+    /// since neither carries any structured metadata of its own.
+    pub reason: StatusChangeReason,
+}
+
+/// `PUT /v1/admin/users/{id}/status`
+///
+/// Changes `id`'s [`UserStatus`], admin-only — or org-admin, for a target
+/// within the caller's own organization; see [`authorize_org_scoped`].
+/// Takes effect immediately: a non-[`UserStatus::Active`] status rejects
+/// the target's next login attempt and every session they currently hold
+/// (see [`AuthenticatedUser::from_request_parts`]).
+pub async fn set_user_status(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+    Json(body): Json<SetUserStatusRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    let target = state
+        .user_logic
+        .set_status(&target_id, body.status)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target.id,
+        format!("status_changed_to_{}_reason_{}", body.status, body.reason),
+    ));
+    state.security_signal.observe(SecurityEvent::AccountStatusChanged {
+        admin_id: auth.user_id.clone(),
+        target_user_id: target.id.clone(),
+        status: body.status.to_string(),
+    });
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLegalHoldRequest {
+    pub hold: bool,
+}
+
+/// `PUT /v1/admin/users/{id}/legal-hold`
+///
+/// Places or lifts a legal hold on `id`'s account, admin-only — or
+/// org-admin, for a target within the caller's own organization; see
+/// [`authorize_org_scoped`]. While under hold,
+/// [`crate::domain::UserLogic::delete`] and [`crate::domain::UserLogic::erase`]
+/// refuse to run against the account, and the retention purge job
+/// (`users::retention`) skips its audit trail and active sessions.
+pub async fn set_legal_hold(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+    Json(body): Json<SetLegalHoldRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    let target = state
+        .user_logic
+        .set_legal_hold(&target_id, body.hold)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target.id,
+        if body.hold { "legal_hold_placed" } else { "legal_hold_lifted" },
+    ));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateSessionNonceRequest {
+    /// Also generate and cut over a fresh session-signing key, so sessions
+    /// forged with a leaked signing key (rather than merely a stolen
+    /// session id) stop verifying too. Defaults to `false`, since
+    /// rotating the key on every replica individually is usually the
+    /// safer sequence (see `POST /v1/admin/session-key/publish` and
+    /// `.../cutover`) — this flag is for when a single-replica deployment
+    /// needs both done at once.
+    #[serde(default)]
+    pub rotate_key: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RotateSessionNonceResponse {
+    pub sessions_revoked: usize,
+    pub key_rotated: bool,
+}
+
+/// `POST /v1/admin/security/rotate-session-nonce`
+///
+/// This service doesn't key sessions off a single rotatable nonce —
+/// [`libsvc::session::SessionManager`] tracks each issued session
+/// individually — so invalidating every outstanding session at once is
+/// realized here as revoking every one of them outright, which has the
+/// same effect: every session a client is currently holding stops
+/// working, including ones whose signing key hasn't leaked. Meant for
+/// incident response after a suspected session or key leak.
+pub async fn rotate_session_nonce(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<RotateSessionNonceRequest>,
+) -> Result<Json<RotateSessionNonceResponse>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let sessions_revoked = state
+        .sessions
+        .revoke_all()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if body.rotate_key {
+        state
+            .session_key
+            .publish_next(Key::generate())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        state
+            .session_key
+            .cutover()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        state.security_signal.observe(SecurityEvent::SessionKeyRotated);
+    }
+
+    Ok(Json(RotateSessionNonceResponse {
+        sessions_revoked,
+        key_rotated: body.rotate_key,
+    }))
+}
+
+/// The largest page [`security_events`] will return in one response,
+/// regardless of what a caller requests.
+const MAX_SECURITY_EVENTS_LIMIT: usize = 200;
+
+/// The page size [`security_events`] uses when a caller doesn't specify
+/// one.
+const DEFAULT_SECURITY_EVENTS_LIMIT: usize = 50;
+
+fn default_security_events_limit() -> usize {
+    DEFAULT_SECURITY_EVENTS_LIMIT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecurityEventsQuery {
+    #[serde(default = "default_security_events_limit")]
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecurityEventsResponse {
+    pub events: Vec<RecordedSecurityEvent>,
+}
+
+/// `GET /v1/admin/security/events`
+///
+/// Returns the most recently observed [`SecurityEvent`]s (failed logins,
+/// invalid session signatures, lockouts, impersonations, key rotations),
+/// newest first, from [`AppState::security_events`]'s own bounded
+/// retention — distinct from [`libsvc::audit::AuditLog`]'s unbounded
+/// record of ordinary CRUD actions, which is admin-scoped nowhere but is
+/// exposed per-user (and only for one's own activity) at
+/// `GET /v1/users/me/activity`.
+pub async fn security_events(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<SecurityEventsQuery>,
+) -> Result<Json<SecurityEventsResponse>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let limit = query.limit.min(MAX_SECURITY_EVENTS_LIMIT);
+    Ok(Json(SecurityEventsResponse { events: state.security_events.recent(limit) }))
+}
+
+/// The largest page [`search_users`] will return in one response, regardless
+/// of what a caller requests.
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// The page size [`search_users`] uses when a caller doesn't specify one.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+fn default_search_limit() -> usize {
+    DEFAULT_SEARCH_LIMIT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchUsersQuery {
+    pub email_prefix: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Every account implicitly holds [`Role::User`] and nothing else,
+    /// since this service grants roles per session rather than storing
+    /// them on the user (see [`UserSearchFilter::role`]). Any other value
+    /// here matches no one.
+    pub role: Option<Role>,
+    /// The closest concept this service has to "verified": whether the
+    /// account's current email has no unconfirmed change pending. See
+    /// [`UserSearchFilter::verified`].
+    pub verified: Option<bool>,
+    /// A fuzzy match against email/username, answered by whatever
+    /// [`libsvc::search_index::SearchIndex`] this deployment has
+    /// configured. See [`UserSearchFilter::text_query`].
+    pub q: Option<String>,
+    /// Matches accounts by whether they're currently under legal hold. See
+    /// [`UserSearchFilter::legal_hold`].
+    pub legal_hold: Option<bool>,
+    /// Matches accounts by [`crate::domain::UserKind`]. Defaults to
+    /// [`crate::domain::UserKind::Human`] when a caller doesn't specify
+    /// one, so service accounts stay out of ordinary admin listings
+    /// unless asked for explicitly.
+    pub kind: Option<crate::domain::UserKind>,
+    /// Matches accounts by [`crate::domain::User::organization_id`].
+    /// Honored only for a caller holding [`Role::Admin`]: a caller who
+    /// only holds [`Role::OrgAdmin`] has this forced to their own
+    /// organization regardless of what they pass here, by
+    /// [`search_users`] itself.
+    pub organization_id: Option<String>,
+    /// Together with [`SearchUsersQuery::attribute_value`], matches
+    /// accounts whose [`crate::domain::User::custom_attributes`] has this
+    /// key set to that value. Either both or neither must be given; see
+    /// [`UserSearchFilter::custom_attribute`].
+    pub attribute_key: Option<String>,
+    pub attribute_value: Option<serde_json::Value>,
+    /// Matches accounts with this exact tag in
+    /// [`crate::domain::User::tags`]. See [`UserSearchFilter::tag`].
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+}
+
+/// The fields of a [`crate::domain::User`] safe to return from an admin
+/// search — notably excluding the pending-email confirmation token, which
+/// `User`'s own `Serialize` impl would include.
+#[derive(Debug, Serialize)]
+pub struct UserSearchResult {
+    pub id: String,
+    pub email: String,
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub avatar_url: Option<String>,
+    pub kind: crate::domain::UserKind,
+    pub organization_id: Option<String>,
+    pub custom_attributes: serde_json::Map<String, serde_json::Value>,
+    pub tags: std::collections::BTreeSet<String>,
+}
+
+impl From<crate::domain::User> for UserSearchResult {
+    fn from(user: crate::domain::User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            created_at: user.created_at,
+            avatar_url: user.avatar_url,
+            kind: user.kind,
+            organization_id: user.organization_id,
+            custom_attributes: user.custom_attributes,
+            tags: user.tags,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchUsersResponse {
+    pub users: Vec<UserSearchResult>,
+    pub has_more: bool,
+}
+
+/// `GET /v1/admin/users/search`
+///
+/// Looks up users by the filters in [`SearchUsersQuery`], admin-only — or
+/// org-admin, in which case [`SearchUsersQuery::organization_id`] is
+/// overridden to the caller's own organization so the result set can never
+/// cross an organization boundary; see [`authorize_org_scoped`]. Returns
+/// [`UserSearchResult`] rather than the stored `User` record, so a caller
+/// can never get back a password hash or a pending-email confirmation
+/// token through this endpoint.
+pub async fn search_users(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<SearchUsersQuery>,
+) -> Result<Json<SearchUsersResponse>, StatusCode> {
+    let organization_id = if auth.roles.contains(&Role::Admin) {
+        query.organization_id
+    } else if auth.roles.contains(&Role::OrgAdmin) {
+        Some(auth.organization_id.clone().ok_or(StatusCode::FORBIDDEN)?)
+    } else {
+        return Err(StatusCode::FORBIDDEN);
+    };
+
+    let custom_attribute = match (query.attribute_key, query.attribute_value) {
+        (Some(key), Some(value)) => Some((key, value)),
+        (None, None) => None,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let filter = UserSearchFilter {
+        email_prefix: query.email_prefix,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        role: query.role,
+        verified: query.verified,
+        text_query: query.q,
+        legal_hold: query.legal_hold,
+        kind: Some(query.kind.unwrap_or(crate::domain::UserKind::Human)),
+        organization_id,
+        custom_attribute,
+        tag: query.tag,
+    };
+    let pagination = Pagination {
+        offset: query.offset,
+        limit: query.limit.min(MAX_SEARCH_LIMIT),
+    };
+
+    let page = state
+        .user_logic
+        .search(&filter, pagination)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    Ok(Json(SearchUsersResponse {
+        users: page.users.into_iter().map(UserSearchResult::from).collect(),
+        has_more: page.has_more,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterServiceAccountRequest {
+    /// The service's own identifier, stored as [`crate::domain::User::email`]
+    /// since it's the account's only unique handle. Not an email address in
+    /// practice, but reusing the field avoids a parallel identity column for
+    /// what's otherwise the same record shape as a human account.
+    pub client_id: String,
+    pub api_key: String,
+}
+
+/// `POST /v1/admin/service-accounts`
+///
+/// Registers a [`crate::domain::UserKind::Service`] account, admin-only
+/// since unlike [`super::handlers::register`] there's no self-service path
+/// for a caller to create one of these for itself.
+pub async fn register_service_account(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<RegisterServiceAccountRequest>,
+) -> Result<Json<UserSearchResult>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    state
+        .user_logic
+        .register_service_account(&body.client_id, &body.api_key)
+        .await
+        .map(|user| Json(UserSearchResult::from(user)))
+        .map_err(|e| status_for(&e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserOrganizationRequest {
+    pub organization_id: Option<String>,
+}
+
+/// `PUT /v1/admin/users/{id}/organization`
+///
+/// Assigns or clears `id`'s organization. Restricted to
+/// [`Role::Admin`] — not also [`Role::OrgAdmin`] — per
+/// [`crate::domain::UserLogic::set_organization`].
+pub async fn set_user_organization(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+    Json(body): Json<SetUserOrganizationRequest>,
+) -> Result<Json<UserSearchResult>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let target = state
+        .user_logic
+        .set_organization(&target_id, body.organization_id)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target.id,
+        "organization_changed",
+    ));
+
+    Ok(Json(UserSearchResult::from(target)))
+}
+
+/// `DELETE /v1/admin/users/{id}`
+///
+/// Permanently deletes `id`'s account and credentials (but, unlike
+/// [`super::handlers::erase_me`], leaves their audit trail intact rather
+/// than anonymizing it), admin-only — or org-admin, for a target within
+/// the caller's own organization; see [`authorize_org_scoped`]. Refuses to
+/// run against an account under legal hold, the same as
+/// [`crate::domain::UserLogic::erase`].
+pub async fn delete_user(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    state
+        .user_logic
+        .delete(&target_id)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(&auth.user_id, &target_id, "admin_deleted"));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomAttributesSchemaResponse {
+    pub schema: Option<serde_json::Value>,
+}
+
+/// `GET /v1/admin/custom-attributes/schema`
+///
+/// Returns the JSON Schema currently configured via
+/// [`set_custom_attributes_schema`], admin-only, or `null` if none has
+/// been set.
+pub async fn get_custom_attributes_schema(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<Json<CustomAttributesSchemaResponse>, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let schema = state.user_logic.get_custom_attributes_schema().await;
+    Ok(Json(CustomAttributesSchemaResponse { schema }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCustomAttributesSchemaRequest {
+    pub schema: Option<serde_json::Value>,
+}
+
+/// `PUT /v1/admin/custom-attributes/schema`
+///
+/// Replaces the JSON Schema every [`set_user_custom_attributes`] call is
+/// validated against, admin-only. `null` clears it, after which any
+/// attributes are accepted. Fails with `409 Conflict` if `schema` isn't
+/// itself a valid JSON Schema document; see
+/// [`crate::domain::UserLogic::set_custom_attributes_schema`].
+pub async fn set_custom_attributes_schema(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<SetCustomAttributesSchemaRequest>,
+) -> Result<StatusCode, StatusCode> {
+    if !auth.roles.contains(&Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    state
+        .user_logic
+        .set_custom_attributes_schema(body.schema)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &auth.user_id,
+        "custom_attributes_schema_changed",
+    ));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserCustomAttributesRequest {
+    pub custom_attributes: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `PUT /v1/admin/users/{id}/custom-attributes`
+///
+/// Replaces `id`'s [`crate::domain::User::custom_attributes`] wholesale,
+/// admin-only — or org-admin, for a target within the caller's own
+/// organization; see [`authorize_org_scoped`]. Fails with `409 Conflict`
+/// if the new attributes don't satisfy the schema currently configured
+/// via [`set_custom_attributes_schema`].
+pub async fn set_user_custom_attributes(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+    Json(body): Json<SetUserCustomAttributesRequest>,
+) -> Result<Json<UserSearchResult>, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    let target = state
+        .user_logic
+        .set_custom_attributes(&target_id, body.custom_attributes)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target.id,
+        "custom_attributes_changed",
+    ));
+
+    Ok(Json(UserSearchResult::from(target)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddUserTagRequest {
+    pub tag: String,
+}
+
+/// `POST /v1/admin/users/{id}/tags`
+///
+/// Adds `tag` to `id`'s [`crate::domain::User::tags`], admin-only — or
+/// org-admin, for a target within the caller's own organization; see
+/// [`authorize_org_scoped`]. Idempotent: adding a tag already present
+/// succeeds without changing anything.
+pub async fn add_user_tag(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(target_id): Path<String>,
+    Json(body): Json<AddUserTagRequest>,
+) -> Result<Json<UserSearchResult>, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    let target = state
+        .user_logic
+        .add_tag(&target_id, body.tag.clone())
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(&auth.user_id, &target.id, format!("tag_added_{}", body.tag)));
+
+    Ok(Json(UserSearchResult::from(target)))
+}
+
+/// `DELETE /v1/admin/users/{id}/tags/{tag}`
+///
+/// Removes `tag` from `id`'s [`crate::domain::User::tags`], admin-only —
+/// or org-admin, for a target within the caller's own organization; see
+/// [`authorize_org_scoped`]. Idempotent: removing a tag that isn't there
+/// succeeds without changing anything.
+pub async fn remove_user_tag(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path((target_id, tag)): Path<(String, String)>,
+) -> Result<Json<UserSearchResult>, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    let target = state
+        .user_logic
+        .remove_tag(&target_id, &tag)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(&auth.user_id, &target.id, format!("tag_removed_{tag}")));
+
+    Ok(Json(UserSearchResult::from(target)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureOverridesResponse {
+    pub overrides: std::collections::HashMap<String, bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetFeatureOverrideRequest {
+    pub enabled: bool,
+}
+
+/// `PUT /v1/admin/users/{id}/feature-flags/{flag}`
+///
+/// Sets `id`'s override for `flag`, evaluated by
+/// [`crate::feature_flags::FeatureFlags::is_enabled`] ahead of the
+/// flag's service-wide default. Admin-only — or org-admin, for a target
+/// within the caller's own organization; see [`authorize_org_scoped`].
+pub async fn set_user_feature_override(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path((target_id, flag)): Path<(String, String)>,
+    Json(body): Json<SetFeatureOverrideRequest>,
+) -> Result<Json<FeatureOverridesResponse>, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    state
+        .feature_flags
+        .set_override(&target_id, &flag, body.enabled)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target_id,
+        format!("feature_override_set_{flag}"),
+    ));
+
+    let overrides = state.feature_flags.overrides_for(&target_id).await.map_err(|e| status_for(&e))?;
+    Ok(Json(FeatureOverridesResponse { overrides }))
+}
+
+/// `DELETE /v1/admin/users/{id}/feature-flags/{flag}`
+///
+/// Clears `id`'s override for `flag`, falling back to its service-wide
+/// default again. Admin-only — or org-admin, for a target within the
+/// caller's own organization; see [`authorize_org_scoped`]. Idempotent:
+/// clearing a flag with no override succeeds without changing anything.
+pub async fn clear_user_feature_override(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path((target_id, flag)): Path<(String, String)>,
+) -> Result<Json<FeatureOverridesResponse>, StatusCode> {
+    require_any_admin_role(&auth)?;
+    let existing = state.user_logic.get(&target_id).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    authorize_org_scoped(&auth, existing.organization_id.as_deref())?;
+
+    state
+        .feature_flags
+        .clear_override(&target_id, &flag)
+        .await
+        .map_err(|e| status_for(&e))?;
+
+    state.audit_log.record(AuditEvent::new(
+        &auth.user_id,
+        &target_id,
+        format!("feature_override_cleared_{flag}"),
+    ));
+
+    let overrides = state.feature_flags.overrides_for(&target_id).await.map_err(|e| status_for(&e))?;
+    Ok(Json(FeatureOverridesResponse { overrides }))
+}
@@ -0,0 +1,221 @@
+//! Extracts the authenticated user from the session attached to a request.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum_extra::extract::CookieJar;
+use libsvc::dpop::DPoPProof;
+use libsvc::metrics::Metrics;
+use libsvc::session::Role;
+
+use super::csrf::SESSION_COOKIE;
+use super::mtls::client_cert_thumbprint;
+use super::state::AppState;
+
+/// Header carrying a DPoP proof's issued-at timestamp (unix seconds).
+const DPOP_IAT_HEADER: &str = "x-dpop-iat";
+/// Header carrying a DPoP proof's single-use nonce.
+const DPOP_NONCE_HEADER: &str = "x-dpop-nonce";
+/// Header carrying a DPoP proof's base64-encoded signature.
+const DPOP_SIGNATURE_HEADER: &str = "x-dpop-signature";
+/// How far a DPoP proof's `iat` may drift from this server's clock before
+/// it's rejected as stale or replayed.
+const DPOP_MAX_SKEW_SECS: u64 = 5;
+
+fn dpop_proof(headers: &axum::http::HeaderMap) -> Option<DPoPProof> {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let iat: u64 = header_str(DPOP_IAT_HEADER)?.parse().ok()?;
+    let nonce = header_str(DPOP_NONCE_HEADER)?.to_string();
+    let signature = header_str(DPOP_SIGNATURE_HEADER)?.to_string();
+    Some(DPoPProof { iat, nonce, signature })
+}
+
+/// The user and session identified by the current request's session.
+pub struct AuthenticatedUser {
+    pub user_id: String,
+    pub session_id: String,
+    pub roles: Vec<Role>,
+    /// Set when this session was issued to an admin impersonating
+    /// `user_id` rather than by `user_id` authenticating directly. Carries
+    /// the impersonating admin's id.
+    pub impersonated_by: Option<String>,
+    /// `user_id`'s own [`crate::domain::User::organization_id`], carried
+    /// here so a [`Role::OrgAdmin`] session's scope can be checked without
+    /// a second lookup. See `crate::http::admin::authorize_org_scoped`.
+    pub organization_id: Option<String>,
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let session = if let Some(token) = parts
+            .headers
+            .get("x-session-token")
+            .and_then(|v| v.to_str().ok())
+        {
+            // An encrypted session carries its own claims, so (unlike a
+            // plain session id) there's no store lookup here — and
+            // therefore no way to honor a revocation issued against it
+            // before it expires on its own. See
+            // [`AppState::session_encryption_key`].
+            let key = state
+                .session_encryption_key
+                .as_deref()
+                .ok_or((StatusCode::UNAUTHORIZED, "encrypted session tokens are not accepted"))?;
+            libsvc::session::decrypt(token, key)
+                .map_err(|_| (StatusCode::UNAUTHORIZED, "invalid or expired session"))?
+        } else {
+            let session_id = match parts
+                .headers
+                .get("x-session-id")
+                .and_then(|v| v.to_str().ok())
+            {
+                Some(id) => id.to_string(),
+                None => CookieJar::from_request_parts(parts, state)
+                    .await
+                    .ok()
+                    .and_then(|jar| jar.get(SESSION_COOKIE).map(|c| c.value().to_string()))
+                    .ok_or((StatusCode::UNAUTHORIZED, "missing session"))?,
+            };
+
+            match state.sessions.get(&session_id) {
+                Ok(Some(session)) => session,
+                Ok(None) => {
+                    state.metrics.increment("sessions_rejected_total");
+                    return Err((StatusCode::UNAUTHORIZED, "unknown session"));
+                }
+                Err(_) => {
+                    return Err((StatusCode::INTERNAL_SERVER_ERROR, "session store unavailable"))
+                }
+            }
+        };
+
+        if !session.verify_claims_with(&state.session_validation) {
+            state.metrics.increment("sessions_rejected_total");
+            return Err((StatusCode::UNAUTHORIZED, "invalid or expired session"));
+        }
+
+        let already_verified = state
+            .session_verification_cache
+            .is_recently_verified(&session.id, session.signature());
+        if !already_verified {
+            if !state
+                .session_key
+                .verify_with(&session, &state.session_validation)
+                .unwrap_or(false)
+            {
+                state
+                    .security_signal
+                    .observe(libsvc::security_signal::SecurityEvent::InvalidSessionSignature {
+                        session_id: session.id.clone(),
+                    });
+                state.metrics.increment("sessions_rejected_total");
+                return Err((StatusCode::UNAUTHORIZED, "invalid or expired session"));
+            }
+            state
+                .session_verification_cache
+                .record_verified(&session.id, session.signature());
+        }
+
+        if session.kind != libsvc::session::SessionKind::Access {
+            state.metrics.increment("sessions_rejected_total");
+            return Err((StatusCode::UNAUTHORIZED, "invalid or expired session"));
+        }
+
+        let now = state.clock.now().timestamp().max(0) as u64;
+        if !state
+            .sessions
+            .verify_activity(&session.id, now, state.session_idle_timeout_secs)
+            .unwrap_or(false)
+        {
+            state.metrics.increment("sessions_rejected_total");
+            return Err((StatusCode::UNAUTHORIZED, "session idle timeout exceeded"));
+        }
+
+        if state.require_cert_binding {
+            if let Some(expected) = &session.cert_thumbprint {
+                let presented = client_cert_thumbprint(&parts.headers);
+                if presented.as_deref() != Some(expected.as_str()) {
+                    state.security_signal.observe(
+                        libsvc::security_signal::SecurityEvent::CertBindingMismatch {
+                            session_id: session.id.clone(),
+                        },
+                    );
+                    state.metrics.increment("sessions_rejected_total");
+                    return Err((StatusCode::UNAUTHORIZED, "certificate binding mismatch"));
+                }
+            }
+        }
+
+        if state.require_dpop && session.dpop_thumbprint.is_some() {
+            let dpop_key = state.dpop_keys.key_for(&session.id);
+            let proof = dpop_proof(&parts.headers);
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("system clock is before the unix epoch")
+                .as_secs();
+            let verified = match (dpop_key, proof) {
+                (Some(key), Some(proof)) => libsvc::dpop::verify_proof(
+                    &key,
+                    state.dpop_nonces.as_ref(),
+                    &proof,
+                    parts.method.as_str(),
+                    parts.uri.path(),
+                    now,
+                    DPOP_MAX_SKEW_SECS,
+                )
+                .is_ok(),
+                _ => false,
+            };
+            if !verified {
+                state.security_signal.observe(
+                    libsvc::security_signal::SecurityEvent::DPoPProofInvalid {
+                        session_id: session.id.clone(),
+                    },
+                );
+                state.metrics.increment("sessions_rejected_total");
+                return Err((StatusCode::UNAUTHORIZED, "missing or invalid proof of possession"));
+            }
+        }
+
+        // A session's signature staying valid says nothing about whether
+        // the account behind it is still allowed to use it, so a status
+        // change takes effect on the very next request rather than only
+        // once the session itself expires.
+        let user = state
+            .user_logic
+            .get(&session.user_id.to_string())
+            .await
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "unknown session"))?;
+        if user.status != crate::domain::UserStatus::Active {
+            state.metrics.increment("sessions_rejected_total");
+            return Err((StatusCode::FORBIDDEN, "account is not active"));
+        }
+
+        if session.roles.iter().any(|role| state.mfa_required_roles.contains(role)) {
+            let enrolled = state
+                .user_logic
+                .mfa_enrolled(&session.user_id.to_string())
+                .await
+                .unwrap_or(false);
+            if !enrolled {
+                state.metrics.increment("sessions_rejected_total");
+                return Err((StatusCode::FORBIDDEN, "mfa enrollment required"));
+            }
+        }
+
+        state.metrics.increment("sessions_verified_total");
+        Ok(AuthenticatedUser {
+            user_id: session.user_id.to_string(),
+            session_id: session.id.clone(),
+            roles: session.roles.clone(),
+            impersonated_by: session.impersonated_by.map(|id| id.to_string()),
+            organization_id: user.organization_id,
+        })
+    }
+}
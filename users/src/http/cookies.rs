@@ -0,0 +1,74 @@
+//! Cookie-based session delivery, as an alternative to the `x-session-id`
+//! header for browser clients that can't safely hold a session token in
+//! JS-accessible storage. [`AppState::cookie_sessions_enabled`] opts a
+//! deployment in: [`crate::http::handlers::issue_session_pair`] sets both
+//! cookies built here whenever a session is issued, and
+//! [`super::with_csrf_protection`] is layered onto the router alongside it
+//! to guard against the session cookie being replayed cross-origin.
+//!
+//! [`AppState::cookie_sessions_enabled`]: super::state::AppState::cookie_sessions_enabled
+
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use base64::Engine;
+use rand::RngCore;
+
+use super::csrf::{CSRF_COOKIE, SESSION_COOKIE};
+
+/// Builds the HttpOnly session cookie set once a session is issued. The
+/// browser sends this back automatically on every request but can't read
+/// or tamper with it from JS.
+pub fn session_cookie(session_id: String) -> Cookie<'static> {
+    Cookie::build((SESSION_COOKIE, session_id))
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/")
+        .build()
+}
+
+/// Builds the companion CSRF cookie, readable by first-party JS so it can
+/// be echoed back in the `x-csrf-token` header (see [`super::csrf`]).
+pub fn csrf_cookie(token: String) -> Cookie<'static> {
+    Cookie::build((CSRF_COOKIE, token))
+        .http_only(false)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/")
+        .build()
+}
+
+/// Generates a fresh CSRF token to pair with a newly issued session; see
+/// [`csrf_cookie`]. Unrelated to the session id itself, so leaking one
+/// doesn't expose the other. URL-safe with no `=` padding, so the value
+/// round-trips through a cookie (and the `x-csrf-token` header a script
+/// echoes it into) without needing percent-encoding.
+pub fn generate_csrf_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_cookie_is_http_only_and_strict() {
+        let cookie = session_cookie("sess-1".to_string());
+        assert!(cookie.http_only().unwrap());
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+        assert_eq!(cookie.value(), "sess-1");
+    }
+
+    #[test]
+    fn csrf_cookie_is_readable_by_scripts() {
+        let cookie = csrf_cookie("token-1".to_string());
+        assert!(!cookie.http_only().unwrap());
+        assert_eq!(cookie.value(), "token-1");
+    }
+
+    #[test]
+    fn generated_csrf_tokens_are_not_reused() {
+        assert_ne!(generate_csrf_token(), generate_csrf_token());
+    }
+}
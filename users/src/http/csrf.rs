@@ -0,0 +1,155 @@
+//! Double-submit CSRF protection for cookie-based session delivery.
+//!
+//! When a session is delivered as an HttpOnly cookie (see [`super::cookies`])
+//! rather than read from the `x-session-id` header, the browser attaches it
+//! to every request automatically — including ones forged by a third-party
+//! page. The CSRF cookie counters that: it holds the same token as a
+//! non-HttpOnly cookie, so only a script running on the first-party origin
+//! can read it and copy it into the `x-csrf-token` header. A forged
+//! cross-origin request can set the session cookie but can't produce a
+//! matching header, so [`verify_csrf`] rejects it.
+//!
+//! A request with no `session_id` cookie at all has nothing for a forged
+//! cross-origin request to ride along with, so [`verify_csrf`] leaves it
+//! alone — that covers `register`/`authenticate` themselves (which issue
+//! the first cookie rather than presenting one) and any caller using the
+//! `x-session-id` header instead of cookies.
+
+use axum::extract::Request;
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_extra::extract::CookieJar;
+
+/// Name of the HttpOnly cookie carrying the session id.
+pub const SESSION_COOKIE: &str = "session_id";
+/// Name of the (non-HttpOnly) cookie carrying the CSRF token.
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Header a first-party script must echo the CSRF token back in.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Rejects mutating requests whose `x-csrf-token` header doesn't match the
+/// `csrf_token` cookie. Safe methods pass through unchecked, since they
+/// shouldn't mutate state and a plain link or image tag can't set headers.
+/// Requests presenting no `session_id` cookie also pass through unchecked;
+/// see the module docs.
+pub async fn verify_csrf(
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return Ok(next.run(request).await);
+    }
+
+    if jar.get(SESSION_COOKIE).is_none() {
+        return Ok(next.run(request).await);
+    }
+
+    let cookie_token = jar.get(CSRF_COOKIE).map(|c| c.value());
+    let header_token = request
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    match (cookie_token, header_token) {
+        (Some(cookie), Some(header)) if cookie == header => Ok(next.run(request).await),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::post;
+    use axum::Router;
+    use tower::util::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/mutate", post(|| async { StatusCode::NO_CONTENT }))
+            .route("/read", axum::routing::get(|| async { StatusCode::OK }))
+            .layer(axum::middleware::from_fn(verify_csrf))
+    }
+
+    #[tokio::test]
+    async fn safe_methods_are_never_checked() {
+        let response = app()
+            .oneshot(Request::builder().uri("/read").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn mutating_request_without_a_session_cookie_is_allowed_through() {
+        // e.g. `register`/`authenticate` themselves: there's no
+        // pre-existing session cookie yet for a forged request to ride
+        // along with, so nothing for this guard to check.
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mutate")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn mutating_request_with_a_session_cookie_but_no_csrf_token_is_rejected() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mutate")
+                    .header("cookie", format!("{SESSION_COOKIE}=sess-1"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn mutating_request_with_mismatched_token_is_rejected() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mutate")
+                    .header("cookie", format!("{SESSION_COOKIE}=sess-1; {CSRF_COOKIE}=abc"))
+                    .header(CSRF_HEADER, "def")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn mutating_request_with_matching_token_is_allowed() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mutate")
+                    .header("cookie", format!("{SESSION_COOKIE}=sess-1; {CSRF_COOKIE}=abc"))
+                    .header(CSRF_HEADER, "abc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+}
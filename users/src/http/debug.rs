@@ -0,0 +1,215 @@
+//! Unauthenticated introspection endpoints for production troubleshooting:
+//! what configuration this instance booted with, which build it's
+//! running, its environment variables, and its async runtime state.
+//! Nothing here exposes secret values, only whether one is set.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use libsvc::metrics::Metrics;
+use libsvc::telemetry;
+use serde::{Deserialize, Serialize};
+
+use super::state::AppState;
+use crate::seed::SEED_ADMIN_PASSWORD_ENV;
+
+#[derive(Debug, Serialize)]
+pub struct RuntimeConfig {
+    pub avatars_path: String,
+    pub static_files_dir: Option<String>,
+    pub static_files_mount: Option<String>,
+    pub seed_fixtures_path: Option<String>,
+    /// Whether [`SEED_ADMIN_PASSWORD_ENV`] is set, without revealing it.
+    pub seed_admin_password_overridden: bool,
+    /// `WORKER_THREADS` as configured at startup, or `None` if left at the
+    /// tokio default (one per CPU). See [`threads`] for the runtime's
+    /// actual worker count, which reflects that default when this is unset.
+    pub worker_threads_configured: Option<usize>,
+    pub max_concurrent_connections: usize,
+}
+
+/// `GET /debug/config`
+pub async fn config() -> Json<RuntimeConfig> {
+    Json(RuntimeConfig {
+        avatars_path: std::env::var("AVATARS_PATH").unwrap_or_else(|_| "avatars".to_string()),
+        static_files_dir: std::env::var("STATIC_FILES_DIR").ok(),
+        static_files_mount: std::env::var("STATIC_FILES_MOUNT").ok(),
+        seed_fixtures_path: std::env::var("SEED_FIXTURES_PATH").ok(),
+        seed_admin_password_overridden: std::env::var(SEED_ADMIN_PASSWORD_ENV).is_ok(),
+        worker_threads_configured: std::env::var("WORKER_THREADS")
+            .ok()
+            .and_then(|value| value.parse().ok()),
+        max_concurrent_connections: std::env::var("MAX_CONCURRENT_CONNECTIONS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(super::DEFAULT_MAX_CONCURRENT_CONNECTIONS),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub git_sha: &'static str,
+    pub rustc_version: &'static str,
+    pub built_at_unix: &'static str,
+}
+
+/// `GET /debug/build`
+pub async fn build() -> Json<BuildInfo> {
+    Json(BuildInfo {
+        git_sha: env!("BUILD_GIT_SHA"),
+        rustc_version: env!("BUILD_RUSTC_VERSION"),
+        built_at_unix: env!("BUILD_TIMESTAMP_UNIX"),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogLevel {
+    pub directives: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLogLevelRequest {
+    pub directives: String,
+}
+
+/// `GET /debug/log-level`
+pub async fn get_log_level(State(state): State<AppState>) -> Result<Json<LogLevel>, StatusCode> {
+    telemetry::current_directives(&state.log_level)
+        .map(|directives| Json(LogLevel { directives }))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// `PUT /debug/log-level`
+///
+/// Replaces the running instance's `tracing` filter directives (e.g.
+/// `"users=debug,info"`) without a restart.
+pub async fn set_log_level(
+    State(state): State<AppState>,
+    Json(body): Json<SetLogLevelRequest>,
+) -> Result<Json<LogLevel>, StatusCode> {
+    telemetry::set_directives(&state.log_level, &body.directives)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok(Json(LogLevel {
+        directives: body.directives,
+    }))
+}
+
+/// Env var name fragments (matched case-insensitively) whose value is
+/// never echoed back by [`env`].
+const REDACTED_NAME_FRAGMENTS: &[&str] = &["PASSWORD", "SECRET", "TOKEN", "KEY"];
+
+const REDACTED_PLACEHOLDER: &str = "<redacted>";
+
+/// `GET /debug/env`
+///
+/// Dumps the process environment with any variable whose name suggests
+/// it holds a credential (matching [`REDACTED_NAME_FRAGMENTS`]) replaced
+/// by [`REDACTED_PLACEHOLDER`], so this stays safe to leave reachable on
+/// an internal debug port.
+pub async fn env() -> Json<std::collections::BTreeMap<String, String>> {
+    let vars = std::env::vars()
+        .map(|(name, value)| {
+            let upper = name.to_ascii_uppercase();
+            if REDACTED_NAME_FRAGMENTS.iter().any(|fragment| upper.contains(fragment)) {
+                (name, REDACTED_PLACEHOLDER.to_string())
+            } else {
+                (name, value)
+            }
+        })
+        .collect();
+    Json(vars)
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuntimeThreadStats {
+    pub worker_threads: usize,
+    pub alive_tasks: usize,
+}
+
+/// `GET /debug/threads`
+///
+/// Reports the tokio runtime's worker thread count and live task count,
+/// to help tell a stuck deployment (tasks piling up, no workers making
+/// progress) from a merely idle one. Per-queue depth and blocking-pool
+/// stats require `tokio_unstable`, which this binary isn't built with,
+/// so they aren't available here.
+pub async fn threads() -> Json<RuntimeThreadStats> {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    Json(RuntimeThreadStats {
+        worker_threads: metrics.num_workers(),
+        alive_tasks: metrics.num_alive_tasks(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub backend: String,
+    pub user_count: u64,
+    pub ping_latency_ms: u128,
+}
+
+/// `GET /debug/ready`
+///
+/// Pings the user repository and reports its backend name, user count,
+/// and ping latency, so a load balancer or operator can tell a healthy
+/// instance from one whose backend is unreachable or slow. The same
+/// reading is recorded into `state.metrics` so it shows up alongside the
+/// counters and summaries at `/debug/metrics`.
+pub async fn ready(State(state): State<AppState>) -> Result<Json<ReadinessReport>, StatusCode> {
+    let stats = state
+        .user_logic
+        .repository_stats()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    state.metrics.set_gauge("repository_user_count", stats.user_count as f64);
+    state
+        .metrics
+        .observe("repository_ping_latency_seconds", stats.ping_latency.as_secs_f64());
+
+    Ok(Json(ReadinessReport {
+        backend: stats.backend,
+        user_count: stats.user_count,
+        ping_latency_ms: stats.ping_latency.as_millis(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn config_never_leaks_the_seed_admin_password() {
+        std::env::set_var(SEED_ADMIN_PASSWORD_ENV, "super-secret");
+        let Json(config) = config().await;
+        std::env::remove_var(SEED_ADMIN_PASSWORD_ENV);
+
+        assert!(config.seed_admin_password_overridden);
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert!(!serialized.contains("super-secret"));
+    }
+
+    #[tokio::test]
+    async fn build_info_reports_a_non_empty_git_sha() {
+        let Json(info) = build().await;
+        assert!(!info.git_sha.is_empty());
+    }
+
+    #[tokio::test]
+    async fn env_redacts_secret_looking_variable_names() {
+        std::env::set_var("DEBUG_ENV_TEST_SECRET", "super-secret");
+        std::env::set_var("DEBUG_ENV_TEST_PLAIN", "plain-value");
+        let Json(vars) = env().await;
+        std::env::remove_var("DEBUG_ENV_TEST_SECRET");
+        std::env::remove_var("DEBUG_ENV_TEST_PLAIN");
+
+        assert_eq!(vars["DEBUG_ENV_TEST_SECRET"], REDACTED_PLACEHOLDER);
+        assert_eq!(vars["DEBUG_ENV_TEST_PLAIN"], "plain-value");
+    }
+
+    #[tokio::test]
+    async fn thread_stats_report_at_least_one_worker() {
+        let Json(stats) = threads().await;
+        assert!(stats.worker_threads >= 1);
+    }
+}
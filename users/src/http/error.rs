@@ -0,0 +1,61 @@
+//! A JSON error body carrying a stable machine-readable `code` alongside
+//! a message localized per the caller's negotiated
+//! [`crate::http::locale::RequestLocale`].
+//!
+//! Most of this service's handlers return a bare [`StatusCode`] on
+//! failure (see [`crate::http::handlers::status_for`]) — that's enough
+//! for operational failures a client branches on by status alone.
+//! [`ApiError`] is for the validation and authentication failures a
+//! user-facing client displays to an end user, where a code to branch on
+//! and a message to show them are both worth sending.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use libsvc::i18n::{self, MessageCode};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip)]
+    pub status: StatusCode,
+}
+
+impl ApiError {
+    /// Builds an `ApiError` for `code`, rendered in `locale` (see
+    /// [`crate::http::locale::RequestLocale`]), returned to the client
+    /// with `status`.
+    pub fn new(status: StatusCode, code: MessageCode, locale: &str) -> Self {
+        let localized = i18n::message(code, locale);
+        Self {
+            code: localized.code,
+            message: localized.message,
+            status,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status;
+        (status, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn carries_the_stable_code_and_the_localized_message() {
+        let error = ApiError::new(StatusCode::UNAUTHORIZED, MessageCode::InvalidCredentials, "es-ES");
+        assert_eq!(error.code, "invalid_credentials");
+        assert_eq!(
+            error.message,
+            "El correo electrónico, nombre de usuario o contraseña que ingresaste es incorrecto."
+        );
+        assert_eq!(error.status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,1051 @@
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Extension, Multipart, Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use foundation::blob::BlobError;
+use foundation::id::Id;
+use libsvc::audit::AuditEvent;
+use libsvc::captcha::CaptchaError;
+use libsvc::fields;
+use libsvc::i18n::MessageCode;
+use libsvc::repository::Error as RepositoryError;
+use libsvc::risk::{RiskAction, RiskContext};
+use libsvc::security_signal::SecurityEvent;
+use libsvc::session::{Role, SessionBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::auth::AuthenticatedUser;
+use super::cookies::{csrf_cookie, generate_csrf_token, session_cookie};
+use super::error::ApiError;
+use super::locale::RequestLocale;
+use super::mtls::client_cert_thumbprint;
+use super::state::AppState;
+use crate::domain::{
+    NotificationCategoryPreferences, Pagination, Theme, UserDataExport, UserPreferences, UserView,
+};
+
+/// Content types accepted for an avatar upload.
+const ALLOWED_AVATAR_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp"];
+
+/// The largest avatar upload accepted, in bytes.
+pub(crate) const MAX_AVATAR_BYTES: usize = 5 * 1024 * 1024;
+
+/// Identifies the caller's device for [`libsvc::risk::RiskCondition::NewDevice`].
+/// Opaque and caller-supplied — there's no fingerprinting beyond trusting it.
+pub const DEVICE_ID_HEADER: &str = "x-device-id";
+
+/// Maps a repository error onto the status code a client should see:
+/// retryable backend failures become 503 so clients know to back off,
+/// not-found and constraint errors map onto the usual 4xx codes.
+pub(crate) fn status_for(err: &RepositoryError) -> StatusCode {
+    if err.is_retryable() {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    match err {
+        RepositoryError::NotFound => StatusCode::NOT_FOUND,
+        RepositoryError::Duplicate(_)
+        | RepositoryError::DuplicateUsername(_)
+        | RepositoryError::ConstraintViolation(_) => StatusCode::CONFLICT,
+        RepositoryError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        RepositoryError::AccountNotActive(_) => StatusCode::FORBIDDEN,
+        RepositoryError::LegalHold(_) => StatusCode::CONFLICT,
+        RepositoryError::PasswordLoginDisabled(_) => StatusCode::FORBIDDEN,
+        RepositoryError::ConnectionError { .. }
+        | RepositoryError::Timeout
+        | RepositoryError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// How long an access session issued by [`authenticate`] stays valid.
+const ACCESS_SESSION_TTL_SECS: u64 = 3600;
+
+/// How long a refresh session issued by [`authenticate`] stays valid, when
+/// [`AppState::issue_refresh_tokens`] is enabled.
+const REFRESH_SESSION_TTL_SECS: u64 = 30 * 24 * 3600;
+
+/// Identifies the kind of token [`AuthenticateResponse::session_id`] is, so
+/// clients that juggle credentials for more than one service know how to
+/// present it. This service only ever issues the one kind.
+pub(crate) const SESSION_TOKEN_TYPE: &str = "session";
+
+/// Checks `response_token` against [`AppState::captcha`], mapping a missing
+/// token or a rejected challenge onto a localized [`ApiError`].
+async fn verify_captcha(
+    state: &AppState,
+    response_token: Option<&str>,
+    locale: &str,
+) -> Result<(), ApiError> {
+    let response_token = response_token
+        .ok_or_else(|| ApiError::new(StatusCode::FORBIDDEN, MessageCode::CaptchaRequired, locale))?;
+    state.captcha.verify(response_token).await.map_err(|e| match e {
+        CaptchaError::ChallengeFailed => {
+            ApiError::new(StatusCode::FORBIDDEN, MessageCode::CaptchaFailed, locale)
+        }
+        CaptchaError::ProviderUnavailable(_) => {
+            ApiError::new(StatusCode::SERVICE_UNAVAILABLE, MessageCode::CaptchaFailed, locale)
+        }
+    })
+}
+
+/// Maps a repository error from [`register`] onto a localized
+/// [`ApiError`], picking a specific [`MessageCode`] for the failures a
+/// registration form would actually show a user next to the offending
+/// field, and falling back to [`MessageCode::InternalError`] for
+/// everything else (backend/connection failures the caller can't act on).
+fn registration_error(err: &RepositoryError, locale: &str) -> ApiError {
+    let code = match err {
+        RepositoryError::Duplicate(_) => MessageCode::EmailAlreadyRegistered,
+        RepositoryError::DuplicateUsername(_) => MessageCode::UsernameAlreadyTaken,
+        RepositoryError::ConstraintViolation(_) => MessageCode::ValidationFailed,
+        _ => MessageCode::InternalError,
+    };
+    ApiError::new(status_for(err), code, locale)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+    /// The solved CAPTCHA challenge token, required when
+    /// [`AppState::require_captcha_for_registration`] is enabled.
+    pub captcha_response: Option<String>,
+}
+
+/// `POST /v1/users/register`
+pub async fn register(
+    State(state): State<AppState>,
+    Extension(locale): Extension<RequestLocale>,
+    Json(body): Json<RegisterRequest>,
+) -> Result<Json<UserView>, ApiError> {
+    if state.require_captcha_for_registration {
+        verify_captcha(&state, body.captcha_response.as_deref(), &locale.0).await?;
+    }
+
+    state
+        .user_logic
+        .register(&body.email, &body.password)
+        .await
+        .map(|user| Json(UserView::from(user)))
+        .map_err(|e| registration_error(&e, &locale.0))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthenticateRequest {
+    /// The user's email address or username; see
+    /// [`crate::domain::UserLogic::authenticate`].
+    pub login: String,
+    pub password: String,
+    /// The solved CAPTCHA challenge token, required once `login` has
+    /// accumulated [`AppState::captcha_after_failed_logins`] consecutive
+    /// failures.
+    pub captcha_response: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthenticateResponse {
+    pub session_id: String,
+    pub token_type: &'static str,
+    /// Unix timestamp the session stops being valid at, so callers know
+    /// when to re-authenticate without having to decode the session id.
+    pub expires_at: u64,
+    pub user_id: String,
+    pub roles: Vec<Role>,
+    /// Set when [`AppState::issue_refresh_tokens`] is enabled. Exchange it
+    /// for a new session at `POST /v1/users/refresh` once `session_id`
+    /// expires, without making the user re-enter their password.
+    pub refresh_token: Option<String>,
+    /// Set when [`AppState::require_dpop`] is enabled. The base64-encoded
+    /// proof-of-possession secret the caller must sign a
+    /// [`libsvc::dpop::DPoPProof`] with on every later request; handed back
+    /// exactly once, here, since this service keeps no other copy for the
+    /// caller to ask for again.
+    pub dpop_secret: Option<String>,
+    /// Set when [`AppState::session_encryption_key`] is configured: the
+    /// access session encrypted into a compact JWE (see
+    /// [`libsvc::session::encrypt`]), presentable via `x-session-token`
+    /// instead of `session_id` when the caller would rather not expose its
+    /// claims to whatever sits between it and this service.
+    pub session_token: Option<String>,
+}
+
+/// `POST /v1/users/authenticate`
+///
+/// Fails with [`MessageCode::MfaEnrollmentRequired`] instead of issuing a
+/// session if [`AppState::mfa_required_roles`] covers a role this
+/// endpoint would grant and the account hasn't enrolled (see
+/// [`crate::domain::UserLogic::mfa_enrolled`]) — the caller is expected to
+/// enroll (e.g. `POST /v1/users/me/sms-otp/request` after setting a phone
+/// number) and retry.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    Extension(locale): Extension<RequestLocale>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(body): Json<AuthenticateRequest>,
+) -> Result<Response, ApiError> {
+    if state.captcha_after_failed_logins > 0
+        && state.failed_logins.count(&body.login) >= state.captcha_after_failed_logins
+    {
+        state.security_signal.observe(SecurityEvent::LoginLockout {
+            identifier: body.login.clone(),
+        });
+        verify_captcha(&state, body.captcha_response.as_deref(), &locale.0).await?;
+    }
+
+    let user = match state.user_logic.authenticate(&body.login, &body.password).await {
+        Ok(user) => user,
+        Err(e) => {
+            if matches!(e, RepositoryError::NotFound) {
+                state.failed_logins.record_failure(&body.login);
+            }
+            return Err(match e {
+                RepositoryError::NotFound => {
+                    ApiError::new(StatusCode::UNAUTHORIZED, MessageCode::InvalidCredentials, &locale.0)
+                }
+                _ => ApiError::new(status_for(&e), MessageCode::InternalError, &locale.0),
+            });
+        }
+    };
+    state.failed_logins.record_success(&body.login);
+
+    let device_id = headers.get(DEVICE_ID_HEADER).and_then(|value| value.to_str().ok());
+    if let Some(policy) = &state.risk_policy {
+        let new_device = device_id.is_some_and(|device_id| !state.known_devices.is_known(&user.id, device_id));
+        let context = RiskContext {
+            ip: addr.ip(),
+            country: state.geoip.country_for(addr.ip()),
+            new_device,
+            at: Utc::now(),
+        };
+        if let Some(rule) = policy.evaluate(&context) {
+            state.security_signal.observe(SecurityEvent::RiskRuleMatched {
+                identifier: body.login.clone(),
+                rule: rule.name.clone(),
+                action: rule.action.to_string(),
+            });
+            match rule.action {
+                RiskAction::Deny => {
+                    return Err(ApiError::new(StatusCode::FORBIDDEN, MessageCode::LoginDenied, &locale.0));
+                }
+                RiskAction::RequireMfa => {
+                    if !state.user_logic.mfa_enrolled(&user.id).await.unwrap_or(false) {
+                        return Err(ApiError::new(
+                            StatusCode::FORBIDDEN,
+                            MessageCode::MfaEnrollmentRequired,
+                            &locale.0,
+                        ));
+                    }
+                }
+                RiskAction::Allow => {}
+            }
+        }
+    }
+
+    if state.mfa_required_roles.contains(&Role::User)
+        && !state.user_logic.mfa_enrolled(&user.id).await.unwrap_or(false)
+    {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, MessageCode::MfaEnrollmentRequired, &locale.0));
+    }
+
+    if let Some(device_id) = device_id {
+        state.known_devices.record(&user.id, device_id);
+    }
+
+    issue_session_pair(&state, &user.id, client_cert_thumbprint(&headers).as_deref())
+        .await
+        .map_err(|status| ApiError::new(status, MessageCode::InternalError, &locale.0))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthenticateServiceAccountRequest {
+    pub client_id: String,
+    pub api_key: String,
+}
+
+/// `POST /v1/users/service-accounts/authenticate`
+///
+/// Like [`authenticate`], but for a [`crate::domain::UserKind::Service`]
+/// account signing in with its API key instead of a human's password; see
+/// [`crate::domain::UserLogic::authenticate_service_account`].
+pub async fn authenticate_service_account(
+    State(state): State<AppState>,
+    Extension(locale): Extension<RequestLocale>,
+    headers: HeaderMap,
+    Json(body): Json<AuthenticateServiceAccountRequest>,
+) -> Result<Response, ApiError> {
+    let user = state
+        .user_logic
+        .authenticate_service_account(&body.client_id, &body.api_key)
+        .await
+        .map_err(|e| match e {
+            RepositoryError::NotFound => {
+                ApiError::new(StatusCode::UNAUTHORIZED, MessageCode::InvalidCredentials, &locale.0)
+            }
+            _ => ApiError::new(status_for(&e), MessageCode::InternalError, &locale.0),
+        })?;
+
+    issue_session_pair(&state, &user.id, client_cert_thumbprint(&headers).as_deref())
+        .await
+        .map_err(|status| ApiError::new(status, MessageCode::InternalError, &locale.0))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// `POST /v1/users/refresh`
+///
+/// Exchanges a refresh session (see [`AuthenticateResponse::refresh_token`])
+/// for a new access/refresh session pair, without the caller re-sending a
+/// password. Rejects anything that isn't a currently-valid
+/// [`libsvc::session::SessionKind::Refresh`] session.
+pub async fn refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<RefreshRequest>,
+) -> Result<Response, StatusCode> {
+    let session = state
+        .sessions
+        .get(&body.refresh_token)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if session.kind != libsvc::session::SessionKind::Refresh
+        || !state
+            .session_key
+            .verify_with(&session, &state.session_validation)
+            .unwrap_or(false)
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    issue_session_pair(
+        &state,
+        &session.user_id.to_string(),
+        client_cert_thumbprint(&headers).as_deref(),
+    )
+    .await
+}
+
+/// Issues a fresh access session for `user_id`, plus a refresh session
+/// alongside it when [`AppState::issue_refresh_tokens`] is enabled. When
+/// [`AppState::require_cert_binding`] is set and `cert_thumbprint` was
+/// presented, both sessions are bound to it (see
+/// [`libsvc::session::SessionBuilder::with_cert_thumbprint`]). When
+/// [`AppState::require_dpop`] is set, both sessions are also bound to a
+/// freshly minted DPoP secret, returned once as
+/// [`AuthenticateResponse::dpop_secret`]. When
+/// [`AppState::session_encryption_key`] is set, the access session is also
+/// returned encrypted as [`AuthenticateResponse::session_token`]. When
+/// [`AppState::cookie_sessions_enabled`] is set, the access session is also
+/// delivered as an HttpOnly `session_id` cookie alongside a paired CSRF
+/// cookie (see [`crate::http::cookies`]), on top of the usual JSON body.
+pub(crate) async fn issue_session_pair(
+    state: &AppState,
+    user_id: &str,
+    cert_thumbprint: Option<&str>,
+) -> Result<Response, StatusCode> {
+    let user_id = Id::try_from(user_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let signing_key = state
+        .session_key
+        .active()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let cert_thumbprint = if state.require_cert_binding { cert_thumbprint } else { None };
+    let dpop = if state.require_dpop { Some(libsvc::dpop::generate_secret()) } else { None };
+
+    let mut session_builder = SessionBuilder::new(user_id, ACCESS_SESSION_TTL_SECS)
+        .with_roles(vec![Role::User])
+        .with_issuer(state.session_validation.issuer.as_str())
+        .with_audience(state.session_validation.audience.as_str());
+    if let Some(thumbprint) = cert_thumbprint {
+        session_builder = session_builder.with_cert_thumbprint(thumbprint);
+    }
+    if let Some((_, dpop_key)) = &dpop {
+        session_builder = session_builder.with_dpop_thumbprint(libsvc::dpop::thumbprint(dpop_key));
+    }
+    let session = session_builder.finish(&signing_key);
+    let session_id = session.id.clone();
+    let expires_at = session.expires_at;
+    let roles = session.roles.clone();
+    if let Some((_, dpop_key)) = &dpop {
+        state.dpop_keys.insert(session_id.clone(), dpop_key.clone());
+    }
+    let session_token = state
+        .session_encryption_key
+        .as_deref()
+        .map(|key| libsvc::session::encrypt(&session, key));
+    state
+        .sessions
+        .insert(session)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let refresh_token = if state.issue_refresh_tokens {
+        let mut refresh_builder = SessionBuilder::new(user_id, REFRESH_SESSION_TTL_SECS)
+            .with_roles(roles.clone())
+            .with_issuer(state.session_validation.issuer.as_str())
+            .with_audience(state.session_validation.audience.as_str())
+            .as_refresh_token();
+        if let Some(thumbprint) = cert_thumbprint {
+            refresh_builder = refresh_builder.with_cert_thumbprint(thumbprint);
+        }
+        if let Some((_, dpop_key)) = &dpop {
+            refresh_builder = refresh_builder.with_dpop_thumbprint(libsvc::dpop::thumbprint(dpop_key));
+        }
+        let refresh_session = refresh_builder.finish(&signing_key);
+        let refresh_token = refresh_session.id.clone();
+        if let Some((_, dpop_key)) = &dpop {
+            state.dpop_keys.insert(refresh_token.clone(), dpop_key.clone());
+        }
+        state
+            .sessions
+            .insert(refresh_session)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Some(refresh_token)
+    } else {
+        None
+    };
+
+    state.audit_log.record(AuditEvent::new(
+        user_id.to_string(),
+        user_id.to_string(),
+        "session_created",
+    ));
+
+    let response = AuthenticateResponse {
+        session_id,
+        token_type: SESSION_TOKEN_TYPE,
+        expires_at,
+        user_id: user_id.to_string(),
+        roles,
+        refresh_token,
+        dpop_secret: dpop.map(|(secret, _)| secret),
+        session_token,
+    };
+
+    if state.cookie_sessions_enabled {
+        let jar = CookieJar::new()
+            .add(session_cookie(response.session_id.clone()))
+            .add(csrf_cookie(generate_csrf_token()));
+        Ok((jar, Json(response)).into_response())
+    } else {
+        Ok(Json(response).into_response())
+    }
+}
+
+/// How long a magic-link token stays redeemable.
+const MAGIC_LINK_TTL_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+/// `POST /v1/users/magic-link`
+///
+/// Always responds `202 Accepted`, whether or not `email` belongs to a
+/// registered user, so the endpoint can't be used to enumerate accounts.
+/// When it does, mails a single-use token (a [`libsvc::session::SessionKind::MagicLink`]
+/// session, stored and expired by [`AppState::sessions`] like any other)
+/// that [`verify_magic_link`] exchanges for a real session.
+pub async fn request_magic_link(
+    State(state): State<AppState>,
+    Json(body): Json<MagicLinkRequest>,
+) -> StatusCode {
+    if let Ok(user) = state.user_logic.find_by_email(&body.email).await {
+        if user.kind == crate::domain::UserKind::Service {
+            return StatusCode::ACCEPTED;
+        }
+        if let (Ok(user_id), Ok(signing_key)) =
+            (Id::try_from(user.id.as_str()), state.session_key.active())
+        {
+            let link_session = SessionBuilder::new(user_id, MAGIC_LINK_TTL_SECS)
+                .with_issuer(state.session_validation.issuer.as_str())
+                .with_audience(state.session_validation.audience.as_str())
+                .as_magic_link()
+                .finish(&signing_key);
+            let token = link_session.id.clone();
+            if state.sessions.insert(link_session).is_ok() {
+                state.mailer.send(
+                    &user.email,
+                    "Your sign-in link",
+                    &format!(
+                        "Use this link to sign in: /v1/users/magic-link/verify?token={token}"
+                    ),
+                );
+            }
+        }
+    }
+    StatusCode::ACCEPTED
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MagicLinkVerifyQuery {
+    pub token: String,
+}
+
+/// `GET /v1/users/magic-link/verify?token=...`
+///
+/// Exchanges a magic-link token for the same session pair [`authenticate`]
+/// issues. The token is single-use: it's revoked the moment it's looked
+/// up, whether or not the exchange that follows succeeds.
+pub async fn verify_magic_link(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MagicLinkVerifyQuery>,
+) -> Result<Response, StatusCode> {
+    let session = state
+        .sessions
+        .get(&query.token)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    state
+        .sessions
+        .revoke(&session.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if session.kind != libsvc::session::SessionKind::MagicLink
+        || !state
+            .session_key
+            .verify_with(&session, &state.session_validation)
+            .unwrap_or(false)
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    issue_session_pair(
+        &state,
+        &session.user_id.to_string(),
+        client_cert_thumbprint(&headers).as_deref(),
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldsQuery {
+    /// A comma-separated list of top-level fields to include in the
+    /// response, e.g. `?fields=id,email`. Omitted entirely, every field is
+    /// returned; see [`libsvc::fields::project`].
+    pub fields: Option<String>,
+}
+
+/// `GET /v1/users/me`
+pub async fn get_me(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<FieldsQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let user = state.user_logic.get(&auth.user_id).await.map_err(|e| status_for(&e))?;
+    let preferences =
+        state.user_logic.get_preferences(&auth.user_id).await.map_err(|e| status_for(&e))?;
+    let view = UserView::with_timezone(user, &preferences.timezone);
+    Ok(Json(fields::project(&view, query.fields.as_deref())))
+}
+
+/// The largest page [`activity`] will return in one response, regardless
+/// of what a caller requests.
+const MAX_ACTIVITY_LIMIT: usize = 100;
+
+/// The page size [`activity`] uses when a caller doesn't specify one.
+const DEFAULT_ACTIVITY_LIMIT: usize = 20;
+
+fn default_activity_limit() -> usize {
+    DEFAULT_ACTIVITY_LIMIT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_activity_limit")]
+    pub limit: usize,
+}
+
+/// `GET /v1/users/me/activity`
+///
+/// Returns a page of the caller's own audit timeline (registrations,
+/// logins, profile changes, sessions issued), newest first, merged from
+/// [`AppState::audit_log`] via [`crate::domain::UserLogic::activity`].
+pub async fn activity(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<crate::domain::ActivityPage>, StatusCode> {
+    let pagination = Pagination { offset: query.offset, limit: query.limit.min(MAX_ACTIVITY_LIMIT) };
+    state
+        .user_logic
+        .activity(&auth.user_id, pagination)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// The largest page [`list_notifications`] will return in one response,
+/// regardless of what a caller requests.
+const MAX_NOTIFICATIONS_LIMIT: usize = 100;
+
+/// The page size [`list_notifications`] uses when a caller doesn't specify
+/// one.
+const DEFAULT_NOTIFICATIONS_LIMIT: usize = 20;
+
+fn default_notifications_limit() -> usize {
+    DEFAULT_NOTIFICATIONS_LIMIT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationsQuery {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_notifications_limit")]
+    pub limit: usize,
+}
+
+/// `GET /v1/users/me/notifications`
+///
+/// Returns a page of the caller's own in-app notification inbox, newest
+/// first, via [`crate::domain::UserLogic::notifications`].
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<NotificationsQuery>,
+) -> Result<Json<crate::domain::NotificationPage>, StatusCode> {
+    let pagination =
+        Pagination { offset: query.offset, limit: query.limit.min(MAX_NOTIFICATIONS_LIMIT) };
+    state
+        .user_logic
+        .notifications(&auth.user_id, pagination)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// `POST /v1/users/me/notifications/:id/read`
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<Json<crate::domain::Notification>, StatusCode> {
+    state
+        .user_logic
+        .mark_notification_read(&auth.user_id, &id)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// `POST /v1/users/me/notifications/read-all`
+pub async fn mark_all_notifications_read(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .mark_all_notifications_read(&auth.user_id)
+        .await
+        .map_err(|e| status_for(&e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterDeviceRequest {
+    pub platform: crate::domain::DevicePlatform,
+    pub token: String,
+}
+
+/// `POST /v1/users/me/devices`
+pub async fn register_device(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<RegisterDeviceRequest>,
+) -> Result<Json<crate::domain::DeviceToken>, StatusCode> {
+    state
+        .user_logic
+        .register_device(&auth.user_id, body.platform, body.token)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// `DELETE /v1/users/me/devices/:token`
+pub async fn unregister_device(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Path(token): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .unregister_device(&auth.user_id, &token)
+        .await
+        .map_err(|e| status_for(&e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `POST /v1/users/me/sms-otp/request`
+pub async fn request_sms_otp(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .request_sms_otp(&auth.user_id)
+        .await
+        .map_err(|e| status_for(&e))?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifySmsOtpRequest {
+    pub code: String,
+}
+
+/// `POST /v1/users/me/sms-otp/verify`
+pub async fn verify_sms_otp(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<VerifySmsOtpRequest>,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .verify_sms_otp(&auth.user_id, &body.code)
+        .await
+        .map_err(|e| status_for(&e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupCodesResponse {
+    /// Shown exactly once — the server only ever stores these hashed, so
+    /// this response is the caller's only chance to display or download
+    /// them.
+    pub codes: Vec<String>,
+}
+
+/// `POST /v1/users/me/backup-codes`. Generates a fresh set of MFA
+/// recovery codes, invalidating any previously issued ones — used both
+/// for first-time setup and to regenerate.
+pub async fn generate_backup_codes(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<Json<BackupCodesResponse>, StatusCode> {
+    state
+        .user_logic
+        .generate_backup_codes(&auth.user_id)
+        .await
+        .map(|codes| Json(BackupCodesResponse { codes }))
+        .map_err(|e| status_for(&e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsumeBackupCodeRequest {
+    pub code: String,
+}
+
+/// `POST /v1/users/me/backup-codes/consume`
+pub async fn consume_backup_code(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<ConsumeBackupCodeRequest>,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .consume_backup_code(&auth.user_id, &body.code)
+        .await
+        .map_err(|e| status_for(&e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET /v1/users/me/data-export`
+pub async fn export_data(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<Json<UserDataExport>, StatusCode> {
+    state
+        .user_logic
+        .export_data(&auth.user_id)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// The action [`libsvc::action_token`] tokens minted by
+/// [`mint_data_export_token`] authorize — kept as a constant so
+/// [`mint_data_export_token`] and the `verify_action_token` layer guarding
+/// [`download_export`] (see [`crate::http::router`]) can't drift apart.
+pub(crate) const DOWNLOAD_EXPORT_ACTION: &str = "download_export";
+
+/// How long a minted data-export link stays usable. Short enough that a
+/// link sitting unused in an inbox stops being a standing way in.
+const DATA_EXPORT_TOKEN_TTL_SECS: u64 = 5 * 60;
+
+#[derive(Serialize)]
+pub struct ActionTokenResponse {
+    pub token: String,
+    pub expires_at: u64,
+}
+
+/// `POST /v1/users/me/data-export/token`
+///
+/// Mints a short-lived [`libsvc::action_token`] authorizing exactly one
+/// download of this user's data export, for callers that want to hand the
+/// link itself to something else (a download manager, a mailed link)
+/// rather than carrying the full session along with it.
+pub async fn mint_data_export_token(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Json<ActionTokenResponse> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("system clock is before the unix epoch")
+        .as_secs();
+    let token = libsvc::action_token::mint(
+        &state.action_token_key,
+        DOWNLOAD_EXPORT_ACTION,
+        &auth.user_id,
+        now,
+        DATA_EXPORT_TOKEN_TTL_SECS,
+    );
+    Json(ActionTokenResponse { token, expires_at: now + DATA_EXPORT_TOKEN_TTL_SECS })
+}
+
+/// `GET /v1/users/me/data-export/download`
+///
+/// Like [`export_data`], but authorized by a single-action
+/// [`libsvc::action_token`] (see [`mint_data_export_token`]) instead of a
+/// session — the `verify_action_token` layer guarding this route (see
+/// [`crate::http::router`]) has already checked the token and inserted
+/// the resource it authorizes as [`super::action_token::AuthorizedResource`]
+/// by the time this handler runs.
+pub async fn download_export(
+    State(state): State<AppState>,
+    Extension(resource): Extension<super::action_token::AuthorizedResource>,
+) -> Result<Json<UserDataExport>, StatusCode> {
+    state
+        .user_logic
+        .export_data(&resource.0)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// `DELETE /v1/users/me`
+pub async fn erase_me(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .user_logic
+        .erase(&auth.user_id)
+        .await
+        .map_err(|e| status_for(&e))?;
+    state
+        .sessions
+        .revoke(&auth.session_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// `PUT /v1/users/me/password`
+///
+/// Revokes every other session belonging to this user once the password is
+/// changed, since a leaked password invalidates the trust placed in any
+/// session issued under the old one.
+pub async fn change_password(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let current_password_ok = state
+        .user_logic
+        .verify_password(&auth.user_id, &body.current_password)
+        .await
+        .map_err(|e| status_for(&e))?;
+    if !current_password_ok {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    state
+        .user_logic
+        .update(&auth.user_id, None, None, Some(body.new_password))
+        .await
+        .map_err(|e| status_for(&e))?;
+    state
+        .sessions
+        .revoke_all_for_user(&auth.user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `POST /v1/users/me/logout-all`
+pub async fn logout_all(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .sessions
+        .revoke_all_for_user(&auth.user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailChangeRequest {
+    pub token: String,
+}
+
+/// `POST /v1/users/me/email/confirm`
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<ConfirmEmailChangeRequest>,
+) -> Result<Json<UserView>, StatusCode> {
+    state
+        .user_logic
+        .confirm_email_change(&auth.user_id, &body.token)
+        .await
+        .map(|user| Json(UserView::from(user)))
+        .map_err(|e| status_for(&e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityQuery {
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AvailabilityResponse {
+    pub available: bool,
+}
+
+/// `GET /v1/users/availability?username=...`
+pub async fn check_availability(
+    State(state): State<AppState>,
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<Json<AvailabilityResponse>, StatusCode> {
+    state
+        .user_logic
+        .username_available(&query.username)
+        .await
+        .map(|available| Json(AvailabilityResponse { available }))
+        .map_err(|e| status_for(&e))
+}
+
+/// `GET /v1/users/me/preferences`
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<FieldsQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    state
+        .user_logic
+        .get_preferences(&auth.user_id)
+        .await
+        .map(|preferences| Json(fields::project(&preferences, query.fields.as_deref())))
+        .map_err(|e| status_for(&e))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutPreferencesRequest {
+    pub notifications_enabled: bool,
+    pub locale: String,
+    pub timezone: String,
+    pub theme: Theme,
+    /// Defaults to every category enabled if omitted, matching
+    /// [`NotificationCategoryPreferences::default`].
+    #[serde(default)]
+    pub notification_categories: NotificationCategoryPreferences,
+    /// The number [`UserLogic::request_sms_otp`] texts a one-time code
+    /// to. Defaults to unset if omitted.
+    #[serde(default)]
+    pub phone: Option<String>,
+}
+
+/// `PUT /v1/users/me/preferences`
+pub async fn put_preferences(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<PutPreferencesRequest>,
+) -> Result<Json<UserPreferences>, StatusCode> {
+    let preferences = UserPreferences {
+        user_id: auth.user_id,
+        notifications_enabled: body.notifications_enabled,
+        locale: body.locale,
+        timezone: body.timezone,
+        theme: body.theme,
+        notification_categories: body.notification_categories,
+        phone: body.phone,
+    };
+    state
+        .user_logic
+        .update_preferences(preferences)
+        .await
+        .map(Json)
+        .map_err(|e| status_for(&e))
+}
+
+/// `PUT /v1/users/me/avatar`
+///
+/// Accepts a single-part multipart upload (any field name), validates its
+/// content type and size, stores the bytes in [`AppState::avatars`] keyed
+/// by the user's id, and points the profile's `avatar_url` at
+/// `GET /v1/avatars/:id`.
+pub async fn put_avatar(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    mut multipart: Multipart,
+) -> Result<Json<UserView>, StatusCode> {
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let content_type = field
+        .content_type()
+        .ok_or(StatusCode::BAD_REQUEST)?
+        .to_string();
+    if !ALLOWED_AVATAR_TYPES.contains(&content_type.as_str()) {
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+    let data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+    if data.len() > MAX_AVATAR_BYTES {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    state
+        .avatars
+        .put(&auth.user_id, &content_type, data.to_vec())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state
+        .user_logic
+        .update_avatar(
+            &auth.user_id,
+            Some(format!("/v1/avatars/{}", auth.user_id)),
+        )
+        .await
+        .map(|user| Json(UserView::from(user)))
+        .map_err(|e| status_for(&e))
+}
+
+/// `GET /v1/avatars/:id`
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, StatusCode> {
+    let (content_type, data) = state.avatars.get(&id).await.map_err(|e| match e {
+        BlobError::NotFound(_) => StatusCode::NOT_FOUND,
+        BlobError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    Ok(([(CONTENT_TYPE, content_type)], data).into_response())
+}
@@ -0,0 +1,118 @@
+//! Rejects connections by IP allow/deny list or GeoIP country before they
+//! reach any handler — and so before [`crate::http::auth::AuthenticatedUser`]
+//! ever gets a chance to run. Every rejection is audited.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use libsvc::audit::{AuditEvent, AuditLog};
+use libsvc::geoip::GeoIpLookup;
+use libsvc::ip_access::IpAccessControl;
+
+/// Shared state behind [`with_ip_filter`]'s middleware function.
+#[derive(Clone)]
+pub struct IpFilter {
+    control: Arc<IpAccessControl>,
+    geoip: Arc<dyn GeoIpLookup>,
+    audit_log: Arc<dyn AuditLog>,
+}
+
+impl IpFilter {
+    pub fn new(
+        control: Arc<IpAccessControl>,
+        geoip: Arc<dyn GeoIpLookup>,
+        audit_log: Arc<dyn AuditLog>,
+    ) -> Self {
+        Self { control, geoip, audit_log }
+    }
+}
+
+/// Layers [`IpFilter`] onto `router`. Requires the server to be served
+/// with `into_make_service_with_connect_info::<SocketAddr>()`, since the
+/// middleware reads the connecting address from [`ConnectInfo`].
+pub fn with_ip_filter(router: axum::Router, filter: IpFilter) -> axum::Router {
+    router.layer(axum::middleware::from_fn_with_state(filter, filter_ip_access))
+}
+
+async fn filter_ip_access(
+    State(filter): State<IpFilter>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = addr.ip();
+    if let Err(reason) = filter.control.check(ip, filter.geoip.as_ref()) {
+        filter.audit_log.record(AuditEvent::new(
+            ip.to_string(),
+            "ip-access",
+            format!("connection blocked: {reason}"),
+        ));
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use axum::routing::get;
+    use axum::Router;
+    use libsvc::audit::MemoryAuditLog;
+    use libsvc::geoip::NoopGeoIpLookup;
+    use libsvc::ip_access::IpAccessConfig;
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn test_router(filter: IpFilter) -> Router {
+        with_ip_filter(Router::new().route("/work", get(|| async { "ok" })), filter)
+    }
+
+    fn request_from(ip: IpAddr) -> HttpRequest<Body> {
+        let mut request = HttpRequest::builder().uri("/work").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(ip, 12345)));
+        request
+    }
+
+    #[tokio::test]
+    async fn an_allowed_address_passes_through() {
+        let filter = IpFilter::new(
+            Arc::new(IpAccessControl::new(IpAccessConfig::default())),
+            Arc::new(NoopGeoIpLookup),
+            Arc::new(MemoryAuditLog::new()),
+        );
+        let response = test_router(filter)
+            .oneshot(request_from(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_denylisted_address_gets_403_and_an_audit_event() {
+        let audit_log = Arc::new(MemoryAuditLog::new());
+        let filter = IpFilter::new(
+            Arc::new(IpAccessControl::new(IpAccessConfig {
+                denylist: vec!["203.0.113.0/24".parse().unwrap()],
+                ..Default::default()
+            })),
+            Arc::new(NoopGeoIpLookup),
+            audit_log.clone(),
+        );
+        let response = test_router(filter)
+            .oneshot(request_from(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(audit_log.for_subject("ip-access").len(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+//! Adaptive load shedding: once too many requests are in flight, or
+//! recent latency suggests the service is already struggling to keep
+//! up, new requests get `503 Service Unavailable` immediately instead
+//! of queueing behind [`crate::http::with_request_timeout`]'s deadline.
+//! Health checks and similar low-cost, high-value endpoints are exempt
+//! so a struggling instance can still be told apart from a dead one.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use libsvc::metrics::Metrics;
+
+/// Weight given to the latest request's latency in the rolling average
+/// [`LoadShedder`] compares against [`LoadSheddingConfig::p99_target`].
+/// Not a true percentile (that needs a real histogram) — an
+/// exponentially-weighted average that leans toward recent requests is a
+/// cheap stand-in that still reacts to a latency spike within a handful
+/// of requests.
+const LATENCY_EWMA_ALPHA: f64 = 0.1;
+
+/// Tunables for [`with_load_shedding`].
+#[derive(Debug, Clone)]
+pub struct LoadSheddingConfig {
+    /// Maximum number of requests allowed in flight at once. Requests
+    /// past this are shed rather than queued.
+    pub max_in_flight: usize,
+    /// Once the rolling latency average (see [`LATENCY_EWMA_ALPHA`])
+    /// exceeds this, new requests are shed even below `max_in_flight`,
+    /// on the theory that the service is already behind.
+    pub p99_target: Duration,
+    /// Request paths exempt from shedding, matched exactly (e.g.
+    /// `/debug/ready`).
+    pub exempt_paths: HashSet<String>,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 512,
+            p99_target: Duration::from_secs(1),
+            exempt_paths: HashSet::new(),
+        }
+    }
+}
+
+/// Shared state behind [`with_load_shedding`]'s middleware function.
+#[derive(Clone)]
+pub struct LoadShedder {
+    config: Arc<LoadSheddingConfig>,
+    in_flight: Arc<AtomicUsize>,
+    latency_ewma_micros: Arc<AtomicU64>,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig, metrics: Arc<dyn Metrics>) -> Self {
+        Self {
+            config: Arc::new(config),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            latency_ewma_micros: Arc::new(AtomicU64::new(0)),
+            metrics,
+        }
+    }
+
+    fn latency_ewma(&self) -> Duration {
+        Duration::from_micros(self.latency_ewma_micros.load(Ordering::Relaxed))
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros() as f64;
+        let previous = self.latency_ewma_micros.load(Ordering::Relaxed) as f64;
+        let updated = if previous == 0.0 {
+            sample
+        } else {
+            (LATENCY_EWMA_ALPHA * sample) + ((1.0 - LATENCY_EWMA_ALPHA) * previous)
+        };
+        self.latency_ewma_micros.store(updated as u64, Ordering::Relaxed);
+    }
+}
+
+/// Layers [`LoadShedder`] onto `router`'s middleware stack.
+pub fn with_load_shedding(router: axum::Router, shedder: LoadShedder) -> axum::Router {
+    router.layer(axum::middleware::from_fn_with_state(shedder, shed_excess_load))
+}
+
+async fn shed_excess_load(State(shedder): State<LoadShedder>, request: Request, next: Next) -> Response {
+    if shedder.config.exempt_paths.contains(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let in_flight = shedder.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+    let over_capacity = in_flight > shedder.config.max_in_flight;
+    let over_latency_target = shedder.latency_ewma() > shedder.config.p99_target;
+
+    if over_capacity || over_latency_target {
+        shedder.in_flight.fetch_sub(1, Ordering::SeqCst);
+        shedder.metrics.increment("requests_shed_total");
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    shedder.in_flight.fetch_sub(1, Ordering::SeqCst);
+    shedder.record_latency(started.elapsed());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use axum::routing::get;
+    use axum::Router;
+    use libsvc::metrics::NoopMetrics;
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn test_router(shedder: LoadShedder) -> Router {
+        with_load_shedding(
+            Router::new().route("/work", get(|| async { "ok" })).route(
+                "/debug/ready",
+                get(|| async { "ready" }),
+            ),
+            shedder,
+        )
+    }
+
+    #[tokio::test]
+    async fn requests_within_capacity_succeed() {
+        let shedder = LoadShedder::new(
+            LoadSheddingConfig {
+                max_in_flight: 10,
+                ..Default::default()
+            },
+            Arc::new(NoopMetrics),
+        );
+        let response = test_router(shedder)
+            .oneshot(HttpRequest::builder().uri("/work").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_request_past_max_in_flight_is_shed_with_503() {
+        let shedder = LoadShedder::new(
+            LoadSheddingConfig {
+                max_in_flight: 0,
+                ..Default::default()
+            },
+            Arc::new(NoopMetrics),
+        );
+        let response = test_router(shedder)
+            .oneshot(HttpRequest::builder().uri("/work").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn an_exempt_path_is_never_shed() {
+        let shedder = LoadShedder::new(
+            LoadSheddingConfig {
+                max_in_flight: 0,
+                exempt_paths: ["/debug/ready".to_string()].into_iter().collect(),
+                ..Default::default()
+            },
+            Arc::new(NoopMetrics),
+        );
+        let response = test_router(shedder)
+            .oneshot(HttpRequest::builder().uri("/debug/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn exceeding_the_latency_target_sheds_even_below_max_in_flight() {
+        let shedder = LoadShedder::new(
+            LoadSheddingConfig {
+                max_in_flight: 100,
+                p99_target: Duration::from_millis(5),
+                ..Default::default()
+            },
+            Arc::new(NoopMetrics),
+        );
+        shedder.record_latency(Duration::from_secs(1));
+
+        let response = test_router(shedder)
+            .oneshot(HttpRequest::builder().uri("/work").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,84 @@
+//! Resolves a request's locale from its `Accept-Language` header, so a
+//! [`crate::http::error::ApiError`] built further down the stack knows
+//! which catalog to render its message from.
+
+use axum::extract::Request;
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::middleware::Next;
+use axum::response::Response;
+use libsvc::i18n;
+
+/// The locales this deployment has message catalogs for. Kept here
+/// rather than in [`libsvc::i18n`] since it's a deployment-level choice
+/// of which locales to serve, not a property of the catalog itself.
+pub const SUPPORTED_LOCALES: &[&str] = &[i18n::DEFAULT_LOCALE, "es-ES"];
+
+/// The locale negotiated for the current request. Inserted by
+/// [`negotiate_locale`] and read back out via the `Extension<RequestLocale>`
+/// extractor by handlers that build a localized [`crate::http::error::ApiError`].
+#[derive(Debug, Clone)]
+pub struct RequestLocale(pub String);
+
+/// Layered onto [`super::router`]. Negotiates `request`'s locale from its
+/// `Accept-Language` header against [`SUPPORTED_LOCALES`] and makes the
+/// result available to handlers as a [`RequestLocale`] extension.
+pub async fn negotiate_locale(mut request: Request, next: Next) -> Response {
+    let accept_language = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    let locale = i18n::negotiate_locale(accept_language, SUPPORTED_LOCALES);
+    request.extensions_mut().insert(RequestLocale(locale));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use axum::routing::get;
+    use axum::{middleware, Extension, Router};
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    async fn echo_locale(Extension(locale): Extension<RequestLocale>) -> String {
+        locale.0
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(echo_locale))
+            .layer(middleware::from_fn(negotiate_locale))
+    }
+
+    #[tokio::test]
+    async fn a_supported_accept_language_is_negotiated() {
+        let response = app()
+            .oneshot(
+                HttpRequest::builder()
+                    .uri("/")
+                    .header(ACCEPT_LANGUAGE, "es-ES,es;q=0.9")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"es-ES");
+    }
+
+    #[tokio::test]
+    async fn a_missing_accept_language_defaults_to_the_default_locale() {
+        let response = app()
+            .oneshot(HttpRequest::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], i18n::DEFAULT_LOCALE.as_bytes());
+    }
+}
@@ -0,0 +1,105 @@
+//! An in-process [`Metrics`] sink that exports counters and observations
+//! in Prometheus's text exposition format, so this service's own activity
+//! — logins, login failures, sessions issued/verified/rejected — shows up
+//! next to whatever else scrapes [`metrics`]. No external metrics crate:
+//! a counter is just a name and a running total, and a summary is just a
+//! count and a sum, so plain maps behind a mutex are all this needs.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use libsvc::metrics::Metrics;
+
+use super::state::AppState;
+
+/// Counters and observation summaries recorded so far, keyed by metric
+/// name. Kept in two maps (rather than one enum) since a counter only
+/// ever needs a running total, while an observation needs a count and a
+/// sum to report as a Prometheus summary.
+#[derive(Default)]
+pub struct PrometheusMetrics {
+    counters: Mutex<BTreeMap<String, u64>>,
+    observations: Mutex<BTreeMap<String, (u64, f64)>>,
+    /// Point-in-time readings (e.g. the repository's current user count)
+    /// that overwrite rather than accumulate — not part of [`Metrics`],
+    /// since neither `increment` nor `observe` fits a value that goes
+    /// down as often as up. Set directly by whoever took the reading
+    /// (see [`crate::http::debug::ready`]).
+    gauges: Mutex<BTreeMap<String, f64>>,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the named gauge to `value`, overwriting whatever it held
+    /// before. For point-in-time readings, such as a repository's current
+    /// user count, that don't fit `increment`/`observe`'s always-growing
+    /// semantics.
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        self.gauges.lock().unwrap().insert(name.to_string(), value);
+    }
+
+    /// Renders every counter, observation summary, and gauge recorded so
+    /// far in Prometheus's text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, value) in self.counters.lock().unwrap().iter() {
+            out.push_str(&format!("# TYPE {name} counter\n{name} {value}\n"));
+        }
+        for (name, (count, sum)) in self.observations.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "# TYPE {name} summary\n{name}_count {count}\n{name}_sum {sum}\n"
+            ));
+        }
+        for (name, value) in self.gauges.lock().unwrap().iter() {
+            out.push_str(&format!("# TYPE {name} gauge\n{name} {value}\n"));
+        }
+        out
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn increment(&self, name: &str) {
+        *self.counters.lock().unwrap().entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    fn observe(&self, name: &str, value: f64) {
+        let mut observations = self.observations.lock().unwrap();
+        let entry = observations.entry(name.to_string()).or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 += value;
+    }
+}
+
+/// `GET /debug/metrics`
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_and_observations_render_in_prometheus_text_format() {
+        let metrics = PrometheusMetrics::new();
+        metrics.increment("logins_total");
+        metrics.increment("logins_total");
+        metrics.observe("session_ttl_seconds", 60.0);
+        metrics.set_gauge("repository_user_count", 3.0);
+        let rendered = metrics.render();
+        assert!(rendered.contains("logins_total 2"));
+        assert!(rendered.contains("session_ttl_seconds_count 1"));
+        assert!(rendered.contains("session_ttl_seconds_sum 60"));
+        assert!(rendered.contains("repository_user_count 3"));
+    }
+}
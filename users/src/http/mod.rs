@@ -0,0 +1,349 @@
+pub mod action_token;
+pub mod admin;
+pub mod auth;
+pub mod cookies;
+pub mod csrf;
+pub mod debug;
+pub mod error;
+mod handlers;
+pub mod ip_filter;
+pub mod load_shedding;
+pub mod locale;
+pub mod metrics;
+pub mod mtls;
+pub mod oauth;
+pub mod oidc;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod request_id;
+pub mod request_signature;
+pub mod saml;
+pub mod service_tokens;
+pub mod state;
+pub mod static_files;
+pub mod token;
+pub mod usage;
+
+use std::time::Duration;
+
+use axum::error_handling::HandleErrorLayer;
+use axum::extract::DefaultBodyLimit;
+use axum::http::StatusCode;
+use axum::routing::{get, post, put};
+use axum::{BoxError, Router};
+use tower::limit::ConcurrencyLimitLayer;
+use tower::timeout::TimeoutLayer;
+use tower::ServiceBuilder;
+
+pub use state::AppState;
+
+/// Default for [`with_connection_limit`] when `MAX_CONCURRENT_CONNECTIONS`
+/// isn't set.
+pub const DEFAULT_MAX_CONCURRENT_CONNECTIONS: usize = 4096;
+
+/// Builds the `users` service's HTTP router.
+pub fn router(state: AppState) -> Router {
+    let usage_state = state.clone();
+    let download_export_token_config = action_token::ActionTokenConfig {
+        key: state.action_token_key.clone(),
+        action: handlers::DOWNLOAD_EXPORT_ACTION,
+    };
+    Router::new()
+        .route("/v1/users/register", post(handlers::register))
+        .route("/v1/users/authenticate", post(handlers::authenticate))
+        .route(
+            "/v1/users/service-accounts/authenticate",
+            post(handlers::authenticate_service_account),
+        )
+        .route("/v1/users/refresh", post(handlers::refresh))
+        .route("/v1/users/magic-link", post(handlers::request_magic_link))
+        .route(
+            "/v1/users/magic-link/verify",
+            get(handlers::verify_magic_link),
+        )
+        .route("/v1/users/me/data-export", get(handlers::export_data))
+        .route(
+            "/v1/users/me/data-export/token",
+            post(handlers::mint_data_export_token),
+        )
+        .route(
+            "/v1/users/me/data-export/download",
+            get(handlers::download_export).layer(axum::middleware::from_fn_with_state(
+                download_export_token_config,
+                action_token::verify_action_token,
+            )),
+        )
+        .route("/v1/users/me/activity", get(handlers::activity))
+        .route(
+            "/v1/users/me/notifications",
+            get(handlers::list_notifications),
+        )
+        .route(
+            "/v1/users/me/notifications/read-all",
+            post(handlers::mark_all_notifications_read),
+        )
+        .route(
+            "/v1/users/me/notifications/:id/read",
+            post(handlers::mark_notification_read),
+        )
+        .route(
+            "/v1/users/me/devices",
+            post(handlers::register_device),
+        )
+        .route(
+            "/v1/users/me/devices/:token",
+            axum::routing::delete(handlers::unregister_device),
+        )
+        .route(
+            "/v1/users/me/sms-otp/request",
+            post(handlers::request_sms_otp),
+        )
+        .route(
+            "/v1/users/me/sms-otp/verify",
+            post(handlers::verify_sms_otp),
+        )
+        .route(
+            "/v1/users/me/backup-codes",
+            post(handlers::generate_backup_codes),
+        )
+        .route(
+            "/v1/users/me/backup-codes/consume",
+            post(handlers::consume_backup_code),
+        )
+        .route(
+            "/v1/users/me",
+            get(handlers::get_me).delete(handlers::erase_me),
+        )
+        .route(
+            "/v1/users/me/email/confirm",
+            post(handlers::confirm_email_change),
+        )
+        .route("/v1/users/me/password", put(handlers::change_password))
+        .route("/v1/users/me/logout-all", post(handlers::logout_all))
+        .route("/v1/users/me/usage", get(usage::usage))
+        .route("/v1/users/me/consents", get(oidc::list_consents))
+        .route(
+            "/v1/users/me/consents/:client_id",
+            axum::routing::delete(oidc::revoke_consent),
+        )
+        .route("/v1/users/availability", get(handlers::check_availability))
+        .route(
+            "/v1/users/me/preferences",
+            get(handlers::get_preferences).put(handlers::put_preferences),
+        )
+        .route(
+            "/v1/users/me/avatar",
+            // A generous transport-level ceiling; `handlers::put_avatar`
+            // enforces the real `MAX_AVATAR_BYTES` limit itself and
+            // returns 413 for anything over it, so this only needs to be
+            // large enough that a rejected upload is rejected by our
+            // code (with a clear error) rather than by the transport.
+            put(handlers::put_avatar)
+                .layer(DefaultBodyLimit::max(handlers::MAX_AVATAR_BYTES * 2)),
+        )
+        .route("/v1/avatars/:id", get(handlers::get_avatar))
+        .route("/v1/saml/metadata", get(saml::metadata))
+        .route("/v1/saml/acs", post(saml::acs))
+        .route(
+            "/v1/service-tokens/exchange",
+            post(service_tokens::exchange),
+        )
+        .route("/v1/oauth/token", post(oauth::token))
+        .route("/v1/token/revoke", post(token::revoke))
+        .route(
+            "/.well-known/openid-configuration",
+            get(oidc::discovery),
+        )
+        .route("/v1/oidc/jwks", get(oidc::jwks))
+        .route("/v1/oidc/authorize", get(oidc::authorize))
+        .route("/v1/oidc/consent", post(oidc::consent))
+        .route("/v1/oidc/token", post(oidc::token))
+        .route("/v1/admin/users/search", get(admin::search_users))
+        .route(
+            "/v1/admin/service-accounts",
+            post(admin::register_service_account),
+        )
+        .route(
+            "/v1/admin/users/:id/impersonate",
+            post(admin::impersonate),
+        )
+        .route(
+            "/v1/admin/users/:id/status",
+            put(admin::set_user_status),
+        )
+        .route(
+            "/v1/admin/users/:id",
+            axum::routing::delete(admin::delete_user),
+        )
+        .route(
+            "/v1/admin/users/:id/organization",
+            put(admin::set_user_organization),
+        )
+        .route(
+            "/v1/admin/users/:id/legal-hold",
+            put(admin::set_legal_hold),
+        )
+        .route(
+            "/v1/admin/users/:id/custom-attributes",
+            put(admin::set_user_custom_attributes),
+        )
+        .route(
+            "/v1/admin/users/:id/tags",
+            post(admin::add_user_tag),
+        )
+        .route(
+            "/v1/admin/users/:id/tags/:tag",
+            axum::routing::delete(admin::remove_user_tag),
+        )
+        .route(
+            "/v1/admin/users/:id/feature-flags/:flag",
+            put(admin::set_user_feature_override).delete(admin::clear_user_feature_override),
+        )
+        .route(
+            "/v1/admin/custom-attributes/schema",
+            get(admin::get_custom_attributes_schema).put(admin::set_custom_attributes_schema),
+        )
+        .route(
+            "/v1/admin/session-key/publish",
+            post(admin::publish_next_session_key),
+        )
+        .route(
+            "/v1/admin/session-key/cutover",
+            post(admin::cutover_session_key),
+        )
+        .route(
+            "/v1/admin/security/rotate-session-nonce",
+            post(admin::rotate_session_nonce),
+        )
+        .route(
+            "/v1/admin/security/events",
+            get(admin::security_events),
+        )
+        .route("/debug/config", get(debug::config))
+        .route("/debug/build", get(debug::build))
+        .route(
+            "/debug/log-level",
+            get(debug::get_log_level).put(debug::set_log_level),
+        )
+        .route("/debug/env", get(debug::env))
+        .route("/debug/threads", get(debug::threads))
+        .route("/debug/ready", get(debug::ready))
+        .route("/debug/metrics", get(metrics::metrics))
+        .layer(axum::middleware::from_fn_with_state(
+            usage_state,
+            usage::track_usage,
+        ))
+        .with_state(state)
+}
+
+/// Layers [`csrf::verify_csrf`] onto `router`, for deployments that opt
+/// into cookie-based session delivery (see [`cookies`]). The browser
+/// attaches the session cookie to every request automatically, including
+/// forged cross-origin ones, so the double-submit CSRF check is required
+/// for mutating routes whenever that delivery mode is enabled.
+pub fn with_csrf_protection(router: Router) -> Router {
+    router.layer(axum::middleware::from_fn(csrf::verify_csrf))
+}
+
+/// Layers [`locale::negotiate_locale`] onto `router`, so every handler
+/// can read the caller's negotiated locale via the `Extension<RequestLocale>`
+/// extractor.
+pub fn with_locale_negotiation(router: Router) -> Router {
+    router.layer(axum::middleware::from_fn(locale::negotiate_locale))
+}
+
+/// Layers [`request_id::track_request`] onto `router`, tagging every
+/// request's logs with a request id and `config`'s service identity.
+pub fn with_request_tracing(
+    router: Router,
+    config: libsvc::telemetry::TelemetryConfig,
+) -> Router {
+    router.layer(axum::middleware::from_fn_with_state(
+        config,
+        request_id::track_request,
+    ))
+}
+
+/// Layers a `timeout` deadline onto `router`. A request that runs past
+/// it gets `504 Gateway Timeout` and its in-flight future — including
+/// any domain call through `UserLogic` — is dropped, cancelling it
+/// rather than letting it run to completion unobserved.
+pub fn with_request_timeout(router: Router, timeout: Duration) -> Router {
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(handle_timeout_error))
+            .layer(TimeoutLayer::new(timeout)),
+    )
+}
+
+/// Layers a concurrent-request ceiling onto `router`. Unlike
+/// [`load_shedding::with_load_shedding`], which rejects excess requests
+/// outright with `503`, a request past `max_concurrent` here simply
+/// queues behind the ones ahead of it — this is the knob an operator
+/// reaches for to bound memory/connection use on a small container,
+/// `with_load_shedding` is the one for shedding load a struggling
+/// instance can't keep up with. Tokio's multi-threaded runtime shares one
+/// pool of worker threads across all connections rather than giving each
+/// worker its own listener the way actix does, so there's no true
+/// "per-worker" limit to expose here — a single service-wide ceiling is
+/// the honest equivalent on this stack.
+pub fn with_connection_limit(router: Router, max_concurrent: usize) -> Router {
+    router.layer(ConcurrencyLimitLayer::new(max_concurrent))
+}
+
+async fn handle_timeout_error(err: BoxError) -> StatusCode {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        tracing::warn!("request exceeded its timeout and was cancelled");
+        StatusCode::GATEWAY_TIMEOUT
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn a_handler_that_outlives_the_timeout_gets_a_504() {
+        let router = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "too slow"
+            }),
+        );
+        let router = with_request_timeout(router, Duration::from_millis(1));
+
+        let response = router
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/slow")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn a_handler_within_the_timeout_responds_normally() {
+        let router = Router::new().route("/fast", get(|| async { "ok" }));
+        let router = with_request_timeout(router, Duration::from_secs(5));
+
+        let response = router
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/fast")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
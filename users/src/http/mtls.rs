@@ -0,0 +1,25 @@
+//! Reads the client certificate thumbprint a TLS-terminating proxy
+//! forwards for an mTLS-authenticated connection.
+//!
+//! This process only terminates plain TCP (see `main::run`), so it never
+//! sees a client certificate itself; a deployment that wants session
+//! binding to one puts a proxy in front that verifies the certificate and
+//! forwards its thumbprint via [`CLIENT_CERT_THUMBPRINT_HEADER`] — the
+//! same trust boundary [`super::ip_filter`] already assumes for a
+//! client's real address, and the same header-driven pattern
+//! [`super::request_signature`] uses for service-to-service trust.
+
+use axum::http::HeaderMap;
+
+/// Header a TLS-terminating proxy sets to the thumbprint of the client
+/// certificate verified on the current connection, once mTLS is enabled
+/// in front of this service.
+pub const CLIENT_CERT_THUMBPRINT_HEADER: &str = "x-client-cert-thumbprint";
+
+/// Reads [`CLIENT_CERT_THUMBPRINT_HEADER`] from `headers`, if present.
+pub fn client_cert_thumbprint(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CLIENT_CERT_THUMBPRINT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
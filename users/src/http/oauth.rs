@@ -0,0 +1,122 @@
+//! [RFC 6749 §4.4](https://www.rfc-editor.org/rfc/rfc6749#section-4.4)'s
+//! client credentials grant, giving a trusted [`crate::domain::UserKind::Service`]
+//! account an OAuth-spec-shaped way to authenticate as itself, alongside
+//! the existing `POST /v1/users/service-accounts/authenticate`. Both reach
+//! [`crate::domain::UserLogic::authenticate_service_account`] — this
+//! endpoint is for callers that speak OAuth2 and expect an
+//! `access_token`/`expires_in`/`scope` body rather than this service's own
+//! session-pair shape.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use foundation::id::Id;
+use libsvc::session::{Role, SessionBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::state::AppState;
+
+/// How long a machine token stays valid. As short-lived as
+/// [`super::service_tokens::exchange`]'s exchanged sessions — a caller
+/// holding one for longer is expected to request a fresh one rather than
+/// this grant supporting a refresh token.
+const MACHINE_TOKEN_TTL_SECS: u64 = 5 * 60;
+
+/// An [RFC 6749 §5.2](https://www.rfc-editor.org/rfc/rfc6749#section-5.2)
+/// error body.
+#[derive(Debug, Serialize)]
+pub struct OAuthErrorBody {
+    pub error: &'static str,
+    pub error_description: &'static str,
+}
+
+/// A `/v1/oauth/token` request was invalid per RFC 6749, reported as a
+/// structured [`OAuthErrorBody`] with the status its error code implies.
+pub struct OAuthError {
+    status: StatusCode,
+    error: &'static str,
+    description: &'static str,
+}
+
+impl OAuthError {
+    fn new(status: StatusCode, error: &'static str, description: &'static str) -> Self {
+        Self { status, error, description }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        (self.status, Json(OAuthErrorBody { error: self.error, error_description: self.description })).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientCredentialsRequest {
+    pub grant_type: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// This grant only ever issues `"user"`-scoped tokens — a
+    /// [`crate::domain::UserKind::Service`] account carries no role set of
+    /// its own to narrow further, unlike
+    /// [`super::service_tokens::TokenExchangeRequest::requested_roles`].
+    /// Requesting anything else fails with `invalid_scope` rather than
+    /// silently granting less (or more) than asked for.
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Always `"Bearer"` per RFC 6749 §5.1, unlike
+    /// [`super::service_tokens::TokenExchangeResponse::token_type`]'s
+    /// `"session"` — this endpoint is judged against the OAuth spec, not
+    /// this service's own conventions.
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub scope: &'static str,
+}
+
+/// `POST /v1/oauth/token`
+pub async fn token(
+    State(state): State<AppState>,
+    Json(body): Json<ClientCredentialsRequest>,
+) -> Result<Json<TokenResponse>, OAuthError> {
+    if body.grant_type != "client_credentials" {
+        return Err(OAuthError::new(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+            "only \"client_credentials\" is supported",
+        ));
+    }
+    if let Some(scope) = &body.scope {
+        if scope.split_whitespace().any(|requested| requested != "user") {
+            return Err(OAuthError::new(StatusCode::BAD_REQUEST, "invalid_scope", "only the \"user\" scope is supported"));
+        }
+    }
+
+    let user = state
+        .user_logic
+        .authenticate_service_account(&body.client_id, &body.client_secret)
+        .await
+        .map_err(|_| OAuthError::new(StatusCode::UNAUTHORIZED, "invalid_client", "client authentication failed"))?;
+    let user_id = Id::try_from(user.id.as_str())
+        .map_err(|_| OAuthError::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "service account has an invalid id"))?;
+
+    let signing_key = state
+        .session_key
+        .active()
+        .map_err(|_| OAuthError::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "no active session-signing key"))?;
+    let session = SessionBuilder::new(user_id, MACHINE_TOKEN_TTL_SECS)
+        .with_roles(vec![Role::User])
+        .with_issuer(state.session_validation.issuer.as_str())
+        .with_audience(state.session_validation.audience.as_str())
+        .finish(&signing_key);
+    let access_token = session.id.clone();
+    state
+        .sessions
+        .insert(session)
+        .map_err(|_| OAuthError::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "failed to issue access token"))?;
+
+    Ok(Json(TokenResponse { access_token, token_type: "Bearer", expires_in: MACHINE_TOKEN_TTL_SECS, scope: "user" }))
+}
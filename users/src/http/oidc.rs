@@ -0,0 +1,475 @@
+//! OpenID Connect: discovery, the authorization code flow with PKCE, and
+//! ID token issuance, turning this service into a minimal identity
+//! provider for internal apps. Every endpoint here is disabled
+//! (`404 Not Found`) unless [`AppState::oidc`] and [`AppState::oidc_clients`]
+//! are both configured — most deployments of this service aren't acting
+//! as an identity provider.
+//!
+//! The flow: a client sends the user to `GET /v1/oidc/authorize`
+//! (authenticated the same way every other endpoint in this service is,
+//! via session header — there's no browser cookie/redirect dance here).
+//! If the user has already consented to this client/scope, a code is
+//! issued immediately; otherwise the caller renders a consent screen from
+//! the response and, once the user decides, calls `POST /v1/oidc/consent`.
+//! Either way the caller ends up with a `redirect_uri` carrying a `code`
+//! to send the user's browser to, which the client's backend redeems at
+//! `POST /v1/oidc/token` for an access session and an ID token. PKCE
+//! (`S256` only) is required on every request, confidential or public
+//! client, since it costs nothing and closes the authorization-code
+//! interception attack even for clients that also hold a secret.
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::Engine;
+use foundation::id::Id;
+use libsvc::id_token::{self, IdTokenClaims};
+use libsvc::oidc::{AuthorizationCode, OidcClient};
+use libsvc::session::{Role, SessionBuilder};
+use percent_encoding::{percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::auth::AuthenticatedUser;
+use super::state::AppState;
+
+/// How long an authorization code may be redeemed before it expires
+/// unused. RFC 6749 §4.1.2 recommends a maximum of ten minutes; this
+/// service's codes are meant to be redeemed by a backend immediately
+/// after the redirect, so a much tighter window is enough.
+const AUTHORIZATION_CODE_TTL_SECS: u64 = 60;
+
+/// How long the access session [`token`] issues stays valid. Short-lived
+/// like [`super::service_tokens::EXCHANGED_SESSION_TTL_SECS`] — a relying
+/// party holding one for longer is expected to re-run the flow rather
+/// than this IdP supporting a refresh grant (yet).
+const ACCESS_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+/// How long an issued ID token stays valid.
+const ID_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Serialize)]
+pub struct DiscoveryDocument {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<&'static str>,
+    pub subject_types_supported: Vec<&'static str>,
+    pub id_token_signing_alg_values_supported: Vec<&'static str>,
+}
+
+/// `GET /.well-known/openid-configuration`
+pub async fn discovery(State(state): State<AppState>) -> Result<Json<DiscoveryDocument>, StatusCode> {
+    let config = state.oidc.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(DiscoveryDocument {
+        authorization_endpoint: format!("{}/v1/oidc/authorize", config.issuer),
+        token_endpoint: format!("{}/v1/oidc/token", config.issuer),
+        jwks_uri: format!("{}/v1/oidc/jwks", config.issuer),
+        issuer: config.issuer.clone(),
+        response_types_supported: vec!["code"],
+        subject_types_supported: vec!["public"],
+        id_token_signing_alg_values_supported: vec!["HS256"],
+    }))
+}
+
+/// `GET /v1/oidc/jwks`
+///
+/// Always empty: this IdP signs ID tokens with the same symmetric HMAC
+/// key every other signed artifact in this service uses (see
+/// [`foundation::key::Key`], [`libsvc::id_token`]) rather than an
+/// asymmetric keypair, so there's no public key to publish. A relying
+/// party here is always a client registered directly with this service
+/// ("a minimal IdP for internal apps"), not an arbitrary third party that
+/// would need to verify a token on its own.
+pub async fn jwks(State(state): State<AppState>) -> Result<Json<Value>, StatusCode> {
+    state.oidc.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(json!({ "keys": [] })))
+}
+
+/// An [RFC 6749 §5.2](https://www.rfc-editor.org/rfc/rfc6749#section-5.2)
+/// error body.
+#[derive(Debug, Serialize)]
+pub struct OAuthErrorBody {
+    pub error: &'static str,
+    pub error_description: &'static str,
+}
+
+/// Everything that can go wrong handling an OIDC request: either the
+/// subsystem isn't configured at all (`404`, matching [`discovery`] and
+/// [`jwks`]), or the request itself is invalid per RFC 6749, reported as
+/// a structured [`OAuthErrorBody`] with the status its error code implies.
+pub enum OidcError {
+    NotConfigured,
+    Spec { status: StatusCode, error: &'static str, description: &'static str },
+}
+
+impl OidcError {
+    fn invalid(status: StatusCode, error: &'static str, description: &'static str) -> Self {
+        Self::Spec { status, error, description }
+    }
+}
+
+impl IntoResponse for OidcError {
+    fn into_response(self) -> Response {
+        match self {
+            OidcError::NotConfigured => StatusCode::NOT_FOUND.into_response(),
+            OidcError::Spec { status, error, description } => {
+                (status, Json(OAuthErrorBody { error, error_description: description })).into_response()
+            }
+        }
+    }
+}
+
+/// Looks up `client_id` and checks `redirect_uri` and `scope` against it,
+/// requiring both [`AppState::oidc`] and [`AppState::oidc_clients`] to be
+/// configured. Shared by [`authorize`] and [`consent`], which both need
+/// the same checks before issuing a code.
+fn validate_client(state: &AppState, client_id: &str, redirect_uri: &str, scope: &str) -> Result<OidcClient, OidcError> {
+    state.oidc.as_ref().ok_or(OidcError::NotConfigured)?;
+    let registry = state.oidc_clients.as_ref().ok_or(OidcError::NotConfigured)?;
+    let client = registry
+        .get(client_id)
+        .ok_or_else(|| OidcError::invalid(StatusCode::BAD_REQUEST, "invalid_client", "unknown client_id"))?;
+    if !client.redirect_uris.iter().any(|uri| uri == redirect_uri) {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "redirect_uri is not registered for this client",
+        ));
+    }
+    if !scope.split_whitespace().all(|requested| client.allowed_scopes.iter().any(|allowed| allowed == requested)) {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            "scope requests a permission this client isn't allowed",
+        ));
+    }
+    Ok(client)
+}
+
+/// Mints and stores a fresh [`AuthorizationCode`], returning the
+/// `redirect_uri` (with `code` and, if present, `state` appended) the
+/// caller should send the user's browser to.
+#[allow(clippy::too_many_arguments)]
+fn issue_code(
+    state: &AppState,
+    user_id: &str,
+    client_id: &str,
+    redirect_uri: &str,
+    scope: &str,
+    code_challenge: &str,
+    nonce: Option<String>,
+    oauth_state: Option<&str>,
+) -> String {
+    let now = state.clock.now().timestamp().max(0) as u64;
+    let code = Id::new().to_string();
+    state.oidc_codes.insert(
+        code.clone(),
+        AuthorizationCode {
+            client_id: client_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            user_id: user_id.to_string(),
+            scope: scope.to_string(),
+            code_challenge: code_challenge.to_string(),
+            nonce,
+            expires_at: now + AUTHORIZATION_CODE_TTL_SECS,
+        },
+    );
+    let mut url = format!("{redirect_uri}?code={}", encode_query_value(&code));
+    if let Some(oauth_state) = oauth_state {
+        url.push_str(&format!("&state={}", encode_query_value(oauth_state)));
+    }
+    url
+}
+
+/// The characters a redirect URL's query string needs `code`, `state`,
+/// and `error` values escaped for: [`CONTROLS`] plus the delimiters
+/// (`&`, `=`, `#`, `+`, `%`, and the usual HTML-unsafe quoting
+/// characters) that would otherwise let a value like `state` inject
+/// extra query parameters or truncate the redirect.
+const QUERY_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'&')
+    .add(b'\'')
+    .add(b'+')
+    .add(b'<')
+    .add(b'>')
+    .add(b'=')
+    .add(b'%');
+
+/// Percent-encodes a value for safe inclusion in a redirect URL's query
+/// string, so a `state` (or other caller-controlled value) containing
+/// `&`, `=`, or `#` can't truncate the redirect or inject extra
+/// parameters into the client's callback.
+fn encode_query_value(value: &str) -> std::borrow::Cow<'_, str> {
+    percent_encode(value.as_bytes(), QUERY_VALUE).into()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeQuery {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub response_type: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuthorizeResponse {
+    /// The user already consented to `client_id` for this `scope`; a
+    /// code has been issued and the caller should navigate to
+    /// `redirect_uri`.
+    Redirect { redirect_uri: String },
+    /// The user hasn't consented yet. The caller should render a consent
+    /// screen for `client_id`/`scope` and, once the user decides, `POST`
+    /// the original request's parameters (plus `approve`) to
+    /// `/v1/oidc/consent`.
+    ConsentRequired { client_id: String, scope: String },
+}
+
+/// `GET /v1/oidc/authorize`
+pub async fn authorize(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Query(query): Query<AuthorizeQuery>,
+) -> Result<Json<AuthorizeResponse>, OidcError> {
+    if query.response_type != "code" {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "unsupported_response_type",
+            "only the \"code\" response type is supported",
+        ));
+    }
+    if query.code_challenge_method != "S256" {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "code_challenge_method must be S256",
+        ));
+    }
+    validate_client(&state, &query.client_id, &query.redirect_uri, &query.scope)?;
+
+    if state.oidc_consents.has_consented(&auth.user_id, &query.client_id, &query.scope) {
+        let redirect_uri = issue_code(
+            &state,
+            &auth.user_id,
+            &query.client_id,
+            &query.redirect_uri,
+            &query.scope,
+            &query.code_challenge,
+            query.nonce,
+            query.state.as_deref(),
+        );
+        return Ok(Json(AuthorizeResponse::Redirect { redirect_uri }));
+    }
+    Ok(Json(AuthorizeResponse::ConsentRequired { client_id: query.client_id, scope: query.scope }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsentRequest {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub nonce: Option<String>,
+    /// Whether the user approved the request. `false` redirects back with
+    /// `error=access_denied` (RFC 6749 §4.1.2.1) rather than a code.
+    pub approve: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentResponse {
+    pub redirect_uri: String,
+}
+
+/// `POST /v1/oidc/consent`
+pub async fn consent(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    Json(body): Json<ConsentRequest>,
+) -> Result<Json<ConsentResponse>, OidcError> {
+    validate_client(&state, &body.client_id, &body.redirect_uri, &body.scope)?;
+
+    if !body.approve {
+        let mut url = format!("{}?error={}", body.redirect_uri, encode_query_value("access_denied"));
+        if let Some(oauth_state) = &body.state {
+            url.push_str(&format!("&state={}", encode_query_value(oauth_state)));
+        }
+        return Ok(Json(ConsentResponse { redirect_uri: url }));
+    }
+
+    state.oidc_consents.record(auth.user_id.clone(), body.client_id.clone(), body.scope.clone());
+    let redirect_uri = issue_code(
+        &state,
+        &auth.user_id,
+        &body.client_id,
+        &body.redirect_uri,
+        &body.scope,
+        &body.code_challenge,
+        body.nonce,
+        body.state.as_deref(),
+    );
+    Ok(Json(ConsentResponse { redirect_uri }))
+}
+
+/// The PKCE `S256` transformation (RFC 7636 §4.2): the base64url (no
+/// padding) encoding of the SHA-256 hash of the verifier.
+fn s256_challenge(code_verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub code: String,
+    pub redirect_uri: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub code_verifier: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// Always `"Bearer"` per RFC 6749 §5.1, unlike
+    /// [`super::service_tokens::TokenExchangeResponse::token_type`]'s
+    /// `"session"` — this endpoint is judged against the OAuth spec, not
+    /// this service's own conventions.
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub id_token: String,
+}
+
+/// `POST /v1/oidc/token`
+pub async fn token(State(state): State<AppState>, Json(body): Json<TokenRequest>) -> Result<Json<TokenResponse>, OidcError> {
+    let config = state.oidc.as_ref().ok_or(OidcError::NotConfigured)?;
+    let registry = state.oidc_clients.as_ref().ok_or(OidcError::NotConfigured)?;
+
+    if body.grant_type != "authorization_code" {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "unsupported_grant_type",
+            "only \"authorization_code\" is supported",
+        ));
+    }
+
+    let client = registry
+        .get(&body.client_id)
+        .ok_or_else(|| OidcError::invalid(StatusCode::UNAUTHORIZED, "invalid_client", "unknown client_id"))?;
+    if let Some(hash) = &client.client_secret_hash {
+        let authenticated = body
+            .client_secret
+            .as_deref()
+            .map(|secret| foundation::hash::verify_password(secret, hash).unwrap_or(false))
+            .unwrap_or(false);
+        if !authenticated {
+            return Err(OidcError::invalid(StatusCode::UNAUTHORIZED, "invalid_client", "client authentication failed"));
+        }
+    }
+
+    let grant = state
+        .oidc_codes
+        .consume(&body.code)
+        .ok_or_else(|| OidcError::invalid(StatusCode::BAD_REQUEST, "invalid_grant", "authorization code is unknown or already used"))?;
+
+    let now = state.clock.now().timestamp().max(0) as u64;
+    if now >= grant.expires_at || grant.client_id != body.client_id || grant.redirect_uri != body.redirect_uri {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "invalid_grant",
+            "authorization code is expired or does not match this request",
+        ));
+    }
+    if s256_challenge(&body.code_verifier) != grant.code_challenge {
+        return Err(OidcError::invalid(
+            StatusCode::BAD_REQUEST,
+            "invalid_grant",
+            "code_verifier does not match the code_challenge from the authorization request",
+        ));
+    }
+
+    let user_id = Id::try_from(grant.user_id.as_str())
+        .map_err(|_| OidcError::invalid(StatusCode::BAD_REQUEST, "invalid_grant", "authorization code names an invalid user"))?;
+    let signing_key = state
+        .session_key
+        .active()
+        .map_err(|_| OidcError::invalid(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "no active session-signing key"))?;
+    let session = SessionBuilder::new(user_id, ACCESS_TOKEN_TTL_SECS)
+        .with_roles(vec![Role::User])
+        .with_issuer(state.session_validation.issuer.as_str())
+        .with_audience(state.session_validation.audience.as_str())
+        .finish(&signing_key);
+    let access_token = session.id.clone();
+    state
+        .sessions
+        .insert(session)
+        .map_err(|_| OidcError::invalid(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "failed to issue access token"))?;
+
+    let id_token = id_token::mint(
+        &state.oidc_signing_key,
+        &IdTokenClaims {
+            issuer: config.issuer.clone(),
+            subject: grant.user_id.clone(),
+            audience: body.client_id.clone(),
+            issued_at: now,
+            expires_at: now + ID_TOKEN_TTL_SECS,
+            nonce: grant.nonce,
+        },
+    );
+
+    Ok(Json(TokenResponse { access_token, token_type: "Bearer", expires_in: ACCESS_TOKEN_TTL_SECS, id_token }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentGrant {
+    pub client_id: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentsResponse {
+    pub consents: Vec<ConsentGrant>,
+}
+
+/// `GET /v1/users/me/consents`
+pub async fn list_consents(State(state): State<AppState>, auth: AuthenticatedUser) -> Result<Json<ConsentsResponse>, OidcError> {
+    state.oidc.as_ref().ok_or(OidcError::NotConfigured)?;
+    state.oidc_clients.as_ref().ok_or(OidcError::NotConfigured)?;
+    let consents = state
+        .oidc_consents
+        .list_for_user(&auth.user_id)
+        .into_iter()
+        .map(|grant| ConsentGrant { client_id: grant.client_id, scope: grant.scope })
+        .collect();
+    Ok(Json(ConsentsResponse { consents }))
+}
+
+/// `DELETE /v1/users/me/consents/:client_id`
+///
+/// Revokes every scope `auth` has previously granted `client_id`. The next
+/// `GET /v1/oidc/authorize` for that client re-shows the consent screen
+/// instead of skipping it, since that check reads straight from
+/// [`libsvc::oidc::ConsentStore::has_consented`] — this handler doesn't
+/// need to invalidate anything else. An already-issued access session or
+/// ID token stays valid until it expires on its own; this service doesn't
+/// support revoking those early.
+pub async fn revoke_consent(
+    State(state): State<AppState>,
+    auth: AuthenticatedUser,
+    axum::extract::Path(client_id): axum::extract::Path<String>,
+) -> Result<StatusCode, OidcError> {
+    state.oidc.as_ref().ok_or(OidcError::NotConfigured)?;
+    state.oidc_clients.as_ref().ok_or(OidcError::NotConfigured)?;
+    state.oidc_consents.revoke(&auth.user_id, &client_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,170 @@
+//! CPU flamegraphs and coarse memory stats for a running instance,
+//! gated behind the `profiling` feature and a loopback-only middleware.
+//! A flamegraph dump exposes function names and call patterns from the
+//! running binary, so it's never meant to be reachable from outside the
+//! host it's running on, and it's opt-in at compile time since `pprof`
+//! pulls in platform-specific sampling support this service doesn't
+//! otherwise need.
+
+use std::fs;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::extract::{ConnectInfo, Query, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use pprof::ProfilerGuardBuilder;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PROFILE_SECONDS: u64 = 10;
+const MAX_PROFILE_SECONDS: u64 = 60;
+
+/// Rejects any request that didn't arrive from the loopback interface.
+/// Requires the server to be run with
+/// `into_make_service_with_connect_info::<SocketAddr>()`, since that's
+/// what populates the [`ConnectInfo`] extractor this reads.
+pub async fn require_localhost(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !addr.ip().is_loopback() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    seconds: Option<u64>,
+}
+
+/// `GET /debug/pprof/profile?seconds=10`
+///
+/// Samples the process for the requested duration (default
+/// [`DEFAULT_PROFILE_SECONDS`], capped at [`MAX_PROFILE_SECONDS`] so one
+/// request can't pin a sampler on the instance indefinitely) and returns
+/// an SVG flamegraph of where it spent CPU time.
+pub async fn profile(Query(query): Query<ProfileQuery>) -> Result<Response, StatusCode> {
+    let seconds = query
+        .seconds
+        .unwrap_or(DEFAULT_PROFILE_SECONDS)
+        .clamp(1, MAX_PROFILE_SECONDS);
+
+    let guard = ProfilerGuardBuilder::default()
+        .frequency(99)
+        .build()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tokio::time::sleep(Duration::from_secs(seconds)).await;
+    let report = guard
+        .report()
+        .build()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut svg = Cursor::new(Vec::new());
+    report
+        .flamegraph(&mut svg)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(([(CONTENT_TYPE, "image/svg+xml")], svg.into_inner()).into_response())
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub vm_rss_kb: Option<u64>,
+    pub vm_size_kb: Option<u64>,
+}
+
+/// `GET /debug/pprof/heap`
+///
+/// This isn't a heap profile — that needs a profiling allocator (e.g.
+/// jemalloc) wired in as the global allocator, which this binary doesn't
+/// do. It's the closest honest substitute: coarse process memory usage
+/// read from `/proc/self/status`, enough to tell whether memory is
+/// growing without attaching a separate profiler.
+pub async fn memory() -> Json<MemoryStats> {
+    Json(read_proc_self_status())
+}
+
+fn read_proc_self_status() -> MemoryStats {
+    let contents = fs::read_to_string("/proc/self/status").unwrap_or_default();
+    parse_proc_self_status(&contents)
+}
+
+fn parse_proc_self_status(contents: &str) -> MemoryStats {
+    let mut stats = MemoryStats::default();
+    for line in contents.lines() {
+        if let Some(rest) = line.strip_prefix("VmRSS:") {
+            stats.vm_rss_kb = parse_kb(rest);
+        } else if let Some(rest) = line.strip_prefix("VmSize:") {
+            stats.vm_size_kb = parse_kb(rest);
+        }
+    }
+    stats
+}
+
+fn parse_kb(field: &str) -> Option<u64> {
+    field.split_whitespace().next()?.parse().ok()
+}
+
+/// A standalone router for the profiling endpoints, meant to be merged
+/// into the main app only when profiling is explicitly enabled (see
+/// `main.rs`), with [`require_localhost`] layered on so it's reachable
+/// only from the host the service runs on.
+pub fn router() -> Router {
+    Router::new()
+        .route("/debug/pprof/profile", get(profile))
+        .route("/debug/pprof/heap", get(memory))
+        .layer(middleware::from_fn(require_localhost))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn request_from(ip: &str) -> HttpRequest<Body> {
+        let mut request = HttpRequest::builder()
+            .uri("/debug/pprof/heap")
+            .body(Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(ip.parse().unwrap(), 0)));
+        request
+    }
+
+    #[tokio::test]
+    async fn loopback_requests_are_allowed_through() {
+        let response = router().oneshot(request_from("127.0.0.1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn non_loopback_requests_are_forbidden() {
+        let response = router().oneshot(request_from("203.0.113.5")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn parses_rss_and_size_from_proc_status_format() {
+        let contents = "Name:\tusers\nVmSize:\t  123456 kB\nVmRSS:\t    7890 kB\n";
+        let stats = parse_proc_self_status(contents);
+        assert_eq!(stats.vm_size_kb, Some(123456));
+        assert_eq!(stats.vm_rss_kb, Some(7890));
+    }
+
+    #[test]
+    fn missing_fields_are_reported_as_none() {
+        let stats = parse_proc_self_status("Name:\tusers\n");
+        assert_eq!(stats, MemoryStats { vm_rss_kb: None, vm_size_kb: None });
+    }
+}
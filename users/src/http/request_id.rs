@@ -0,0 +1,105 @@
+//! Tags every request with a unique id and the service's identity, so a
+//! single request's log lines can be grep'd out of aggregated JSON
+//! output by `request_id`, `service_name`, `service_version`, or
+//! `environment` — and a caller can correlate a response with its logs
+//! via the echoed `x-request-id` header.
+
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use foundation::id::Id;
+use libsvc::telemetry::TelemetryConfig;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Wraps request handling in a span carrying `config`'s service identity
+/// and a freshly generated request id, then echoes that id back in the
+/// `x-request-id` response header.
+pub async fn track_request(
+    State(config): State<TelemetryConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let request_id = Id::new().to_string();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let span = tracing::info_span!(
+        "request",
+        service_name = %config.service_name,
+        service_version = %config.service_version,
+        environment = %config.environment,
+        request_id = %request_id,
+        method = %method,
+        path = %path,
+    );
+
+    async move {
+        let mut response = next.run(request).await;
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use axum::middleware;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn test_config() -> TelemetryConfig {
+        TelemetryConfig {
+            service_name: "users".to_string(),
+            service_version: "0.0.0".to_string(),
+            environment: "test".to_string(),
+            default_directives: "info".to_string(),
+            format: libsvc::telemetry::LogFormat::Pretty,
+        }
+    }
+
+    #[tokio::test]
+    async fn every_response_carries_a_request_id_header() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(test_config(), track_request));
+
+        let response = app
+            .oneshot(HttpRequest::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+    }
+
+    #[tokio::test]
+    async fn request_ids_are_unique_per_request() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(test_config(), track_request));
+
+        let first = app
+            .clone()
+            .oneshot(HttpRequest::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let second = app
+            .oneshot(HttpRequest::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_ne!(
+            first.headers().get(REQUEST_ID_HEADER),
+            second.headers().get(REQUEST_ID_HEADER)
+        );
+    }
+}
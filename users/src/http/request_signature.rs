@@ -0,0 +1,202 @@
+//! Verifies [`libsvc::request_signing`] signatures on inbound requests,
+//! for internal service-to-service calls that skip bearer tokens
+//! entirely. Only requests carrying [`SERVICE_ID_HEADER`] are checked, so
+//! this layers safely onto the same router as session-authenticated and
+//! anonymous routes.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use libsvc::request_signing::{verify, RequestSignature, RequestSigningKeyStore};
+
+/// Header carrying the signing client's id.
+pub const SERVICE_ID_HEADER: &str = "x-service-id";
+/// Header carrying the base64-encoded HMAC signature.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// Header carrying the unix-seconds timestamp the signature covers.
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// Largest request body [`verify_request_signature`] will buffer to hash.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Shared state behind [`with_request_signing`]'s middleware function.
+#[derive(Clone)]
+pub struct RequestSigningConfig {
+    pub keys: Arc<dyn RequestSigningKeyStore>,
+    /// How far a request's timestamp may drift from this server's clock
+    /// before it's rejected as stale or replayed.
+    pub max_skew_secs: u64,
+}
+
+/// Layers signature verification onto `router`.
+pub fn with_request_signing(router: axum::Router, config: RequestSigningConfig) -> axum::Router {
+    router.layer(axum::middleware::from_fn_with_state(config, verify_request_signature))
+}
+
+async fn verify_request_signature(
+    axum::extract::State(config): axum::extract::State<RequestSigningConfig>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(client_id) = header_value(&request, SERVICE_ID_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let signature = header_value(&request, SIGNATURE_HEADER).ok_or(StatusCode::UNAUTHORIZED)?;
+    let timestamp: u64 = header_value(&request, SIGNATURE_TIMESTAMP_HEADER)
+        .and_then(|value| value.parse().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let (parts, body) = request.into_parts();
+    let body_bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before the unix epoch")
+        .as_secs();
+    let signature = RequestSignature { client_id, signature, timestamp };
+    verify(
+        config.keys.as_ref(),
+        &signature,
+        &method,
+        &path,
+        &body_bytes,
+        now,
+        config.max_skew_secs,
+    )
+    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let request = Request::from_parts(parts, Body::from(body_bytes));
+    Ok(next.run(request).await)
+}
+
+fn header_value(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use axum::routing::post;
+    use axum::Router;
+    use foundation::key::Key;
+    use libsvc::request_signing::{sign, InMemoryRequestSigningKeyStore};
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn test_router(config: RequestSigningConfig) -> Router {
+        with_request_signing(
+            Router::new().route("/work", post(|| async { "ok" })),
+            config,
+        )
+    }
+
+    #[tokio::test]
+    async fn a_request_without_signature_headers_is_unaffected() {
+        let config = RequestSigningConfig {
+            keys: Arc::new(InMemoryRequestSigningKeyStore::new([])),
+            max_skew_secs: 60,
+        };
+        let response = test_router(config)
+            .oneshot(
+                HttpRequest::builder()
+                    .method("POST")
+                    .uri("/work")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn unix_now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[tokio::test]
+    async fn a_correctly_signed_request_is_let_through() {
+        let key = Key::generate();
+        let config = RequestSigningConfig {
+            keys: Arc::new(InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())])),
+            max_skew_secs: 60,
+        };
+        let timestamp = unix_now();
+        let signature = sign("svc-a", &key, "POST", "/work", b"payload", timestamp);
+        let response = test_router(config)
+            .oneshot(
+                HttpRequest::builder()
+                    .method("POST")
+                    .uri("/work")
+                    .header(SERVICE_ID_HEADER, "svc-a")
+                    .header(SIGNATURE_HEADER, signature.signature)
+                    .header(SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+                    .body(Body::from("payload"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_request_with_a_tampered_body_is_rejected() {
+        let key = Key::generate();
+        let config = RequestSigningConfig {
+            keys: Arc::new(InMemoryRequestSigningKeyStore::new([("svc-a".to_string(), key.clone())])),
+            max_skew_secs: 60,
+        };
+        let timestamp = unix_now();
+        let signature = sign("svc-a", &key, "POST", "/work", b"payload", timestamp);
+        let response = test_router(config)
+            .oneshot(
+                HttpRequest::builder()
+                    .method("POST")
+                    .uri("/work")
+                    .header(SERVICE_ID_HEADER, "svc-a")
+                    .header(SIGNATURE_HEADER, signature.signature)
+                    .header(SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+                    .body(Body::from("tampered"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn a_request_from_an_unregistered_client_is_rejected() {
+        let config = RequestSigningConfig {
+            keys: Arc::new(InMemoryRequestSigningKeyStore::new([])),
+            max_skew_secs: 60,
+        };
+        let response = test_router(config)
+            .oneshot(
+                HttpRequest::builder()
+                    .method("POST")
+                    .uri("/work")
+                    .header(SERVICE_ID_HEADER, "svc-unknown")
+                    .header(SIGNATURE_HEADER, "bm90LWEtcmVhbC1zaWc=")
+                    .header(SIGNATURE_TIMESTAMP_HEADER, "1000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
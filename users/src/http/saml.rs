@@ -0,0 +1,76 @@
+//! SAML 2.0 service-provider endpoints: SP metadata and the assertion
+//! consumer service (ACS) IdPs redirect back to after authenticating a
+//! user. Disabled (every route here answers `404 Not Found`) unless
+//! [`AppState::saml`] is configured.
+
+use axum::extract::{Form, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use libsvc::saml::SamlError;
+use serde::Deserialize;
+
+use super::handlers::issue_session_pair;
+use super::mtls::client_cert_thumbprint;
+use super::state::AppState;
+
+/// `GET /v1/saml/metadata`
+///
+/// The SP metadata document an administrator uploads to their IdP to
+/// configure the integration.
+pub async fn metadata(State(state): State<AppState>) -> Result<Response, StatusCode> {
+    let config = state.saml.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<EntityDescriptor xmlns="urn:oasis:names:tc:SAML:2.0:metadata" entityID="{entity_id}">
+  <SPSSODescriptor protocolSupportEnumeration="urn:oasis:names:tc:SAML:2.0:protocol">
+    <AssertionConsumerService Binding="urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST" Location="{acs_url}" index="0" isDefault="true"/>
+  </SPSSODescriptor>
+</EntityDescriptor>"#,
+        entity_id = config.entity_id,
+        acs_url = config.acs_url,
+    );
+    Ok(([(CONTENT_TYPE, "application/samlmetadata+xml")], xml).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcsRequest {
+    #[serde(rename = "SAMLResponse")]
+    pub saml_response: String,
+}
+
+/// `POST /v1/saml/acs`
+///
+/// The assertion consumer service: the IdP's browser redirect lands here
+/// with a base64-encoded, form-POSTed `SAMLResponse`. The assertion's
+/// subject is looked up as a local user by email, the same way
+/// [`crate::http::handlers::request_magic_link`] resolves an email to a
+/// user, and on success a normal session pair is issued — this flow is
+/// just a different way to reach [`issue_session_pair`], not a different
+/// kind of session.
+pub async fn acs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(body): Form<AcsRequest>,
+) -> Result<Response, StatusCode> {
+    let config = state.saml.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(&body.saml_response)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let xml = String::from_utf8(decoded).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let assertion = config.verifier.verify(&xml).map_err(|err| match err {
+        SamlError::SignatureInvalid | SamlError::Expired => StatusCode::UNAUTHORIZED,
+        SamlError::Malformed(_) => StatusCode::BAD_REQUEST,
+    })?;
+
+    let user = state
+        .user_logic
+        .find_by_email(&assertion.subject)
+        .await
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    issue_session_pair(&state, &user.id, client_cert_thumbprint(&headers).as_deref()).await
+}
@@ -0,0 +1,95 @@
+//! RFC 8693-style token exchange: a trusted service presents its own API
+//! key to obtain a short-lived session acting as a user, with the
+//! exchanging service recorded in the session's claims (see
+//! [`libsvc::session::Session::exchanged_by`]) for auditing. Disabled
+//! (`404 Not Found`) unless [`AppState::service_accounts`] is configured.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use foundation::id::Id;
+use libsvc::session::{Role, SessionBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::handlers::status_for;
+use super::state::AppState;
+
+/// How long an exchanged session stays valid. Deliberately much shorter
+/// than [`crate::http::handlers::authenticate`]'s access session, since
+/// it's minted for a single delegated task rather than an interactive
+/// sign-in.
+const EXCHANGED_SESSION_TTL_SECS: u64 = 5 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct TokenExchangeRequest {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The id of the user to act as.
+    pub subject: String,
+    /// The roles the resulting session should carry. Narrowed to the
+    /// intersection with the service account's allowed roles; defaults to
+    /// the full allowed set when omitted.
+    pub requested_roles: Option<Vec<Role>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenExchangeResponse {
+    pub session_id: String,
+    pub token_type: &'static str,
+    pub expires_at: u64,
+    pub user_id: String,
+    pub roles: Vec<Role>,
+}
+
+/// `POST /v1/service-tokens/exchange`
+pub async fn exchange(
+    State(state): State<AppState>,
+    Json(body): Json<TokenExchangeRequest>,
+) -> Result<Json<TokenExchangeResponse>, StatusCode> {
+    let registry = state.service_accounts.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let account = registry
+        .authenticate(&body.client_id, &body.client_secret)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = state
+        .user_logic
+        .get(&body.subject)
+        .await
+        .map_err(|e| status_for(&e))?;
+    let user_id = Id::try_from(user.id.as_str()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let roles: Vec<Role> = body
+        .requested_roles
+        .unwrap_or_else(|| account.allowed_roles.clone())
+        .into_iter()
+        .filter(|role| account.allowed_roles.contains(role))
+        .collect();
+    if roles.is_empty() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let signing_key = state
+        .session_key
+        .active()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let session = SessionBuilder::new(user_id, EXCHANGED_SESSION_TTL_SECS)
+        .with_roles(roles.clone())
+        .with_issuer(state.session_validation.issuer.as_str())
+        .with_audience(state.session_validation.audience.as_str())
+        .exchanged_by(account.client_id)
+        .finish(&signing_key);
+    let session_id = session.id.clone();
+    let expires_at = session.expires_at;
+    state
+        .sessions
+        .insert(session)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(TokenExchangeResponse {
+        session_id,
+        token_type: super::handlers::SESSION_TOKEN_TYPE,
+        expires_at,
+        user_id: user_id.to_string(),
+        roles,
+    }))
+}
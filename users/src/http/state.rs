@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use foundation::blob::BlobStorage;
+use foundation::clock::Clock;
+use libsvc::audit::AuditLog;
+use libsvc::captcha::{CaptchaVerifier, FailedLoginTracker};
+use libsvc::dpop::{DPoPKeyStore, DPoPNonceStore};
+use libsvc::geoip::GeoIpLookup;
+use libsvc::mailer::Mailer;
+use libsvc::saml::SamlSpConfig;
+use libsvc::security_signal::{RetainingSecuritySignal, SecuritySignal};
+use libsvc::service_account::ServiceAccountRegistry;
+use libsvc::session::{KeyRing, Role, SessionManager, SessionValidation};
+use libsvc::telemetry::LogLevelHandle;
+use libsvc::verification_cache::VerificationCache;
+
+use crate::domain::{UsageRepository, UserLogic};
+use crate::feature_flags::FeatureFlags;
+
+/// Shared state handed to every HTTP handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub user_logic: Arc<dyn UserLogic>,
+    pub sessions: Arc<SessionManager>,
+    pub session_key: Arc<KeyRing>,
+    pub session_validation: SessionValidation,
+    /// Whether [`crate::http::handlers::authenticate`] should also issue a
+    /// long-lived refresh session alongside the access session.
+    pub issue_refresh_tokens: bool,
+    pub audit_log: Arc<dyn AuditLog>,
+    pub avatars: Arc<dyn BlobStorage>,
+    pub log_level: LogLevelHandle,
+    /// Notified of sessions that fail signature verification, for
+    /// suspicious-activity detection.
+    pub security_signal: Arc<dyn SecuritySignal>,
+    pub captcha: Arc<dyn CaptchaVerifier>,
+    /// Whether [`crate::http::handlers::register`] requires a solved
+    /// CAPTCHA challenge. `captcha` itself still decides whether that
+    /// challenge is actually checked against a provider.
+    pub require_captcha_for_registration: bool,
+    /// The number of consecutive failed logins for an identifier (see
+    /// [`FailedLoginTracker`]) after which
+    /// [`crate::http::handlers::authenticate`] requires a solved CAPTCHA
+    /// challenge. `0` never requires one.
+    pub captcha_after_failed_logins: u32,
+    pub failed_logins: Arc<FailedLoginTracker>,
+    /// Sends the [`crate::http::handlers::request_magic_link`] email
+    /// directly, since that handler issues the session token itself rather
+    /// than going through [`UserLogic`].
+    pub mailer: Arc<dyn Mailer>,
+    /// SAML SSO configuration, when this deployment has an IdP
+    /// integration set up. `None` disables [`crate::http::saml`]'s
+    /// endpoints entirely.
+    pub saml: Option<Arc<SamlSpConfig>>,
+    /// Trusted services allowed to exchange their own API key for a
+    /// session acting as a user. `None` disables
+    /// [`crate::http::service_tokens`]'s endpoint entirely.
+    pub service_accounts: Option<Arc<dyn ServiceAccountRegistry>>,
+    /// Backs [`crate::http::usage`]'s per-user call accounting.
+    pub usage: Arc<dyn UsageRepository>,
+    /// The number of calls a user may make per UTC calendar day before
+    /// [`crate::http::usage::track_usage`] starts returning `429 Too Many
+    /// Requests`. `None` leaves usage uncapped (but still counted).
+    pub usage_quota_per_day: Option<u32>,
+    /// The time [`crate::http::usage::track_usage`] and
+    /// [`crate::http::usage::usage`] bucket calls by calendar day against.
+    pub clock: Arc<dyn Clock>,
+    /// Counts sessions verified and rejected by [`crate::http::auth`], and
+    /// is also handed to [`UserLogic`] and `sessions` so logins and
+    /// session issuance land in the same sink. Served in Prometheus text
+    /// format at [`crate::http::metrics::metrics`].
+    pub metrics: Arc<crate::http::metrics::PrometheusMetrics>,
+    /// Lets [`crate::http::auth::AuthenticatedUser`] skip re-verifying a
+    /// session's signature when a recent request already verified the
+    /// same one. Disabled by default; see
+    /// [`libsvc::verification_cache::VerificationCacheConfig`].
+    pub session_verification_cache: Arc<VerificationCache>,
+    /// The same [`RetainingSecuritySignal`] `security_signal` points to
+    /// (concrete, not `dyn`, since [`crate::http::admin::security_events`]
+    /// needs [`RetainingSecuritySignal::recent`], which isn't part of the
+    /// [`SecuritySignal`] trait every other caller uses).
+    pub security_events: Arc<RetainingSecuritySignal>,
+    /// Whether sessions issued over an mTLS connection should be bound to
+    /// the presenting client certificate's thumbprint (see
+    /// [`crate::http::mtls`]), and whether [`crate::http::auth::AuthenticatedUser`]
+    /// should reject a bound session presented with a different one.
+    /// Deployments without a client-cert-verifying proxy in front leave
+    /// this off, since there's no thumbprint header to trust.
+    pub require_cert_binding: bool,
+    /// Whether sessions should be issued with DPoP-style proof-of-possession
+    /// required (see [`libsvc::dpop`]), and whether
+    /// [`crate::http::auth::AuthenticatedUser`] should reject a bound
+    /// session presented without a valid proof.
+    pub require_dpop: bool,
+    /// Server-side half of each DPoP-bound session's proof-of-possession
+    /// key, looked up by session id.
+    pub dpop_keys: Arc<dyn DPoPKeyStore>,
+    /// Nonces recently seen in a valid DPoP proof, so the same proof can't
+    /// be replayed within its own clock-skew window.
+    pub dpop_nonces: Arc<dyn DPoPNonceStore>,
+    /// When set, [`crate::http::handlers::issue_session_pair`] also hands
+    /// back the access session as an encrypted JWE (see
+    /// [`libsvc::session::encrypt`]) for callers that need a self-contained
+    /// token whose claims aren't readable by an intermediary, and
+    /// [`crate::http::auth::AuthenticatedUser`] accepts one presented via
+    /// `x-session-token` in place of a plain session id. `None` disables
+    /// the feature entirely.
+    pub session_encryption_key: Option<Arc<foundation::crypto::EncryptionKey>>,
+    /// Signs and verifies [`libsvc::action_token`]s: narrowly scoped,
+    /// minutes-lived tokens that authorize a single action (e.g.
+    /// downloading a data export) without a session. One static key is
+    /// enough since, unlike [`session_key`](Self::session_key), these
+    /// tokens are never expected to outlive a key rotation — they expire
+    /// on their own within minutes.
+    pub action_token_key: Arc<foundation::key::Key>,
+    /// Backs [`crate::http::admin`]'s feature-flag override endpoints and
+    /// any in-process call to [`FeatureFlags::is_enabled`].
+    pub feature_flags: Arc<FeatureFlags>,
+    /// Roles that must complete MFA enrollment (see
+    /// [`UserLogic::mfa_enrolled`]) before a session carrying one is
+    /// accepted. [`crate::http::handlers::authenticate`] refuses to issue
+    /// a session for an unenrolled account whose granted roles intersect
+    /// this set, and [`crate::http::auth::AuthenticatedUser`] rejects an
+    /// already-issued session the same way — covering a session minted
+    /// elsewhere (e.g. by an internal identity service sharing this
+    /// deployment's signing key) with a role this service never grants
+    /// itself. Empty disables the policy entirely.
+    pub mfa_required_roles: std::collections::HashSet<Role>,
+    /// Resolves a login's IP to a country for [`libsvc::risk::RiskCondition::Country`].
+    /// [`libsvc::geoip::NoopGeoIpLookup`] when this deployment has no GeoIP
+    /// database configured, in which case that condition never matches.
+    pub geoip: Arc<dyn GeoIpLookup>,
+    /// Evaluated by [`crate::http::handlers::authenticate`] against each
+    /// login attempt; `None` allows every login unconditionally. See
+    /// [`crate::risk_policy::load`] for how this is configured.
+    pub risk_policy: Option<Arc<libsvc::risk::RiskPolicy>>,
+    /// Backs [`libsvc::risk::RiskCondition::NewDevice`] by remembering which
+    /// device ids (from the `x-device-id` header) have been seen per user.
+    pub known_devices: Arc<libsvc::risk::KnownDeviceTracker>,
+    /// How long a session may go without being presented before
+    /// [`crate::http::auth::AuthenticatedUser`] rejects it even though its
+    /// `expires_at` hasn't passed yet. `None` disables the idle timeout, so
+    /// only absolute expiry applies.
+    pub session_idle_timeout_secs: Option<u64>,
+    /// This deployment's identity as an OIDC provider, if it's acting as
+    /// one. `None` disables [`crate::http::oidc`]'s endpoints entirely.
+    pub oidc: Option<Arc<libsvc::oidc::OidcProviderConfig>>,
+    /// Clients registered to request sessions through
+    /// [`crate::http::oidc`]'s authorization code flow. `None` disables
+    /// `/v1/oidc/authorize`, `/v1/oidc/consent`, and `/v1/oidc/token` the
+    /// same way [`Self::service_accounts`] disables token exchange.
+    pub oidc_clients: Option<Arc<dyn libsvc::oidc::OidcClientRegistry>>,
+    /// Authorization codes issued by [`crate::http::oidc::authorize`] and
+    /// [`crate::http::oidc::consent`], redeemed exactly once by
+    /// [`crate::http::oidc::token`].
+    pub oidc_codes: Arc<dyn libsvc::oidc::AuthorizationCodeStore>,
+    /// Which users have already consented to which clients and scopes, so
+    /// [`crate::http::oidc::authorize`] can skip the consent screen on a
+    /// returning visit.
+    pub oidc_consents: Arc<dyn libsvc::oidc::ConsentStore>,
+    /// Signs and verifies ID tokens minted by [`crate::http::oidc::token`]
+    /// (see [`libsvc::id_token`]). Separate from [`Self::action_token_key`]
+    /// despite the same HMAC scheme, since ID tokens have their own
+    /// lifecycle and audience and shouldn't be invalidated by rotating
+    /// the unrelated action-token key.
+    pub oidc_signing_key: Arc<foundation::key::Key>,
+    /// Whether [`crate::http::handlers::issue_session_pair`] should also
+    /// deliver the session as an HttpOnly cookie (see
+    /// [`crate::http::cookies`]), alongside a paired CSRF cookie, for
+    /// browser clients that can't safely hold `session_id` in JS-accessible
+    /// storage. A deployment that turns this on must also layer
+    /// [`crate::http::with_csrf_protection`] onto the router, since the
+    /// session cookie is otherwise attached by the browser to forged
+    /// cross-origin requests with nothing checking for it. Off by default,
+    /// since the header-based delivery it's an alternative to needs
+    /// neither cookie.
+    pub cookie_sessions_enabled: bool,
+}
@@ -0,0 +1,95 @@
+//! An optional static-file mount for serving a bundled admin UI (or any
+//! other single-page app) directly from this service, so a deployment
+//! doesn't need a separate web server just to host a handful of static
+//! assets.
+
+use std::path::{Path, PathBuf};
+
+use axum::Router;
+use tower_http::services::{ServeDir, ServeFile};
+
+/// Where to serve static files from, and under which path.
+#[derive(Debug, Clone)]
+pub struct StaticFilesConfig {
+    /// The URL path the files are mounted under, e.g. `/`.
+    pub mount_path: String,
+    /// The directory on disk holding the built assets, including
+    /// `index.html`.
+    pub directory: PathBuf,
+}
+
+/// Nests a [`ServeDir`] at `config.mount_path`, falling back to
+/// `index.html` for any path it doesn't recognize (missing file, or a
+/// client-side route like `/settings`), so a single-page app's router
+/// keeps working on a hard refresh or a deep link.
+pub fn with_static_files(router: Router, config: StaticFilesConfig) -> Router {
+    let index = index_html_path(&config.directory);
+    let service = ServeDir::new(&config.directory).fallback(ServeFile::new(index));
+    router.nest_service(&config.mount_path, service)
+}
+
+fn index_html_path(directory: &Path) -> PathBuf {
+    directory.join("index.html")
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::util::ServiceExt;
+
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serves_an_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "index.html", "home");
+        write(dir.path(), "app.js", "console.log(1)");
+        let router = with_static_files(
+            Router::new(),
+            StaticFilesConfig {
+                mount_path: "/".to_string(),
+                directory: dir.path().to_path_buf(),
+            },
+        );
+
+        let response = router
+            .oneshot(Request::get("/app.js").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "console.log(1)".as_bytes());
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_index_html_for_unknown_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "index.html", "home");
+        let router = with_static_files(
+            Router::new(),
+            StaticFilesConfig {
+                mount_path: "/".to_string(),
+                directory: dir.path().to_path_buf(),
+            },
+        );
+
+        let response = router
+            .oneshot(Request::get("/settings").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "home".as_bytes());
+    }
+}
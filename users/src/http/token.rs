@@ -0,0 +1,49 @@
+//! `POST /v1/token/revoke`: an [RFC 7009](https://www.rfc-editor.org/rfc/rfc7009)
+//! token revocation endpoint. Anyone holding an access or refresh session
+//! id may revoke it directly, without needing a separate authenticated
+//! session — possession of the token is itself the authority to kill it,
+//! the same way [`crate::http::handlers::request_magic_link`] treats
+//! possession of an inbox as proof of identity. Per RFC 7009 section 2.2,
+//! the response is always `200 OK` regardless of whether the token was
+//! found, already revoked, or never existed, so the endpoint can't be
+//! used to probe which tokens are live.
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use libsvc::audit::AuditEvent;
+use serde::Deserialize;
+
+use super::request_signature::SERVICE_ID_HEADER;
+use super::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeTokenRequest {
+    pub token: String,
+    /// Accepted for RFC 7009 compliance but unused: a session id alone
+    /// already determines whether it's an access or refresh token.
+    #[allow(dead_code)]
+    pub token_type_hint: Option<String>,
+}
+
+/// `POST /v1/token/revoke`
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<RevokeTokenRequest>,
+) -> StatusCode {
+    if let Ok(Some(session)) = state.sessions.get(&body.token) {
+        let _ = state.sessions.revoke(&body.token);
+        let source = headers
+            .get(SERVICE_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| session.user_id.to_string());
+        state.audit_log.record(AuditEvent::new(
+            source,
+            session.user_id.to_string(),
+            "token_revoked",
+        ));
+    }
+    StatusCode::OK
+}
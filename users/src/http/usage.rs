@@ -0,0 +1,79 @@
+//! Per-user daily API call accounting and quota enforcement.
+//!
+//! [`track_usage`] records one call per authenticated request against
+//! [`AppState::usage`] and rejects with `429 Too Many Requests` once the
+//! caller has exceeded [`AppState::usage_quota_per_day`] for the day.
+//! Requests without a recognizable session pass through unmetered, since a
+//! quota is a per-identity concern, not a per-request one.
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use serde::Serialize;
+
+use super::auth::AuthenticatedUser;
+use super::csrf::SESSION_COOKIE;
+use super::state::AppState;
+
+/// Layered onto [`super::router`]. Runs ahead of every handler, so a caller
+/// over quota is turned away before doing any real work.
+pub async fn track_usage(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(session_id) = request
+        .headers()
+        .get("x-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| jar.get(SESSION_COOKIE).map(|c| c.value().to_string()))
+    else {
+        return Ok(next.run(request).await);
+    };
+
+    let Ok(Some(session)) = state.sessions.get(&session_id) else {
+        return Ok(next.run(request).await);
+    };
+
+    let count = state
+        .usage
+        .increment(&session.user_id.to_string(), state.clock.now().date_naive())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Some(quota) = state.usage_quota_per_day {
+        if count > quota as u64 {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageResponse {
+    pub calls_today: u64,
+    pub quota_per_day: Option<u32>,
+}
+
+/// `GET /v1/users/me/usage`
+pub async fn usage(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<Json<UsageResponse>, StatusCode> {
+    let calls_today = state
+        .usage
+        .get(&user.user_id, state.clock.now().date_naive())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(UsageResponse {
+        calls_today,
+        quota_per_day: state.usage_quota_per_day,
+    }))
+}
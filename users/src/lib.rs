@@ -0,0 +1,17 @@
+//! The `users` service: domain logic for user accounts and the HTTP API
+//! that exposes it.
+
+pub mod backup;
+pub mod digest;
+pub mod domain;
+pub mod feature_flags;
+pub mod http;
+pub mod logic;
+pub mod notifications;
+pub mod repository;
+pub mod retention;
+pub mod risk_policy;
+pub mod seed;
+pub mod storage;
+#[cfg(feature = "test-util")]
+pub mod test_util;
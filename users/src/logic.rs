@@ -0,0 +1,1418 @@
+//! The default [`UserLogic`] implementation, built on top of a
+//! [`UserRepository`] and a shared [`AuditLog`].
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use foundation::clock::{Clock, SystemClock};
+use foundation::hash::{hash_password, verify_password};
+use foundation::id::{DefaultIdGenerator, IdGenerator};
+use libsvc::audit::{AuditEvent, AuditLog};
+use libsvc::mail_templates;
+use libsvc::mailer::Mailer;
+use libsvc::metrics::{Metrics, NoopMetrics};
+use libsvc::rate_limit::{RateLimiterConfig, SlidingWindowRateLimiter};
+use libsvc::repository::{Error, Result};
+use libsvc::search_index::{NoopSearchIndex, SearchDocument, SearchIndex};
+use libsvc::security_signal::{LoggingSecuritySignal, SecurityEvent, SecuritySignal};
+use libsvc::session::SessionManager;
+use libsvc::sms::{LoggingSmsSender, SmsSender};
+use libsvc::unit_of_work::{NoopUnitOfWorkFactory, UnitOfWorkFactory};
+use rand::Rng;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::domain::{
+    matches_search_filter, paginate_activity, paginate_search_results, ActivityPage,
+    BackupCodesRepository, Credentials, CredentialsRepository, DevicePlatform, DeviceToken,
+    Notification, NotificationCategory, NotificationPage, Pagination, PendingEmailChange,
+    RepositoryStats, SessionExport, SmsOtp, SmsOtpRepository, User, UserDataExport, UserKind,
+    UserLogic, UserPreferences, UserPreferencesRepository, UserRepository, UserSearchFilter,
+    UserSearchPage, UserStatus,
+};
+use crate::notifications::NotificationMailer;
+use crate::repository::backup_codes::MemoryBackupCodesRepository;
+use crate::repository::devices::MemoryDeviceRepository;
+use crate::repository::digest::MemoryDigestQueueRepository;
+use crate::repository::notifications::MemoryNotificationRepository;
+use libsvc::pusher::LoggingPusher;
+use crate::repository::preferences::MemoryUserPreferencesRepository;
+use crate::repository::sms_otp::MemorySmsOtpRepository;
+
+/// Holds the admin-configured JSON Schema [`UserLogicImpl::set_custom_attributes`]
+/// validates every write against, shared by every clone of the
+/// [`UserLogicImpl`] it's built into (the same way [`KeyRing`] is shared
+/// across a service rather than owned by a single request). `None` while
+/// no schema has been set, in which case any attributes are accepted.
+///
+/// [`KeyRing`]: libsvc::session::KeyRing
+pub struct CustomAttributesSchemaStore {
+    current: Mutex<Option<(Value, jsonschema::Validator)>>,
+}
+
+impl CustomAttributesSchemaStore {
+    pub fn new() -> Self {
+        Self { current: Mutex::new(None) }
+    }
+
+    /// The schema currently in effect, exactly as last passed to
+    /// [`CustomAttributesSchemaStore::set`].
+    pub fn get(&self) -> Option<Value> {
+        self.current
+            .lock()
+            .expect("custom attributes schema lock poisoned")
+            .as_ref()
+            .map(|(schema, _)| schema.clone())
+    }
+
+    /// Replaces the schema, rejecting `schema` outright if it isn't a
+    /// valid JSON Schema document rather than storing something that
+    /// could never successfully validate anything.
+    pub fn set(&self, schema: Option<Value>) -> Result<()> {
+        let compiled = schema
+            .map(|schema| {
+                jsonschema::validator_for(&schema)
+                    .map(|validator| (schema, validator))
+                    .map_err(|err| {
+                        Error::ConstraintViolation(format!("invalid custom attributes schema: {err}"))
+                    })
+            })
+            .transpose()?;
+        *self.current.lock().expect("custom attributes schema lock poisoned") = compiled;
+        Ok(())
+    }
+
+    /// Validates `attributes` against the current schema, succeeding
+    /// trivially if none is configured.
+    pub fn validate(&self, attributes: &Map<String, Value>) -> Result<()> {
+        let guard = self.current.lock().expect("custom attributes schema lock poisoned");
+        if let Some((_, validator)) = guard.as_ref() {
+            let instance = Value::Object(attributes.clone());
+            validator.validate(&instance).map_err(|err| {
+                Error::ConstraintViolation(format!("custom attributes failed schema validation: {err}"))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for CustomAttributesSchemaStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The most candidates [`UserLogicImpl::search`] pulls from the
+/// configured [`SearchIndex`] for a text query, before applying the
+/// filter's remaining structural constraints and paginating. Generous
+/// enough that a real engine's own relevance ranking rarely needs a
+/// second page to satisfy one of ours.
+const MAX_TEXT_SEARCH_CANDIDATES: usize = 500;
+
+/// How long a [`UserLogic::request_sms_otp`] code stays valid before
+/// [`UserLogic::verify_sms_otp`] rejects it as expired. Short, since the
+/// code is delivered over SMS and expected to be typed back in within the
+/// same session.
+const SMS_OTP_TTL: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Generates a 6-digit one-time code, left-padded with zeros.
+fn generate_sms_otp_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+/// How many backup codes [`UserLogicImpl::generate_backup_codes`] issues.
+const BACKUP_CODE_COUNT: usize = 10;
+
+/// Generates one `XXXX-XXXX` backup code from 5 random bytes, formatted
+/// uppercase hex for easy transcription.
+fn generate_backup_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill(&mut bytes);
+    let hex = bytes.iter().map(|b| format!("{b:02X}")).collect::<String>();
+    format!("{}-{}", &hex[..4], &hex[4..])
+}
+
+/// Hashes `code` for storage, the same lightweight way
+/// [`libsvc::verification_cache`] hashes short-lived signatures rather
+/// than the heavier [`foundation::hash::hash_password`] meant for
+/// long-lived credentials. Shared by [`UserLogic::verify_sms_otp`] and
+/// [`UserLogic::consume_backup_code`], since both check a short one-time
+/// code against a stored hash rather than a long-lived credential.
+fn hash_one_time_code(code: &str) -> String {
+    use base64::Engine;
+    let mut hasher = Sha256::new();
+    hasher.update(code.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// The fields of `user` kept in sync with the configured [`SearchIndex`].
+fn search_document(user: &User) -> SearchDocument {
+    let mut document = SearchDocument::new(user.id.clone()).with_field("email", &user.email);
+    if let Some(username) = &user.username {
+        document = document.with_field("username", username);
+    }
+    document
+}
+
+pub struct UserLogicImpl {
+    repository: Arc<dyn UserRepository>,
+    credentials: Arc<dyn CredentialsRepository>,
+    audit_log: Arc<dyn AuditLog>,
+    mailer: Arc<dyn Mailer>,
+    unit_of_work: Arc<dyn UnitOfWorkFactory>,
+    login_rate_limiter: SlidingWindowRateLimiter,
+    preferences_repository: Arc<dyn UserPreferencesRepository>,
+    security_signal: Arc<dyn SecuritySignal>,
+    search_index: Arc<dyn SearchIndex>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    metrics: Arc<dyn Metrics>,
+    custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+    notifications: Arc<NotificationMailer>,
+    sms_sender: Arc<dyn SmsSender>,
+    sms_otp_repository: Arc<dyn SmsOtpRepository>,
+    backup_codes_repository: Arc<dyn BackupCodesRepository>,
+    sessions: Arc<SessionManager>,
+}
+
+impl UserLogicImpl {
+    pub fn new(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+    ) -> Self {
+        Self::with_unit_of_work(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            Arc::new(NoopUnitOfWorkFactory),
+        )
+    }
+
+    /// Like [`UserLogicImpl::new`], but with an explicit [`UnitOfWorkFactory`]
+    /// for backends that can roll back a create/record or delete/anonymize
+    /// pair as a single transaction.
+    pub fn with_unit_of_work(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+    ) -> Self {
+        Self::with_login_rate_limit(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            RateLimiterConfig::default(),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_unit_of_work`], but with an explicit
+    /// [`RateLimiterConfig`] for throttling [`UserLogic::authenticate`]
+    /// attempts per identifier.
+    pub fn with_login_rate_limit(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+    ) -> Self {
+        Self::with_preferences_repository(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            Arc::new(MemoryUserPreferencesRepository::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_login_rate_limit`], but with an explicit
+    /// [`UserPreferencesRepository`] backing [`UserLogic::get_preferences`]
+    /// and [`UserLogic::update_preferences`].
+    pub fn with_preferences_repository(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+    ) -> Self {
+        Self::with_security_signal(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            Arc::new(LoggingSecuritySignal::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_preferences_repository`], but with an
+    /// explicit [`SecuritySignal`] notified of suspicious activity
+    /// observed during [`UserLogic::authenticate`], for deployments that
+    /// want it forwarded to a risk engine instead of just logged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_security_signal(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+    ) -> Self {
+        Self::with_search_index(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            Arc::new(NoopSearchIndex),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_security_signal`], but with an explicit
+    /// [`SearchIndex`] kept in sync with every profile mutation and
+    /// queried by [`UserLogic::search`]'s [`UserSearchFilter::text_query`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_search_index(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+    ) -> Self {
+        Self::with_clock(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            Arc::new(SystemClock),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_search_index`], but with an explicit
+    /// [`Clock`] that `created_at` and pending-email timestamps are drawn
+    /// from, for tests that need control over them.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_clock(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self::with_id_generator(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            Arc::new(DefaultIdGenerator::default()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_clock`], but with an explicit
+    /// [`IdGenerator`] that user ids and pending-email confirmation tokens
+    /// are drawn from, for tests that need predictable ids and deployments
+    /// that want a different scheme (ULIDs, a Snowflake generator).
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_id_generator(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+    ) -> Self {
+        Self::with_metrics(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            Arc::new(NoopMetrics),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_id_generator`], but with an explicit
+    /// [`Metrics`] sink that login and session counters are reported to.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_metrics(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+    ) -> Self {
+        Self::with_custom_attributes_schema(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            Arc::new(CustomAttributesSchemaStore::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_metrics`], but with an explicit
+    /// [`CustomAttributesSchemaStore`], for deployments that need to seed
+    /// one already configured at startup rather than setting it later via
+    /// [`UserLogic::set_custom_attributes_schema`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_custom_attributes_schema(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+        custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+    ) -> Self {
+        let notifications = Arc::new(NotificationMailer::new(
+            mailer.clone(),
+            preferences_repository.clone(),
+            Arc::new(MemoryDigestQueueRepository::new()),
+            Arc::new(MemoryNotificationRepository::new()),
+            Arc::new(MemoryDeviceRepository::new()),
+            Arc::new(LoggingPusher::new()),
+            clock.clone(),
+            ids.clone(),
+        ));
+        Self::with_notifications(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            custom_attributes_schema,
+            notifications,
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_custom_attributes_schema`], but with an
+    /// explicit [`NotificationMailer`], for deployments that want
+    /// unsolicited notifications (see [`NotificationCategory`]) delivered
+    /// or queued through a shared instance rather than the per-service
+    /// default built from `mailer` and `preferences_repository`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_notifications(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+        custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+        notifications: Arc<NotificationMailer>,
+    ) -> Self {
+        Self::with_sms(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            custom_attributes_schema,
+            notifications,
+            Arc::new(LoggingSmsSender::new()),
+            Arc::new(MemorySmsOtpRepository::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_notifications`], but with an explicit
+    /// [`SmsSender`] and [`SmsOtpRepository`], for deployments that want
+    /// [`UserLogic::request_sms_otp`] to go out through a real carrier
+    /// gateway (see [`libsvc::sms`]) rather than just being logged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_sms(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+        custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+        notifications: Arc<NotificationMailer>,
+        sms_sender: Arc<dyn SmsSender>,
+        sms_otp_repository: Arc<dyn SmsOtpRepository>,
+    ) -> Self {
+        Self::with_backup_codes(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            custom_attributes_schema,
+            notifications,
+            sms_sender,
+            sms_otp_repository,
+            Arc::new(MemoryBackupCodesRepository::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_sms`], but with an explicit
+    /// [`BackupCodesRepository`], for deployments that want
+    /// [`UserLogic::generate_backup_codes`]/[`UserLogic::consume_backup_code`]
+    /// backed by durable storage rather than the in-memory default.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_backup_codes(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+        custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+        notifications: Arc<NotificationMailer>,
+        sms_sender: Arc<dyn SmsSender>,
+        sms_otp_repository: Arc<dyn SmsOtpRepository>,
+        backup_codes_repository: Arc<dyn BackupCodesRepository>,
+    ) -> Self {
+        Self::with_sessions(
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limit,
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            custom_attributes_schema,
+            notifications,
+            sms_sender,
+            sms_otp_repository,
+            backup_codes_repository,
+            Arc::new(SessionManager::new()),
+        )
+    }
+
+    /// Like [`UserLogicImpl::with_backup_codes`], but with an explicit
+    /// [`SessionManager`], shared with the same one the HTTP layer issues
+    /// and verifies sessions against, so [`UserLogic::export_data`] can
+    /// report a user's own active sessions alongside their profile and
+    /// audit history.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_sessions(
+        repository: Arc<dyn UserRepository>,
+        credentials: Arc<dyn CredentialsRepository>,
+        audit_log: Arc<dyn AuditLog>,
+        mailer: Arc<dyn Mailer>,
+        unit_of_work: Arc<dyn UnitOfWorkFactory>,
+        login_rate_limit: RateLimiterConfig,
+        preferences_repository: Arc<dyn UserPreferencesRepository>,
+        security_signal: Arc<dyn SecuritySignal>,
+        search_index: Arc<dyn SearchIndex>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        metrics: Arc<dyn Metrics>,
+        custom_attributes_schema: Arc<CustomAttributesSchemaStore>,
+        notifications: Arc<NotificationMailer>,
+        sms_sender: Arc<dyn SmsSender>,
+        sms_otp_repository: Arc<dyn SmsOtpRepository>,
+        backup_codes_repository: Arc<dyn BackupCodesRepository>,
+        sessions: Arc<SessionManager>,
+    ) -> Self {
+        Self {
+            repository,
+            credentials,
+            audit_log,
+            mailer,
+            unit_of_work,
+            login_rate_limiter: SlidingWindowRateLimiter::new(login_rate_limit),
+            preferences_repository,
+            security_signal,
+            search_index,
+            clock,
+            ids,
+            metrics,
+            custom_attributes_schema,
+            notifications,
+            sms_sender,
+            sms_otp_repository,
+            backup_codes_repository,
+            sessions,
+        }
+    }
+
+    /// Re-indexes `user`, logging (rather than propagating) a failure: the
+    /// index is a derived, rebuildable view, so a write to it falling
+    /// behind shouldn't fail the profile mutation that triggered it.
+    async fn reindex(&self, user: &User) {
+        if let Err(err) = self.search_index.index(search_document(user)).await {
+            tracing::warn!(user_id = %user.id, %err, "failed to update search index");
+        }
+    }
+
+    /// Removes `id` from the index. See [`UserLogicImpl::reindex`] for why
+    /// failures are logged rather than propagated.
+    async fn unindex(&self, id: &str) {
+        if let Err(err) = self.search_index.remove(id).await {
+            tracing::warn!(user_id = %id, %err, "failed to remove user from search index");
+        }
+    }
+}
+
+#[async_trait]
+impl UserLogic for UserLogicImpl {
+    async fn register(&self, email: &str, password: &str) -> Result<User> {
+        let password_hash =
+            hash_password(password).map_err(|e| Error::Backend(e.to_string()))?;
+        let user = User {
+            id: self.ids.generate().to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: self.clock.now(),
+            pending_email: None,
+            avatar_url: None,
+            status: UserStatus::Active,
+            legal_hold: false,
+            kind: UserKind::Human,
+            organization_id: None,
+            custom_attributes: Map::new(),
+            tags: BTreeSet::new(),
+        };
+        let uow = self.unit_of_work.begin().await?;
+        let user = match self.repository.create(user).await {
+            Ok(user) => user,
+            Err(e) => {
+                uow.rollback().await?;
+                return Err(e);
+            }
+        };
+        if let Err(e) = self
+            .credentials
+            .create(Credentials {
+                user_id: user.id.clone(),
+                password_hash,
+            })
+            .await
+        {
+            uow.rollback().await?;
+            return Err(e);
+        }
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "registered"));
+        uow.commit().await?;
+        self.reindex(&user).await;
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "register", user_id = %user.id, "user registered");
+        Ok(user)
+    }
+
+    async fn register_service_account(&self, client_id: &str, api_key: &str) -> Result<User> {
+        let password_hash =
+            hash_password(api_key).map_err(|e| Error::Backend(e.to_string()))?;
+        let user = User {
+            id: self.ids.generate().to_string(),
+            email: client_id.to_string(),
+            username: None,
+            created_at: self.clock.now(),
+            pending_email: None,
+            avatar_url: None,
+            status: UserStatus::Active,
+            legal_hold: false,
+            kind: UserKind::Service,
+            organization_id: None,
+            custom_attributes: Map::new(),
+            tags: BTreeSet::new(),
+        };
+        let uow = self.unit_of_work.begin().await?;
+        let user = match self.repository.create(user).await {
+            Ok(user) => user,
+            Err(e) => {
+                uow.rollback().await?;
+                return Err(e);
+            }
+        };
+        if let Err(e) = self
+            .credentials
+            .create(Credentials {
+                user_id: user.id.clone(),
+                password_hash,
+            })
+            .await
+        {
+            uow.rollback().await?;
+            return Err(e);
+        }
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "service_account_registered"));
+        uow.commit().await?;
+        self.reindex(&user).await;
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "register_service_account", user_id = %user.id, "service account registered");
+        Ok(user)
+    }
+
+    async fn authenticate(&self, identifier: &str, password: &str) -> Result<User> {
+        let allowed = self
+            .login_rate_limiter
+            .check(identifier)
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        if !allowed {
+            self.audit_log.record(AuditEvent::new(
+                identifier,
+                identifier,
+                "authentication_rate_limited",
+            ));
+            return Err(Error::RateLimited(identifier.to_string()));
+        }
+
+        let user = match self.repository.get_by_email(identifier).await {
+            Ok(user) => user,
+            Err(Error::NotFound) => match self.repository.get_by_username(identifier).await {
+                Ok(user) => user,
+                Err(Error::NotFound) => {
+                    self.security_signal.observe(SecurityEvent::InvalidCredentials {
+                        identifier: identifier.to_string(),
+                    });
+                    self.metrics.increment("login_failures_total");
+                    return Err(Error::NotFound);
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+        if user.kind == UserKind::Service {
+            self.audit_log.record(AuditEvent::new(
+                &user.id,
+                &user.id,
+                "login_rejected_service_account",
+            ));
+            self.metrics.increment("login_failures_total");
+            return Err(Error::PasswordLoginDisabled(user.id.clone()));
+        }
+        let credentials = self.credentials.get(&user.id).await?;
+        let ok = verify_password(password, &credentials.password_hash)
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        if !ok {
+            self.security_signal.observe(SecurityEvent::InvalidCredentials {
+                identifier: identifier.to_string(),
+            });
+            self.metrics.increment("login_failures_total");
+            return Err(Error::NotFound);
+        }
+        if user.status != UserStatus::Active {
+            self.audit_log.record(AuditEvent::new(
+                &user.id,
+                &user.id,
+                "login_rejected_inactive_account",
+            ));
+            self.metrics.increment("login_failures_total");
+            return Err(Error::AccountNotActive(user.id.clone()));
+        }
+        self.metrics.increment("logins_total");
+        self.audit_log.record(AuditEvent::new(&user.id, &user.id, "logged_in"));
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "authenticate", user_id = %user.id, "user authenticated");
+        Ok(user)
+    }
+
+    async fn authenticate_service_account(&self, client_id: &str, api_key: &str) -> Result<User> {
+        let allowed = self
+            .login_rate_limiter
+            .check(client_id)
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        if !allowed {
+            self.audit_log.record(AuditEvent::new(
+                client_id,
+                client_id,
+                "authentication_rate_limited",
+            ));
+            return Err(Error::RateLimited(client_id.to_string()));
+        }
+
+        let user = match self.repository.get_by_email(client_id).await {
+            Ok(user) if user.kind == UserKind::Service => user,
+            Ok(_) | Err(Error::NotFound) => {
+                self.security_signal.observe(SecurityEvent::InvalidCredentials {
+                    identifier: client_id.to_string(),
+                });
+                self.metrics.increment("login_failures_total");
+                return Err(Error::NotFound);
+            }
+            Err(e) => return Err(e),
+        };
+        let credentials = self.credentials.get(&user.id).await?;
+        let ok = verify_password(api_key, &credentials.password_hash)
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        if !ok {
+            self.security_signal.observe(SecurityEvent::InvalidCredentials {
+                identifier: client_id.to_string(),
+            });
+            self.metrics.increment("login_failures_total");
+            return Err(Error::NotFound);
+        }
+        if user.status != UserStatus::Active {
+            self.audit_log.record(AuditEvent::new(
+                &user.id,
+                &user.id,
+                "login_rejected_inactive_account",
+            ));
+            self.metrics.increment("login_failures_total");
+            return Err(Error::AccountNotActive(user.id.clone()));
+        }
+        self.metrics.increment("logins_total");
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "service_account_logged_in"));
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "authenticate_service_account", user_id = %user.id, "service account authenticated");
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        self.repository.get(id).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<User> {
+        self.repository.get_by_email(email).await
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        email: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        if let Some(username) = username {
+            user.username = Some(username);
+        }
+        if let Some(new_email) = email {
+            let pending = PendingEmailChange {
+                new_email: new_email.clone(),
+                confirmation_token: self.ids.generate().to_string(),
+            };
+            let locale = self
+                .preferences_repository
+                .get(&user.id)
+                .await
+                .map(|preferences| preferences.locale)
+                .unwrap_or_else(|_| UserPreferences::defaults(&user.id).locale);
+            let mut vars = std::collections::HashMap::new();
+            vars.insert("token", pending.confirmation_token.as_str());
+            match mail_templates::render(mail_templates::EmailTemplate::Verification, &locale, &vars)
+            {
+                Ok(email) => self.mailer.send(&new_email, &email.subject, &email.body),
+                Err(err) => tracing::warn!(%err, "failed to render verification email, skipping"),
+            }
+            self.notifications
+                .notify(
+                    &user.id,
+                    &user.email,
+                    NotificationCategory::Security,
+                    "Email change requested",
+                    &format!("A change to {new_email} was requested for your account. If this wasn't you, contact support."),
+                )
+                .await;
+            user.pending_email = Some(pending);
+        }
+        if let Some(password) = password {
+            let password_hash =
+                hash_password(&password).map_err(|e| Error::Backend(e.to_string()))?;
+            self.credentials
+                .update(Credentials {
+                    user_id: user.id.clone(),
+                    password_hash,
+                })
+                .await?;
+        }
+        let user = self.repository.update(user).await?;
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "updated_profile"));
+        self.reindex(&user).await;
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "update", user_id = %user.id, "user profile updated");
+        Ok(user)
+    }
+
+    async fn verify_password(&self, id: &str, password: &str) -> Result<bool> {
+        let credentials = self.credentials.get(id).await?;
+        verify_password(password, &credentials.password_hash)
+            .map_err(|e| Error::Backend(e.to_string()))
+    }
+
+    async fn update_avatar(&self, id: &str, avatar_url: Option<String>) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.avatar_url = avatar_url;
+        let user = self.repository.update(user).await?;
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "updated_avatar"));
+        Ok(user)
+    }
+
+    async fn confirm_email_change(&self, id: &str, token: &str) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        let pending = user
+            .pending_email
+            .take()
+            .ok_or(Error::NotFound)?;
+        if pending.confirmation_token != token {
+            user.pending_email = Some(pending);
+            return Err(Error::NotFound);
+        }
+        user.email = pending.new_email;
+        let user = self.repository.update(user).await?;
+        self.audit_log
+            .record(AuditEvent::new(&user.id, &user.id, "confirmed_email_change"));
+        self.reindex(&user).await;
+        Ok(user)
+    }
+
+    async fn username_available(&self, username: &str) -> Result<bool> {
+        match self.repository.get_by_username(username).await {
+            Ok(_) => Ok(false),
+            Err(Error::NotFound) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        let Some(query) = &filter.text_query else {
+            return self.repository.search(filter, pagination).await;
+        };
+
+        let candidate_ids = self
+            .search_index
+            .search(query, MAX_TEXT_SEARCH_CANDIDATES)
+            .await
+            .map_err(|e| Error::Backend(e.to_string()))?;
+        let mut matches = Vec::new();
+        for id in candidate_ids {
+            if let Ok(user) = self.repository.get(&id).await {
+                if matches_search_filter(&user, filter) {
+                    matches.push(user);
+                }
+            }
+        }
+        Ok(paginate_search_results(matches, pagination))
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        if self.repository.get(id).await?.legal_hold {
+            return Err(Error::LegalHold(id.to_string()));
+        }
+        self.repository.delete(id).await?;
+        self.credentials.delete(id).await?;
+        self.audit_log
+            .record(AuditEvent::new(id, id, "deleted"));
+        self.unindex(id).await;
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "delete", user_id = %id, "user deleted");
+        Ok(())
+    }
+
+    async fn export_data(&self, id: &str) -> Result<UserDataExport> {
+        let profile = self.repository.get(id).await?;
+        let sessions = self
+            .sessions
+            .list_for_user(id)
+            .map_err(|e| Error::Backend(e.to_string()))?
+            .into_iter()
+            .map(SessionExport::from)
+            .collect();
+        let audit_events = self.audit_log.for_subject(id);
+        self.audit_log
+            .record(AuditEvent::new(id, id, "exported_data"));
+        Ok(UserDataExport {
+            profile: profile.into(),
+            sessions,
+            audit_events,
+        })
+    }
+
+    async fn erase(&self, id: &str) -> Result<()> {
+        if self.repository.get(id).await?.legal_hold {
+            return Err(Error::LegalHold(id.to_string()));
+        }
+        let uow = self.unit_of_work.begin().await?;
+        if let Err(e) = self.repository.delete(id).await {
+            uow.rollback().await?;
+            return Err(e);
+        }
+        if let Err(e) = self.credentials.delete(id).await {
+            uow.rollback().await?;
+            return Err(e);
+        }
+        self.audit_log.anonymize_subject(id);
+        uow.commit().await?;
+        self.unindex(id).await;
+        #[cfg(feature = "tracing-domain")]
+        tracing::info!(operation = "erase", user_id = %id, "user erased and audit trail anonymized");
+        Ok(())
+    }
+
+    async fn get_preferences(&self, id: &str) -> Result<UserPreferences> {
+        self.preferences_repository.get(id).await
+    }
+
+    async fn update_preferences(&self, preferences: UserPreferences) -> Result<UserPreferences> {
+        let preferences = self.preferences_repository.put(preferences).await?;
+        self.audit_log.record(AuditEvent::new(
+            &preferences.user_id,
+            &preferences.user_id,
+            "updated_preferences",
+        ));
+        Ok(preferences)
+    }
+
+    async fn repository_stats(&self) -> Result<RepositoryStats> {
+        self.repository.stats().await
+    }
+
+    async fn activity(&self, id: &str, pagination: Pagination) -> Result<ActivityPage> {
+        Ok(paginate_activity(self.audit_log.for_subject(id), pagination))
+    }
+
+    async fn set_status(&self, id: &str, status: UserStatus) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.status = status;
+        self.repository.update(user).await
+    }
+
+    async fn set_legal_hold(&self, id: &str, hold: bool) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.legal_hold = hold;
+        self.repository.update(user).await
+    }
+
+    async fn set_organization(&self, id: &str, organization_id: Option<String>) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.organization_id = organization_id;
+        self.repository.update(user).await
+    }
+
+    async fn get_custom_attributes_schema(&self) -> Option<Value> {
+        self.custom_attributes_schema.get()
+    }
+
+    async fn set_custom_attributes_schema(&self, schema: Option<Value>) -> Result<()> {
+        self.custom_attributes_schema.set(schema)
+    }
+
+    async fn set_custom_attributes(&self, id: &str, attributes: Map<String, Value>) -> Result<User> {
+        self.custom_attributes_schema.validate(&attributes)?;
+        let mut user = self.repository.get(id).await?;
+        user.custom_attributes = attributes;
+        self.repository.update(user).await
+    }
+
+    async fn add_tag(&self, id: &str, tag: String) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.tags.insert(tag);
+        self.repository.update(user).await
+    }
+
+    async fn remove_tag(&self, id: &str, tag: &str) -> Result<User> {
+        let mut user = self.repository.get(id).await?;
+        user.tags.remove(tag);
+        self.repository.update(user).await
+    }
+
+    async fn notifications(&self, id: &str, pagination: Pagination) -> Result<NotificationPage> {
+        self.notifications.inbox(id, pagination).await
+    }
+
+    async fn mark_notification_read(&self, id: &str, notification_id: &str) -> Result<Notification> {
+        self.notifications.mark_read(id, notification_id).await
+    }
+
+    async fn mark_all_notifications_read(&self, id: &str) -> Result<u64> {
+        self.notifications.mark_all_read(id).await
+    }
+
+    async fn register_device(
+        &self,
+        id: &str,
+        platform: DevicePlatform,
+        token: String,
+    ) -> Result<DeviceToken> {
+        self.notifications.register_device(id, platform, token).await
+    }
+
+    async fn unregister_device(&self, id: &str, token: &str) -> Result<()> {
+        self.notifications.unregister_device(id, token).await
+    }
+
+    async fn request_sms_otp(&self, id: &str) -> Result<()> {
+        let preferences = self.preferences_repository.get(id).await?;
+        let phone = preferences
+            .phone
+            .ok_or_else(|| Error::ConstraintViolation("no phone number on file".to_string()))?;
+
+        let code = generate_sms_otp_code();
+        self.sms_otp_repository
+            .store(SmsOtp {
+                phone: phone.clone(),
+                code_hash: hash_one_time_code(&code),
+                expires_at: self.clock.now() + SMS_OTP_TTL,
+            })
+            .await?;
+
+        if let Err(err) = self.sms_sender.send(&phone, &format!("Your verification code is {code}")).await {
+            tracing::warn!(%err, "failed to send sms otp");
+        }
+        Ok(())
+    }
+
+    async fn verify_sms_otp(&self, id: &str, code: &str) -> Result<()> {
+        let preferences = self.preferences_repository.get(id).await?;
+        let phone = preferences.phone.ok_or(Error::NotFound)?;
+
+        let otp = self.sms_otp_repository.take(&phone).await?.ok_or(Error::NotFound)?;
+        if otp.expires_at < self.clock.now() || otp.code_hash != hash_one_time_code(code) {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn generate_backup_codes(&self, id: &str) -> Result<Vec<String>> {
+        let codes: Vec<String> = (0..BACKUP_CODE_COUNT).map(|_| generate_backup_code()).collect();
+        let code_hashes = codes.iter().map(|code| hash_one_time_code(code)).collect();
+        self.backup_codes_repository.store(id, code_hashes).await?;
+        Ok(codes)
+    }
+
+    async fn consume_backup_code(&self, id: &str, code: &str) -> Result<()> {
+        let consumed =
+            self.backup_codes_repository.consume(id, &hash_one_time_code(code)).await?;
+        if !consumed {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn mfa_enrolled(&self, id: &str) -> Result<bool> {
+        let preferences = self.preferences_repository.get(id).await?;
+        Ok(preferences.phone.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::credentials::MemoryCredentialsRepository;
+    use crate::repository::memory::MemoryUserRepository;
+    use libsvc::audit::MemoryAuditLog;
+    use libsvc::mailer::LoggingMailer;
+
+    fn logic() -> (UserLogicImpl, Arc<MemoryAuditLog>) {
+        let audit_log = Arc::new(MemoryAuditLog::new());
+        (
+            UserLogicImpl::new(
+                Arc::new(MemoryUserRepository::new()),
+                Arc::new(MemoryCredentialsRepository::new()),
+                audit_log.clone(),
+                Arc::new(LoggingMailer::new()),
+            ),
+            audit_log,
+        )
+    }
+
+    #[tokio::test]
+    async fn register_then_authenticate_succeeds() {
+        let (logic, _audit_log) = logic();
+        logic.register("a@example.com", "password123").await.unwrap();
+        assert!(logic
+            .authenticate("a@example.com", "password123")
+            .await
+            .is_ok());
+        assert!(logic
+            .authenticate("a@example.com", "wrong")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn export_data_includes_profile_and_audit_history() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        let export = logic.export_data(&user.id).await.unwrap();
+        assert_eq!(export.profile.id, user.id);
+        assert!(!export.audit_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_with_new_email_defers_until_confirmed() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+
+        let updated = logic
+            .update(&user.id, Some("b@example.com".to_string()), None, None)
+            .await
+            .unwrap();
+        assert_eq!(updated.email, "a@example.com");
+        let pending = updated.pending_email.expect("pending email change");
+        assert_eq!(pending.new_email, "b@example.com");
+
+        let confirmed = logic
+            .confirm_email_change(&user.id, &pending.confirmation_token)
+            .await
+            .unwrap();
+        assert_eq!(confirmed.email, "b@example.com");
+        assert!(confirmed.pending_email.is_none());
+    }
+
+    #[tokio::test]
+    async fn authenticate_accepts_username_or_email() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        logic
+            .update(&user.id, None, Some("alice".to_string()), None)
+            .await
+            .unwrap();
+        assert!(logic.authenticate("alice", "password123").await.is_ok());
+        assert!(logic
+            .authenticate("a@example.com", "password123")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn deactivated_accounts_cannot_authenticate() {
+        let (logic, audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+
+        logic.set_status(&user.id, UserStatus::Deactivated).await.unwrap();
+
+        assert!(matches!(
+            logic.authenticate("a@example.com", "password123").await,
+            Err(Error::AccountNotActive(_))
+        ));
+        assert_eq!(
+            audit_log
+                .for_subject(&user.id)
+                .into_iter()
+                .filter(|e| e.action == "login_rejected_inactive_account")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn reactivating_an_account_restores_login() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+
+        logic.set_status(&user.id, UserStatus::Banned).await.unwrap();
+        logic.set_status(&user.id, UserStatus::Active).await.unwrap();
+
+        assert!(logic
+            .authenticate("a@example.com", "password123")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn username_available_reflects_registrations() {
+        let (logic, _audit_log) = logic();
+        assert!(logic.username_available("alice").await.unwrap());
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        logic
+            .update(&user.id, None, Some("alice".to_string()), None)
+            .await
+            .unwrap();
+        assert!(!logic.username_available("alice").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn confirm_email_change_rejects_wrong_token() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        logic
+            .update(&user.id, Some("b@example.com".to_string()), None, None)
+            .await
+            .unwrap();
+        assert!(logic
+            .confirm_email_change(&user.id, "not-the-token")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn register_does_not_record_audit_event_when_create_fails() {
+        let (logic, audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        assert!(logic.register("a@example.com", "password123").await.is_err());
+        let registrations = audit_log
+            .for_subject(&user.id)
+            .into_iter()
+            .filter(|e| e.action == "registered")
+            .count();
+        assert_eq!(registrations, 1);
+    }
+
+    #[tokio::test]
+    async fn authenticate_is_throttled_after_repeated_attempts() {
+        let audit_log = Arc::new(MemoryAuditLog::new());
+        let logic = UserLogicImpl::with_login_rate_limit(
+            Arc::new(MemoryUserRepository::new()),
+            Arc::new(MemoryCredentialsRepository::new()),
+            audit_log.clone(),
+            Arc::new(LoggingMailer::new()),
+            Arc::new(libsvc::unit_of_work::NoopUnitOfWorkFactory),
+            RateLimiterConfig {
+                max_attempts: 2,
+                window: std::time::Duration::from_secs(60),
+            },
+        );
+        logic.register("a@example.com", "password123").await.unwrap();
+
+        assert!(logic.authenticate("a@example.com", "wrong").await.is_err());
+        assert!(logic.authenticate("a@example.com", "wrong").await.is_err());
+        assert!(matches!(
+            logic.authenticate("a@example.com", "password123").await,
+            Err(Error::RateLimited(_))
+        ));
+        assert_eq!(
+            audit_log
+                .for_subject("a@example.com")
+                .into_iter()
+                .filter(|e| e.action == "authentication_rate_limited")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn preferences_default_until_explicitly_set() {
+        let (logic, _audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+
+        let defaults = logic.get_preferences(&user.id).await.unwrap();
+        assert!(defaults.notifications_enabled);
+        assert_eq!(defaults.theme, crate::domain::Theme::System);
+
+        let mut updated = defaults;
+        updated.theme = crate::domain::Theme::Dark;
+        logic.update_preferences(updated).await.unwrap();
+
+        assert_eq!(
+            logic.get_preferences(&user.id).await.unwrap().theme,
+            crate::domain::Theme::Dark
+        );
+    }
+
+    #[tokio::test]
+    async fn erase_deletes_user_and_anonymizes_audit_trail() {
+        let (logic, audit_log) = logic();
+        let user = logic.register("a@example.com", "password123").await.unwrap();
+        logic.erase(&user.id).await.unwrap();
+        assert!(logic.get(&user.id).await.is_err());
+        assert!(audit_log.for_subject(&user.id).is_empty());
+    }
+}
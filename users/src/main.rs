@@ -0,0 +1,559 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::Engine;
+use foundation::blob::FilesystemBlobStorage;
+use foundation::clock::SystemClock;
+use foundation::id::DefaultIdGenerator;
+use foundation::key::Key;
+use libsvc::audit::MemoryAuditLog;
+use libsvc::captcha::{CaptchaVerifier, DisabledCaptchaVerifier, FailedLoginTracker, HttpCaptchaConfig, HttpCaptchaVerifier};
+use libsvc::dpop::{InMemoryDPoPKeyStore, InMemoryDPoPNonceStore};
+use libsvc::geoip::NoopGeoIpLookup;
+use libsvc::ip_access::{IpAccessConfig, IpAccessControl};
+use libsvc::mailer::LoggingMailer;
+use libsvc::pusher::{FcmConfig, FcmPusher, LoggingPusher, Pusher};
+use libsvc::rate_limit::RateLimiterConfig;
+use libsvc::request_signing::InMemoryRequestSigningKeyStore;
+use libsvc::risk::KnownDeviceTracker;
+use libsvc::search_index::NoopSearchIndex;
+use libsvc::security_signal::{LoggingSecuritySignal, RetainingSecuritySignal};
+use libsvc::session::{KeyRing, Role, SessionManager, SessionValidation};
+use libsvc::sms::{
+    default_sms_cost_guard_config, LoggingSmsSender, RateLimitedSmsSender, SmsSender,
+    TwilioConfig, TwilioSmsSender,
+};
+use libsvc::telemetry::{LogFormat, TelemetryConfig};
+use libsvc::unit_of_work::NoopUnitOfWorkFactory;
+use libsvc::verification_cache::{VerificationCache, VerificationCacheConfig};
+use users::domain::{UserLogic, UserRepository};
+use users::feature_flags::FeatureFlags;
+use users::http::metrics::PrometheusMetrics;
+use users::http::ip_filter::{with_ip_filter, IpFilter};
+use users::http::load_shedding::{with_load_shedding, LoadShedder, LoadSheddingConfig};
+use users::http::request_signature::{with_request_signing, RequestSigningConfig};
+use users::http::static_files::{with_static_files, StaticFilesConfig};
+use users::http::{
+    router, with_connection_limit, with_csrf_protection, with_locale_negotiation,
+    with_request_timeout, with_request_tracing, AppState, DEFAULT_MAX_CONCURRENT_CONNECTIONS,
+};
+use users::digest::{self, DigestConfig};
+use users::logic::UserLogicImpl;
+use users::notifications::NotificationMailer;
+use users::repository::credentials::MemoryCredentialsRepository;
+use users::repository::digest::MemoryDigestQueueRepository;
+use users::repository::feature_flags::MemoryFeatureOverridesRepository;
+use users::repository::memory::MemoryUserRepository;
+use users::repository::devices::MemoryDeviceRepository;
+use users::repository::notifications::MemoryNotificationRepository;
+use users::repository::backup_codes::MemoryBackupCodesRepository;
+use users::repository::preferences::MemoryUserPreferencesRepository;
+use users::repository::sms_otp::MemorySmsOtpRepository;
+use users::repository::usage::MemoryUsageRepository;
+use users::retention::{self, RetentionConfig};
+use users::risk_policy;
+use users::seed;
+
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_SESSION_AUDIENCE: &str = "users";
+const DEFAULT_SESSION_LEEWAY_SECS: u64 = 30;
+const DEFAULT_CAPTCHA_AFTER_FAILED_LOGINS: u32 = 5;
+const DEFAULT_SESSION_VERIFICATION_CACHE_TTL_SECS: u64 = 5;
+const DEFAULT_DPOP_NONCE_TTL_SECS: u64 = 60;
+const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 512;
+const DEFAULT_LATENCY_TARGET_MS: u64 = 1000;
+const DEFAULT_SECURITY_EVENT_LOG_CAPACITY: usize = 1000;
+const DEFAULT_SERVICE_SIGNING_MAX_SKEW_SECS: u64 = 300;
+
+/// Parses `SERVICE_SIGNING_KEYS`, a comma-separated list of
+/// `client_id:base64-encoded-key` pairs, e.g.
+/// `SERVICE_SIGNING_KEYS=billing:kS3…,reporting:qP1…`. An entry that
+/// doesn't parse is logged and skipped rather than failing startup.
+fn service_signing_keys_from_env(raw: &str) -> Vec<(String, Key)> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let (client_id, encoded_key) = entry.split_once(':')?;
+            match base64::engine::general_purpose::STANDARD.decode(encoded_key) {
+                Ok(secret) => Some((client_id.to_string(), Key::from_bytes(secret))),
+                Err(_) => {
+                    tracing::warn!(client_id, "ignoring invalid SERVICE_SIGNING_KEYS entry");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Loads the base64-encoded 256-bit key `SESSION_ENCRYPTION_KEY` must hold
+/// for [`AppState::session_encryption_key`] to be enabled. Returns `None`
+/// (leaving the feature off) if the variable isn't set; logs and returns
+/// `None` if it's set but doesn't decode to 32 bytes, rather than failing
+/// startup over a misconfigured optional feature.
+fn load_session_encryption_key() -> Option<Arc<foundation::crypto::EncryptionKey>> {
+    let encoded = std::env::var("SESSION_ENCRYPTION_KEY").ok()?;
+    let decoded = match base64::engine::general_purpose::STANDARD.decode(&encoded) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            tracing::warn!("SESSION_ENCRYPTION_KEY is not valid base64; leaving session encryption disabled");
+            return None;
+        }
+    };
+    let key: [u8; 32] = match decoded.try_into() {
+        Ok(key) => key,
+        Err(bytes) => {
+            tracing::warn!(
+                len = bytes.len(),
+                "SESSION_ENCRYPTION_KEY must decode to 32 bytes; leaving session encryption disabled"
+            );
+            return None;
+        }
+    };
+    Some(Arc::new(foundation::crypto::EncryptionKey::from_bytes(key)))
+}
+
+/// Parses a comma-separated list of CIDR ranges out of env var `name`,
+/// such as `IP_DENYLIST=203.0.113.0/24,198.51.100.0/24`. An entry that
+/// doesn't parse as a CIDR range is logged and skipped rather than
+/// failing startup, so one typo doesn't take the whole list down with it.
+fn cidr_list_from_env(name: &str) -> Vec<ipnet::IpNet> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .filter_map(|entry| match entry.parse() {
+                    Ok(net) => Some(net),
+                    Err(_) => {
+                        tracing::warn!(entry, name, "ignoring invalid CIDR range");
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parses `MFA_REQUIRED_ROLES`, a comma-separated list of role names (e.g.
+/// `MFA_REQUIRED_ROLES=admin,org_admin`) matching [`Role`]'s
+/// [`std::fmt::Display`] form, into [`AppState::mfa_required_roles`]. An
+/// entry that doesn't match a known role is logged and skipped rather than
+/// failing startup.
+fn mfa_required_roles_from_env(name: &str) -> std::collections::HashSet<Role> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .filter_map(|entry| match entry {
+                    "user" => Some(Role::User),
+                    "admin" => Some(Role::Admin),
+                    "support" => Some(Role::Support),
+                    "org_admin" => Some(Role::OrgAdmin),
+                    _ => {
+                        tracing::warn!(entry, name, "ignoring invalid role in MFA_REQUIRED_ROLES");
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Loads the [`libsvc::risk::RiskPolicy`] [`RISK_POLICY_PATH`] points at, if
+/// set. A policy that fails to load is treated as a startup error, same as
+/// [`SEED_FIXTURES_PATH`](seed::load_fixtures) — better to fail fast than to
+/// silently run without conditional access rules a deployment thinks are
+/// active.
+fn risk_policy_from_env() -> Option<Arc<libsvc::risk::RiskPolicy>> {
+    std::env::var("RISK_POLICY_PATH").ok().map(|path| {
+        Arc::new(risk_policy::load(&path).unwrap_or_else(|e| {
+            panic!("failed to load risk policy from {path}: {e}");
+        }))
+    })
+}
+
+/// Builds this deployment's [`libsvc::oidc::OidcProviderConfig`] from
+/// `OIDC_ISSUER`, if set. `None` leaves `users::http::oidc`'s endpoints
+/// disabled, since most deployments of this service aren't acting as an
+/// identity provider.
+fn oidc_from_env() -> Option<Arc<libsvc::oidc::OidcProviderConfig>> {
+    std::env::var("OIDC_ISSUER")
+        .ok()
+        .map(|issuer| Arc::new(libsvc::oidc::OidcProviderConfig { issuer }))
+}
+
+fn main() {
+    // Built manually, rather than via `#[tokio::main]`, so `WORKER_THREADS`
+    // can size the runtime's thread pool before any task runs on it — a
+    // small container and a large host need different defaults, and
+    // restarting with a new value is this binary's answer to "zero-downtime
+    // scaling" on a stack without actix's per-worker processes.
+    let worker_threads = std::env::var("WORKER_THREADS")
+        .ok()
+        .and_then(|value| value.parse().ok());
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(worker_threads) = worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+    let runtime = builder.build().expect("failed to build tokio runtime");
+    runtime.block_on(run());
+}
+
+async fn run() {
+    let telemetry_config = TelemetryConfig {
+        service_name: "users".to_string(),
+        service_version: env!("CARGO_PKG_VERSION").to_string(),
+        environment: std::env::var("SERVICE_ENVIRONMENT")
+            .unwrap_or_else(|_| "development".to_string()),
+        default_directives: "info".to_string(),
+        format: match std::env::var("LOG_FORMAT").as_deref() {
+            Ok("json") => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        },
+    };
+    let log_level = libsvc::telemetry::init(&telemetry_config);
+
+    let repository = Arc::new(MemoryUserRepository::new());
+    let credentials = Arc::new(MemoryCredentialsRepository::new());
+
+    if let Ok(path) = std::env::var("SEED_FIXTURES_PATH") {
+        let fixtures = seed::load_fixtures(&path).expect("failed to load seed fixtures");
+        let repository: &dyn UserRepository = repository.as_ref();
+        seed::seed(repository, credentials.as_ref(), fixtures)
+            .await
+            .expect("failed to seed initial users");
+        tracing::info!("seeded initial users from {path}");
+    }
+
+    let audit_log = Arc::new(MemoryAuditLog::new());
+    let ip_filter = IpFilter::new(
+        Arc::new(IpAccessControl::new(IpAccessConfig {
+            allowlist: cidr_list_from_env("IP_ALLOWLIST"),
+            denylist: cidr_list_from_env("IP_DENYLIST"),
+            blocked_countries: std::env::var("GEO_BLOCKED_COUNTRIES")
+                .ok()
+                .map(|value| value.split(',').map(str::trim).filter(|c| !c.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default(),
+        })),
+        // No GeoIP database ships with this service; see `libsvc::geoip`.
+        // `blocked_countries` above has no effect until a real lookup is
+        // wired up in its place.
+        Arc::new(NoopGeoIpLookup),
+        audit_log.clone(),
+    );
+    let mailer = Arc::new(LoggingMailer::new());
+    let metrics = Arc::new(PrometheusMetrics::new());
+    let security_events = Arc::new(RetainingSecuritySignal::new(
+        Arc::new(LoggingSecuritySignal::new()),
+        std::env::var("SECURITY_EVENT_LOG_CAPACITY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_SECURITY_EVENT_LOG_CAPACITY),
+    ));
+    let preferences = Arc::new(MemoryUserPreferencesRepository::new());
+    let digest_queue = Arc::new(MemoryDigestQueueRepository::new());
+    let notification_inbox = Arc::new(MemoryNotificationRepository::new());
+    let devices = Arc::new(MemoryDeviceRepository::new());
+    let pusher: Arc<dyn Pusher> = match (std::env::var("FCM_ENDPOINT"), std::env::var("FCM_ACCESS_TOKEN")) {
+        (Ok(endpoint), Ok(access_token)) => {
+            Arc::new(FcmPusher::new(FcmConfig { endpoint, access_token }))
+        }
+        _ => Arc::new(LoggingPusher::new()),
+    };
+    let notifications = Arc::new(NotificationMailer::new(
+        mailer.clone(),
+        preferences.clone(),
+        digest_queue.clone(),
+        notification_inbox,
+        devices,
+        pusher,
+        Arc::new(SystemClock),
+        Arc::new(DefaultIdGenerator::default()),
+    ));
+    let sms_sender: Arc<dyn SmsSender> =
+        match (std::env::var("TWILIO_ACCOUNT_SID"), std::env::var("TWILIO_AUTH_TOKEN"), std::env::var("TWILIO_FROM_NUMBER")) {
+            (Ok(account_sid), Ok(auth_token), Ok(from_number)) => Arc::new(RateLimitedSmsSender::new(
+                Arc::new(TwilioSmsSender::new(TwilioConfig { account_sid, auth_token, from_number })),
+                default_sms_cost_guard_config(),
+            )),
+            _ => Arc::new(LoggingSmsSender::new()),
+        };
+    let sms_otp = Arc::new(MemorySmsOtpRepository::new());
+    let backup_codes = Arc::new(MemoryBackupCodesRepository::new());
+    let sessions = Arc::new(SessionManager::with_metrics(metrics.clone()));
+    let user_logic: Arc<dyn UserLogic> = Arc::new(UserLogicImpl::with_sessions(
+        repository.clone(),
+        credentials,
+        audit_log.clone(),
+        mailer.clone(),
+        Arc::new(NoopUnitOfWorkFactory),
+        RateLimiterConfig::default(),
+        preferences,
+        security_events.clone(),
+        Arc::new(NoopSearchIndex),
+        Arc::new(SystemClock),
+        Arc::new(DefaultIdGenerator::default()),
+        metrics.clone(),
+        Arc::new(users::logic::CustomAttributesSchemaStore::new()),
+        notifications,
+        sms_sender,
+        sms_otp,
+        backup_codes,
+        sessions.clone(),
+    ));
+
+    let avatars_path = std::env::var("AVATARS_PATH").unwrap_or_else(|_| "avatars".to_string());
+    let avatars = Arc::new(
+        FilesystemBlobStorage::open(avatars_path).expect("failed to open avatar storage"),
+    );
+
+    let session_validation = SessionValidation {
+        issuer: telemetry_config.service_name.clone(),
+        audience: std::env::var("SESSION_AUDIENCE")
+            .unwrap_or_else(|_| DEFAULT_SESSION_AUDIENCE.to_string()),
+        leeway_seconds: std::env::var("SESSION_LEEWAY_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_SESSION_LEEWAY_SECS),
+    };
+
+    let captcha: Arc<dyn CaptchaVerifier> =
+        match (std::env::var("CAPTCHA_VERIFY_URL"), std::env::var("CAPTCHA_SECRET_KEY")) {
+            (Ok(verify_url), Ok(secret_key)) => {
+                Arc::new(HttpCaptchaVerifier::new(HttpCaptchaConfig { verify_url, secret_key }))
+            }
+            _ => Arc::new(DisabledCaptchaVerifier::new()),
+        };
+
+    let cookie_sessions_enabled = std::env::var("COOKIE_SESSIONS_ENABLED").as_deref() == Ok("1");
+
+    retention::spawn_periodic(
+        RetentionConfig {
+            audit_retention: Duration::from_secs(
+                std::env::var("AUDIT_RETENTION_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(RetentionConfig::default().audit_retention.as_secs()),
+            ),
+            security_event_retention: Duration::from_secs(
+                std::env::var("SECURITY_EVENT_RETENTION_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(RetentionConfig::default().security_event_retention.as_secs()),
+            ),
+            expired_session_retention: Duration::from_secs(
+                std::env::var("EXPIRED_SESSION_RETENTION_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(RetentionConfig::default().expired_session_retention.as_secs()),
+            ),
+            interval: Duration::from_secs(
+                std::env::var("RETENTION_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(RetentionConfig::default().interval.as_secs()),
+            ),
+        },
+        audit_log.clone(),
+        security_events.clone(),
+        sessions.clone(),
+        repository.clone(),
+        Arc::new(SystemClock),
+        metrics.clone(),
+    );
+
+    digest::spawn_periodic(
+        DigestConfig {
+            interval: Duration::from_secs(
+                std::env::var("DIGEST_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DigestConfig::default().interval.as_secs()),
+            ),
+        },
+        digest_queue,
+        repository.clone(),
+        mailer.clone(),
+    );
+
+    let state = AppState {
+        user_logic,
+        sessions,
+        session_key: Arc::new(KeyRing::new(Key::generate())),
+        session_validation,
+        issue_refresh_tokens: std::env::var("ISSUE_REFRESH_TOKENS").as_deref() == Ok("1"),
+        audit_log,
+        avatars,
+        log_level,
+        security_signal: security_events.clone(),
+        captcha,
+        require_captcha_for_registration: std::env::var("REQUIRE_CAPTCHA_FOR_REGISTRATION")
+            .as_deref()
+            == Ok("1"),
+        captcha_after_failed_logins: std::env::var("CAPTCHA_AFTER_FAILED_LOGINS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CAPTCHA_AFTER_FAILED_LOGINS),
+        failed_logins: Arc::new(FailedLoginTracker::new()),
+        mailer,
+        // SAML SSO requires a deployment-specific `SamlAssertionVerifier`
+        // (see `libsvc::saml`); none ships by default, so the endpoints
+        // stay disabled until an operator wires one up.
+        saml: None,
+        // No service accounts are registered by default, so token
+        // exchange stays disabled until an operator configures one.
+        service_accounts: None,
+        usage: Arc::new(MemoryUsageRepository::new()),
+        usage_quota_per_day: std::env::var("USAGE_QUOTA_PER_DAY")
+            .ok()
+            .and_then(|value| value.parse().ok()),
+        clock: Arc::new(SystemClock),
+        metrics: metrics.clone(),
+        session_verification_cache: Arc::new(VerificationCache::new(VerificationCacheConfig {
+            enabled: std::env::var("SESSION_VERIFICATION_CACHE_ENABLED").as_deref() == Ok("1"),
+            ttl: Duration::from_secs(
+                std::env::var("SESSION_VERIFICATION_CACHE_TTL_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_SESSION_VERIFICATION_CACHE_TTL_SECS),
+            ),
+        })),
+        security_events,
+        require_cert_binding: std::env::var("REQUIRE_CERT_BINDING").as_deref() == Ok("1"),
+        require_dpop: std::env::var("REQUIRE_DPOP").as_deref() == Ok("1"),
+        dpop_keys: Arc::new(InMemoryDPoPKeyStore::new()),
+        dpop_nonces: Arc::new(InMemoryDPoPNonceStore::new(Duration::from_secs(
+            std::env::var("DPOP_NONCE_TTL_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_DPOP_NONCE_TTL_SECS),
+        ))),
+        session_encryption_key: load_session_encryption_key(),
+        action_token_key: Arc::new(Key::generate()),
+        feature_flags: Arc::new(FeatureFlags::new(Arc::new(
+            MemoryFeatureOverridesRepository::new(),
+        ))),
+        mfa_required_roles: mfa_required_roles_from_env("MFA_REQUIRED_ROLES"),
+        // Shares `ip_filter`'s "no GeoIP database ships with this service"
+        // default; see `libsvc::geoip`.
+        geoip: Arc::new(NoopGeoIpLookup),
+        risk_policy: risk_policy_from_env(),
+        known_devices: Arc::new(KnownDeviceTracker::new()),
+        session_idle_timeout_secs: std::env::var("SESSION_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok()),
+        oidc: oidc_from_env(),
+        // No clients are registered by default, so the authorization code
+        // flow stays disabled until an operator configures one — same
+        // rationale as `service_accounts` above.
+        oidc_clients: None,
+        oidc_codes: Arc::new(libsvc::oidc::InMemoryAuthorizationCodeStore::new()),
+        oidc_consents: Arc::new(libsvc::oidc::InMemoryConsentStore::new()),
+        oidc_signing_key: Arc::new(Key::generate()),
+        cookie_sessions_enabled,
+    };
+
+    let load_shedder = LoadShedder::new(
+        LoadSheddingConfig {
+            max_in_flight: std::env::var("MAX_IN_FLIGHT_REQUESTS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT_REQUESTS),
+            p99_target: Duration::from_millis(
+                std::env::var("LATENCY_TARGET_MS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_LATENCY_TARGET_MS),
+            ),
+            // Health and metrics endpoints must stay reachable even
+            // while the service is shedding user traffic, since that's
+            // exactly when an operator or load balancer needs them most.
+            exempt_paths: [
+                "/debug/ready",
+                "/debug/metrics",
+                "/debug/build",
+                "/debug/config",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        },
+        metrics,
+    );
+
+    let request_timeout_secs = std::env::var("REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS);
+    let max_concurrent_connections = std::env::var("MAX_CONCURRENT_CONNECTIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_CONNECTIONS);
+
+    let router = router(state);
+    let router = with_locale_negotiation(router);
+    let router = if cookie_sessions_enabled {
+        tracing::info!("cookie-based session delivery enabled; layering CSRF protection");
+        with_csrf_protection(router)
+    } else {
+        router
+    };
+    let router = if let Ok(raw_keys) = std::env::var("SERVICE_SIGNING_KEYS") {
+        let keys = service_signing_keys_from_env(&raw_keys);
+        tracing::info!("request signature verification enabled for {} service(s)", keys.len());
+        with_request_signing(
+            router,
+            RequestSigningConfig {
+                keys: Arc::new(InMemoryRequestSigningKeyStore::new(keys)),
+                max_skew_secs: std::env::var("SERVICE_SIGNING_MAX_SKEW_SECS")
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_SERVICE_SIGNING_MAX_SKEW_SECS),
+            },
+        )
+    } else {
+        router
+    };
+    let router = with_load_shedding(router, load_shedder);
+    let router = with_connection_limit(router, max_concurrent_connections);
+    let router = with_request_timeout(router, Duration::from_secs(request_timeout_secs));
+    let router = with_request_tracing(router, telemetry_config);
+    // Outermost: a blocked address shouldn't count against load shedding
+    // or consume a connection slot, and doesn't need a request trace.
+    let mut app = with_ip_filter(router, ip_filter);
+    if let Ok(directory) = std::env::var("STATIC_FILES_DIR") {
+        let mount_path = std::env::var("STATIC_FILES_MOUNT").unwrap_or_else(|_| "/".to_string());
+        app = with_static_files(
+            app,
+            StaticFilesConfig {
+                mount_path: mount_path.clone(),
+                directory: directory.clone().into(),
+            },
+        );
+        tracing::info!("serving static files from {directory} at {mount_path}");
+    }
+
+    #[cfg(feature = "profiling")]
+    if std::env::var("ENABLE_PROFILING").as_deref() == Ok("1") {
+        app = app.merge(users::http::profiling::router());
+        tracing::info!("profiling endpoints enabled at /debug/pprof (loopback only)");
+    }
+
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
+        .await
+        .expect("failed to bind listener");
+    tracing::info!("users service listening on 0.0.0.0:8080");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .expect("server error");
+}
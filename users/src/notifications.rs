@@ -0,0 +1,347 @@
+//! Enforces a user's [`NotificationCategoryPreferences`] ahead of the raw
+//! [`Mailer`], so call sites that send an unsolicited notification (as
+//! opposed to transactional mail the user directly triggered, like a
+//! confirmation link) don't each have to check the preference themselves.
+//! [`NotificationCategory::Digest`] notifications are queued via
+//! [`DigestQueueRepository`] instead of sent immediately; see
+//! [`crate::digest`] for the job that batches and sends them. Every call to
+//! [`NotificationMailer::notify`] also records an in-app [`Notification`]
+//! via [`NotificationRepository`], regardless of `category`'s email
+//! preference — the inbox is a separate channel an account can't opt out
+//! of, so a user who's muted a category's email still sees it if they look.
+//! [`NotificationCategory::Security`] notifications are additionally pushed
+//! to every device [`DeviceRepository`] has registered for the user, so a
+//! security alert reaches a mobile client even if the user doesn't have
+//! the app's email open; a device whose token [`Pusher`] reports as
+//! invalid is unregistered rather than pushed to again.
+
+use std::sync::Arc;
+
+use foundation::clock::Clock;
+use foundation::id::IdGenerator;
+use libsvc::mailer::Mailer;
+use libsvc::pusher::{PushError, Pusher};
+use libsvc::repository::Result;
+
+use crate::domain::{
+    DeviceRepository, DigestQueueRepository, Notification, NotificationCategory, NotificationPage,
+    NotificationRepository, Pagination, QueuedDigestNotification, UserPreferencesRepository,
+};
+
+/// Routes a notification through a user's [`NotificationCategoryPreferences`]
+/// before it reaches [`Mailer`] or [`DigestQueueRepository`], and always
+/// records it to [`NotificationRepository`] for the in-app inbox.
+pub struct NotificationMailer {
+    mailer: Arc<dyn Mailer>,
+    preferences: Arc<dyn UserPreferencesRepository>,
+    digest_queue: Arc<dyn DigestQueueRepository>,
+    inbox: Arc<dyn NotificationRepository>,
+    devices: Arc<dyn DeviceRepository>,
+    pusher: Arc<dyn Pusher>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl NotificationMailer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mailer: Arc<dyn Mailer>,
+        preferences: Arc<dyn UserPreferencesRepository>,
+        digest_queue: Arc<dyn DigestQueueRepository>,
+        inbox: Arc<dyn NotificationRepository>,
+        devices: Arc<dyn DeviceRepository>,
+        pusher: Arc<dyn Pusher>,
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+    ) -> Self {
+        Self { mailer, preferences, digest_queue, inbox, devices, pusher, clock, ids }
+    }
+
+    /// Sends (or, for [`NotificationCategory::Digest`], queues) a
+    /// notification to `to` on `user_id`'s behalf unless `user_id` has
+    /// opted out of `category`'s email delivery, and unconditionally
+    /// records it to `user_id`'s in-app inbox. Fails open on a preference
+    /// lookup error — the same way [`crate::logic::UserLogicImpl::update`]
+    /// falls back to [`crate::domain::UserPreferences::defaults`] rather
+    /// than letting a preferences-store hiccup block the notification
+    /// outright.
+    pub async fn notify(
+        &self,
+        user_id: &str,
+        to: &str,
+        category: NotificationCategory,
+        subject: &str,
+        body: &str,
+    ) {
+        let notification = Notification {
+            id: self.ids.generate().to_string(),
+            user_id: user_id.to_string(),
+            category,
+            subject: subject.to_string(),
+            body: body.to_string(),
+            created_at: self.clock.now(),
+            read_at: None,
+        };
+        if let Err(err) = self.inbox.create(notification).await {
+            tracing::warn!(%err, user_id, "failed to record in-app notification");
+        }
+
+        let enabled = match self.preferences.get(user_id).await {
+            Ok(preferences) => preferences.notification_categories.is_enabled(category),
+            Err(err) => {
+                tracing::warn!(%err, user_id, "failed to look up notification preferences, sending anyway");
+                true
+            }
+        };
+        if category == NotificationCategory::Security {
+            self.push_to_devices(user_id, subject, body).await;
+        }
+
+        if !enabled {
+            return;
+        }
+
+        if category == NotificationCategory::Digest {
+            let notification =
+                QueuedDigestNotification { subject: subject.to_string(), body: body.to_string() };
+            if let Err(err) = self.digest_queue.enqueue(user_id, notification).await {
+                tracing::warn!(%err, user_id, "failed to enqueue digest notification");
+            }
+            return;
+        }
+
+        self.mailer.send(to, subject, body);
+    }
+
+    /// Pushes `subject`/`body` to every device registered to `user_id`,
+    /// unregistering any token [`Pusher`] reports as invalid so it isn't
+    /// tried again.
+    async fn push_to_devices(&self, user_id: &str, subject: &str, body: &str) {
+        let devices = match self.devices.list_for_user(user_id).await {
+            Ok(devices) => devices,
+            Err(err) => {
+                tracing::warn!(%err, user_id, "failed to look up registered devices");
+                return;
+            }
+        };
+        for device in devices {
+            match self.pusher.push(&device.token, subject, body).await {
+                Ok(()) => {}
+                Err(PushError::InvalidToken) => {
+                    if let Err(err) = self.devices.remove(user_id, &device.token).await {
+                        tracing::warn!(%err, user_id, "failed to unregister invalid device token");
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(%err, user_id, "failed to push notification to device");
+                }
+            }
+        }
+    }
+
+    /// Returns a page of `user_id`'s in-app inbox, newest first.
+    pub async fn inbox(&self, user_id: &str, pagination: Pagination) -> Result<NotificationPage> {
+        self.inbox.list(user_id, pagination).await
+    }
+
+    /// Marks `id` read on `user_id`'s behalf.
+    pub async fn mark_read(&self, user_id: &str, id: &str) -> Result<Notification> {
+        self.inbox.mark_read(user_id, id, self.clock.now()).await
+    }
+
+    /// Marks every one of `user_id`'s unread notifications read, returning
+    /// how many were updated.
+    pub async fn mark_all_read(&self, user_id: &str) -> Result<u64> {
+        self.inbox.mark_all_read(user_id, self.clock.now()).await
+    }
+
+    /// Registers `platform`/`token` to receive push notifications for
+    /// `user_id`.
+    pub async fn register_device(
+        &self,
+        user_id: &str,
+        platform: crate::domain::DevicePlatform,
+        token: String,
+    ) -> Result<crate::domain::DeviceToken> {
+        self.devices
+            .register(crate::domain::DeviceToken {
+                id: self.ids.generate().to_string(),
+                user_id: user_id.to_string(),
+                platform,
+                token,
+                created_at: self.clock.now(),
+            })
+            .await
+    }
+
+    /// Unregisters `token` from `user_id`'s devices.
+    pub async fn unregister_device(&self, user_id: &str, token: &str) -> Result<()> {
+        self.devices.remove(user_id, token).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DevicePlatform, NotificationCategoryPreferences, UserPreferences};
+    use crate::repository::devices::MemoryDeviceRepository;
+    use crate::repository::digest::MemoryDigestQueueRepository;
+    use crate::repository::notifications::MemoryNotificationRepository;
+    use crate::repository::preferences::MemoryUserPreferencesRepository;
+    use foundation::clock::SystemClock;
+    use foundation::id::DefaultIdGenerator;
+    use libsvc::mailer::LoggingMailer;
+    use libsvc::pusher::LoggingPusher;
+
+    /// A [`Pusher`] that always reports `token` as invalid, for exercising
+    /// [`NotificationMailer`]'s token-cleanup path without a real provider.
+    struct AlwaysInvalidTokenPusher;
+
+    #[async_trait::async_trait]
+    impl Pusher for AlwaysInvalidTokenPusher {
+        async fn push(
+            &self,
+            _token: &str,
+            _subject: &str,
+            _body: &str,
+        ) -> std::result::Result<(), PushError> {
+            Err(PushError::InvalidToken)
+        }
+    }
+
+    fn notification_mailer_with_pusher(
+        pusher: Arc<dyn Pusher>,
+    ) -> (
+        NotificationMailer,
+        Arc<MemoryDigestQueueRepository>,
+        Arc<MemoryUserPreferencesRepository>,
+        Arc<MemoryDeviceRepository>,
+    ) {
+        let mailer = Arc::new(LoggingMailer::new());
+        let preferences = Arc::new(MemoryUserPreferencesRepository::new());
+        let digest_queue = Arc::new(MemoryDigestQueueRepository::new());
+        let inbox = Arc::new(MemoryNotificationRepository::new());
+        let devices = Arc::new(MemoryDeviceRepository::new());
+        let notifications = NotificationMailer::new(
+            mailer,
+            preferences.clone(),
+            digest_queue.clone(),
+            inbox,
+            devices.clone(),
+            pusher,
+            Arc::new(SystemClock),
+            Arc::new(DefaultIdGenerator::default()),
+        );
+        (notifications, digest_queue, preferences, devices)
+    }
+
+    fn notification_mailer() -> (NotificationMailer, Arc<MemoryDigestQueueRepository>, Arc<MemoryUserPreferencesRepository>) {
+        let (notifications, digest_queue, preferences, _devices) =
+            notification_mailer_with_pusher(Arc::new(LoggingPusher::new()));
+        (notifications, digest_queue, preferences)
+    }
+
+    #[tokio::test]
+    async fn a_notification_sends_immediately_when_its_category_is_enabled() {
+        let (notifications, digest_queue, _preferences) = notification_mailer();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Security, "subject", "body")
+            .await;
+
+        assert!(digest_queue.drain_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_notification_is_suppressed_when_its_category_is_disabled() {
+        let (notifications, digest_queue, preferences) = notification_mailer();
+        let mut disabled = UserPreferences::defaults("user-1");
+        disabled.notification_categories = NotificationCategoryPreferences {
+            security_enabled: false,
+            ..disabled.notification_categories
+        };
+        preferences.put(disabled).await.unwrap();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Security, "subject", "body")
+            .await;
+
+        assert!(digest_queue.drain_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_security_notification_pushes_to_every_registered_device() {
+        let (notifications, _digest_queue, _preferences, devices) =
+            notification_mailer_with_pusher(Arc::new(LoggingPusher::new()));
+        notifications.register_device("user-1", DevicePlatform::Fcm, "token-1".to_string()).await.unwrap();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Security, "subject", "body")
+            .await;
+
+        // LoggingPusher never fails, so the device stays registered.
+        assert_eq!(devices.list_for_user("user-1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_device_is_unregistered_after_an_invalid_token_response() {
+        let (notifications, _digest_queue, _preferences, devices) =
+            notification_mailer_with_pusher(Arc::new(AlwaysInvalidTokenPusher));
+        notifications.register_device("user-1", DevicePlatform::Fcm, "token-1".to_string()).await.unwrap();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Security, "subject", "body")
+            .await;
+
+        assert!(devices.list_for_user("user-1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_digest_notification_is_queued_rather_than_sent_immediately() {
+        let (notifications, digest_queue, _preferences) = notification_mailer();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Digest, "subject", "body")
+            .await;
+
+        let queued = digest_queue.drain_all().await.unwrap();
+        assert_eq!(queued.get("user-1").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_notification_still_lands_in_the_inbox_when_its_email_category_is_disabled() {
+        let (notifications, _digest_queue, preferences) = notification_mailer();
+        let mut disabled = UserPreferences::defaults("user-1");
+        disabled.notification_categories = NotificationCategoryPreferences {
+            product_enabled: false,
+            ..disabled.notification_categories
+        };
+        preferences.put(disabled).await.unwrap();
+
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Product, "subject", "body")
+            .await;
+
+        let page = notifications.inbox("user-1", Pagination { offset: 0, limit: 10 }).await.unwrap();
+        assert_eq!(page.notifications.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn mark_read_and_mark_all_read_update_the_inbox() {
+        let (notifications, _digest_queue, _preferences) = notification_mailer();
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Product, "a", "a body")
+            .await;
+        notifications
+            .notify("user-1", "user-1@example.com", NotificationCategory::Product, "b", "b body")
+            .await;
+
+        let page = notifications.inbox("user-1", Pagination { offset: 0, limit: 10 }).await.unwrap();
+        let first_id = page.notifications[0].id.clone();
+        let marked = notifications.mark_read("user-1", &first_id).await.unwrap();
+        assert!(marked.read_at.is_some());
+
+        let updated = notifications.mark_all_read("user-1").await.unwrap();
+        assert_eq!(updated, 1);
+    }
+}
@@ -0,0 +1,72 @@
+//! An in-memory [`BackupCodesRepository`], suitable for tests and local
+//! development.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::BackupCodesRepository;
+
+#[derive(Default)]
+pub struct MemoryBackupCodesRepository {
+    by_user_id: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl MemoryBackupCodesRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BackupCodesRepository for MemoryBackupCodesRepository {
+    async fn store(&self, user_id: &str, code_hashes: Vec<String>) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("backup codes repository lock poisoned".to_string()))?;
+        by_user_id.insert(user_id.to_string(), code_hashes.into_iter().collect());
+        Ok(())
+    }
+
+    async fn consume(&self, user_id: &str, code_hash: &str) -> Result<bool> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("backup codes repository lock poisoned".to_string()))?;
+        Ok(by_user_id.get_mut(user_id).is_some_and(|hashes| hashes.remove(code_hash)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn consuming_a_code_for_a_user_with_none_stored_returns_false() {
+        let repo = MemoryBackupCodesRepository::new();
+        assert!(!repo.consume("user-1", "hash-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_stored_code_can_be_consumed_exactly_once() {
+        let repo = MemoryBackupCodesRepository::new();
+        repo.store("user-1", vec!["hash-1".to_string(), "hash-2".to_string()]).await.unwrap();
+
+        assert!(repo.consume("user-1", "hash-1").await.unwrap());
+        assert!(!repo.consume("user-1", "hash-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn storing_a_new_set_replaces_the_old_one() {
+        let repo = MemoryBackupCodesRepository::new();
+        repo.store("user-1", vec!["hash-1".to_string()]).await.unwrap();
+        repo.store("user-1", vec!["hash-2".to_string()]).await.unwrap();
+
+        assert!(!repo.consume("user-1", "hash-1").await.unwrap());
+        assert!(repo.consume("user-1", "hash-2").await.unwrap());
+    }
+}
@@ -0,0 +1,420 @@
+//! A read-through [`UserRepository`] decorator that caches lookups by id
+//! and email, so repeated reads of the same user don't round-trip to the
+//! backend on every request. [`CachedRepository::with_invalidation`]
+//! additionally publishes evictions through a
+//! [`libsvc::invalidation::CacheInvalidator`], so a write on one replica
+//! evicts the entry on others within milliseconds instead of leaving
+//! them to serve it stale until TTL expiry.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use libsvc::invalidation::CacheInvalidator;
+use libsvc::repository::Result;
+
+use crate::domain::{
+    Pagination, RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage,
+};
+
+/// Tunables for [`CachedRepository`].
+#[derive(Debug, Clone)]
+pub struct CachedRepositoryConfig {
+    /// Maximum number of entries held per lookup key (id, email).
+    pub capacity: usize,
+    /// How long a cached entry stays valid before a fresh read is forced.
+    pub ttl: Duration,
+}
+
+impl Default for CachedRepositoryConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Hit/miss counters for a [`CachedRepository`], suitable for exporting to
+/// a metrics backend.
+#[derive(Debug, Default)]
+pub struct CachedRepositoryMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedRepositoryMetrics {
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+struct CacheEntry {
+    user: User,
+    inserted_at: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    order: VecDeque<String>,
+}
+
+/// A small, bounded, TTL'd LRU cache keyed by string (id or email).
+struct Cache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+impl Cache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn get(&self, key: &str) -> Option<User> {
+        let mut state = self.lock();
+        match state.entries.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.ttl => {}
+            Some(_) => {
+                state.entries.remove(key);
+                state.order.retain(|k| k != key);
+                return None;
+            }
+            None => return None,
+        }
+        state.order.retain(|k| k != key);
+        state.order.push_back(key.to_string());
+        state.entries.get(key).map(|entry| entry.user.clone())
+    }
+
+    fn insert(&self, key: String, user: User) {
+        let mut state = self.lock();
+        state.order.retain(|k| k != &key);
+        state.order.push_back(key.clone());
+        state.entries.insert(
+            key,
+            CacheEntry {
+                user,
+                inserted_at: Instant::now(),
+            },
+        );
+        while state.order.len() > self.capacity {
+            if let Some(oldest) = state.order.pop_front() {
+                state.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn invalidate(&self, key: &str) {
+        let mut state = self.lock();
+        state.entries.remove(key);
+        state.order.retain(|k| k != key);
+    }
+}
+
+/// Wraps a [`UserRepository`], caching `get`/`get_by_email` lookups with a
+/// TTL and evicting entries explicitly on `update`/`delete`. Optionally
+/// publishes those evictions through a [`CacheInvalidator`] and listens
+/// for ones from other replicas, so a write on one instance doesn't
+/// leave another instance serving the old value until TTL expiry.
+pub struct CachedRepository<R> {
+    inner: R,
+    by_id: Arc<Cache>,
+    by_email: Arc<Cache>,
+    metrics: Arc<CachedRepositoryMetrics>,
+    invalidator: Option<Arc<dyn CacheInvalidator>>,
+}
+
+impl<R: UserRepository> CachedRepository<R> {
+    /// Wraps `inner` with the default capacity and TTL.
+    pub fn new(inner: R) -> Self {
+        Self::with_config(inner, CachedRepositoryConfig::default())
+    }
+
+    /// Wraps `inner` with a custom capacity and TTL.
+    pub fn with_config(inner: R, config: CachedRepositoryConfig) -> Self {
+        Self {
+            inner,
+            by_id: Arc::new(Cache::new(config.capacity, config.ttl)),
+            by_email: Arc::new(Cache::new(config.capacity, config.ttl)),
+            metrics: Arc::new(CachedRepositoryMetrics::default()),
+            invalidator: None,
+        }
+    }
+
+    /// Wraps `inner` like [`CachedRepository::with_config`], additionally
+    /// publishing evictions to `invalidator` and spawning a task that
+    /// evicts entries invalidated by other replicas. `invalidator`'s
+    /// events are keyed by id or email, matching [`CachedRepository`]'s
+    /// own cache keys.
+    pub fn with_invalidation(
+        inner: R,
+        config: CachedRepositoryConfig,
+        invalidator: Arc<dyn CacheInvalidator>,
+    ) -> Self {
+        let by_id = Arc::new(Cache::new(config.capacity, config.ttl));
+        let by_email = Arc::new(Cache::new(config.capacity, config.ttl));
+
+        let mut events = invalidator.subscribe();
+        let subscriber_by_id = by_id.clone();
+        let subscriber_by_email = by_email.clone();
+        tokio::spawn(async move {
+            while let Ok(event) = events.recv().await {
+                subscriber_by_id.invalidate(&event.key);
+                subscriber_by_email.invalidate(&event.key);
+            }
+        });
+
+        Self {
+            inner,
+            by_id,
+            by_email,
+            metrics: Arc::new(CachedRepositoryMetrics::default()),
+            invalidator: Some(invalidator),
+        }
+    }
+
+    /// A shared handle to this repository's cache metrics.
+    pub fn metrics(&self) -> Arc<CachedRepositoryMetrics> {
+        self.metrics.clone()
+    }
+
+    fn warm(&self, user: &User) {
+        self.by_id.insert(user.id.clone(), user.clone());
+        self.by_email.insert(user.email.clone(), user.clone());
+    }
+
+    async fn invalidate(&self, user: &User) {
+        self.by_id.invalidate(&user.id);
+        self.by_email.invalidate(&user.email);
+        if let Some(invalidator) = &self.invalidator {
+            // Best-effort: a dropped invalidation only costs other
+            // replicas a stale read until TTL expiry, not correctness.
+            let _ = invalidator.publish(&user.id).await;
+            let _ = invalidator.publish(&user.email).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<R: UserRepository> UserRepository for CachedRepository<R> {
+    async fn create(&self, user: User) -> Result<User> {
+        let user = self.inner.create(user).await?;
+        self.warm(&user);
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        if let Some(user) = self.by_id.get(id) {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(user);
+        }
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        let user = self.inner.get(id).await?;
+        self.warm(&user);
+        Ok(user)
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        if let Some(user) = self.by_email.get(email) {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(user);
+        }
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        let user = self.inner.get_by_email(email).await?;
+        self.warm(&user);
+        Ok(user)
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        self.inner.get_by_username(username).await
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        let previous = self.inner.get(&user.id).await.ok();
+        let updated = self.inner.update(user).await?;
+        if let Some(previous) = previous {
+            self.invalidate(&previous).await;
+        }
+        self.warm(&updated);
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        if let Ok(user) = self.inner.get(id).await {
+            self.invalidate(&user).await;
+        }
+        self.inner.delete(id).await
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        // Not worth caching: admin search queries are low-volume and the
+        // filter/pagination combination makes for a poor cache key.
+        self.inner.search(filter, pagination).await
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        // Same reasoning as search: not worth a cache entry.
+        self.inner.count(filter).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        self.inner.exists_by_email(email).await
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        // Not cached, same reasoning as search/count: a health check is
+        // only useful if it reflects the backend's current state.
+        self.inner.stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libsvc::invalidation::LocalCacheInvalidator;
+
+    use crate::repository::memory::MemoryUserRepository;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn second_read_is_served_from_cache() {
+        let repo = CachedRepository::new(MemoryUserRepository::new());
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+
+        assert_eq!(repo.get("1").await.unwrap().id, "1");
+        assert_eq!(repo.metrics().hits(), 1); // warmed by create()
+        assert_eq!(repo.metrics().misses(), 0);
+
+        assert_eq!(repo.get_by_email("a@example.com").await.unwrap().id, "1");
+        assert_eq!(repo.metrics().hits(), 2);
+        assert_eq!(repo.metrics().misses(), 0);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_refetched() {
+        let repo = CachedRepository::with_config(
+            MemoryUserRepository::new(),
+            CachedRepositoryConfig {
+                capacity: 10,
+                ttl: Duration::from_millis(1),
+            },
+        );
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        repo.get("1").await.unwrap();
+        assert_eq!(repo.metrics().misses(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_invalidates_the_old_email_entry() {
+        let repo = CachedRepository::new(MemoryUserRepository::new());
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.get_by_email("a@example.com").await.unwrap();
+
+        let mut updated = sample_user("1", "b@example.com");
+        updated.username = Some("a".to_string());
+        repo.update(updated).await.unwrap();
+
+        assert!(repo.get_by_email("a@example.com").await.is_err());
+        assert_eq!(repo.get_by_email("b@example.com").await.unwrap().id, "1");
+    }
+
+    #[tokio::test]
+    async fn delete_invalidates_both_cache_entries() {
+        let repo = CachedRepository::new(MemoryUserRepository::new());
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.get("1").await.unwrap();
+
+        repo.delete("1").await.unwrap();
+
+        assert!(repo.get("1").await.is_err());
+        assert!(repo.get_by_email("a@example.com").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_entry_past_capacity() {
+        let repo = CachedRepository::with_config(
+            MemoryUserRepository::new(),
+            CachedRepositoryConfig {
+                capacity: 1,
+                ttl: Duration::from_secs(60),
+            },
+        );
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.create(sample_user("2", "b@example.com")).await.unwrap();
+
+        // "1" was evicted from the id cache to make room for "2".
+        repo.get("1").await.unwrap();
+        assert_eq!(repo.metrics().misses(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_on_one_instance_invalidates_another_sharing_the_invalidator() {
+        let invalidator: Arc<dyn CacheInvalidator> = Arc::new(LocalCacheInvalidator::new());
+
+        let replica_a = CachedRepository::with_invalidation(
+            MemoryUserRepository::new(),
+            CachedRepositoryConfig::default(),
+            invalidator.clone(),
+        );
+        let replica_b = CachedRepository::with_invalidation(
+            MemoryUserRepository::new(),
+            CachedRepositoryConfig::default(),
+            invalidator.clone(),
+        );
+
+        // Each replica has its own backend, as they would on separate
+        // hosts; the shared invalidator is what keeps their caches honest.
+        replica_a.create(sample_user("1", "a@example.com")).await.unwrap();
+        replica_b.create(sample_user("1", "a@example.com")).await.unwrap();
+        replica_b.get("1").await.unwrap();
+        assert_eq!(replica_b.metrics().hits(), 1);
+
+        let mut updated = sample_user("1", "b@example.com");
+        updated.username = Some("a".to_string());
+        replica_a.update(updated).await.unwrap();
+
+        // Give the background invalidation task a moment to process the
+        // published event before asserting it took effect.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        replica_b.get("1").await.unwrap();
+        assert_eq!(replica_b.metrics().misses(), 1);
+    }
+}
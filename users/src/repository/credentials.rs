@@ -0,0 +1,105 @@
+//! An in-memory [`CredentialsRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{Credentials, CredentialsRepository};
+
+#[derive(Default)]
+pub struct MemoryCredentialsRepository {
+    by_user_id: Mutex<HashMap<String, Credentials>>,
+}
+
+impl MemoryCredentialsRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CredentialsRepository for MemoryCredentialsRepository {
+    async fn create(&self, credentials: Credentials) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("credentials repository lock poisoned".to_string()))?;
+        by_user_id.insert(credentials.user_id.clone(), credentials);
+        Ok(())
+    }
+
+    async fn get(&self, user_id: &str) -> Result<Credentials> {
+        let by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("credentials repository lock poisoned".to_string()))?;
+        by_user_id.get(user_id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn update(&self, credentials: Credentials) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("credentials repository lock poisoned".to_string()))?;
+        if !by_user_id.contains_key(&credentials.user_id) {
+            return Err(Error::NotFound);
+        }
+        by_user_id.insert(credentials.user_id.clone(), credentials);
+        Ok(())
+    }
+
+    async fn delete(&self, user_id: &str) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("credentials repository lock poisoned".to_string()))?;
+        by_user_id.remove(user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(user_id: &str, password_hash: &str) -> Credentials {
+        Credentials {
+            user_id: user_id.to_string(),
+            password_hash: password_hash.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_then_get_returns_the_stored_credentials() {
+        let repo = MemoryCredentialsRepository::new();
+        repo.create(credentials("1", "hash")).await.unwrap();
+        let stored = repo.get("1").await.unwrap();
+        assert_eq!(stored.password_hash, "hash");
+    }
+
+    #[tokio::test]
+    async fn get_fails_for_an_unknown_user() {
+        let repo = MemoryCredentialsRepository::new();
+        assert!(matches!(repo.get("missing").await, Err(Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn update_replaces_the_stored_password_hash() {
+        let repo = MemoryCredentialsRepository::new();
+        repo.create(credentials("1", "hash")).await.unwrap();
+        repo.update(credentials("1", "new-hash")).await.unwrap();
+        assert_eq!(repo.get("1").await.unwrap().password_hash, "new-hash");
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_stored_credentials() {
+        let repo = MemoryCredentialsRepository::new();
+        repo.create(credentials("1", "hash")).await.unwrap();
+        repo.delete("1").await.unwrap();
+        assert!(matches!(repo.get("1").await, Err(Error::NotFound)));
+    }
+}
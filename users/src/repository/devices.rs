@@ -0,0 +1,100 @@
+//! An in-memory [`DeviceRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{DeviceRepository, DeviceToken};
+
+#[derive(Default)]
+pub struct MemoryDeviceRepository {
+    by_user_id: Mutex<HashMap<String, Vec<DeviceToken>>>,
+}
+
+impl MemoryDeviceRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DeviceRepository for MemoryDeviceRepository {
+    async fn register(&self, device: DeviceToken) -> Result<DeviceToken> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("device repository lock poisoned".to_string()))?;
+        let devices = by_user_id.entry(device.user_id.clone()).or_default();
+        devices.retain(|existing| existing.token != device.token);
+        devices.push(device.clone());
+        Ok(device)
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<DeviceToken>> {
+        let by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("device repository lock poisoned".to_string()))?;
+        Ok(by_user_id.get(user_id).cloned().unwrap_or_default())
+    }
+
+    async fn remove(&self, user_id: &str, token: &str) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("device repository lock poisoned".to_string()))?;
+        if let Some(devices) = by_user_id.get_mut(user_id) {
+            devices.retain(|existing| existing.token != token);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DevicePlatform;
+    use chrono::Utc;
+
+    fn sample_device(user_id: &str, token: &str) -> DeviceToken {
+        DeviceToken {
+            id: "device-1".to_string(),
+            user_id: user_id.to_string(),
+            platform: DevicePlatform::Fcm,
+            token: token.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn list_for_user_is_empty_with_no_devices_registered() {
+        let repo = MemoryDeviceRepository::new();
+        assert!(repo.list_for_user("user-1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn registering_the_same_token_twice_does_not_duplicate_it() {
+        let repo = MemoryDeviceRepository::new();
+        repo.register(sample_device("user-1", "token-1")).await.unwrap();
+        repo.register(sample_device("user-1", "token-1")).await.unwrap();
+
+        let devices = repo.list_for_user("user-1").await.unwrap();
+        assert_eq!(devices.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn removing_a_device_is_scoped_to_the_owning_user() {
+        let repo = MemoryDeviceRepository::new();
+        repo.register(sample_device("user-1", "token-1")).await.unwrap();
+
+        repo.remove("user-2", "token-1").await.unwrap();
+        assert_eq!(repo.list_for_user("user-1").await.unwrap().len(), 1);
+
+        repo.remove("user-1", "token-1").await.unwrap();
+        assert!(repo.list_for_user("user-1").await.unwrap().is_empty());
+    }
+}
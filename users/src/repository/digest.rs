@@ -0,0 +1,86 @@
+//! An in-memory [`DigestQueueRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{DigestQueueRepository, QueuedDigestNotification};
+
+#[derive(Default)]
+pub struct MemoryDigestQueueRepository {
+    by_user_id: Mutex<HashMap<String, Vec<QueuedDigestNotification>>>,
+}
+
+impl MemoryDigestQueueRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DigestQueueRepository for MemoryDigestQueueRepository {
+    async fn enqueue(&self, user_id: &str, notification: QueuedDigestNotification) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("digest queue repository lock poisoned".to_string()))?;
+        by_user_id.entry(user_id.to_string()).or_default().push(notification);
+        Ok(())
+    }
+
+    async fn drain_all(&self) -> Result<HashMap<String, Vec<QueuedDigestNotification>>> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("digest queue repository lock poisoned".to_string()))?;
+        Ok(std::mem::take(&mut *by_user_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drain_all_is_empty_with_nothing_queued() {
+        let repo = MemoryDigestQueueRepository::new();
+        assert!(repo.drain_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn enqueue_then_drain_all_returns_notifications_grouped_by_user() {
+        let repo = MemoryDigestQueueRepository::new();
+        repo.enqueue(
+            "1",
+            QueuedDigestNotification { subject: "a".to_string(), body: "a body".to_string() },
+        )
+        .await
+        .unwrap();
+        repo.enqueue(
+            "1",
+            QueuedDigestNotification { subject: "b".to_string(), body: "b body".to_string() },
+        )
+        .await
+        .unwrap();
+
+        let drained = repo.drain_all().await.unwrap();
+        assert_eq!(drained.get("1").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn draining_clears_the_queue() {
+        let repo = MemoryDigestQueueRepository::new();
+        repo.enqueue(
+            "1",
+            QueuedDigestNotification { subject: "a".to_string(), body: "a body".to_string() },
+        )
+        .await
+        .unwrap();
+        repo.drain_all().await.unwrap();
+        assert!(repo.drain_all().await.unwrap().is_empty());
+    }
+}
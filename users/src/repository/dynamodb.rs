@@ -0,0 +1,480 @@
+//! An AWS DynamoDB-backed [`UserRepository`], available behind the
+//! `dynamodb` feature.
+//!
+//! Uses a single table keyed by `pk`, holding three item shapes:
+//!
+//! - `USER#<id>` — the user's profile.
+//! - `EMAIL#<email>` — a marker item that exists only to make email unique,
+//!   pointing back at `user_id`.
+//! - `USERNAME#<username>` — the same, for usernames.
+//!
+//! Creating or updating a user writes the profile and its marker items in
+//! one [`TransactWriteItems`](aws_sdk_dynamodb::operation::transact_write_items)
+//! call with `attribute_not_exists(pk)` conditions on the markers, so a
+//! duplicate email or username is rejected atomically rather than only
+//! being caught by a racy read-then-write. Lookups by email and username
+//! go through the `email-index` and `username-index` global secondary
+//! indexes rather than the marker items, since GSIs are eventually
+//! consistent but cheaper than a marker round-trip for reads.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, TransactWriteItem};
+use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, Utc};
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{
+    matches_search_filter, paginate_search_results, Pagination, PendingEmailChange,
+    RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage, UserStatus,
+};
+
+fn user_pk(id: &str) -> String {
+    format!("USER#{id}")
+}
+
+fn email_pk(email: &str) -> String {
+    format!("EMAIL#{email}")
+}
+
+fn username_pk(username: &str) -> String {
+    format!("USERNAME#{username}")
+}
+
+fn s(value: impl Into<String>) -> AttributeValue {
+    AttributeValue::S(value.into())
+}
+
+fn user_to_item(user: &User) -> HashMap<String, AttributeValue> {
+    let mut item = HashMap::from([
+        ("pk".to_string(), s(user_pk(&user.id))),
+        ("id".to_string(), s(&user.id)),
+        ("email".to_string(), s(&user.email)),
+        ("created_at".to_string(), s(user.created_at.to_rfc3339())),
+        ("status".to_string(), s(user.status.as_str())),
+        ("legal_hold".to_string(), AttributeValue::Bool(user.legal_hold)),
+    ]);
+    if let Some(username) = &user.username {
+        item.insert("username".to_string(), s(username));
+    }
+    if let Some(avatar_url) = &user.avatar_url {
+        item.insert("avatar_url".to_string(), s(avatar_url));
+    }
+    if let Some(pending) = &user.pending_email {
+        item.insert("pending_email_new_email".to_string(), s(&pending.new_email));
+        item.insert(
+            "pending_email_token".to_string(),
+            s(&pending.confirmation_token),
+        );
+    }
+    item.insert("kind".to_string(), s(user_kind_str(user.kind)));
+    if let Some(organization_id) = &user.organization_id {
+        item.insert("organization_id".to_string(), s(organization_id));
+    }
+    if !user.custom_attributes.is_empty() {
+        item.insert(
+            "custom_attributes".to_string(),
+            s(serde_json::to_string(&user.custom_attributes).expect("custom attributes are valid JSON")),
+        );
+    }
+    if !user.tags.is_empty() {
+        item.insert(
+            "tags".to_string(),
+            AttributeValue::Ss(user.tags.iter().cloned().collect()),
+        );
+    }
+    item
+}
+
+fn user_kind_str(kind: crate::domain::UserKind) -> &'static str {
+    match kind {
+        crate::domain::UserKind::Human => "human",
+        crate::domain::UserKind::Service => "service",
+    }
+}
+
+fn user_kind_from_str(value: &str) -> crate::domain::UserKind {
+    match value {
+        "service" => crate::domain::UserKind::Service,
+        _ => crate::domain::UserKind::Human,
+    }
+}
+
+fn item_to_user(item: &HashMap<String, AttributeValue>) -> Result<User> {
+    let get_str = |key: &str| -> Result<String> {
+        item.get(key)
+            .and_then(|v| v.as_s().ok())
+            .cloned()
+            .ok_or_else(|| Error::Backend(format!("item missing string attribute `{key}`")))
+    };
+
+    let pending_email = match (
+        item.get("pending_email_new_email").and_then(|v| v.as_s().ok()),
+        item.get("pending_email_token").and_then(|v| v.as_s().ok()),
+    ) {
+        (Some(new_email), Some(token)) => Some(PendingEmailChange {
+            new_email: new_email.clone(),
+            confirmation_token: token.clone(),
+        }),
+        _ => None,
+    };
+
+    Ok(User {
+        id: get_str("id")?,
+        email: get_str("email")?,
+        username: item.get("username").and_then(|v| v.as_s().ok()).cloned(),
+        avatar_url: item.get("avatar_url").and_then(|v| v.as_s().ok()).cloned(),
+        created_at: DateTime::parse_from_rfc3339(&get_str("created_at")?)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| Error::Backend(e.to_string()))?,
+        pending_email,
+        status: item
+            .get("status")
+            .and_then(|v| v.as_s().ok())
+            .and_then(|s| s.parse::<UserStatus>().ok())
+            .unwrap_or_default(),
+        legal_hold: item.get("legal_hold").and_then(|v| v.as_bool().ok()).copied().unwrap_or(false),
+        kind: item.get("kind").and_then(|v| v.as_s().ok()).map(|s| user_kind_from_str(s)).unwrap_or_default(),
+        organization_id: item.get("organization_id").and_then(|v| v.as_s().ok()).cloned(),
+        custom_attributes: item
+            .get("custom_attributes")
+            .and_then(|v| v.as_s().ok())
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default(),
+        tags: item
+            .get("tags")
+            .and_then(|v| v.as_ss().ok())
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Whether a DynamoDB SDK error represents a transient condition worth
+/// retrying (throttling or a server-side fault), as opposed to a request
+/// that will fail the same way again.
+fn is_transient<E, R>(err: &SdkError<E, R>) -> bool {
+    matches!(
+        err,
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_)
+    )
+}
+
+fn map_sdk_error<E: std::fmt::Display, R>(err: SdkError<E, R>) -> Error
+where
+    SdkError<E, R>: std::error::Error + Send + Sync + 'static,
+{
+    if is_transient(&err) {
+        Error::connection(err)
+    } else {
+        Error::Backend(err.to_string())
+    }
+}
+
+/// Configuration for [`DynamoDbUserRepository`].
+#[derive(Debug, Clone)]
+pub struct DynamoDbUserRepositoryConfig {
+    pub table_name: String,
+}
+
+/// A [`UserRepository`] backed by a single DynamoDB table.
+pub struct DynamoDbUserRepository {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbUserRepository {
+    pub fn new(client: Client, config: DynamoDbUserRepositoryConfig) -> Self {
+        Self {
+            client,
+            table_name: config.table_name,
+        }
+    }
+
+    fn user_put(&self, user: &User) -> Put {
+        Put::builder()
+            .table_name(&self.table_name)
+            .set_item(Some(user_to_item(user)))
+            .build()
+            .expect("user put item is well-formed")
+    }
+
+    fn marker_put(&self, pk: String, user_id: &str) -> Put {
+        Put::builder()
+            .table_name(&self.table_name)
+            .item("pk", s(pk))
+            .item("user_id", s(user_id))
+            .condition_expression("attribute_not_exists(pk)")
+            .build()
+            .expect("marker put item is well-formed")
+    }
+
+    async fn get_item(&self, pk: String) -> Result<Option<HashMap<String, AttributeValue>>> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pk", s(pk))
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+        Ok(output.item)
+    }
+
+    async fn query_index(
+        &self,
+        index_name: &str,
+        key_name: &str,
+        value: &str,
+    ) -> Result<Option<HashMap<String, AttributeValue>>> {
+        let output = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(index_name)
+            .key_condition_expression("#k = :v")
+            .expression_attribute_names("#k", key_name)
+            .expression_attribute_values(":v", s(value))
+            .limit(1)
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+        Ok(output.items.and_then(|mut items| items.pop()))
+    }
+
+    /// Scans the whole table for `USER#` items. There is no index covering
+    /// the ad-hoc filter combinations [`UserSearchFilter`] allows, so this
+    /// reads every profile and filters client-side; acceptable for the
+    /// admin-only, low-volume search endpoint this backs, but not something
+    /// to put on a hot path.
+    async fn scan_users(&self) -> Result<Vec<User>> {
+        let mut users = Vec::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let mut request = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("begins_with(pk, :prefix)")
+                .expression_attribute_values(":prefix", s("USER#"));
+            if let Some(key) = exclusive_start_key {
+                request = request.set_exclusive_start_key(Some(key));
+            }
+            let output = request.send().await.map_err(map_sdk_error)?;
+            for item in output.items.unwrap_or_default() {
+                users.push(item_to_user(&item)?);
+            }
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl UserRepository for DynamoDbUserRepository {
+    async fn create(&self, user: User) -> Result<User> {
+        let mut items = vec![TransactWriteItem::builder().put(self.user_put(&user)).build()];
+        items.push(
+            TransactWriteItem::builder()
+                .put(self.marker_put(email_pk(&user.email), &user.id))
+                .build(),
+        );
+        if let Some(username) = &user.username {
+            items.push(
+                TransactWriteItem::builder()
+                    .put(self.marker_put(username_pk(username), &user.id))
+                    .build(),
+            );
+        }
+
+        self.client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await
+            .map_err(|err| match err.as_service_error() {
+                Some(service_err) if service_err.is_transaction_canceled_exception() => {
+                    Error::Duplicate(user.email.clone())
+                }
+                _ => map_sdk_error(err),
+            })?;
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        let item = self.get_item(user_pk(id)).await?.ok_or(Error::NotFound)?;
+        item_to_user(&item)
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        let item = self
+            .query_index("email-index", "email", email)
+            .await?
+            .ok_or(Error::NotFound)?;
+        item_to_user(&item)
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        let item = self
+            .query_index("username-index", "username", username)
+            .await?
+            .ok_or(Error::NotFound)?;
+        item_to_user(&item)
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        let previous = self.get(&user.id).await?;
+
+        let mut items = vec![TransactWriteItem::builder().put(self.user_put(&user)).build()];
+
+        if previous.email != user.email {
+            items.push(
+                TransactWriteItem::builder()
+                    .delete(
+                        aws_sdk_dynamodb::types::Delete::builder()
+                            .table_name(&self.table_name)
+                            .key("pk", s(email_pk(&previous.email)))
+                            .build()
+                            .expect("delete key is well-formed"),
+                    )
+                    .build(),
+            );
+            items.push(
+                TransactWriteItem::builder()
+                    .put(self.marker_put(email_pk(&user.email), &user.id))
+                    .build(),
+            );
+        }
+
+        if previous.username != user.username {
+            if let Some(old_username) = &previous.username {
+                items.push(
+                    TransactWriteItem::builder()
+                        .delete(
+                            aws_sdk_dynamodb::types::Delete::builder()
+                                .table_name(&self.table_name)
+                                .key("pk", s(username_pk(old_username)))
+                                .build()
+                                .expect("delete key is well-formed"),
+                        )
+                        .build(),
+                );
+            }
+            if let Some(new_username) = &user.username {
+                items.push(
+                    TransactWriteItem::builder()
+                        .put(self.marker_put(username_pk(new_username), &user.id))
+                        .build(),
+                );
+            }
+        }
+
+        self.client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await
+            .map_err(|err| match err.as_service_error() {
+                Some(service_err) if service_err.is_transaction_canceled_exception() => {
+                    Error::Duplicate(user.email.clone())
+                }
+                _ => map_sdk_error(err),
+            })?;
+        Ok(user)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let user = self.get(id).await?;
+
+        let mut items = vec![TransactWriteItem::builder()
+            .delete(
+                aws_sdk_dynamodb::types::Delete::builder()
+                    .table_name(&self.table_name)
+                    .key("pk", s(user_pk(id)))
+                    .build()
+                    .expect("delete key is well-formed"),
+            )
+            .build()];
+        items.push(
+            TransactWriteItem::builder()
+                .delete(
+                    aws_sdk_dynamodb::types::Delete::builder()
+                        .table_name(&self.table_name)
+                        .key("pk", s(email_pk(&user.email)))
+                        .build()
+                        .expect("delete key is well-formed"),
+                )
+                .build(),
+        );
+        if let Some(username) = &user.username {
+            items.push(
+                TransactWriteItem::builder()
+                    .delete(
+                        aws_sdk_dynamodb::types::Delete::builder()
+                            .table_name(&self.table_name)
+                            .key("pk", s(username_pk(username)))
+                            .build()
+                            .expect("delete key is well-formed"),
+                    )
+                    .build(),
+            );
+        }
+
+        self.client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        let matches: Vec<User> = self
+            .scan_users()
+            .await?
+            .into_iter()
+            .filter(|user| matches_search_filter(user, filter))
+            .collect();
+        Ok(paginate_search_results(matches, pagination))
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        Ok(self
+            .scan_users()
+            .await?
+            .into_iter()
+            .filter(|user| matches_search_filter(user, filter))
+            .count() as u64)
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        Ok(self.get_item(email_pk(email)).await?.is_some())
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        let started = Instant::now();
+        self.client
+            .describe_table()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+        let user_count = self.count(&UserSearchFilter::default()).await?;
+        Ok(RepositoryStats {
+            backend: "dynamodb".to_string(),
+            user_count,
+            ping_latency: started.elapsed(),
+        })
+    }
+}
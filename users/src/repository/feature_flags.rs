@@ -0,0 +1,90 @@
+//! An in-memory [`FeatureOverridesRepository`], suitable for tests and
+//! local development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::FeatureOverridesRepository;
+
+#[derive(Default)]
+pub struct MemoryFeatureOverridesRepository {
+    by_user_id: Mutex<HashMap<String, HashMap<String, bool>>>,
+}
+
+impl MemoryFeatureOverridesRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FeatureOverridesRepository for MemoryFeatureOverridesRepository {
+    async fn get_overrides(&self, user_id: &str) -> Result<HashMap<String, bool>> {
+        let by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("feature overrides repository lock poisoned".to_string()))?;
+        Ok(by_user_id.get(user_id).cloned().unwrap_or_default())
+    }
+
+    async fn set_override(&self, user_id: &str, flag: &str, enabled: bool) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("feature overrides repository lock poisoned".to_string()))?;
+        by_user_id
+            .entry(user_id.to_string())
+            .or_default()
+            .insert(flag.to_string(), enabled);
+        Ok(())
+    }
+
+    async fn clear_override(&self, user_id: &str, flag: &str) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("feature overrides repository lock poisoned".to_string()))?;
+        if let Some(overrides) = by_user_id.get_mut(user_id) {
+            overrides.remove(flag);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn get_overrides_is_empty_for_an_unconfigured_user() {
+        let repo = MemoryFeatureOverridesRepository::new();
+        assert!(repo.get_overrides("1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn set_override_then_get_overrides_returns_it() {
+        let repo = MemoryFeatureOverridesRepository::new();
+        repo.set_override("1", "beta-search", true).await.unwrap();
+        let overrides = repo.get_overrides("1").await.unwrap();
+        assert_eq!(overrides.get("beta-search"), Some(&true));
+    }
+
+    #[tokio::test]
+    async fn clear_override_removes_it() {
+        let repo = MemoryFeatureOverridesRepository::new();
+        repo.set_override("1", "beta-search", true).await.unwrap();
+        repo.clear_override("1", "beta-search").await.unwrap();
+        assert!(repo.get_overrides("1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn clearing_an_unset_override_is_a_no_op() {
+        let repo = MemoryFeatureOverridesRepository::new();
+        repo.clear_override("1", "beta-search").await.unwrap();
+        assert!(repo.get_overrides("1").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! A disk-persisted [`UserRepository`]: an in-memory index kept durable by
+//! a snapshot file plus an append-only log of mutations made since the
+//! last snapshot, so single-node deployments and local development keep
+//! their data across restarts without a database.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{
+    Pagination, RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage,
+};
+use crate::repository::memory::MemoryUserRepository;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum LogRecord {
+    Put(Box<User>),
+    Delete(String),
+}
+
+fn io_err(err: std::io::Error) -> Error {
+    Error::Backend(err.to_string())
+}
+
+fn json_err(err: serde_json::Error) -> Error {
+    Error::Backend(err.to_string())
+}
+
+/// A [`UserRepository`] backed by an in-memory index that is also written
+/// to disk: a `snapshot.json` holding the full data set, and a `log.jsonl`
+/// of mutations appended (and `fsync`'d) since that snapshot was taken. On
+/// [`FileUserRepository::open`], the snapshot is loaded first and the log
+/// replayed on top of it, so recovery only reads as much log as has
+/// accumulated since the last [`FileUserRepository::compact`].
+pub struct FileUserRepository {
+    memory: MemoryUserRepository,
+    snapshot_path: PathBuf,
+    log_path: PathBuf,
+    log_file: Mutex<File>,
+}
+
+impl FileUserRepository {
+    /// Opens (or creates) the repository rooted at `dir`, replaying any
+    /// existing snapshot and log found there.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(io_err)?;
+        let snapshot_path = dir.join("snapshot.json");
+        let log_path = dir.join("log.jsonl");
+
+        let memory = MemoryUserRepository::new();
+        if snapshot_path.exists() {
+            let data = fs::read_to_string(&snapshot_path).map_err(io_err)?;
+            let users: Vec<User> = serde_json::from_str(&data).map_err(json_err)?;
+            for user in users {
+                memory.load(user)?;
+            }
+        }
+        if log_path.exists() {
+            let file = File::open(&log_path).map_err(io_err)?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(io_err)?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line).map_err(json_err)? {
+                    LogRecord::Put(user) => memory.load(*user)?,
+                    LogRecord::Delete(id) => memory.forget(&id)?,
+                }
+            }
+        }
+
+        let log_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(io_err)?;
+
+        Ok(Self {
+            memory,
+            snapshot_path,
+            log_path,
+            log_file: Mutex::new(log_file),
+        })
+    }
+
+    fn append(&self, record: &LogRecord) -> Result<()> {
+        let line = serde_json::to_string(record).map_err(json_err)?;
+        let mut file = self
+            .log_file
+            .lock()
+            .map_err(|_| Error::Backend("file repository log lock poisoned".to_string()))?;
+        writeln!(file, "{line}").map_err(io_err)?;
+        file.sync_all().map_err(io_err)
+    }
+
+    /// Writes a fresh snapshot of the current data set and truncates the
+    /// log, so a future restart doesn't need to replay history already
+    /// reflected in the snapshot. Safe to call periodically in the
+    /// background; it only ever reads the current in-memory state.
+    pub fn compact(&self) -> Result<()> {
+        let users = self.memory.all()?;
+        let data = serde_json::to_string(&users).map_err(json_err)?;
+        fs::write(&self.snapshot_path, &data).map_err(io_err)?;
+        File::open(&self.snapshot_path)
+            .and_then(|f| f.sync_all())
+            .map_err(io_err)?;
+
+        let mut file = self
+            .log_file
+            .lock()
+            .map_err(|_| Error::Backend("file repository log lock poisoned".to_string()))?;
+        *file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.log_path)
+            .map_err(io_err)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for FileUserRepository {
+    async fn create(&self, user: User) -> Result<User> {
+        let user = self.memory.create(user).await?;
+        self.append(&LogRecord::Put(Box::new(user.clone())))?;
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        self.memory.get(id).await
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        self.memory.get_by_email(email).await
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        self.memory.get_by_username(username).await
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        let user = self.memory.update(user).await?;
+        self.append(&LogRecord::Put(Box::new(user.clone())))?;
+        Ok(user)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.memory.delete(id).await?;
+        self.append(&LogRecord::Delete(id.to_string()))
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        self.memory.search(filter, pagination).await
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        self.memory.count(filter).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        self.memory.exists_by_email(email).await
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        let started = Instant::now();
+        // The disk is only touched on writes; a ping that only reads the
+        // in-memory index would say nothing about whether the log file is
+        // still writable, so check that too.
+        self.log_path.metadata().map_err(io_err)?;
+        let RepositoryStats { user_count, .. } = self.memory.stats().await?;
+        Ok(RepositoryStats {
+            backend: "file".to_string(),
+            user_count,
+            ping_latency: started.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn data_survives_reopening_without_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let repo = FileUserRepository::open(dir.path()).unwrap();
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        drop(repo);
+
+        let reopened = FileUserRepository::open(dir.path()).unwrap();
+        assert_eq!(reopened.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn compaction_folds_the_log_into_a_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let repo = FileUserRepository::open(dir.path()).unwrap();
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.compact().unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("log.jsonl")).unwrap(), "");
+        drop(repo);
+
+        let reopened = FileUserRepository::open(dir.path()).unwrap();
+        assert_eq!(reopened.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn deletes_are_replayed_on_top_of_the_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let repo = FileUserRepository::open(dir.path()).unwrap();
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.compact().unwrap();
+        repo.delete("1").await.unwrap();
+        drop(repo);
+
+        let reopened = FileUserRepository::open(dir.path()).unwrap();
+        assert!(reopened.get("1").await.is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! An in-memory [`UserRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{
+    matches_search_filter, paginate_search_results, Pagination, RepositoryStats, User,
+    UserRepository, UserSearchFilter, UserSearchPage,
+};
+
+#[derive(Default)]
+struct Index {
+    users: HashMap<String, User>,
+    by_email: HashMap<String, String>,
+    by_username: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct MemoryUserRepository {
+    index: Mutex<Index>,
+}
+
+impl MemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Index>> {
+        self.index
+            .lock()
+            .map_err(|_| Error::Backend("user repository lock poisoned".to_string()))
+    }
+
+    /// Inserts `user` directly into the index, bypassing the duplicate
+    /// checks `create` performs. Used to replay already-accepted records
+    /// from a durable log, where uniqueness was already enforced once.
+    pub(crate) fn load(&self, user: User) -> Result<()> {
+        let mut index = self.lock()?;
+        index.by_email.insert(user.email.clone(), user.id.clone());
+        if let Some(username) = &user.username {
+            index.by_username.insert(username.clone(), user.id.clone());
+        }
+        index.users.insert(user.id.clone(), user);
+        Ok(())
+    }
+
+    /// Removes `id` directly from the index without requiring it to exist.
+    /// Used to replay a delete record from a durable log.
+    pub(crate) fn forget(&self, id: &str) -> Result<()> {
+        let mut index = self.lock()?;
+        if let Some(user) = index.users.remove(id) {
+            index.by_email.remove(&user.email);
+            if let Some(username) = &user.username {
+                index.by_username.remove(username);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every user currently held, for snapshotting.
+    pub(crate) fn all(&self) -> Result<Vec<User>> {
+        Ok(self.lock()?.users.values().cloned().collect())
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryUserRepository {
+    async fn create(&self, user: User) -> Result<User> {
+        let mut index = self.lock()?;
+        if index.by_email.contains_key(&user.email) {
+            return Err(Error::Duplicate(user.email));
+        }
+        if let Some(username) = &user.username {
+            if index.by_username.contains_key(username) {
+                return Err(Error::DuplicateUsername(username.clone()));
+            }
+        }
+        index.by_email.insert(user.email.clone(), user.id.clone());
+        if let Some(username) = &user.username {
+            index.by_username.insert(username.clone(), user.id.clone());
+        }
+        index.users.insert(user.id.clone(), user.clone());
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        self.lock()?.users.get(id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        let index = self.lock()?;
+        let id = index.by_email.get(email).ok_or(Error::NotFound)?;
+        index.users.get(id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        let index = self.lock()?;
+        let id = index.by_username.get(username).ok_or(Error::NotFound)?;
+        index.users.get(id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        let mut index = self.lock()?;
+        let previous = index.users.get(&user.id).cloned().ok_or(Error::NotFound)?;
+
+        if user.email != previous.email && index.by_email.contains_key(&user.email) {
+            return Err(Error::Duplicate(user.email));
+        }
+        if let Some(username) = &user.username {
+            if previous.username.as_deref() != Some(username.as_str())
+                && index.by_username.contains_key(username)
+            {
+                return Err(Error::DuplicateUsername(username.clone()));
+            }
+        }
+
+        index.by_email.remove(&previous.email);
+        index.by_email.insert(user.email.clone(), user.id.clone());
+        if let Some(username) = &previous.username {
+            index.by_username.remove(username);
+        }
+        if let Some(username) = &user.username {
+            index.by_username.insert(username.clone(), user.id.clone());
+        }
+
+        index.users.insert(user.id.clone(), user.clone());
+        Ok(user)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let mut index = self.lock()?;
+        let user = index.users.remove(id).ok_or(Error::NotFound)?;
+        index.by_email.remove(&user.email);
+        if let Some(username) = &user.username {
+            index.by_username.remove(username);
+        }
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        let matches: Vec<User> = self
+            .lock()?
+            .users
+            .values()
+            .filter(|user| matches_search_filter(user, filter))
+            .cloned()
+            .collect();
+        Ok(paginate_search_results(matches, pagination))
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        Ok(self
+            .lock()?
+            .users
+            .values()
+            .filter(|user| matches_search_filter(user, filter))
+            .count() as u64)
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        Ok(self.lock()?.by_email.contains_key(email))
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        let started = Instant::now();
+        let user_count = self.lock()?.users.len() as u64;
+        Ok(RepositoryStats {
+            backend: "memory".to_string(),
+            user_count,
+            ping_latency: started.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_rejects_duplicate_email() {
+        let repo = MemoryUserRepository::new();
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        let err = repo
+            .create(sample_user("2", "a@example.com"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Duplicate(_)));
+    }
+
+    #[tokio::test]
+    async fn create_rejects_duplicate_username() {
+        let repo = MemoryUserRepository::new();
+        let mut alice = sample_user("1", "a@example.com");
+        alice.username = Some("alice".to_string());
+        repo.create(alice).await.unwrap();
+
+        let mut bob = sample_user("2", "b@example.com");
+        bob.username = Some("alice".to_string());
+        let err = repo.create(bob).await.unwrap_err();
+        assert!(matches!(err, Error::DuplicateUsername(_)));
+    }
+
+    #[tokio::test]
+    async fn get_returns_not_found_for_missing_user() {
+        let repo = MemoryUserRepository::new();
+        assert!(matches!(repo.get("missing").await, Err(Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn get_by_username_finds_indexed_user() {
+        let repo = MemoryUserRepository::new();
+        let mut alice = sample_user("1", "a@example.com");
+        alice.username = Some("alice".to_string());
+        repo.create(alice).await.unwrap();
+        assert_eq!(repo.get_by_username("alice").await.unwrap().id, "1");
+    }
+
+    #[tokio::test]
+    async fn search_filters_by_email_prefix() {
+        let repo = MemoryUserRepository::new();
+        repo.create(sample_user("1", "alice@example.com")).await.unwrap();
+        repo.create(sample_user("2", "bob@example.com")).await.unwrap();
+
+        let filter = UserSearchFilter {
+            email_prefix: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let page = repo
+            .search(&filter, Pagination { offset: 0, limit: 10 })
+            .await
+            .unwrap();
+        assert_eq!(page.users.len(), 1);
+        assert_eq!(page.users[0].id, "1");
+        assert!(!page.has_more);
+    }
+
+    #[tokio::test]
+    async fn search_paginates_results_in_creation_order() {
+        let repo = MemoryUserRepository::new();
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        repo.create(sample_user("2", "b@example.com")).await.unwrap();
+        repo.create(sample_user("3", "c@example.com")).await.unwrap();
+
+        let page = repo
+            .search(&UserSearchFilter::default(), Pagination { offset: 1, limit: 1 })
+            .await
+            .unwrap();
+        assert_eq!(page.users.len(), 1);
+        assert_eq!(page.users[0].id, "2");
+        assert!(page.has_more);
+    }
+
+    #[tokio::test]
+    async fn count_reflects_the_filter_not_just_the_total() {
+        let repo = MemoryUserRepository::new();
+        repo.create(sample_user("1", "alice@example.com")).await.unwrap();
+        repo.create(sample_user("2", "bob@example.com")).await.unwrap();
+
+        let filter = UserSearchFilter {
+            email_prefix: Some("alice".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(repo.count(&filter).await.unwrap(), 1);
+        assert_eq!(repo.count(&UserSearchFilter::default()).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn exists_by_email_reflects_registrations() {
+        let repo = MemoryUserRepository::new();
+        assert!(!repo.exists_by_email("a@example.com").await.unwrap());
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        assert!(repo.exists_by_email("a@example.com").await.unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+//! Support for moving the user store from one [`UserRepository`] backend
+//! to another without downtime: [`MigratingRepository`] keeps both
+//! backends written to while [`copy_in_batches`] (driven by
+//! `svc-admin migrate-store`) backfills `new` with everything `old`
+//! already held, so a deployment can flip from, say, memory/SQLite to
+//! Postgres by wrapping live traffic in [`MigratingRepository`], running
+//! the backfill once, and then dropping down to `new` alone.
+
+use async_trait::async_trait;
+use libsvc::repository::Result;
+
+use crate::domain::{
+    Pagination, RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage,
+};
+
+/// Wraps two [`UserRepository`] backends during a migration. Writes are
+/// applied to `old` first (still the system of record until cutover) and
+/// then `new`, so a failure on either leaves neither silently behind.
+/// Reads prefer `new`, falling back to `old` for a record
+/// [`copy_in_batches`] hasn't backfilled yet.
+pub struct MigratingRepository<Old, New> {
+    old: Old,
+    new: New,
+}
+
+impl<Old: UserRepository, New: UserRepository> MigratingRepository<Old, New> {
+    pub fn new(old: Old, new: New) -> Self {
+        Self { old, new }
+    }
+}
+
+#[async_trait]
+impl<Old: UserRepository, New: UserRepository> UserRepository for MigratingRepository<Old, New> {
+    async fn create(&self, user: User) -> Result<User> {
+        self.old.create(user.clone()).await?;
+        self.new.create(user).await
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        match self.new.get(id).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.old.get(id).await,
+        }
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        match self.new.get_by_email(email).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.old.get_by_email(email).await,
+        }
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        match self.new.get_by_username(username).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.old.get_by_username(username).await,
+        }
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        self.old.update(user.clone()).await?;
+        self.new.update(user).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.old.delete(id).await?;
+        self.new.delete(id).await
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        match self.new.search(filter, pagination).await {
+            Ok(page) => Ok(page),
+            Err(_) => self.old.search(filter, pagination).await,
+        }
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        match self.new.count(filter).await {
+            Ok(count) => Ok(count),
+            Err(_) => self.old.count(filter).await,
+        }
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        match self.new.exists_by_email(email).await {
+            Ok(exists) => Ok(exists),
+            Err(_) => self.old.exists_by_email(email).await,
+        }
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        self.new.stats().await
+    }
+}
+
+/// How many users a single [`copy_in_batches`] call processed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationSummary {
+    pub copied: u64,
+    pub skipped_existing: u64,
+}
+
+/// Copies every user in `old` into `new`, `batch_size` at a time, so
+/// `svc-admin migrate-store` never has to hold more than one batch in
+/// memory regardless of store size. Users already present in `new`
+/// (matched by email) are left untouched, so re-running after a partial
+/// failure only copies what's still missing.
+pub async fn copy_in_batches(
+    old: &dyn UserRepository,
+    new: &dyn UserRepository,
+    batch_size: usize,
+) -> Result<MigrationSummary> {
+    let mut pagination = Pagination { offset: 0, limit: batch_size };
+    let mut summary = MigrationSummary::default();
+    loop {
+        let page = old.search(&UserSearchFilter::default(), pagination).await?;
+        let page_len = page.users.len();
+        for user in page.users {
+            if new.exists_by_email(&user.email).await? {
+                summary.skipped_existing += 1;
+                continue;
+            }
+            new.create(user).await?;
+            summary.copied += 1;
+        }
+        if !page.has_more {
+            break;
+        }
+        pagination.offset += page_len;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::memory::MemoryUserRepository;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_land_in_both_backends() {
+        let old = MemoryUserRepository::new();
+        let new = MemoryUserRepository::new();
+        let migrating = MigratingRepository::new(old, new);
+
+        migrating.create(sample_user("1", "a@example.com")).await.unwrap();
+
+        assert_eq!(migrating.old.get("1").await.unwrap().email, "a@example.com");
+        assert_eq!(migrating.new.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn reads_fall_back_to_old_for_records_not_yet_backfilled() {
+        let old = MemoryUserRepository::new();
+        old.create(sample_user("1", "a@example.com")).await.unwrap();
+        let new = MemoryUserRepository::new();
+        let migrating = MigratingRepository::new(old, new);
+
+        assert_eq!(migrating.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn reads_prefer_new_once_backfilled() {
+        let old = MemoryUserRepository::new();
+        old.create(sample_user("1", "old@example.com")).await.unwrap();
+        let new = MemoryUserRepository::new();
+        new.create(sample_user("1", "new@example.com")).await.unwrap();
+        let migrating = MigratingRepository::new(old, new);
+
+        assert_eq!(migrating.get("1").await.unwrap().email, "new@example.com");
+    }
+
+    #[tokio::test]
+    async fn copy_in_batches_backfills_everything_from_old() {
+        let old = MemoryUserRepository::new();
+        for i in 0..5 {
+            old.create(sample_user(&i.to_string(), &format!("user{i}@example.com")))
+                .await
+                .unwrap();
+        }
+        let new = MemoryUserRepository::new();
+
+        let summary = copy_in_batches(&old, &new, 2).await.unwrap();
+        assert_eq!(summary.copied, 5);
+        assert_eq!(summary.skipped_existing, 0);
+        assert_eq!(new.count(&UserSearchFilter::default()).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn copy_in_batches_skips_users_already_present_in_new() {
+        let old = MemoryUserRepository::new();
+        old.create(sample_user("1", "a@example.com")).await.unwrap();
+        let new = MemoryUserRepository::new();
+        new.create(sample_user("1", "a@example.com")).await.unwrap();
+
+        let summary = copy_in_batches(&old, &new, 10).await.unwrap();
+        assert_eq!(summary.copied, 0);
+        assert_eq!(summary.skipped_existing, 1);
+    }
+}
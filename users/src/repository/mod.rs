@@ -0,0 +1,17 @@
+pub mod backup_codes;
+pub mod cached;
+pub mod credentials;
+pub mod devices;
+pub mod digest;
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+pub mod feature_flags;
+pub mod file;
+pub mod memory;
+pub mod migrating;
+pub mod notifications;
+pub mod preferences;
+pub mod read_replica;
+pub mod resilient;
+pub mod sms_otp;
+pub mod usage;
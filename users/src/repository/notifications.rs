@@ -0,0 +1,148 @@
+//! An in-memory [`NotificationRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{paginate_notifications, Notification, NotificationPage, NotificationRepository, Pagination};
+
+#[derive(Default)]
+pub struct MemoryNotificationRepository {
+    by_user_id: Mutex<HashMap<String, Vec<Notification>>>,
+}
+
+impl MemoryNotificationRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for MemoryNotificationRepository {
+    async fn create(&self, notification: Notification) -> Result<()> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("notification repository lock poisoned".to_string()))?;
+        by_user_id.entry(notification.user_id.clone()).or_default().push(notification);
+        Ok(())
+    }
+
+    async fn list(&self, user_id: &str, pagination: Pagination) -> Result<NotificationPage> {
+        let by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("notification repository lock poisoned".to_string()))?;
+        let notifications = by_user_id.get(user_id).cloned().unwrap_or_default();
+        Ok(paginate_notifications(notifications, pagination))
+    }
+
+    async fn mark_read(
+        &self,
+        user_id: &str,
+        id: &str,
+        read_at: DateTime<Utc>,
+    ) -> Result<Notification> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("notification repository lock poisoned".to_string()))?;
+        let notifications = by_user_id.get_mut(user_id).ok_or(Error::NotFound)?;
+        let notification = notifications
+            .iter_mut()
+            .find(|notification| notification.id == id)
+            .ok_or(Error::NotFound)?;
+        notification.read_at = Some(read_at);
+        Ok(notification.clone())
+    }
+
+    async fn mark_all_read(&self, user_id: &str, read_at: DateTime<Utc>) -> Result<u64> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("notification repository lock poisoned".to_string()))?;
+        let Some(notifications) = by_user_id.get_mut(user_id) else {
+            return Ok(0);
+        };
+        let mut updated = 0;
+        for notification in notifications.iter_mut() {
+            if notification.read_at.is_none() {
+                notification.read_at = Some(read_at);
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::NotificationCategory;
+
+    fn sample_notification(id: &str, user_id: &str) -> Notification {
+        Notification {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            category: NotificationCategory::Product,
+            subject: "subject".to_string(),
+            body: "body".to_string(),
+            created_at: Utc::now(),
+            read_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn list_is_empty_for_a_user_with_no_notifications() {
+        let repo = MemoryNotificationRepository::new();
+        let page = repo.list("1", Pagination { offset: 0, limit: 10 }).await.unwrap();
+        assert!(page.notifications.is_empty());
+        assert!(!page.has_more);
+    }
+
+    #[tokio::test]
+    async fn create_then_list_returns_newest_first() {
+        let repo = MemoryNotificationRepository::new();
+        let mut older = sample_notification("1", "user-1");
+        older.created_at = Utc::now() - chrono::Duration::seconds(60);
+        let newer = sample_notification("2", "user-1");
+        repo.create(older).await.unwrap();
+        repo.create(newer).await.unwrap();
+
+        let page = repo.list("user-1", Pagination { offset: 0, limit: 10 }).await.unwrap();
+        assert_eq!(page.notifications.len(), 2);
+        assert_eq!(page.notifications[0].id, "2");
+        assert_eq!(page.notifications[1].id, "1");
+    }
+
+    #[tokio::test]
+    async fn mark_read_sets_read_at_and_is_scoped_to_the_owning_user() {
+        let repo = MemoryNotificationRepository::new();
+        repo.create(sample_notification("1", "user-1")).await.unwrap();
+
+        let err = repo.mark_read("user-2", "1", Utc::now()).await.unwrap_err();
+        assert!(matches!(err, Error::NotFound));
+
+        let marked = repo.mark_read("user-1", "1", Utc::now()).await.unwrap();
+        assert!(marked.read_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn mark_all_read_only_updates_currently_unread_notifications() {
+        let repo = MemoryNotificationRepository::new();
+        repo.create(sample_notification("1", "user-1")).await.unwrap();
+        repo.create(sample_notification("2", "user-1")).await.unwrap();
+        repo.mark_read("user-1", "1", Utc::now()).await.unwrap();
+
+        let updated = repo.mark_all_read("user-1", Utc::now()).await.unwrap();
+        assert_eq!(updated, 1);
+
+        let again = repo.mark_all_read("user-1", Utc::now()).await.unwrap();
+        assert_eq!(again, 0);
+    }
+}
@@ -0,0 +1,73 @@
+//! An in-memory [`UserPreferencesRepository`], suitable for tests and
+//! local development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{UserPreferences, UserPreferencesRepository};
+
+#[derive(Default)]
+pub struct MemoryUserPreferencesRepository {
+    by_user_id: Mutex<HashMap<String, UserPreferences>>,
+}
+
+impl MemoryUserPreferencesRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UserPreferencesRepository for MemoryUserPreferencesRepository {
+    async fn get(&self, user_id: &str) -> Result<UserPreferences> {
+        let by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("preferences repository lock poisoned".to_string()))?;
+        Ok(by_user_id
+            .get(user_id)
+            .cloned()
+            .unwrap_or_else(|| UserPreferences::defaults(user_id)))
+    }
+
+    async fn put(&self, preferences: UserPreferences) -> Result<UserPreferences> {
+        let mut by_user_id = self
+            .by_user_id
+            .lock()
+            .map_err(|_| Error::Backend("preferences repository lock poisoned".to_string()))?;
+        by_user_id.insert(preferences.user_id.clone(), preferences.clone());
+        Ok(preferences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Theme;
+
+    #[tokio::test]
+    async fn get_returns_defaults_for_unset_preferences() {
+        let repo = MemoryUserPreferencesRepository::new();
+        let preferences = repo.get("1").await.unwrap();
+        assert_eq!(preferences.user_id, "1");
+        assert!(preferences.notifications_enabled);
+        assert_eq!(preferences.theme, Theme::System);
+    }
+
+    #[tokio::test]
+    async fn put_then_get_returns_the_stored_preferences() {
+        let repo = MemoryUserPreferencesRepository::new();
+        let mut preferences = UserPreferences::defaults("1");
+        preferences.theme = Theme::Dark;
+        preferences.locale = "fr-FR".to_string();
+        repo.put(preferences.clone()).await.unwrap();
+
+        let stored = repo.get("1").await.unwrap();
+        assert_eq!(stored.theme, Theme::Dark);
+        assert_eq!(stored.locale, "fr-FR");
+    }
+}
@@ -0,0 +1,277 @@
+//! Read/write splitting for deployments whose [`UserRepository`] backend
+//! offers separate primary and replica connections — for example, a SQL
+//! store with one writable primary and one or more read replicas behind
+//! it. This crate has no SQL-backed repository of its own
+//! ([`crate::repository::memory`], [`crate::repository::file`], and the
+//! `dynamodb` feature are the only backends implemented so far), so
+//! [`ReadReplicaRepository`] is written generically over any two
+//! [`UserRepository`]s: whichever `Primary`/`Replica` types a SQL
+//! implementation eventually provides, wrapping them in this decorator
+//! is enough to get read routing.
+//!
+//! Mutations always go to `primary`. Reads normally go to `replica`, but
+//! a record just written through this decorator is "sticky" to
+//! `primary` for a configurable window afterwards, so replication lag
+//! can't make a just-created or just-updated user look missing or stale.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use libsvc::repository::Result;
+
+use crate::domain::{
+    Pagination, RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage,
+};
+
+/// Tunables for [`ReadReplicaRepository`].
+#[derive(Debug, Clone)]
+pub struct ReadReplicaConfig {
+    /// How long after a write a record's id and email stay pinned to
+    /// `primary` for reads, to cover typical replication lag.
+    pub stickiness: Duration,
+}
+
+impl Default for ReadReplicaConfig {
+    fn default() -> Self {
+        Self {
+            stickiness: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Tracks which keys (ids and emails) were recently written, so reads for
+/// them can be routed to `primary` instead of a possibly-lagging replica.
+#[derive(Default)]
+struct StickySet {
+    written_at: HashMap<String, Instant>,
+}
+
+impl StickySet {
+    fn lock(mutex: &Mutex<StickySet>) -> MutexGuard<'_, StickySet> {
+        mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn mark(&mut self, key: &str) {
+        self.written_at.insert(key.to_string(), Instant::now());
+    }
+
+    fn is_sticky(&mut self, key: &str, window: Duration) -> bool {
+        match self.written_at.get(key) {
+            Some(written_at) if written_at.elapsed() < window => true,
+            Some(_) => {
+                self.written_at.remove(key);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Routes mutations to a writable `primary` and reads to a `replica`,
+/// falling back to `primary` for a record recently written through this
+/// decorator (see the module docs) or if `replica` itself errors.
+pub struct ReadReplicaRepository<Primary, Replica> {
+    primary: Primary,
+    replica: Replica,
+    config: ReadReplicaConfig,
+    recent_writes: Mutex<StickySet>,
+}
+
+impl<Primary: UserRepository, Replica: UserRepository> ReadReplicaRepository<Primary, Replica> {
+    /// Wraps `primary`/`replica` with the default stickiness window.
+    pub fn new(primary: Primary, replica: Replica) -> Self {
+        Self::with_config(primary, replica, ReadReplicaConfig::default())
+    }
+
+    /// Wraps `primary`/`replica` with a custom stickiness window.
+    pub fn with_config(primary: Primary, replica: Replica, config: ReadReplicaConfig) -> Self {
+        Self {
+            primary,
+            replica,
+            config,
+            recent_writes: Mutex::new(StickySet::default()),
+        }
+    }
+
+    fn mark_written(&self, keys: &[&str]) {
+        let mut recent_writes = StickySet::lock(&self.recent_writes);
+        for key in keys {
+            recent_writes.mark(key);
+        }
+    }
+
+    fn is_sticky(&self, key: &str) -> bool {
+        StickySet::lock(&self.recent_writes).is_sticky(key, self.config.stickiness)
+    }
+}
+
+#[async_trait]
+impl<Primary: UserRepository, Replica: UserRepository> UserRepository
+    for ReadReplicaRepository<Primary, Replica>
+{
+    async fn create(&self, user: User) -> Result<User> {
+        let user = self.primary.create(user).await?;
+        self.mark_written(&[&user.id, &user.email]);
+        Ok(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        if self.is_sticky(id) {
+            return self.primary.get(id).await;
+        }
+        match self.replica.get(id).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.primary.get(id).await,
+        }
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        if self.is_sticky(email) {
+            return self.primary.get_by_email(email).await;
+        }
+        match self.replica.get_by_email(email).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.primary.get_by_email(email).await,
+        }
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        match self.replica.get_by_username(username).await {
+            Ok(user) => Ok(user),
+            Err(_) => self.primary.get_by_username(username).await,
+        }
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        let user = self.primary.update(user).await?;
+        self.mark_written(&[&user.id, &user.email]);
+        Ok(user)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.primary.delete(id).await?;
+        self.mark_written(&[id]);
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        match self.replica.search(filter, pagination).await {
+            Ok(page) => Ok(page),
+            Err(_) => self.primary.search(filter, pagination).await,
+        }
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        match self.replica.count(filter).await {
+            Ok(count) => Ok(count),
+            Err(_) => self.primary.count(filter).await,
+        }
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        if self.is_sticky(email) {
+            return self.primary.exists_by_email(email).await;
+        }
+        match self.replica.exists_by_email(email).await {
+            Ok(exists) => Ok(exists),
+            Err(_) => self.primary.exists_by_email(email).await,
+        }
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        // Health reporting should reflect the backend operators actually
+        // write to and care about the health of.
+        self.primary.stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::memory::MemoryUserRepository;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_only_land_in_primary() {
+        let primary = MemoryUserRepository::new();
+        let replica = MemoryUserRepository::new();
+        let repo = ReadReplicaRepository::new(primary, replica);
+
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+
+        assert!(repo.primary.get("1").await.is_ok());
+        assert!(repo.replica.get("1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_fall_back_to_primary_when_missing_from_replica() {
+        let primary = MemoryUserRepository::new();
+        primary.create(sample_user("1", "a@example.com")).await.unwrap();
+        let replica = MemoryUserRepository::new();
+        let repo = ReadReplicaRepository::new(primary, replica);
+
+        assert_eq!(repo.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn a_just_created_user_is_read_from_primary_during_the_stickiness_window() {
+        let primary = MemoryUserRepository::new();
+        let replica = MemoryUserRepository::new();
+        let repo = ReadReplicaRepository::with_config(
+            primary,
+            replica,
+            ReadReplicaConfig {
+                stickiness: Duration::from_secs(60),
+            },
+        );
+
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+
+        // The replica hasn't caught up (it's empty), but the read still
+        // succeeds because it's routed to primary during the window.
+        assert_eq!(repo.get("1").await.unwrap().email, "a@example.com");
+        assert_eq!(repo.get_by_email("a@example.com").await.unwrap().id, "1");
+    }
+
+    #[tokio::test]
+    async fn stickiness_expires_and_falls_back_to_the_replica_routing() {
+        let primary = MemoryUserRepository::new();
+        let replica = MemoryUserRepository::new();
+        let repo = ReadReplicaRepository::with_config(
+            primary,
+            replica,
+            ReadReplicaConfig {
+                stickiness: Duration::from_millis(1),
+            },
+        );
+
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        // Past the window, reads go to the (still-empty) replica and
+        // only succeed via its fallback to primary, not stickiness.
+        assert_eq!(repo.get("1").await.unwrap().email, "a@example.com");
+    }
+}
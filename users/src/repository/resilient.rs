@@ -0,0 +1,378 @@
+//! A [`UserRepository`] decorator that retries transient failures with
+//! jittered backoff and opens a circuit after repeated failures, so a
+//! struggling backend doesn't get hammered by every in-flight request's
+//! retries on top of its own.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use libsvc::repository::{Error, Result};
+use rand::Rng;
+use tokio::time::sleep;
+
+use crate::domain::{
+    Pagination, RepositoryStats, User, UserRepository, UserSearchFilter, UserSearchPage,
+};
+
+/// Tunables for [`ResilientRepository`].
+#[derive(Debug, Clone)]
+pub struct ResilientRepositoryConfig {
+    /// Maximum number of attempts per call, including the first.
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubles on each subsequent retry.
+    pub base_backoff: Duration,
+    /// Upper bound on backoff, before jitter is added.
+    pub max_backoff: Duration,
+    /// Consecutive failures required to open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before allowing another attempt.
+    pub open_circuit_for: Duration,
+}
+
+impl Default for ResilientRepositoryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            failure_threshold: 5,
+            open_circuit_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Counters describing how a [`ResilientRepository`] has behaved, suitable
+/// for exporting to a metrics backend.
+#[derive(Debug, Default)]
+pub struct ResilientRepositoryMetrics {
+    attempts: AtomicU64,
+    retries: AtomicU64,
+    circuit_trips: AtomicU64,
+    circuit_rejections: AtomicU64,
+}
+
+impl ResilientRepositoryMetrics {
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    pub fn circuit_trips(&self) -> u64 {
+        self.circuit_trips.load(Ordering::Relaxed)
+    }
+
+    pub fn circuit_rejections(&self) -> u64 {
+        self.circuit_rejections.load(Ordering::Relaxed)
+    }
+}
+
+/// The circuit is open and calls are being rejected without reaching the
+/// inner repository.
+#[derive(Debug)]
+struct CircuitOpenError;
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("circuit breaker is open")
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Wraps a [`UserRepository`] with retries, jittered backoff, and a circuit
+/// breaker, so repeated transient failures fail fast instead of piling up
+/// retries against a backend that is already struggling.
+pub struct ResilientRepository<R> {
+    inner: R,
+    config: ResilientRepositoryConfig,
+    metrics: Arc<ResilientRepositoryMetrics>,
+    circuit: Mutex<CircuitState>,
+}
+
+impl<R: UserRepository> ResilientRepository<R> {
+    /// Wraps `inner` with the default retry and circuit-breaker thresholds.
+    pub fn new(inner: R) -> Self {
+        Self::with_config(inner, ResilientRepositoryConfig::default())
+    }
+
+    /// Wraps `inner` with custom thresholds.
+    pub fn with_config(inner: R, config: ResilientRepositoryConfig) -> Self {
+        Self {
+            inner,
+            config,
+            metrics: Arc::new(ResilientRepositoryMetrics::default()),
+            circuit: Mutex::new(CircuitState {
+                consecutive_failures: 0,
+                open_until: None,
+            }),
+        }
+    }
+
+    /// A shared handle to this repository's metrics.
+    pub fn metrics(&self) -> Arc<ResilientRepositoryMetrics> {
+        self.metrics.clone()
+    }
+
+    fn lock_circuit(&self) -> std::sync::MutexGuard<'_, CircuitState> {
+        self.circuit
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn circuit_open(&self) -> bool {
+        matches!(self.lock_circuit().open_until, Some(until) if Instant::now() < until)
+    }
+
+    fn record_success(&self) {
+        let mut circuit = self.lock_circuit();
+        circuit.consecutive_failures = 0;
+        circuit.open_until = None;
+    }
+
+    fn record_failure(&self) {
+        let mut circuit = self.lock_circuit();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.config.failure_threshold {
+            circuit.open_until = Some(Instant::now() + self.config.open_circuit_for);
+            self.metrics.circuit_trips.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .config
+            .base_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = exponential.min(self.config.max_backoff);
+        let jitter_ms = rand::thread_rng().gen_range(0..=(capped.as_millis() as u64 / 2).max(1));
+        capped + Duration::from_millis(jitter_ms)
+    }
+
+    /// Runs `op`, retrying retryable failures with backoff until
+    /// `max_attempts` is reached, and short-circuits entirely while the
+    /// circuit is open.
+    async fn call<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.circuit_open() {
+            self.metrics
+                .circuit_rejections
+                .fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "tracing-domain")]
+            tracing::warn!(operation = "repository_call", "circuit open, rejecting call");
+            return Err(Error::connection(CircuitOpenError));
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.metrics.attempts.fetch_add(1, Ordering::Relaxed);
+            match op().await {
+                Ok(value) => {
+                    self.record_success();
+                    return Ok(value);
+                }
+                Err(err) if err.is_retryable() && attempt < self.config.max_attempts => {
+                    self.record_failure();
+                    self.metrics.retries.fetch_add(1, Ordering::Relaxed);
+                    #[cfg(feature = "tracing-domain")]
+                    tracing::debug!(operation = "repository_call", attempt, %err, "retrying after retryable repository error");
+                    sleep(self.backoff_for(attempt)).await;
+                }
+                Err(err) => {
+                    self.record_failure();
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<R: UserRepository> UserRepository for ResilientRepository<R> {
+    async fn create(&self, user: User) -> Result<User> {
+        self.call(|| self.inner.create(user.clone())).await
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        self.call(|| self.inner.get(id)).await
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        self.call(|| self.inner.get_by_email(email)).await
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        self.call(|| self.inner.get_by_username(username)).await
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        self.call(|| self.inner.update(user.clone())).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.call(|| self.inner.delete(id)).await
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        self.call(|| self.inner.search(filter, pagination)).await
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        self.call(|| self.inner.count(filter)).await
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        self.call(|| self.inner.exists_by_email(email)).await
+    }
+
+    async fn stats(&self) -> Result<RepositoryStats> {
+        self.call(|| self.inner.stats()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    use crate::repository::memory::MemoryUserRepository;
+
+    fn sample_user(id: &str, email: &str) -> User {
+        User {
+            id: id.to_string(),
+            email: email.to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn passes_through_successful_calls() {
+        let repo = ResilientRepository::new(MemoryUserRepository::new());
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        assert_eq!(repo.get("1").await.unwrap().email, "a@example.com");
+        assert_eq!(repo.metrics().attempts(), 2);
+        assert_eq!(repo.metrics().retries(), 0);
+    }
+
+    /// A repository double that fails with a retryable connection error a
+    /// fixed number of times before succeeding.
+    struct FlakyRepository {
+        remaining_failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl UserRepository for FlakyRepository {
+        async fn create(&self, user: User) -> Result<User> {
+            if self.remaining_failures.fetch_sub(1, Ordering::SeqCst) > 0 {
+                return Err(Error::connection(CircuitOpenError));
+            }
+            Ok(user)
+        }
+
+        async fn get(&self, _id: &str) -> Result<User> {
+            Err(Error::NotFound)
+        }
+
+        async fn get_by_email(&self, _email: &str) -> Result<User> {
+            Err(Error::NotFound)
+        }
+
+        async fn get_by_username(&self, _username: &str) -> Result<User> {
+            Err(Error::NotFound)
+        }
+
+        async fn update(&self, user: User) -> Result<User> {
+            Ok(user)
+        }
+
+        async fn delete(&self, _id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn search(
+            &self,
+            _filter: &UserSearchFilter,
+            _pagination: Pagination,
+        ) -> Result<UserSearchPage> {
+            Err(Error::NotFound)
+        }
+
+        async fn count(&self, _filter: &UserSearchFilter) -> Result<u64> {
+            Err(Error::NotFound)
+        }
+
+        async fn exists_by_email(&self, _email: &str) -> Result<bool> {
+            Err(Error::NotFound)
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_retryable_failures_until_success() {
+        let repo = ResilientRepository::with_config(
+            FlakyRepository {
+                remaining_failures: AtomicU32::new(2),
+            },
+            ResilientRepositoryConfig {
+                base_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(5),
+                ..ResilientRepositoryConfig::default()
+            },
+        );
+        repo.create(sample_user("1", "a@example.com")).await.unwrap();
+        assert_eq!(repo.metrics().retries(), 2);
+    }
+
+    #[tokio::test]
+    async fn opens_circuit_after_repeated_failures() {
+        let repo = ResilientRepository::with_config(
+            FlakyRepository {
+                remaining_failures: AtomicU32::new(u32::MAX),
+            },
+            ResilientRepositoryConfig {
+                max_attempts: 1,
+                base_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(5),
+                failure_threshold: 2,
+                open_circuit_for: Duration::from_secs(60),
+            },
+        );
+        assert!(repo.create(sample_user("1", "a@example.com")).await.is_err());
+        assert!(repo.create(sample_user("1", "a@example.com")).await.is_err());
+        assert_eq!(repo.metrics().circuit_trips(), 1);
+
+        let err = repo
+            .create(sample_user("1", "a@example.com"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ConnectionError { .. }));
+        assert_eq!(repo.metrics().circuit_rejections(), 1);
+    }
+}
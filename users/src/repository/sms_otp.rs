@@ -0,0 +1,83 @@
+//! An in-memory [`SmsOtpRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{SmsOtp, SmsOtpRepository};
+
+#[derive(Default)]
+pub struct MemorySmsOtpRepository {
+    by_phone: Mutex<HashMap<String, SmsOtp>>,
+}
+
+impl MemorySmsOtpRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SmsOtpRepository for MemorySmsOtpRepository {
+    async fn store(&self, otp: SmsOtp) -> Result<()> {
+        let mut by_phone = self
+            .by_phone
+            .lock()
+            .map_err(|_| Error::Backend("sms otp repository lock poisoned".to_string()))?;
+        by_phone.insert(otp.phone.clone(), otp);
+        Ok(())
+    }
+
+    async fn take(&self, phone: &str) -> Result<Option<SmsOtp>> {
+        let mut by_phone = self
+            .by_phone
+            .lock()
+            .map_err(|_| Error::Backend("sms otp repository lock poisoned".to_string()))?;
+        Ok(by_phone.remove(phone))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn sample_otp(phone: &str) -> SmsOtp {
+        SmsOtp { phone: phone.to_string(), code_hash: "hash".to_string(), expires_at: Utc::now() }
+    }
+
+    #[tokio::test]
+    async fn taking_an_otp_for_an_unknown_phone_returns_none() {
+        let repo = MemorySmsOtpRepository::new();
+        assert!(repo.take("+15555550100").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_stored_otp_is_returned_once_and_then_gone() {
+        let repo = MemorySmsOtpRepository::new();
+        repo.store(sample_otp("+15555550100")).await.unwrap();
+
+        let otp = repo.take("+15555550100").await.unwrap();
+        assert_eq!(otp.unwrap().code_hash, "hash");
+        assert!(repo.take("+15555550100").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn storing_a_new_otp_replaces_the_outstanding_one() {
+        let repo = MemorySmsOtpRepository::new();
+        repo.store(sample_otp("+15555550100")).await.unwrap();
+        repo.store(SmsOtp {
+            code_hash: "second-hash".to_string(),
+            ..sample_otp("+15555550100")
+        })
+        .await
+        .unwrap();
+
+        let otp = repo.take("+15555550100").await.unwrap().unwrap();
+        assert_eq!(otp.code_hash, "second-hash");
+    }
+}
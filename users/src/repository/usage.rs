@@ -0,0 +1,81 @@
+//! An in-memory [`UsageRepository`], suitable for tests and local
+//! development.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use libsvc::repository::{Error, Result};
+
+use crate::domain::UsageRepository;
+
+#[derive(Default)]
+pub struct MemoryUsageRepository {
+    counts: Mutex<HashMap<(String, NaiveDate), u64>>,
+}
+
+impl MemoryUsageRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UsageRepository for MemoryUsageRepository {
+    async fn increment(&self, user_id: &str, day: NaiveDate) -> Result<u64> {
+        let mut counts = self
+            .counts
+            .lock()
+            .map_err(|_| Error::Backend("usage repository lock poisoned".to_string()))?;
+        let count = counts.entry((user_id.to_string(), day)).or_insert(0);
+        *count += 1;
+        Ok(*count)
+    }
+
+    async fn get(&self, user_id: &str, day: NaiveDate) -> Result<u64> {
+        let counts = self
+            .counts
+            .lock()
+            .map_err(|_| Error::Backend("usage repository lock poisoned".to_string()))?;
+        Ok(counts.get(&(user_id.to_string(), day)).copied().unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn increments_return_a_running_total_per_day() {
+        let repo = MemoryUsageRepository::new();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        assert_eq!(repo.increment("1", day).await.unwrap(), 1);
+        assert_eq!(repo.increment("1", day).await.unwrap(), 2);
+        assert_eq!(repo.get("1", day).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn counts_are_tracked_independently_per_user_and_day() {
+        let repo = MemoryUsageRepository::new();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let tomorrow = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        repo.increment("1", today).await.unwrap();
+        repo.increment("2", today).await.unwrap();
+        repo.increment("1", tomorrow).await.unwrap();
+
+        assert_eq!(repo.get("1", today).await.unwrap(), 1);
+        assert_eq!(repo.get("2", today).await.unwrap(), 1);
+        assert_eq!(repo.get("1", tomorrow).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn unrecorded_usage_reads_as_zero() {
+        let repo = MemoryUsageRepository::new();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(repo.get("nobody", day).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,53 @@
+//! Loads the [`libsvc::risk::RiskPolicy`] [`crate::http::handlers::authenticate`]
+//! evaluates logins against from a YAML file — this service's other
+//! configuration is JSON (see [`crate::seed`]), but a risk policy is hand
+//! edited by whoever owns it far more often than it's generated, and YAML's
+//! support for comments next to each rule is worth the inconsistency.
+
+use std::path::Path;
+
+use libsvc::repository::{Error, Result};
+use libsvc::risk::RiskPolicy;
+
+/// Reads `path` as a YAML-encoded [`RiskPolicy`].
+pub fn load(path: impl AsRef<Path>) -> Result<RiskPolicy> {
+    let data = std::fs::read_to_string(path).map_err(|e| Error::Backend(e.to_string()))?;
+    serde_yaml::from_str(&data).map_err(|e| Error::Backend(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_rules_in_the_order_they_appear_in_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("risk.yaml");
+        std::fs::write(
+            &path,
+            r#"
+rules:
+  - name: blocked-range
+    conditions:
+      - type: ip_range
+        ranges: ["203.0.113.0/24"]
+    action: deny
+  - name: new-device
+    conditions:
+      - type: new_device
+    action: require_mfa
+"#,
+        )
+        .unwrap();
+
+        let policy = load(&path).unwrap();
+        assert_eq!(policy.rules.len(), 2);
+        assert_eq!(policy.rules[0].name, "blocked-range");
+        assert_eq!(policy.rules[1].name, "new-device");
+    }
+
+    #[test]
+    fn a_missing_file_is_an_error() {
+        assert!(load("/nonexistent/risk.yaml").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Loads a fixed set of initial users into a repository on first boot, so
+//! a fresh environment (local dev, a new staging namespace) always starts
+//! with a usable admin account instead of requiring a manual signup step.
+//!
+//! Fixtures are a JSON array of [`SeedUser`] records. The repo's other
+//! configuration is JSON-only so far (see `serde_json` usage elsewhere);
+//! YAML support can be added alongside it later if a deployment actually
+//! wants it, rather than pulling in a YAML parser up front.
+
+use std::path::Path;
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use foundation::hash::hash_password;
+use foundation::id::Id;
+use libsvc::repository::{Error, Result};
+
+use crate::domain::{
+    Credentials, CredentialsRepository, User, UserKind, UserRepository, UserStatus,
+};
+
+/// The environment variable that, if set, overrides the password of the
+/// fixture whose username is `admin`. Lets the admin account's real
+/// password stay out of the fixture file in long-lived environments.
+pub const SEED_ADMIN_PASSWORD_ENV: &str = "SEED_ADMIN_PASSWORD";
+
+/// A single user record to seed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeedUser {
+    pub email: String,
+    pub username: Option<String>,
+    pub password: String,
+}
+
+/// Reads `path` as a JSON array of [`SeedUser`] records.
+pub fn load_fixtures(path: impl AsRef<Path>) -> Result<Vec<SeedUser>> {
+    let data = std::fs::read_to_string(path).map_err(|e| Error::Backend(e.to_string()))?;
+    serde_json::from_str(&data).map_err(|e| Error::Backend(e.to_string()))
+}
+
+/// Creates any `fixtures` not already present in `repository`, matched by
+/// email, so running this against an already-seeded environment is a
+/// no-op. The `admin` fixture's password is overridden from
+/// [`SEED_ADMIN_PASSWORD_ENV`] when that variable is set.
+pub async fn seed(
+    repository: &dyn UserRepository,
+    credentials: &dyn CredentialsRepository,
+    fixtures: Vec<SeedUser>,
+) -> Result<()> {
+    for fixture in fixtures {
+        if repository.exists_by_email(&fixture.email).await? {
+            continue;
+        }
+
+        let password = if fixture.username.as_deref() == Some("admin") {
+            std::env::var(SEED_ADMIN_PASSWORD_ENV).unwrap_or(fixture.password)
+        } else {
+            fixture.password
+        };
+        let password_hash = hash_password(&password).map_err(|e| Error::Backend(e.to_string()))?;
+
+        let user = repository
+            .create(User {
+                id: Id::new().to_string(),
+                email: fixture.email,
+                username: fixture.username,
+                created_at: Utc::now(),
+                pending_email: None,
+                avatar_url: None,
+                status: UserStatus::Active,
+                legal_hold: false,
+                kind: UserKind::Human,
+                organization_id: None,
+                custom_attributes: Default::default(),
+                tags: Default::default(),
+            })
+            .await?;
+        credentials
+            .create(Credentials {
+                user_id: user.id,
+                password_hash,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::credentials::MemoryCredentialsRepository;
+    use crate::repository::memory::MemoryUserRepository;
+
+    fn fixture(email: &str, username: &str) -> SeedUser {
+        SeedUser {
+            email: email.to_string(),
+            username: Some(username.to_string()),
+            password: "password123".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn seeds_users_that_do_not_already_exist() {
+        let repo = MemoryUserRepository::new();
+        let credentials = MemoryCredentialsRepository::new();
+        seed(&repo, &credentials, vec![fixture("admin@example.com", "admin")])
+            .await
+            .unwrap();
+        assert!(repo.get_by_email("admin@example.com").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reseeding_is_idempotent() {
+        let repo = MemoryUserRepository::new();
+        let credentials = MemoryCredentialsRepository::new();
+        let fixtures = vec![fixture("admin@example.com", "admin")];
+        seed(&repo, &credentials, fixtures.clone()).await.unwrap();
+        seed(&repo, &credentials, fixtures).await.unwrap();
+        assert_eq!(repo.get_by_username("admin").await.unwrap().email, "admin@example.com");
+    }
+
+    #[tokio::test]
+    async fn admin_password_is_overridden_from_env() {
+        std::env::set_var(SEED_ADMIN_PASSWORD_ENV, "overridden-password");
+        let repo = MemoryUserRepository::new();
+        let credentials = MemoryCredentialsRepository::new();
+        seed(&repo, &credentials, vec![fixture("admin@example.com", "admin")])
+            .await
+            .unwrap();
+        std::env::remove_var(SEED_ADMIN_PASSWORD_ENV);
+
+        let admin = repo.get_by_email("admin@example.com").await.unwrap();
+        let admin_credentials = credentials.get(&admin.id).await.unwrap();
+        assert!(foundation::hash::verify_password(
+            "overridden-password",
+            &admin_credentials.password_hash
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,6 @@
+//! Blob storage backends beyond the filesystem one in
+//! [`foundation::blob`], for deployments that need durability across
+//! instances rather than a single local disk.
+
+#[cfg(feature = "s3")]
+pub mod s3;
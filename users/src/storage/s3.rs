@@ -0,0 +1,85 @@
+//! An S3-backed [`BlobStorage`], available behind the `s3` feature.
+//!
+//! Each blob is stored as a single object keyed by the caller's `key`,
+//! with the content type set on the object itself via
+//! [`PutObject`](aws_sdk_s3::operation::put_object)'s `content_type`
+//! field, so a read only needs one request rather than a side lookup.
+
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+
+use foundation::blob::{BlobError, BlobStorage, Result};
+
+/// Configuration for [`S3BlobStorage`].
+#[derive(Debug, Clone)]
+pub struct S3BlobStorageConfig {
+    pub bucket: String,
+}
+
+/// A [`BlobStorage`] backed by a single S3 bucket.
+pub struct S3BlobStorage {
+    client: Client,
+    bucket: String,
+}
+
+impl S3BlobStorage {
+    pub fn new(client: Client, config: S3BlobStorageConfig) -> Self {
+        Self {
+            client,
+            bucket: config.bucket,
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStorage for S3BlobStorage {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .map_err(|e| BlobError::Backend(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<(String, Vec<u8>)> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(err) if err.is_no_such_key() => BlobError::NotFound(key.to_string()),
+                _ => BlobError::Backend(e.to_string()),
+            })?;
+        let content_type = output
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let data = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| BlobError::Backend(e.to_string()))?
+            .to_vec();
+        Ok((content_type, data))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| BlobError::Backend(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+//! Hand-rolled test doubles for [`UserRepository`] and [`UserLogic`], so
+//! downstream code (HTTP handlers, other services embedding this crate)
+//! can be unit-tested against canned responses instead of wiring a real
+//! repository or logic implementation. Gated behind the `test-util`
+//! feature so it never ships as part of a normal build.
+
+use async_trait::async_trait;
+use libsvc::repository::{Error, Result};
+use serde_json::{Map, Value};
+
+use crate::domain::{
+    ActivityPage, DevicePlatform, DeviceToken, Notification, NotificationPage, Pagination,
+    RepositoryStats, User, UserDataExport, UserLogic, UserPreferences, UserRepository,
+    UserSearchFilter, UserSearchPage, UserStatus,
+};
+
+fn unconfigured<T>(method: &str) -> Result<T> {
+    Err(Error::Backend(format!("mock method `{method}` not configured")))
+}
+
+type UserFn = Box<dyn Fn(User) -> Result<User> + Send + Sync>;
+type StrFn<T> = Box<dyn Fn(&str) -> Result<T> + Send + Sync>;
+type StrStrFn<T> = Box<dyn Fn(&str, &str) -> Result<T> + Send + Sync>;
+type LogicUpdateFn = Box<
+    dyn Fn(&str, Option<String>, Option<String>, Option<String>) -> Result<User> + Send + Sync,
+>;
+type PreferencesFn = Box<dyn Fn(UserPreferences) -> Result<UserPreferences> + Send + Sync>;
+type AvatarFn = Box<dyn Fn(&str, Option<String>) -> Result<User> + Send + Sync>;
+type SearchFn = Box<dyn Fn(&UserSearchFilter, Pagination) -> Result<UserSearchPage> + Send + Sync>;
+type CountFn = Box<dyn Fn(&UserSearchFilter) -> Result<u64> + Send + Sync>;
+type ExistsByEmailFn = Box<dyn Fn(&str) -> Result<bool> + Send + Sync>;
+type RepositoryStatsFn = Box<dyn Fn() -> Result<RepositoryStats> + Send + Sync>;
+type ActivityFn = Box<dyn Fn(&str, Pagination) -> Result<ActivityPage> + Send + Sync>;
+type SetStatusFn = Box<dyn Fn(&str, UserStatus) -> Result<User> + Send + Sync>;
+type SetLegalHoldFn = Box<dyn Fn(&str, bool) -> Result<User> + Send + Sync>;
+type VerifyPasswordFn = Box<dyn Fn(&str, &str) -> Result<bool> + Send + Sync>;
+type SetOrganizationFn = Box<dyn Fn(&str, Option<String>) -> Result<User> + Send + Sync>;
+type GetCustomAttributesSchemaFn = Box<dyn Fn() -> Option<Value> + Send + Sync>;
+type SetCustomAttributesSchemaFn = Box<dyn Fn(Option<Value>) -> Result<()> + Send + Sync>;
+type SetCustomAttributesFn = Box<dyn Fn(&str, Map<String, Value>) -> Result<User> + Send + Sync>;
+type AddTagFn = Box<dyn Fn(&str, String) -> Result<User> + Send + Sync>;
+type RemoveTagFn = Box<dyn Fn(&str, &str) -> Result<User> + Send + Sync>;
+type NotificationsFn = Box<dyn Fn(&str, Pagination) -> Result<NotificationPage> + Send + Sync>;
+type MarkNotificationReadFn = Box<dyn Fn(&str, &str) -> Result<Notification> + Send + Sync>;
+type MarkAllNotificationsReadFn = Box<dyn Fn(&str) -> Result<u64> + Send + Sync>;
+type RegisterDeviceFn =
+    Box<dyn Fn(&str, DevicePlatform, String) -> Result<DeviceToken> + Send + Sync>;
+type UnregisterDeviceFn = Box<dyn Fn(&str, &str) -> Result<()> + Send + Sync>;
+type GenerateBackupCodesFn = Box<dyn Fn(&str) -> Result<Vec<String>> + Send + Sync>;
+type MfaEnrolledFn = Box<dyn Fn(&str) -> Result<bool> + Send + Sync>;
+
+/// A [`UserRepository`] whose behavior is whatever closure was assigned
+/// to each field. Unassigned methods return a [`Error::Backend`]
+/// complaining which one was called, so a test fails loudly instead of
+/// silently exercising unintended behavior.
+pub struct MockUserRepository {
+    pub create: UserFn,
+    pub get: StrFn<User>,
+    pub get_by_email: StrFn<User>,
+    pub get_by_username: StrFn<User>,
+    pub update: UserFn,
+    pub delete: StrFn<()>,
+    pub search: SearchFn,
+    pub count: CountFn,
+    pub exists_by_email: ExistsByEmailFn,
+}
+
+impl Default for MockUserRepository {
+    fn default() -> Self {
+        Self {
+            create: Box::new(|_| unconfigured("create")),
+            get: Box::new(|_| unconfigured("get")),
+            get_by_email: Box::new(|_| unconfigured("get_by_email")),
+            get_by_username: Box::new(|_| unconfigured("get_by_username")),
+            update: Box::new(|_| unconfigured("update")),
+            delete: Box::new(|_| unconfigured("delete")),
+            search: Box::new(|_, _| unconfigured("search")),
+            count: Box::new(|_| unconfigured("count")),
+            exists_by_email: Box::new(|_| unconfigured("exists_by_email")),
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for MockUserRepository {
+    async fn create(&self, user: User) -> Result<User> {
+        (self.create)(user)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        (self.get)(id)
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User> {
+        (self.get_by_email)(email)
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<User> {
+        (self.get_by_username)(username)
+    }
+
+    async fn update(&self, user: User) -> Result<User> {
+        (self.update)(user)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        (self.delete)(id)
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        (self.search)(filter, pagination)
+    }
+
+    async fn count(&self, filter: &UserSearchFilter) -> Result<u64> {
+        (self.count)(filter)
+    }
+
+    async fn exists_by_email(&self, email: &str) -> Result<bool> {
+        (self.exists_by_email)(email)
+    }
+}
+
+/// A [`UserLogic`] whose behavior is whatever closure was assigned to
+/// each field. See [`MockUserRepository`] for the rationale.
+pub struct MockUserLogic {
+    pub register: StrStrFn<User>,
+    pub authenticate: StrStrFn<User>,
+    pub get: StrFn<User>,
+    pub find_by_email: StrFn<User>,
+    pub update: LogicUpdateFn,
+    pub username_available: StrFn<bool>,
+    pub confirm_email_change: StrStrFn<User>,
+    pub delete: StrFn<()>,
+    pub export_data: StrFn<UserDataExport>,
+    pub erase: StrFn<()>,
+    pub get_preferences: StrFn<UserPreferences>,
+    pub update_preferences: PreferencesFn,
+    pub update_avatar: AvatarFn,
+    pub search: SearchFn,
+    pub repository_stats: RepositoryStatsFn,
+    pub activity: ActivityFn,
+    pub set_status: SetStatusFn,
+    pub set_legal_hold: SetLegalHoldFn,
+    pub register_service_account: StrStrFn<User>,
+    pub authenticate_service_account: StrStrFn<User>,
+    pub verify_password: VerifyPasswordFn,
+    pub set_organization: SetOrganizationFn,
+    pub get_custom_attributes_schema: GetCustomAttributesSchemaFn,
+    pub set_custom_attributes_schema: SetCustomAttributesSchemaFn,
+    pub set_custom_attributes: SetCustomAttributesFn,
+    pub add_tag: AddTagFn,
+    pub remove_tag: RemoveTagFn,
+    pub notifications: NotificationsFn,
+    pub mark_notification_read: MarkNotificationReadFn,
+    pub mark_all_notifications_read: MarkAllNotificationsReadFn,
+    pub register_device: RegisterDeviceFn,
+    pub unregister_device: UnregisterDeviceFn,
+    pub request_sms_otp: StrFn<()>,
+    pub verify_sms_otp: StrStrFn<()>,
+    pub generate_backup_codes: GenerateBackupCodesFn,
+    pub consume_backup_code: StrStrFn<()>,
+    pub mfa_enrolled: MfaEnrolledFn,
+}
+
+impl Default for MockUserLogic {
+    fn default() -> Self {
+        Self {
+            register: Box::new(|_, _| unconfigured("register")),
+            authenticate: Box::new(|_, _| unconfigured("authenticate")),
+            get: Box::new(|_| unconfigured("get")),
+            find_by_email: Box::new(|_| unconfigured("find_by_email")),
+            update: Box::new(|_, _, _, _| unconfigured("update")),
+            username_available: Box::new(|_| unconfigured("username_available")),
+            confirm_email_change: Box::new(|_, _| unconfigured("confirm_email_change")),
+            delete: Box::new(|_| unconfigured("delete")),
+            export_data: Box::new(|_| unconfigured("export_data")),
+            erase: Box::new(|_| unconfigured("erase")),
+            get_preferences: Box::new(|_| unconfigured("get_preferences")),
+            update_preferences: Box::new(|_| unconfigured("update_preferences")),
+            update_avatar: Box::new(|_, _| unconfigured("update_avatar")),
+            search: Box::new(|_, _| unconfigured("search")),
+            repository_stats: Box::new(|| unconfigured("repository_stats")),
+            activity: Box::new(|_, _| unconfigured("activity")),
+            set_status: Box::new(|_, _| unconfigured("set_status")),
+            set_legal_hold: Box::new(|_, _| unconfigured("set_legal_hold")),
+            register_service_account: Box::new(|_, _| unconfigured("register_service_account")),
+            authenticate_service_account: Box::new(|_, _| {
+                unconfigured("authenticate_service_account")
+            }),
+            verify_password: Box::new(|_, _| unconfigured("verify_password")),
+            set_organization: Box::new(|_, _| unconfigured("set_organization")),
+            get_custom_attributes_schema: Box::new(|| None),
+            set_custom_attributes_schema: Box::new(|_| {
+                unconfigured("set_custom_attributes_schema")
+            }),
+            set_custom_attributes: Box::new(|_, _| unconfigured("set_custom_attributes")),
+            add_tag: Box::new(|_, _| unconfigured("add_tag")),
+            remove_tag: Box::new(|_, _| unconfigured("remove_tag")),
+            notifications: Box::new(|_, _| unconfigured("notifications")),
+            mark_notification_read: Box::new(|_, _| unconfigured("mark_notification_read")),
+            mark_all_notifications_read: Box::new(|_| unconfigured("mark_all_notifications_read")),
+            register_device: Box::new(|_, _, _| unconfigured("register_device")),
+            unregister_device: Box::new(|_, _| unconfigured("unregister_device")),
+            request_sms_otp: Box::new(|_| unconfigured("request_sms_otp")),
+            verify_sms_otp: Box::new(|_, _| unconfigured("verify_sms_otp")),
+            generate_backup_codes: Box::new(|_| unconfigured("generate_backup_codes")),
+            consume_backup_code: Box::new(|_, _| unconfigured("consume_backup_code")),
+            mfa_enrolled: Box::new(|_| unconfigured("mfa_enrolled")),
+        }
+    }
+}
+
+#[async_trait]
+impl UserLogic for MockUserLogic {
+    async fn register(&self, email: &str, password: &str) -> Result<User> {
+        (self.register)(email, password)
+    }
+
+    async fn authenticate(&self, identifier: &str, password: &str) -> Result<User> {
+        (self.authenticate)(identifier, password)
+    }
+
+    async fn get(&self, id: &str) -> Result<User> {
+        (self.get)(id)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<User> {
+        (self.find_by_email)(email)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        email: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<User> {
+        (self.update)(id, email, username, password)
+    }
+
+    async fn username_available(&self, username: &str) -> Result<bool> {
+        (self.username_available)(username)
+    }
+
+    async fn confirm_email_change(&self, id: &str, token: &str) -> Result<User> {
+        (self.confirm_email_change)(id, token)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        (self.delete)(id)
+    }
+
+    async fn export_data(&self, id: &str) -> Result<UserDataExport> {
+        (self.export_data)(id)
+    }
+
+    async fn erase(&self, id: &str) -> Result<()> {
+        (self.erase)(id)
+    }
+
+    async fn get_preferences(&self, id: &str) -> Result<UserPreferences> {
+        (self.get_preferences)(id)
+    }
+
+    async fn update_preferences(&self, preferences: UserPreferences) -> Result<UserPreferences> {
+        (self.update_preferences)(preferences)
+    }
+
+    async fn update_avatar(&self, id: &str, avatar_url: Option<String>) -> Result<User> {
+        (self.update_avatar)(id, avatar_url)
+    }
+
+    async fn search(
+        &self,
+        filter: &UserSearchFilter,
+        pagination: Pagination,
+    ) -> Result<UserSearchPage> {
+        (self.search)(filter, pagination)
+    }
+
+    async fn repository_stats(&self) -> Result<RepositoryStats> {
+        (self.repository_stats)()
+    }
+
+    async fn activity(&self, id: &str, pagination: Pagination) -> Result<ActivityPage> {
+        (self.activity)(id, pagination)
+    }
+
+    async fn set_status(&self, id: &str, status: UserStatus) -> Result<User> {
+        (self.set_status)(id, status)
+    }
+
+    async fn set_legal_hold(&self, id: &str, hold: bool) -> Result<User> {
+        (self.set_legal_hold)(id, hold)
+    }
+
+    async fn register_service_account(&self, client_id: &str, api_key: &str) -> Result<User> {
+        (self.register_service_account)(client_id, api_key)
+    }
+
+    async fn authenticate_service_account(&self, client_id: &str, api_key: &str) -> Result<User> {
+        (self.authenticate_service_account)(client_id, api_key)
+    }
+
+    async fn verify_password(&self, id: &str, password: &str) -> Result<bool> {
+        (self.verify_password)(id, password)
+    }
+
+    async fn set_organization(&self, id: &str, organization_id: Option<String>) -> Result<User> {
+        (self.set_organization)(id, organization_id)
+    }
+
+    async fn get_custom_attributes_schema(&self) -> Option<Value> {
+        (self.get_custom_attributes_schema)()
+    }
+
+    async fn set_custom_attributes_schema(&self, schema: Option<Value>) -> Result<()> {
+        (self.set_custom_attributes_schema)(schema)
+    }
+
+    async fn set_custom_attributes(&self, id: &str, attributes: Map<String, Value>) -> Result<User> {
+        (self.set_custom_attributes)(id, attributes)
+    }
+
+    async fn add_tag(&self, id: &str, tag: String) -> Result<User> {
+        (self.add_tag)(id, tag)
+    }
+
+    async fn remove_tag(&self, id: &str, tag: &str) -> Result<User> {
+        (self.remove_tag)(id, tag)
+    }
+
+    async fn notifications(&self, id: &str, pagination: Pagination) -> Result<NotificationPage> {
+        (self.notifications)(id, pagination)
+    }
+
+    async fn mark_notification_read(&self, id: &str, notification_id: &str) -> Result<Notification> {
+        (self.mark_notification_read)(id, notification_id)
+    }
+
+    async fn mark_all_notifications_read(&self, id: &str) -> Result<u64> {
+        (self.mark_all_notifications_read)(id)
+    }
+
+    async fn register_device(
+        &self,
+        id: &str,
+        platform: DevicePlatform,
+        token: String,
+    ) -> Result<DeviceToken> {
+        (self.register_device)(id, platform, token)
+    }
+
+    async fn unregister_device(&self, id: &str, token: &str) -> Result<()> {
+        (self.unregister_device)(id, token)
+    }
+
+    async fn request_sms_otp(&self, id: &str) -> Result<()> {
+        (self.request_sms_otp)(id)
+    }
+
+    async fn verify_sms_otp(&self, id: &str, code: &str) -> Result<()> {
+        (self.verify_sms_otp)(id, code)
+    }
+
+    async fn generate_backup_codes(&self, id: &str) -> Result<Vec<String>> {
+        (self.generate_backup_codes)(id)
+    }
+
+    async fn consume_backup_code(&self, id: &str, code: &str) -> Result<()> {
+        (self.consume_backup_code)(id, code)
+    }
+
+    async fn mfa_enrolled(&self, id: &str) -> Result<bool> {
+        (self.mfa_enrolled)(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_user() -> User {
+        User {
+            id: "1".to_string(),
+            email: "a@example.com".to_string(),
+            username: None,
+            created_at: chrono::Utc::now(),
+            pending_email: None,
+            avatar_url: None,
+            status: crate::domain::UserStatus::Active,
+            legal_hold: false,
+            kind: crate::domain::UserKind::Human,
+            organization_id: None,
+            custom_attributes: Map::new(),
+            tags: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn configured_method_returns_the_canned_value() {
+        let repo = MockUserRepository {
+            get: Box::new(|_| Ok(sample_user())),
+            ..Default::default()
+        };
+        assert_eq!(repo.get("1").await.unwrap().email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn unconfigured_method_fails_loudly() {
+        let repo = MockUserRepository::default();
+        assert!(repo.get("1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mock_logic_delegates_to_its_configured_closures() {
+        let logic = MockUserLogic {
+            username_available: Box::new(|name| Ok(name == "free")),
+            ..Default::default()
+        };
+        assert!(logic.username_available("free").await.unwrap());
+        assert!(!logic.username_available("taken").await.unwrap());
+    }
+}
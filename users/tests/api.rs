@@ -0,0 +1,3871 @@
+//! End-to-end tests that hit the `users` service over HTTP, exercising
+//! the router, session authentication, and repository together rather
+//! than the domain logic in isolation.
+
+mod support;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::Engine;
+use foundation::id::Id;
+use foundation::hash::hash_password;
+use libsvc::oidc::{InMemoryOidcClientRegistry, OidcClient};
+use libsvc::risk::{RiskAction, RiskCondition, RiskPolicy, RiskRule};
+use libsvc::saml::{SamlAssertion, SamlAssertionVerifier, SamlError, SamlSpConfig};
+use libsvc::service_account::{InMemoryServiceAccountRegistry, ServiceAccount};
+use libsvc::session::{Role, SessionBuilder};
+use support::{
+    spawn_app, spawn_app_with_cert_binding, spawn_app_with_cookie_sessions, spawn_app_with_dpop,
+    spawn_app_with_mfa_required_roles, spawn_app_with_oidc_clients, spawn_app_with_oidc_issuer,
+    spawn_app_with_risk_policy, spawn_app_with_saml, spawn_app_with_search_index,
+    spawn_app_with_service_accounts, spawn_app_with_session_encryption,
+    spawn_app_with_session_idle_timeout, spawn_app_with_session_verification_cache,
+    spawn_app_with_session_verification_cache_and_no_leeway, spawn_app_with_usage_quota,
+};
+
+/// A [`SamlAssertionVerifier`] test double standing in for a real SAML
+/// library: `"valid-response"` decodes to a fixed assertion, anything
+/// else is rejected as an invalid signature.
+struct StubSamlVerifier;
+
+impl SamlAssertionVerifier for StubSamlVerifier {
+    fn verify(&self, raw_response_xml: &str) -> Result<SamlAssertion, SamlError> {
+        if raw_response_xml == "valid-response" {
+            Ok(SamlAssertion {
+                subject: "samluser@example.com".to_string(),
+                attributes: HashMap::new(),
+            })
+        } else {
+            Err(SamlError::SignatureInvalid)
+        }
+    }
+}
+
+fn test_saml_config() -> Arc<SamlSpConfig> {
+    Arc::new(SamlSpConfig {
+        entity_id: "https://users.example.com/v1/saml/metadata".to_string(),
+        acs_url: "https://users.example.com/v1/saml/acs".to_string(),
+        verifier: Arc::new(StubSamlVerifier),
+    })
+}
+
+#[tokio::test]
+async fn availability_reflects_registered_usernames() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/availability?username=nobody"))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["available"], true);
+}
+
+#[tokio::test]
+async fn authenticate_issues_a_session_for_valid_credentials() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("authme@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "authme@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+    assert!(body["expires_at"].as_u64().unwrap() > 0);
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+}
+
+#[tokio::test]
+async fn authenticate_response_carries_expiry_identity_and_a_refresh_token() {
+    let app = spawn_app().await;
+    let user = app
+        .user_logic
+        .register("richresponse@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "richresponse@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["token_type"], "session");
+    assert_eq!(body["user_id"], user.id);
+    assert_eq!(body["roles"], serde_json::json!(["User"]));
+    let refresh_token = body["refresh_token"].as_str().unwrap().to_string();
+
+    let refreshed = app
+        .client
+        .post(app.url("/v1/users/refresh"))
+        .json(&serde_json::json!({"refresh_token": refresh_token}))
+        .send()
+        .await
+        .unwrap();
+    assert!(refreshed.status().is_success());
+    let refreshed_body: serde_json::Value = refreshed.json().await.unwrap();
+    let new_session_id = refreshed_body["session_id"].as_str().unwrap().to_string();
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", new_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+}
+
+#[tokio::test]
+async fn a_refresh_token_cannot_be_used_as_an_access_session() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("refreshmisuse@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "refreshmisuse@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let refresh_token = body["refresh_token"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", refresh_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn changing_password_revokes_every_existing_session() {
+    let app = spawn_app().await;
+    let (user, session_id) = app
+        .register_and_sign_in("changepw@example.com", "password123")
+        .await;
+    let other_session_id = {
+        let signing_key = app.session_key.active().unwrap();
+        let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 3600)
+            .with_issuer("users")
+            .with_audience("web")
+            .finish(&signing_key);
+        let id = session.id.clone();
+        app.sessions.insert(session).unwrap();
+        id
+    };
+
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/password"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"current_password": "password123", "new_password": "newpassword456"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    for id in [session_id, other_session_id] {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me/data-export"))
+            .header("x-session-id", id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    let reauthenticated = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "changepw@example.com", "password": "newpassword456"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(reauthenticated.status().is_success());
+}
+
+#[tokio::test]
+async fn changing_password_requires_the_current_one() {
+    let app = spawn_app().await;
+    let (_, session_id) = app
+        .register_and_sign_in("changepwwrong@example.com", "password123")
+        .await;
+
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/password"))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({"current_password": "wrong", "new_password": "newpassword456"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn logout_all_revokes_every_session_for_the_caller() {
+    let app = spawn_app().await;
+    let (user, session_id) = app
+        .register_and_sign_in("logoutall@example.com", "password123")
+        .await;
+    let other_session_id = {
+        let signing_key = app.session_key.active().unwrap();
+        let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 3600)
+            .with_issuer("users")
+            .with_audience("web")
+            .finish(&signing_key);
+        let id = session.id.clone();
+        app.sessions.insert(session).unwrap();
+        id
+    };
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/me/logout-all"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    for id in [session_id, other_session_id] {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me/data-export"))
+            .header("x-session-id", id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn authenticate_rejects_wrong_password() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("authme2@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "authme2@example.com", "password": "wrong"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn register_creates_a_user_that_can_then_authenticate() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/register"))
+        .json(&serde_json::json!({"email": "newuser@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["email"], "newuser@example.com");
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "newuser@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn magic_link_request_always_returns_accepted() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("magiclinkuser@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/magic-link"))
+        .json(&serde_json::json!({"email": "magiclinkuser@example.com"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/magic-link"))
+        .json(&serde_json::json!({"email": "nosuchuser@example.com"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn magic_link_token_exchanges_for_a_working_single_use_session() {
+    let app = spawn_app().await;
+    let user = app
+        .user_logic
+        .register("magiclinkverify@example.com", "password123")
+        .await
+        .unwrap();
+
+    let token = {
+        let signing_key = app.session_key.active().unwrap();
+        let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 900)
+            .with_issuer("users")
+            .with_audience("web")
+            .as_magic_link()
+            .finish(&signing_key);
+        let token = session.id.clone();
+        app.sessions.insert(session).unwrap();
+        token
+    };
+
+    let response = app
+        .client
+        .get(app.url(&format!("/v1/users/magic-link/verify?token={token}")))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["user_id"], user.id);
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+
+    let response = app
+        .client
+        .get(app.url(&format!("/v1/users/magic-link/verify?token={token}")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn a_magic_link_token_cannot_be_used_as_an_access_session() {
+    let app = spawn_app().await;
+    let user = app
+        .user_logic
+        .register("magiclinkmisuse@example.com", "password123")
+        .await
+        .unwrap();
+
+    let token = {
+        let signing_key = app.session_key.active().unwrap();
+        let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 900)
+            .with_issuer("users")
+            .with_audience("web")
+            .as_magic_link()
+            .finish(&signing_key);
+        let token = session.id.clone();
+        app.sessions.insert(session).unwrap();
+        token
+    };
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn saml_endpoints_404_when_sso_is_not_configured() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/v1/saml/metadata")).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let response = app
+        .client
+        .post(app.url("/v1/saml/acs"))
+        .form(&[("SAMLResponse", "anything")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn saml_metadata_advertises_the_configured_entity_id_and_acs_url() {
+    let app = spawn_app_with_saml(Some(test_saml_config())).await;
+
+    let response = app.client.get(app.url("/v1/saml/metadata")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body = response.text().await.unwrap();
+    assert!(body.contains("https://users.example.com/v1/saml/metadata"));
+    assert!(body.contains("https://users.example.com/v1/saml/acs"));
+}
+
+#[tokio::test]
+async fn saml_acs_maps_a_valid_assertion_to_a_local_user_and_issues_a_session() {
+    let app = spawn_app_with_saml(Some(test_saml_config())).await;
+    app.user_logic
+        .register("samluser@example.com", "password123")
+        .await
+        .unwrap();
+
+    let encoded = base64::engine::general_purpose::STANDARD
+        .encode("valid-response");
+    let response = app
+        .client
+        .post(app.url("/v1/saml/acs"))
+        .form(&[("SAMLResponse", encoded.as_str())])
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+}
+
+#[tokio::test]
+async fn saml_acs_rejects_a_response_that_fails_verification() {
+    let app = spawn_app_with_saml(Some(test_saml_config())).await;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode("forged-response");
+    let response = app
+        .client
+        .post(app.url("/v1/saml/acs"))
+        .form(&[("SAMLResponse", encoded.as_str())])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+fn test_service_account_registry() -> Arc<InMemoryServiceAccountRegistry> {
+    Arc::new(InMemoryServiceAccountRegistry::new(vec![ServiceAccount {
+        client_id: "billing-service".to_string(),
+        key_hash: hash_password("s3cret-api-key").unwrap(),
+        allowed_roles: vec![Role::User],
+    }]))
+}
+
+#[tokio::test]
+async fn token_exchange_is_disabled_without_registered_service_accounts() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/service-tokens/exchange"))
+        .json(&serde_json::json!({
+            "client_id": "billing-service",
+            "client_secret": "s3cret-api-key",
+            "subject": "whoever",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn token_exchange_issues_a_scoped_session_acting_as_the_subject() {
+    let app = spawn_app_with_service_accounts(test_service_account_registry()).await;
+    let user = app
+        .user_logic
+        .register("delegate-target@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/service-tokens/exchange"))
+        .json(&serde_json::json!({
+            "client_id": "billing-service",
+            "client_secret": "s3cret-api-key",
+            "subject": user.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["user_id"], user.id);
+    assert_eq!(body["roles"], serde_json::json!(["User"]));
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+}
+
+#[tokio::test]
+async fn token_exchange_rejects_a_wrong_client_secret() {
+    let app = spawn_app_with_service_accounts(test_service_account_registry()).await;
+    let user = app
+        .user_logic
+        .register("delegate-target2@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/service-tokens/exchange"))
+        .json(&serde_json::json!({
+            "client_id": "billing-service",
+            "client_secret": "wrong-key",
+            "subject": user.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn token_exchange_rejects_roles_outside_the_service_accounts_allowance() {
+    let app = spawn_app_with_service_accounts(test_service_account_registry()).await;
+    let user = app
+        .user_logic
+        .register("delegate-target3@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/service-tokens/exchange"))
+        .json(&serde_json::json!({
+            "client_id": "billing-service",
+            "client_secret": "s3cret-api-key",
+            "subject": user.id,
+            "requested_roles": ["Admin"],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+/// Registers a [`crate::domain::UserKind::Service`] account with
+/// `client_id`/`api_key`, for `/v1/oauth/token` tests, the same way
+/// [`admins_can_register_a_service_account_that_authenticates_by_api_key`]
+/// does for the existing `/v1/users/service-accounts/authenticate`.
+async fn register_test_service_account(app: &support::TestApp, client_id: &str, api_key: &str) {
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("oauthadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+    let response = app
+        .client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({"client_id": client_id, "api_key": api_key}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn oauth_token_issues_a_session_usable_by_ordinary_session_middleware() {
+    let app = spawn_app().await;
+    register_test_service_account(&app, "billing-worker", "super-secret-key").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/oauth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "client_credentials",
+            "client_id": "billing-worker",
+            "client_secret": "super-secret-key",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["scope"], "user");
+    let access_token = body["access_token"].as_str().unwrap().to_string();
+
+    let me = app.client.get(app.url("/v1/users/me")).header("x-session-id", &access_token).send().await.unwrap();
+    assert!(me.status().is_success());
+}
+
+#[tokio::test]
+async fn oauth_token_rejects_an_unsupported_grant_type() {
+    let app = spawn_app().await;
+    register_test_service_account(&app, "billing-worker2", "super-secret-key").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/oauth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "password",
+            "client_id": "billing-worker2",
+            "client_secret": "super-secret-key",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "unsupported_grant_type");
+}
+
+#[tokio::test]
+async fn oauth_token_rejects_a_wrong_client_secret() {
+    let app = spawn_app().await;
+    register_test_service_account(&app, "billing-worker3", "super-secret-key").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/oauth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "client_credentials",
+            "client_id": "billing-worker3",
+            "client_secret": "wrong-key",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_client");
+}
+
+#[tokio::test]
+async fn oauth_token_rejects_an_unrecognized_scope_value() {
+    let app = spawn_app().await;
+    register_test_service_account(&app, "billing-worker4", "super-secret-key").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/oauth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "client_credentials",
+            "client_id": "billing-worker4",
+            "client_secret": "super-secret-key",
+            "scope": "admin",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_scope");
+}
+
+#[tokio::test]
+async fn get_me_returns_the_full_profile_by_default() {
+    let app = spawn_app().await;
+    let (user, session_id) = app.register_and_sign_in("fields@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["id"], user.id);
+    assert_eq!(body["email"], "fields@example.com");
+    assert!(body.get("password_hash").is_none());
+}
+
+#[tokio::test]
+async fn get_me_renders_created_at_in_the_users_preferred_timezone() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("tz@example.com", "password123").await;
+
+    app.client
+        .put(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id.clone())
+        .json(&serde_json::json!({
+            "notifications_enabled": true,
+            "locale": "en-US",
+            "timezone": "America/New_York",
+            "theme": "system",
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["created_at_local"]["timezone"], "America/New_York");
+    assert_eq!(body["created_at_local"]["utc"], body["created_at"]);
+}
+
+#[tokio::test]
+async fn get_me_with_fields_query_returns_only_the_requested_keys() {
+    let app = spawn_app().await;
+    let (user, session_id) = app.register_and_sign_in("sparse@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me?fields=id,email"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body.as_object().unwrap().len(), 2);
+    assert_eq!(body["id"], user.id);
+    assert_eq!(body["email"], "sparse@example.com");
+}
+
+#[tokio::test]
+async fn usage_reports_calls_made_so_far_today() {
+    let app = spawn_app().await;
+    let (_user, session_id) = app.register_and_sign_in("usage@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/usage"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    // The call to fetch usage is itself counted.
+    assert_eq!(body["calls_today"], 1);
+    assert!(body["quota_per_day"].is_null());
+}
+
+#[tokio::test]
+async fn usage_is_metered_per_user_and_returns_429_once_the_quota_is_exhausted() {
+    let app = spawn_app_with_usage_quota(2).await;
+    let (_user, session_id) = app.register_and_sign_in("quota@example.com", "password123").await;
+
+    for _ in 0..2 {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me/usage"))
+            .header("x-session-id", &session_id)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+    }
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/usage"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn repeated_failed_logins_require_a_captcha_response() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("captchagate@example.com", "password123")
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        let response = app
+            .client
+            .post(app.url("/v1/users/authenticate"))
+            .json(&serde_json::json!({"login": "captchagate@example.com", "password": "wrong"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "captchagate@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({
+            "login": "captchagate@example.com",
+            "password": "password123",
+            "captcha_response": "solved",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn data_export_requires_a_valid_session() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn a_session_header_with_non_ascii_bytes_is_rejected_not_panicked_on() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", reqwest::header::HeaderValue::from_bytes(&[0xff, 0xfe]).unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn data_export_returns_the_authenticated_users_profile() {
+    let app = spawn_app().await;
+    let (user, session_id) = app.register_and_sign_in("export@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["profile"]["id"], user.id);
+    let sessions = body["sessions"].as_array().unwrap();
+    assert!(
+        sessions.iter().any(|s| s["id"] == session_id),
+        "expected the active session {session_id} in the export, got {sessions:?}"
+    );
+}
+
+#[tokio::test]
+async fn activity_merges_registration_login_and_session_events_newest_first() {
+    let app = spawn_app().await;
+
+    let register_response = app
+        .client
+        .post(app.url("/v1/users/register"))
+        .json(&serde_json::json!({
+            "email": "activity@example.com",
+            "password": "password123",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(register_response.status().is_success());
+
+    let auth_response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({
+            "login": "activity@example.com",
+            "password": "password123",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(auth_response.status().is_success());
+    let auth_body: serde_json::Value = auth_response.json().await.unwrap();
+    let session_id = auth_body["session_id"].as_str().unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/activity"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let actions: Vec<&str> =
+        body["events"].as_array().unwrap().iter().map(|e| e["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, vec!["session_created", "logged_in", "registered"]);
+    assert_eq!(body["has_more"], false);
+}
+
+#[tokio::test]
+async fn activity_is_paginated() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("activitypaged@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/activity?limit=1"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["events"].as_array().unwrap().len(), 1);
+    assert_eq!(body["has_more"], false);
+}
+
+#[tokio::test]
+async fn erase_me_deletes_the_account_and_revokes_the_session() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("erase@example.com", "password123").await;
+
+    let response = app
+        .client
+        .delete(app.url("/v1/users/me"))
+        .header("x-session-id", session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn preferences_round_trip_through_the_api() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("prefs@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["theme"], "system");
+
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id.clone())
+        .json(&serde_json::json!({
+            "notifications_enabled": false,
+            "locale": "fr-FR",
+            "timezone": "America/New_York",
+            "theme": "dark",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["theme"], "dark");
+    assert_eq!(body["locale"], "fr-FR");
+    assert_eq!(body["notifications_enabled"], false);
+    assert_eq!(body["timezone"], "America/New_York");
+}
+
+#[tokio::test]
+async fn notification_categories_default_to_all_enabled() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("newprefs@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["notification_categories"]["security_enabled"], true);
+    assert_eq!(body["notification_categories"]["product_enabled"], true);
+    assert_eq!(body["notification_categories"]["digest_enabled"], true);
+}
+
+#[tokio::test]
+async fn notification_categories_can_be_set_and_are_returned_on_the_next_read() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("catprefs@example.com", "password123").await;
+
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id.clone())
+        .json(&serde_json::json!({
+            "notifications_enabled": true,
+            "locale": "en-US",
+            "timezone": "UTC",
+            "theme": "system",
+            "notification_categories": {
+                "security_enabled": true,
+                "product_enabled": false,
+                "digest_enabled": false,
+            },
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/preferences"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["notification_categories"]["security_enabled"], true);
+    assert_eq!(body["notification_categories"]["product_enabled"], false);
+    assert_eq!(body["notification_categories"]["digest_enabled"], false);
+}
+
+#[tokio::test]
+async fn notifications_inbox_is_empty_for_a_new_account() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("emptyinbox@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/notifications"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["notifications"].as_array().unwrap().len(), 0);
+    assert_eq!(body["has_more"], false);
+}
+
+#[tokio::test]
+async fn marking_a_nonexistent_notification_read_is_not_found() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("marknotfound@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/me/notifications/does-not-exist/read"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn marking_all_notifications_read_with_an_empty_inbox_succeeds() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("markallempty@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/me/notifications/read-all"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn registering_a_device_returns_it_and_unregistering_removes_it() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("devices@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/me/devices"))
+        .header("x-session-id", session_id.clone())
+        .json(&serde_json::json!({
+            "platform": "fcm",
+            "token": "device-token-1",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["platform"], "fcm");
+    assert_eq!(body["token"], "device-token-1");
+
+    let response = app
+        .client
+        .delete(app.url("/v1/users/me/devices/device-token-1"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn unregistering_a_device_that_was_never_registered_is_a_no_op() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("devicenoop@example.com", "password123").await;
+
+    let response = app
+        .client
+        .delete(app.url("/v1/users/me/devices/does-not-exist"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn avatar_upload_is_validated_and_served_back() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("avatar@example.com", "password123").await;
+
+    let oversized = reqwest::multipart::Form::new().part(
+        "avatar",
+        reqwest::multipart::Part::bytes(vec![0u8; 6 * 1024 * 1024]).mime_str("image/png").unwrap(),
+    );
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/avatar"))
+        .header("x-session-id", session_id.clone())
+        .multipart(oversized)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+    let wrong_type = reqwest::multipart::Form::new().part(
+        "avatar",
+        reqwest::multipart::Part::bytes(vec![1, 2, 3]).mime_str("text/plain").unwrap(),
+    );
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/avatar"))
+        .header("x-session-id", session_id.clone())
+        .multipart(wrong_type)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let form = reqwest::multipart::Form::new().part(
+        "avatar",
+        reqwest::multipart::Part::bytes(vec![1, 2, 3]).mime_str("image/png").unwrap(),
+    );
+    let response = app
+        .client
+        .put(app.url("/v1/users/me/avatar"))
+        .header("x-session-id", session_id)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let avatar_url = body["avatar_url"].as_str().unwrap().to_string();
+
+    let response = app.client.get(app.url(&avatar_url)).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "image/png"
+    );
+    assert_eq!(response.bytes().await.unwrap().to_vec(), vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn debug_endpoints_expose_config_and_build_info() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/debug/config")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["avatars_path"], "avatars");
+
+    let response = app.client.get(app.url("/debug/build")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(!body["git_sha"].as_str().unwrap().is_empty());
+}
+
+// The `tracing` filter handle is process-global (see
+// `libsvc::telemetry::init`), so these share a single test to avoid
+// racing against other tests' log-level changes when run in parallel.
+#[tokio::test]
+async fn log_level_can_be_read_changed_and_validated_at_runtime() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/debug/log-level")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let response = app
+        .client
+        .put(app.url("/debug/log-level"))
+        .json(&serde_json::json!({ "directives": "users=not_a_real_level" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let response = app
+        .client
+        .put(app.url("/debug/log-level"))
+        .json(&serde_json::json!({ "directives": "users=debug,warn" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["directives"], "users=debug,warn");
+
+    let response = app.client.get(app.url("/debug/log-level")).send().await.unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["directives"], "users=debug,warn");
+}
+
+#[tokio::test]
+async fn debug_ready_reports_repository_stats_and_feeds_metrics() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/debug/ready")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["backend"], "memory");
+
+    let response = app.client.get(app.url("/debug/metrics")).send().await.unwrap();
+    let body = response.text().await.unwrap();
+    assert!(body.contains("repository_user_count"));
+    assert!(body.contains("repository_ping_latency_seconds"));
+}
+
+#[tokio::test]
+async fn debug_env_and_threads_endpoints_respond() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/debug/env")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body.is_object());
+
+    let response = app.client.get(app.url("/debug/threads")).send().await.unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["worker_threads"].as_u64().unwrap() >= 1);
+}
+
+#[tokio::test]
+async fn responses_carry_a_request_id_header() {
+    let app = spawn_app().await;
+
+    let response = app.client.get(app.url("/v1/users/availability?username=nobody")).send().await.unwrap();
+    assert!(response.headers().contains_key("x-request-id"));
+}
+
+#[tokio::test]
+async fn sessions_issued_for_a_different_audience_are_rejected() {
+    let app = spawn_app().await;
+    let (user, _) = app.register_and_sign_in("wrongaudience@example.com", "password123").await;
+
+    let signing_key = app.session_key.active().unwrap();
+    let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 3600)
+        .with_issuer("users")
+        .with_audience("some-other-service")
+        .finish(&signing_key);
+    let session_id = session.id.clone();
+    app.sessions.insert(session).unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn repeated_requests_with_the_same_session_populate_the_verification_cache() {
+    let app = spawn_app_with_session_verification_cache().await;
+    let (_, session_id) = app.register_and_sign_in("cachedsession@example.com", "password123").await;
+
+    for _ in 0..3 {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me"))
+            .header("x-session-id", session_id.clone())
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+    }
+
+    let session = app.sessions.get(&session_id).unwrap().unwrap();
+    assert!(app
+        .session_verification_cache
+        .is_recently_verified(&session.id, session.signature()));
+}
+
+#[tokio::test]
+async fn a_cache_hit_still_rejects_a_session_that_expired_within_the_ttl_window() {
+    // The verification cache only remembers that a signature checked out;
+    // it must not let a session that's since expired keep riding on that
+    // cache hit.
+    let app = spawn_app_with_session_verification_cache_and_no_leeway().await;
+    let user = app
+        .user_logic
+        .register("expiringcachedsession@example.com", "password123")
+        .await
+        .unwrap();
+
+    let signing_key = app.session_key.active().unwrap();
+    let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 1)
+        .with_issuer("users")
+        .with_audience("web")
+        .finish(&signing_key);
+    let session_id = session.id.clone();
+    app.sessions.insert(session.clone()).unwrap();
+
+    let first = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    assert!(first.status().is_success());
+    assert!(app
+        .session_verification_cache
+        .is_recently_verified(&session.id, session.signature()));
+
+    tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+
+    let second = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(second.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_impersonate() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("victim@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("regular@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/impersonate", target.id)))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_rotate_the_session_key() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("notadmin@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/session-key/publish"))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({ "secret": "next-secret" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admins_can_rotate_the_session_key_without_invalidating_existing_sessions() {
+    let app = spawn_app().await;
+    let (_, existing_session_id) = app.register_and_sign_in("rotated@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("keyadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/session-key/cutover"))
+        .header("x-session-id", admin_session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/session-key/publish"))
+        .header("x-session-id", admin_session_id.clone())
+        .json(&serde_json::json!({ "secret": "next-secret" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", existing_session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/session-key/cutover"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", existing_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn non_admins_cannot_rotate_the_session_nonce() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("notadmin2@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/security/rotate-session-nonce"))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn rotating_the_session_nonce_invalidates_every_outstanding_session() {
+    let app = spawn_app().await;
+    let (_, first_session_id) = app.register_and_sign_in("leaked1@example.com", "password123").await;
+    let (_, second_session_id) = app.register_and_sign_in("leaked2@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("nonceadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/security/rotate-session-nonce"))
+        .header("x-session-id", admin_session_id.clone())
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["sessions_revoked"], 3);
+    assert_eq!(body["key_rotated"], false);
+
+    for session_id in [first_session_id, second_session_id, admin_session_id] {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me/data-export"))
+            .header("x-session-id", session_id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn non_admins_cannot_list_security_events() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("notadmin3@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/security/events"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn security_events_lists_failed_logins_newest_first_separately_from_the_audit_log() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("victim@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("secadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    app.client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({ "login": "victim@example.com", "password": "wrong-password" }))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/security/events"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events[0]["event"]["kind"], "InvalidCredentials");
+    assert_eq!(events[0]["event"]["identifier"], "victim@example.com");
+
+    // Served from its own bounded retention, not the general audit log.
+    let recent = app.security_events.recent(10);
+    assert!(recent
+        .iter()
+        .any(|recorded| recorded.event
+            == libsvc::security_signal::SecurityEvent::InvalidCredentials {
+                identifier: "victim@example.com".to_string()
+            }));
+}
+
+#[tokio::test]
+async fn rotating_the_session_nonce_can_also_rotate_the_signing_key() {
+    let app = spawn_app().await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("nonceandkeyadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/security/rotate-session-nonce"))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({ "rotate_key": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["key_rotated"], true);
+
+    // The admin's own session was revoked along with everyone else's, so
+    // a freshly issued session under the new key is what proves it's live.
+    let (_, new_session_id) = app.register_and_sign_in("postkeyrotation@example.com", "password123").await;
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", new_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn admins_can_impersonate_and_act_as_the_target_user() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("target@example.com", "password123").await;
+    let (admin, admin_session_id) = app
+        .register_and_sign_in_with_roles("support@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/impersonate", target.id)))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let impersonation_session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", impersonation_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["profile"]["id"], target.id);
+
+    let audit_events = app.user_logic.export_data(&target.id).await.unwrap().audit_events;
+    assert!(audit_events
+        .iter()
+        .any(|e| e.action == format!("admin {} acting as user {}", admin.id, target.id)));
+}
+
+#[tokio::test]
+async fn admins_can_deactivate_an_account_and_it_immediately_stops_authenticating() {
+    let app = spawn_app().await;
+    let (target, target_session_id) =
+        app.register_and_sign_in("deactivateme@example.com", "password123").await;
+    let (admin, admin_session_id) = app
+        .register_and_sign_in_with_roles("statusadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"status": "deactivated", "reason": "user_requested"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    // The session issued before deactivation is signed correctly but must
+    // now be rejected, since the account behind it is no longer active.
+    let response = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", &target_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({
+            "login": "deactivateme@example.com",
+            "password": "password123",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let audit_events = app.user_logic.export_data(&target.id).await.unwrap().audit_events;
+    assert!(audit_events
+        .iter()
+        .any(|e| e.action == "status_changed_to_deactivated_reason_user_requested"
+            && e.actor_id == admin.id));
+}
+
+#[tokio::test]
+async fn reactivating_an_account_lets_it_authenticate_again() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("reactivateme@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("statusadmin2@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    app.client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"status": "banned", "reason": "terms_of_service_violation"}))
+        .send()
+        .await
+        .unwrap();
+    app.client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"status": "active", "reason": "user_requested"}))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({
+            "login": "reactivateme@example.com",
+            "password": "password123",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn non_admins_cannot_change_a_users_status() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("victimstatus@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("regularstatus@example.com", "password123").await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({"status": "deactivated", "reason": "user_requested"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn a_legal_hold_blocks_erasure_until_lifted() {
+    let app = spawn_app().await;
+    let (target, target_session_id) =
+        app.register_and_sign_in("holdme@example.com", "password123").await;
+    let (admin, admin_session_id) = app
+        .register_and_sign_in_with_roles("holdadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/legal-hold", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"hold": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .delete(app.url("/v1/users/me"))
+        .header("x-session-id", &target_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let audit_events = app.user_logic.export_data(&target.id).await.unwrap().audit_events;
+    assert!(audit_events
+        .iter()
+        .any(|e| e.action == "legal_hold_placed" && e.actor_id == admin.id));
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/legal-hold", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"hold": false}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .delete(app.url("/v1/users/me"))
+        .header("x-session-id", &target_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_place_a_legal_hold() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("victimhold@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("regularhold@example.com", "password123").await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/legal-hold", target.id)))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({"hold": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admin_can_change_the_status_of_a_user_in_their_own_organization() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("orgmate@example.com", "password123").await;
+    app.user_logic
+        .set_organization(&target.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgadmin1@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic
+        .set_organization(&org_admin.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"status": "deactivated", "reason": "user_requested"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn an_org_admin_cannot_change_the_status_of_a_user_in_a_different_organization() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("otherorgmate@example.com", "password123").await;
+    app.user_logic
+        .set_organization(&target.id, Some("globex".to_string()))
+        .await
+        .unwrap();
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgadmin2@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic
+        .set_organization(&org_admin.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/status", target.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"status": "deactivated", "reason": "user_requested"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admin_with_no_organization_of_their_own_cannot_act_on_anyone() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("orphantarget@example.com", "password123").await;
+    app.user_logic
+        .set_organization(&target.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+    let (_, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgadmin3@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/legal-hold", target.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"hold": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admins_search_is_scoped_to_their_own_organization_regardless_of_what_they_ask_for() {
+    let app = spawn_app().await;
+    let (org_mate, _) = app.register_and_sign_in("searchorgmate@example.com", "password123").await;
+    app.user_logic
+        .set_organization(&org_mate.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+    let (other_org, _) = app.register_and_sign_in("searchotherorg@example.com", "password123").await;
+    app.user_logic
+        .set_organization(&other_org.id, Some("globex".to_string()))
+        .await
+        .unwrap();
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgadmin4@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic
+        .set_organization(&org_admin.id, Some("acme".to_string()))
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?organization_id=globex"))
+        .header("x-session-id", org_admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let emails: Vec<&str> = body["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["email"].as_str().unwrap())
+        .collect();
+    assert!(emails.contains(&"searchorgmate@example.com"));
+    assert!(!emails.contains(&"searchotherorg@example.com"));
+}
+
+#[tokio::test]
+async fn set_user_organization_is_restricted_to_full_admins() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("reassignme@example.com", "password123").await;
+    let (_, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgadmin5@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/organization", target.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"organization_id": "acme"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("orgreassignadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/organization", target.id)))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({"organization_id": "acme"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["organization_id"], "acme");
+}
+
+#[tokio::test]
+async fn admins_can_delete_a_user_and_the_audit_trail_survives() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("deleteme@example.com", "password123").await;
+    let (admin, admin_session_id) = app
+        .register_and_sign_in_with_roles("deleteadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({
+            "login": "deleteme@example.com",
+            "password": "password123",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let _ = admin;
+}
+
+#[tokio::test]
+async fn deleting_a_user_under_legal_hold_is_refused() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("heldfromdeletion@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("holddeleteadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+    app.client
+        .put(app.url(&format!("/v1/admin/users/{}/legal-hold", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"hold": true}))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_delete_a_user() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("victimdelete@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("regulardelete@example.com", "password123").await;
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}", target.id)))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admins_can_set_a_schema_and_compliant_custom_attributes_are_accepted() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("withattrs@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("attrschemaadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url("/v1/admin/custom-attributes/schema"))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({
+            "schema": {
+                "type": "object",
+                "properties": { "tier": { "type": "string" } },
+                "required": ["tier"],
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/custom-attributes", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"custom_attributes": {"tier": "gold"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["custom_attributes"]["tier"], "gold");
+}
+
+#[tokio::test]
+async fn custom_attributes_failing_the_configured_schema_are_rejected() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("badattrs@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("badattrschemaadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    app.client
+        .put(app.url("/v1/admin/custom-attributes/schema"))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({
+            "schema": {
+                "type": "object",
+                "properties": { "tier": { "type": "string" } },
+                "required": ["tier"],
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/custom-attributes", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"custom_attributes": {"tier": 7}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_set_the_custom_attributes_schema_or_a_users_attributes() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("attrvictim@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("attrregular@example.com", "password123").await;
+
+    let response = app
+        .client
+        .put(app.url("/v1/admin/custom-attributes/schema"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"schema": null}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/custom-attributes", target.id)))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"custom_attributes": {}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admin_can_set_custom_attributes_for_a_user_in_their_own_organization() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("orgattrs@example.com", "password123").await;
+    app.user_logic.set_organization(&target.id, Some("acme".to_string())).await.unwrap();
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgattrsadmin@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic.set_organization(&org_admin.id, Some("acme".to_string())).await.unwrap();
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/custom-attributes", target.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"custom_attributes": {"tier": "silver"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admins_can_add_and_remove_a_tag() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("taggable@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("tagadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/tags", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"tag": "beta"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tags"], serde_json::json!(["beta"]));
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}/tags/beta", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tags"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn adding_a_tag_twice_and_removing_an_absent_tag_are_both_no_ops() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("idempotenttag@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("idempotenttagadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    for _ in 0..2 {
+        let response = app
+            .client
+            .post(app.url(&format!("/v1/admin/users/{}/tags", target.id)))
+            .header("x-session-id", &admin_session_id)
+            .json(&serde_json::json!({"tag": "beta"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}/tags/not-present", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tags"], serde_json::json!(["beta"]));
+}
+
+#[tokio::test]
+async fn non_admins_cannot_add_or_remove_tags() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("tagvictim@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("tagregular@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/tags", target.id)))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"tag": "beta"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}/tags/beta", target.id)))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let missing_response = app
+        .client
+        .post(app.url("/v1/admin/users/does-not-exist/tags"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"tag": "beta"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing_response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admin_can_tag_a_user_in_their_own_organization_but_not_elsewhere() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("orgtag@example.com", "password123").await;
+    app.user_logic.set_organization(&target.id, Some("acme".to_string())).await.unwrap();
+    let (outsider, _) = app.register_and_sign_in("othertag@example.com", "password123").await;
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgtagadmin@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic.set_organization(&org_admin.id, Some("acme".to_string())).await.unwrap();
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/tags", target.id)))
+        .header("x-session-id", &org_admin_session_id)
+        .json(&serde_json::json!({"tag": "beta"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = app
+        .client
+        .post(app.url(&format!("/v1/admin/users/{}/tags", outsider.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"tag": "beta"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn searching_by_tag_matches_only_users_with_that_tag() {
+    let app = spawn_app().await;
+    let (tagged, _) = app.register_and_sign_in("hastag@example.com", "password123").await;
+    app.register_and_sign_in("notagsearch@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("tagsearchadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+    app.user_logic.add_tag(&tagged.id, "beta".to_string()).await.unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?tag=beta"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["id"], tagged.id);
+}
+
+#[tokio::test]
+async fn admins_can_set_and_clear_a_users_feature_flag_override() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("flaggable@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("flagadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .json(&serde_json::json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["overrides"]["beta-search"], true);
+    assert!(app.feature_flags.is_enabled("beta-search", &target.id).await.unwrap());
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", target.id)))
+        .header("x-session-id", &admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["overrides"].as_object().unwrap().is_empty());
+    assert!(!app.feature_flags.is_enabled("beta-search", &target.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn a_per_user_override_takes_precedence_over_the_service_wide_default() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("flagdefault@example.com", "password123").await;
+    app.feature_flags.set_default("beta-search", true).unwrap();
+
+    app.feature_flags.set_override(&target.id, "beta-search", false).await.unwrap();
+    assert!(!app.feature_flags.is_enabled("beta-search", &target.id).await.unwrap());
+    assert!(app.feature_flags.is_enabled("beta-search", "someone-else").await.unwrap());
+}
+
+#[tokio::test]
+async fn non_admins_cannot_set_or_clear_feature_flag_overrides() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("flagvictim@example.com", "password123").await;
+    let (_, session_id) = app.register_and_sign_in("flagregular@example.com", "password123").await;
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", target.id)))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .delete(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", target.id)))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_org_admin_can_override_a_flag_for_a_user_in_their_own_organization_but_not_elsewhere() {
+    let app = spawn_app().await;
+    let (target, _) = app.register_and_sign_in("orgflag@example.com", "password123").await;
+    app.user_logic.set_organization(&target.id, Some("acme".to_string())).await.unwrap();
+    let (outsider, _) = app.register_and_sign_in("otherflag@example.com", "password123").await;
+    let (org_admin, org_admin_session_id) = app
+        .register_and_sign_in_with_roles("orgflagadmin@example.com", "password123", vec![Role::OrgAdmin])
+        .await;
+    app.user_logic.set_organization(&org_admin.id, Some("acme".to_string())).await.unwrap();
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", target.id)))
+        .header("x-session-id", &org_admin_session_id)
+        .json(&serde_json::json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = app
+        .client
+        .put(app.url(&format!("/v1/admin/users/{}/feature-flags/beta-search", outsider.id)))
+        .header("x-session-id", org_admin_session_id)
+        .json(&serde_json::json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn a_cert_bound_session_is_rejected_over_a_different_certificate() {
+    let app = spawn_app_with_cert_binding().await;
+    app.user_logic
+        .register("certbound@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .header("x-client-cert-thumbprint", "aa:bb:cc")
+        .json(&serde_json::json!({"login": "certbound@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let matching = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .header("x-client-cert-thumbprint", "aa:bb:cc")
+        .send()
+        .await
+        .unwrap();
+    assert!(matching.status().is_success());
+
+    let mismatched = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .header("x-client-cert-thumbprint", "dd:ee:ff")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(mismatched.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let missing = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+/// Pulls `name`'s value out of a response's `set-cookie` headers, up to
+/// the first `;`, the way a browser would before sending it back.
+fn cookie_value(response: &reqwest::Response, name: &str) -> String {
+    response
+        .headers()
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .find_map(|value| {
+            let raw = value.to_str().ok()?;
+            let (cookie_name, rest) = raw.split_once('=')?;
+            (cookie_name == name).then(|| rest.split(';').next().unwrap_or_default().to_string())
+        })
+        .unwrap_or_else(|| panic!("no {name} cookie in the response"))
+}
+
+#[tokio::test]
+async fn cookie_sessions_are_issued_and_require_a_matching_csrf_token() {
+    let app = spawn_app_with_cookie_sessions().await;
+    app.user_logic
+        .register("cookiesession@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "cookiesession@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let session_id = cookie_value(&response, "session_id");
+    let csrf_token = cookie_value(&response, "csrf_token");
+    let cookie_header = format!("session_id={session_id}; csrf_token={csrf_token}");
+
+    // The session cookie alone authenticates a forged cross-origin
+    // request just fine, but without the matching header the CSRF layer
+    // rejects it before the mutating handler ever runs.
+    let forged = app
+        .client
+        .post(app.url("/v1/users/me/logout-all"))
+        .header("cookie", &cookie_header)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forged.status(), reqwest::StatusCode::FORBIDDEN);
+
+    // A first-party caller that read the CSRF cookie and echoed it back
+    // in the header gets through.
+    let legitimate = app
+        .client
+        .post(app.url("/v1/users/me/logout-all"))
+        .header("cookie", &cookie_header)
+        .header("x-csrf-token", &csrf_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(legitimate.status(), reqwest::StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn cert_binding_is_a_no_op_when_disabled() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("nocertbinding@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .header("x-client-cert-thumbprint", "aa:bb:cc")
+        .json(&serde_json::json!({"login": "nocertbinding@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .header("x-client-cert-thumbprint", "dd:ee:ff")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn a_dpop_bound_session_requires_a_valid_proof_of_possession() {
+    let app = spawn_app_with_dpop().await;
+    app.user_logic
+        .register("dpopbound@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "dpopbound@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+    let secret = body["dpop_secret"].as_str().unwrap().to_string();
+    let key = libsvc::dpop::key_from_secret(&secret).unwrap();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let proof = libsvc::dpop::prove(&key, "GET", "/v1/users/me/data-export", now, "nonce-1");
+    let valid = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .header("x-dpop-iat", proof.iat.to_string())
+        .header("x-dpop-nonce", &proof.nonce)
+        .header("x-dpop-signature", &proof.signature)
+        .send()
+        .await
+        .unwrap();
+    assert!(valid.status().is_success());
+
+    let replayed = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .header("x-dpop-iat", proof.iat.to_string())
+        .header("x-dpop-nonce", &proof.nonce)
+        .header("x-dpop-signature", &proof.signature)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(replayed.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let missing = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn an_encrypted_session_token_authenticates_in_place_of_a_session_id() {
+    let app = spawn_app_with_session_encryption().await;
+    app.user_logic
+        .register("encryptedtoken@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "encryptedtoken@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let token = body["session_token"].as_str().unwrap().to_string();
+    assert!(!token.contains(body["user_id"].as_str().unwrap()));
+
+    let via_token = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-token", &token)
+        .send()
+        .await
+        .unwrap();
+    assert!(via_token.status().is_success());
+
+    let tampered = format!("{token}x");
+    let rejected = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-token", &tampered)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn encrypted_session_tokens_are_rejected_when_the_feature_is_disabled() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("noencryption@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-token", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_search_users() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("regular@example.com", "password123").await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admins_can_search_users_by_email_prefix() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("alice@example.com", "password123").await;
+    app.register_and_sign_in("bob@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=alice"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["email"], "alice@example.com");
+}
+
+#[tokio::test]
+async fn search_results_exclude_the_password_hash() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("carol@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin2@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=carol"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let user = &body["users"][0];
+    assert!(user.get("password_hash").is_none());
+    assert!(user.get("pending_email").is_none());
+}
+
+#[tokio::test]
+async fn search_respects_pagination_and_reports_more_results() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("page1@example.com", "password123").await;
+    app.register_and_sign_in("page2@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin3@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=page&limit=1"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["users"].as_array().unwrap().len(), 1);
+    assert_eq!(body["has_more"], true);
+}
+
+#[tokio::test]
+async fn searching_by_a_role_other_than_user_matches_nobody() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("onlyuser@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin4@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=onlyuser&role=Admin"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["users"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn searching_by_q_uses_the_configured_search_index() {
+    let app = spawn_app_with_search_index().await;
+    app.register_and_sign_in("dana@example.com", "password123").await;
+    app.register_and_sign_in("unrelated@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin5@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?q=dana"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["email"], "dana@example.com");
+}
+
+#[tokio::test]
+async fn without_a_configured_search_index_q_matches_nobody() {
+    let app = spawn_app().await;
+    app.register_and_sign_in("erin@example.com", "password123").await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("searchadmin6@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?q=erin"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["users"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn admins_can_register_a_service_account_that_authenticates_by_api_key() {
+    let app = spawn_app().await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("svcadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({"client_id": "billing-worker", "api_key": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "service");
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/service-accounts/authenticate"))
+        .json(&serde_json::json!({"client_id": "billing-worker", "api_key": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/service-accounts/authenticate"))
+        .json(&serde_json::json!({"client_id": "billing-worker", "api_key": "wrong-key"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn non_admins_cannot_register_a_service_account() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("notanadmin@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", session_id)
+        .json(&serde_json::json!({"client_id": "sneaky-worker", "api_key": "whatever"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn a_service_account_cannot_authenticate_with_a_password() {
+    let app = spawn_app().await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("svcadmin2@example.com", "password123", vec![Role::Admin])
+        .await;
+    app.client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({"client_id": "reporting-worker", "api_key": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "reporting-worker", "password": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn service_accounts_are_excluded_from_admin_search_by_default() {
+    let app = spawn_app().await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("svcadmin3@example.com", "password123", vec![Role::Admin])
+        .await;
+    app.client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", admin_session_id.clone())
+        .json(&serde_json::json!({"client_id": "hidden-worker", "api_key": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=hidden-worker"))
+        .header("x-session-id", admin_session_id.clone())
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["users"].as_array().unwrap().len(), 0);
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=hidden-worker&kind=service"))
+        .header("x-session-id", admin_session_id)
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["email"], "hidden-worker");
+}
+
+#[tokio::test]
+async fn a_magic_link_is_not_sent_for_a_service_account() {
+    let app = spawn_app().await;
+    let (_, admin_session_id) = app
+        .register_and_sign_in_with_roles("svcadmin4@example.com", "password123", vec![Role::Admin])
+        .await;
+    app.client
+        .post(app.url("/v1/admin/service-accounts"))
+        .header("x-session-id", admin_session_id)
+        .json(&serde_json::json!({"client_id": "no-inbox-worker", "api_key": "super-secret-key"}))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/magic-link"))
+        .json(&serde_json::json!({"email": "no-inbox-worker"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+}
+
+async fn enroll_in_mfa(app: &support::TestApp, user_id: &str) {
+    app.user_logic
+        .update_preferences(users::domain::UserPreferences {
+            phone: Some("+15550100".to_string()),
+            ..users::domain::UserPreferences::defaults(user_id)
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_is_rejected_until_an_mfa_required_role_enrolls() {
+    let app = spawn_app_with_mfa_required_roles(vec![Role::User]).await;
+    let user = app
+        .user_logic
+        .register("mfarequired@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "mfarequired@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "mfa_enrollment_required");
+
+    enroll_in_mfa(&app, &user.id).await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "mfarequired@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn a_session_with_an_mfa_required_role_is_rejected_until_its_holder_enrolls() {
+    let app = spawn_app_with_mfa_required_roles(vec![Role::Admin]).await;
+    let (admin, session_id) = app
+        .register_and_sign_in_with_roles("unenrolledadmin@example.com", "password123", vec![Role::Admin])
+        .await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=a"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    enroll_in_mfa(&app, &admin.id).await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/admin/users/search?email_prefix=a"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn mfa_required_roles_is_a_no_op_when_empty() {
+    let app = spawn_app().await;
+    app.user_logic
+        .register("nomfapolicy@example.com", "password123")
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "nomfapolicy@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn a_risk_rule_matching_an_ip_range_denies_the_login() {
+    let app = spawn_app_with_risk_policy(RiskPolicy {
+        rules: vec![RiskRule {
+            name: "blocked-range".to_string(),
+            conditions: vec![RiskCondition::IpRange { ranges: vec!["127.0.0.1/32".parse().unwrap()] }],
+            action: RiskAction::Deny,
+        }],
+    })
+    .await;
+    app.user_logic.register("riskdenied@example.com", "password123").await.unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .json(&serde_json::json!({"login": "riskdenied@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "login_denied");
+
+    let recent = app.security_events.recent(10);
+    assert!(recent.iter().any(|recorded| matches!(
+        &recorded.event,
+        libsvc::security_signal::SecurityEvent::RiskRuleMatched { rule, .. } if rule == "blocked-range"
+    )));
+}
+
+#[tokio::test]
+async fn a_risk_rule_requiring_mfa_is_rejected_until_the_account_enrolls() {
+    let app = spawn_app_with_risk_policy(RiskPolicy {
+        rules: vec![RiskRule {
+            name: "new-device-challenge".to_string(),
+            conditions: vec![RiskCondition::NewDevice],
+            action: RiskAction::RequireMfa,
+        }],
+    })
+    .await;
+    let user = app.user_logic.register("riskmfa@example.com", "password123").await.unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .header("x-device-id", "device-a")
+        .json(&serde_json::json!({"login": "riskmfa@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "mfa_enrollment_required");
+
+    enroll_in_mfa(&app, &user.id).await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .header("x-device-id", "device-a")
+        .json(&serde_json::json!({"login": "riskmfa@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn no_risk_policy_allows_every_login() {
+    let app = spawn_app().await;
+    app.user_logic.register("noriskpolicy@example.com", "password123").await.unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/v1/users/authenticate"))
+        .header("x-device-id", "device-z")
+        .json(&serde_json::json!({"login": "noriskpolicy@example.com", "password": "password123"}))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn a_session_idle_longer_than_the_timeout_is_rejected_even_though_not_expired() {
+    let app = spawn_app_with_session_idle_timeout(1).await;
+    let (_, session_id) = app.register_and_sign_in("idletimeout@example.com", "password123").await;
+
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn activity_within_the_idle_timeout_keeps_a_session_alive() {
+    let app = spawn_app_with_session_idle_timeout(5).await;
+    let (_, session_id) = app.register_and_sign_in("idleactive@example.com", "password123").await;
+
+    for _ in 0..3 {
+        let response = app
+            .client
+            .get(app.url("/v1/users/me/data-export"))
+            .header("x-session-id", &session_id)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+}
+
+#[tokio::test]
+async fn no_idle_timeout_configured_allows_an_indefinitely_idle_session() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("noidletimeout@example.com", "password123").await;
+
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    let response = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn revoking_a_token_via_the_revoke_endpoint_invalidates_it() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("revoketoken@example.com", "password123").await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/token/revoke"))
+        .json(&serde_json::json!({"token": session_id}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let export = app
+        .client
+        .get(app.url("/v1/users/me/data-export"))
+        .header("x-session-id", session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(export.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn revoking_an_unknown_token_still_returns_ok() {
+    let app = spawn_app().await;
+
+    let response = app
+        .client
+        .post(app.url("/v1/token/revoke"))
+        .json(&serde_json::json!({"token": "does-not-exist", "token_type_hint": "refresh_token"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn revoking_a_token_records_an_audit_event_for_its_owner() {
+    let app = spawn_app().await;
+    let (user, session_id) = app.register_and_sign_in("revoketokenaudit@example.com", "password123").await;
+
+    app.client
+        .post(app.url("/v1/token/revoke"))
+        .json(&serde_json::json!({"token": session_id}))
+        .send()
+        .await
+        .unwrap();
+
+    let activity = app
+        .user_logic
+        .activity(&user.id, users::domain::Pagination { offset: 0, limit: 10 })
+        .await
+        .unwrap();
+    assert!(activity.events.iter().any(|event| event.action == "token_revoked"));
+}
+
+#[tokio::test]
+async fn oidc_discovery_describes_the_configured_issuer() {
+    let app = spawn_app_with_oidc_issuer("https://accounts.example.com").await;
+
+    let response = app
+        .client
+        .get(app.url("/.well-known/openid-configuration"))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["issuer"], "https://accounts.example.com");
+    assert_eq!(body["authorization_endpoint"], "https://accounts.example.com/v1/oidc/authorize");
+    assert_eq!(body["token_endpoint"], "https://accounts.example.com/v1/oidc/token");
+    assert_eq!(body["jwks_uri"], "https://accounts.example.com/v1/oidc/jwks");
+
+    let jwks = app.client.get(app.url("/v1/oidc/jwks")).send().await.unwrap();
+    assert!(jwks.status().is_success());
+    let jwks_body: serde_json::Value = jwks.json().await.unwrap();
+    assert_eq!(jwks_body["keys"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn oidc_endpoints_404_when_not_configured_as_a_provider() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("oidcunconfigured@example.com", "password123").await;
+
+    let discovery = app.client.get(app.url("/.well-known/openid-configuration")).send().await.unwrap();
+    assert_eq!(discovery.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let jwks = app.client.get(app.url("/v1/oidc/jwks")).send().await.unwrap();
+    assert_eq!(jwks.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let authorize = app
+        .client
+        .get(app.url(
+            "/v1/oidc/authorize?client_id=c&redirect_uri=https://app.example.com/cb&response_type=code&scope=openid&code_challenge=x&code_challenge_method=S256",
+        ))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(authorize.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let token = app
+        .client
+        .post(app.url("/v1/oidc/token"))
+        .json(&serde_json::json!({
+            "grant_type": "authorization_code",
+            "code": "whatever",
+            "redirect_uri": "https://app.example.com/cb",
+            "client_id": "c",
+            "code_verifier": "x",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(token.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+/// A PKCE verifier/challenge pair for the authorization code flow tests
+/// below, computed the same way the `S256` transformation (RFC 7636 §4.2)
+/// does on the server side.
+fn pkce_pair() -> (String, String) {
+    use sha2::{Digest, Sha256};
+    let verifier = "a-sufficiently-long-and-random-code-verifier-value".to_string();
+    let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+fn confidential_client_registry() -> Arc<InMemoryOidcClientRegistry> {
+    Arc::new(InMemoryOidcClientRegistry::new(vec![
+        OidcClient {
+            client_id: "confidential-app".to_string(),
+            client_secret_hash: Some(hash_password("s3cret").unwrap()),
+            redirect_uris: vec!["https://app.example.com/callback".to_string()],
+            allowed_scopes: vec!["openid".to_string(), "profile".to_string()],
+        },
+        OidcClient {
+            client_id: "public-app".to_string(),
+            client_secret_hash: None,
+            redirect_uris: vec!["https://spa.example.com/callback".to_string()],
+            allowed_scopes: vec!["openid".to_string()],
+        },
+    ]))
+}
+
+fn extract_code(redirect_uri: &str) -> String {
+    let query = redirect_uri.split_once('?').unwrap().1;
+    let encoded = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("code="))
+        .expect("redirect_uri carried no code");
+    percent_encoding::percent_decode_str(encoded).decode_utf8().unwrap().into_owned()
+}
+
+#[tokio::test]
+async fn oidc_authorization_code_flow_issues_a_usable_access_token_and_id_token() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (user, session_id) = app.register_and_sign_in("oidcflow@example.com", "password123").await;
+    let (verifier, challenge) = pkce_pair();
+
+    let authorize: serde_json::Value = app
+        .client
+        .get(app.url(&format!(
+            "/v1/oidc/authorize?client_id=confidential-app&redirect_uri=https://app.example.com/callback&response_type=code&scope=openid&code_challenge={challenge}&code_challenge_method=S256"
+        )))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(authorize["status"], "consent_required");
+    assert_eq!(authorize["client_id"], "confidential-app");
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = extract_code(consent["redirect_uri"].as_str().unwrap());
+
+    let token_response = app
+        .client
+        .post(app.url("/v1/oidc/token"))
+        .json(&serde_json::json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "redirect_uri": "https://app.example.com/callback",
+            "client_id": "confidential-app",
+            "client_secret": "s3cret",
+            "code_verifier": verifier,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(token_response.status(), reqwest::StatusCode::OK);
+    let token_body: serde_json::Value = token_response.json().await.unwrap();
+    assert_eq!(token_body["token_type"], "Bearer");
+    assert!(token_body["access_token"].as_str().is_some());
+    assert!(token_body["id_token"].as_str().is_some());
+
+    let access_token = token_body["access_token"].as_str().unwrap();
+    let me = app
+        .client
+        .get(app.url("/v1/users/me"))
+        .header("x-session-id", access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(me.status(), reqwest::StatusCode::OK);
+    let me_body: serde_json::Value = me.json().await.unwrap();
+    assert_eq!(me_body["id"], user.id);
+}
+
+#[tokio::test]
+async fn oidc_authorize_skips_consent_for_a_returning_user() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcreturning@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    app.client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let authorize: serde_json::Value = app
+        .client
+        .get(app.url(&format!(
+            "/v1/oidc/authorize?client_id=confidential-app&redirect_uri=https://app.example.com/callback&response_type=code&scope=openid&code_challenge={challenge}&code_challenge_method=S256"
+        )))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(authorize["status"], "redirect");
+    assert!(authorize["redirect_uri"].as_str().unwrap().starts_with("https://app.example.com/callback?code="));
+}
+
+#[tokio::test]
+async fn oidc_consent_denial_redirects_with_access_denied() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcdeny@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": false,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(consent["redirect_uri"], "https://app.example.com/callback?error=access_denied");
+}
+
+#[tokio::test]
+async fn oidc_redirects_percent_encode_a_state_with_reserved_characters() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcstate@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+    let state_value = "a&b=c#d";
+
+    let denial: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "state": state_value,
+            "code_challenge": challenge,
+            "approve": false,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        denial["redirect_uri"],
+        "https://app.example.com/callback?error=access_denied&state=a%26b%3Dc%23d"
+    );
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "state": state_value,
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let redirect_uri = consent["redirect_uri"].as_str().unwrap();
+    assert!(redirect_uri.ends_with("&state=a%26b%3Dc%23d"), "got {redirect_uri}");
+    let code = extract_code(redirect_uri);
+    assert!(!code.is_empty());
+}
+
+#[tokio::test]
+async fn oidc_token_rejects_a_mismatched_pkce_verifier() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcpkce@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = extract_code(consent["redirect_uri"].as_str().unwrap());
+
+    let response = app
+        .client
+        .post(app.url("/v1/oidc/token"))
+        .json(&serde_json::json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "redirect_uri": "https://app.example.com/callback",
+            "client_id": "confidential-app",
+            "client_secret": "s3cret",
+            "code_verifier": "the-wrong-verifier",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn oidc_token_rejects_an_already_redeemed_code() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcreuse@example.com", "password123").await;
+    let (verifier, challenge) = pkce_pair();
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = extract_code(consent["redirect_uri"].as_str().unwrap());
+
+    let request_body = serde_json::json!({
+        "grant_type": "authorization_code",
+        "code": code,
+        "redirect_uri": "https://app.example.com/callback",
+        "client_id": "confidential-app",
+        "client_secret": "s3cret",
+        "code_verifier": verifier,
+    });
+    let first = app.client.post(app.url("/v1/oidc/token")).json(&request_body).send().await.unwrap();
+    assert_eq!(first.status(), reqwest::StatusCode::OK);
+
+    let second = app.client.post(app.url("/v1/oidc/token")).json(&request_body).send().await.unwrap();
+    assert_eq!(second.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = second.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn oidc_authorize_rejects_an_unregistered_redirect_uri() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcbadredirect@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    let response = app
+        .client
+        .get(app.url(&format!(
+            "/v1/oidc/authorize?client_id=confidential-app&redirect_uri=https://evil.example.com/callback&response_type=code&scope=openid&code_challenge={challenge}&code_challenge_method=S256"
+        )))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_request");
+}
+
+#[tokio::test]
+async fn oidc_token_succeeds_for_a_public_client_without_a_secret() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcpublic@example.com", "password123").await;
+    let (verifier, challenge) = pkce_pair();
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "public-app",
+            "redirect_uri": "https://spa.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = extract_code(consent["redirect_uri"].as_str().unwrap());
+
+    let response = app
+        .client
+        .post(app.url("/v1/oidc/token"))
+        .json(&serde_json::json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "redirect_uri": "https://spa.example.com/callback",
+            "client_id": "public-app",
+            "code_verifier": verifier,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn oidc_token_rejects_a_confidential_client_with_a_wrong_secret() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcwrongsecret@example.com", "password123").await;
+    let (verifier, challenge) = pkce_pair();
+
+    let consent: serde_json::Value = app
+        .client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let code = extract_code(consent["redirect_uri"].as_str().unwrap());
+
+    let response = app
+        .client
+        .post(app.url("/v1/oidc/token"))
+        .json(&serde_json::json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "redirect_uri": "https://app.example.com/callback",
+            "client_id": "confidential-app",
+            "client_secret": "wrong-secret",
+            "code_verifier": verifier,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_client");
+}
+
+#[tokio::test]
+async fn consents_lists_every_client_a_user_has_approved() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcconsentlist@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    app.client
+        .post(app.url("/v1/oidc/consent"))
+        .header("x-session-id", &session_id)
+        .json(&serde_json::json!({
+            "client_id": "confidential-app",
+            "redirect_uri": "https://app.example.com/callback",
+            "scope": "openid",
+            "code_challenge": challenge,
+            "approve": true,
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let response = app.client.get(app.url("/v1/users/me/consents")).header("x-session-id", &session_id).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["consents"], serde_json::json!([{"client_id": "confidential-app", "scope": "openid"}]));
+}
+
+#[tokio::test]
+async fn consents_is_empty_for_a_user_who_has_approved_nothing() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcconsentempty@example.com", "password123").await;
+
+    let response = app.client.get(app.url("/v1/users/me/consents")).header("x-session-id", &session_id).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["consents"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn revoking_a_consent_makes_authorize_ask_again_and_leaves_other_clients_untouched() {
+    let app = spawn_app_with_oidc_clients("https://accounts.example.com", confidential_client_registry()).await;
+    let (_, session_id) = app.register_and_sign_in("oidcconsentrevoke@example.com", "password123").await;
+    let (_, challenge) = pkce_pair();
+
+    for client_id in ["confidential-app", "public-app"] {
+        let redirect_uri = if client_id == "confidential-app" {
+            "https://app.example.com/callback"
+        } else {
+            "https://spa.example.com/callback"
+        };
+        app.client
+            .post(app.url("/v1/oidc/consent"))
+            .header("x-session-id", &session_id)
+            .json(&serde_json::json!({
+                "client_id": client_id,
+                "redirect_uri": redirect_uri,
+                "scope": "openid",
+                "code_challenge": challenge,
+                "approve": true,
+            }))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let revoke = app
+        .client
+        .delete(app.url("/v1/users/me/consents/confidential-app"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoke.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let consents: serde_json::Value =
+        app.client.get(app.url("/v1/users/me/consents")).header("x-session-id", &session_id).send().await.unwrap().json().await.unwrap();
+    assert_eq!(consents["consents"], serde_json::json!([{"client_id": "public-app", "scope": "openid"}]));
+
+    let authorize: serde_json::Value = app
+        .client
+        .get(app.url(&format!(
+            "/v1/oidc/authorize?client_id=confidential-app&redirect_uri=https://app.example.com/callback&response_type=code&scope=openid&code_challenge={challenge}&code_challenge_method=S256"
+        )))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(authorize["status"], "consent_required");
+}
+
+#[tokio::test]
+async fn consents_endpoints_404_when_not_configured_as_a_provider() {
+    let app = spawn_app().await;
+    let (_, session_id) = app.register_and_sign_in("oidcconsentdisabled@example.com", "password123").await;
+
+    let list = app.client.get(app.url("/v1/users/me/consents")).header("x-session-id", &session_id).send().await.unwrap();
+    assert_eq!(list.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let revoke = app
+        .client
+        .delete(app.url("/v1/users/me/consents/some-client"))
+        .header("x-session-id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoke.status(), reqwest::StatusCode::NOT_FOUND);
+}
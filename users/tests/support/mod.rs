@@ -0,0 +1,424 @@
+//! Shared setup for the `users` service's integration tests: boots the
+//! real HTTP router on an OS-assigned port against an in-memory
+//! repository, so each test gets an isolated, fully wired instance to
+//! send requests against.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use foundation::blob::FilesystemBlobStorage;
+use foundation::clock::SystemClock;
+use foundation::id::{DefaultIdGenerator, Id};
+use foundation::key::Key;
+use libsvc::audit::MemoryAuditLog;
+use libsvc::captcha::{DisabledCaptchaVerifier, FailedLoginTracker};
+use libsvc::dpop::{InMemoryDPoPKeyStore, InMemoryDPoPNonceStore};
+use libsvc::geoip::NoopGeoIpLookup;
+use libsvc::mailer::LoggingMailer;
+use libsvc::oidc::{OidcClientRegistry, OidcProviderConfig};
+use libsvc::pusher::LoggingPusher;
+use libsvc::risk::{KnownDeviceTracker, RiskPolicy};
+use libsvc::saml::SamlSpConfig;
+use libsvc::search_index::{InMemorySearchIndex, SearchIndex};
+use libsvc::security_signal::{LoggingSecuritySignal, RetainingSecuritySignal};
+use libsvc::service_account::ServiceAccountRegistry;
+use libsvc::session::{KeyRing, Role, SessionBuilder, SessionManager, SessionValidation};
+use libsvc::sms::LoggingSmsSender;
+use libsvc::telemetry::{LogFormat, TelemetryConfig};
+use libsvc::rate_limit::RateLimiterConfig;
+use libsvc::unit_of_work::NoopUnitOfWorkFactory;
+use libsvc::verification_cache::{VerificationCache, VerificationCacheConfig};
+use users::domain::{User, UserLogic};
+use users::feature_flags::FeatureFlags;
+use users::http::metrics::PrometheusMetrics;
+use users::http::{
+    router, with_csrf_protection, with_locale_negotiation, with_request_timeout,
+    with_request_tracing, AppState,
+};
+use users::logic::{CustomAttributesSchemaStore, UserLogicImpl};
+use users::notifications::NotificationMailer;
+use users::repository::backup_codes::MemoryBackupCodesRepository;
+use users::repository::credentials::MemoryCredentialsRepository;
+use users::repository::devices::MemoryDeviceRepository;
+use users::repository::digest::MemoryDigestQueueRepository;
+use users::repository::feature_flags::MemoryFeatureOverridesRepository;
+use users::repository::memory::MemoryUserRepository;
+use users::repository::notifications::MemoryNotificationRepository;
+use users::repository::preferences::MemoryUserPreferencesRepository;
+use users::repository::sms_otp::MemorySmsOtpRepository;
+use users::repository::usage::MemoryUsageRepository;
+
+/// Generous enough that no real test request ever hits it; exists so the
+/// timeout middleware is exercised end-to-end like every other layer.
+const TEST_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const TEST_SESSION_ISSUER: &str = "users";
+const TEST_SESSION_AUDIENCE: &str = "web";
+
+fn test_session_validation(leeway_seconds: u64) -> SessionValidation {
+    SessionValidation {
+        issuer: TEST_SESSION_ISSUER.to_string(),
+        audience: TEST_SESSION_AUDIENCE.to_string(),
+        leeway_seconds,
+    }
+}
+
+/// A running instance of the `users` service plus what's needed to talk
+/// to it and to mint sessions for it in tests.
+pub struct TestApp {
+    pub address: String,
+    pub client: reqwest::Client,
+    pub user_logic: Arc<dyn UserLogic>,
+    pub sessions: Arc<SessionManager>,
+    pub session_key: Arc<KeyRing>,
+    pub session_verification_cache: Arc<VerificationCache>,
+    pub security_events: Arc<RetainingSecuritySignal>,
+    pub feature_flags: Arc<FeatureFlags>,
+    /// Kept alive for the lifetime of the test; the avatar store holds a
+    /// path into it.
+    _avatars_dir: tempfile::TempDir,
+}
+
+impl TestApp {
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.address, path)
+    }
+
+    /// Registers a fresh user and returns it along with a session id
+    /// already valid for authenticating as that user.
+    pub async fn register_and_sign_in(&self, email: &str, password: &str) -> (User, String) {
+        self.register_and_sign_in_with_roles(email, password, vec![Role::User])
+            .await
+    }
+
+    /// Like [`TestApp::register_and_sign_in`], but with an explicit role
+    /// set, for tests that need an admin or support session.
+    pub async fn register_and_sign_in_with_roles(
+        &self,
+        email: &str,
+        password: &str,
+        roles: Vec<Role>,
+    ) -> (User, String) {
+        let user = self
+            .user_logic
+            .register(email, password)
+            .await
+            .expect("registration failed");
+
+        let signing_key = self.session_key.active().expect("session key ring unavailable");
+        let session = SessionBuilder::new(Id::try_from(user.id.as_str()).unwrap(), 3600)
+            .with_roles(roles)
+            .with_issuer(TEST_SESSION_ISSUER)
+            .with_audience(TEST_SESSION_AUDIENCE)
+            .finish(&signing_key);
+        let session_id = session.id.clone();
+        self.sessions.insert(session).expect("failed to insert session");
+
+        (user, session_id)
+    }
+}
+
+/// Builds the application with a fresh in-memory repository and serves it
+/// on a random available port.
+pub async fn spawn_app() -> TestApp {
+    spawn_app_with(
+        None, None, None, None, false, false, false, false, vec![], None, None, None, None, false,
+        5,
+    )
+    .await
+}
+
+/// Like [`spawn_app`], but with SAML SSO configured using `saml`, for
+/// tests that need to exercise `/v1/saml/metadata` and `/v1/saml/acs`.
+pub async fn spawn_app_with_saml(saml: Option<Arc<SamlSpConfig>>) -> TestApp {
+    spawn_app_with(saml, None, None, None, false, false, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with service accounts registered for
+/// `/v1/service-tokens/exchange`.
+pub async fn spawn_app_with_service_accounts(
+    service_accounts: Arc<dyn ServiceAccountRegistry>,
+) -> TestApp {
+    spawn_app_with(None, Some(service_accounts), None, None, false, false, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with a daily call quota enforced, for tests
+/// that need to exercise `/v1/users/me/usage` and the 429 it returns once
+/// a caller runs over.
+pub async fn spawn_app_with_usage_quota(quota_per_day: u32) -> TestApp {
+    spawn_app_with(None, None, Some(quota_per_day), None, false, false, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with a [`SearchIndex`] kept in sync with the
+/// user store, for tests that need to exercise
+/// `/v1/admin/users/search`'s `q` fuzzy-text parameter.
+pub async fn spawn_app_with_search_index() -> TestApp {
+    spawn_app_with(None, None, None, Some(Arc::new(InMemorySearchIndex::new())), false, false, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::session_verification_cache`]
+/// enabled, for tests that exercise its skip-reverification behavior.
+pub async fn spawn_app_with_session_verification_cache() -> TestApp {
+    spawn_app_with(None, None, None, None, true, false, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::require_cert_binding`]
+/// enabled, for tests that exercise mTLS session binding.
+pub async fn spawn_app_with_cert_binding() -> TestApp {
+    spawn_app_with(None, None, None, None, false, true, false, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::require_dpop`] enabled, for
+/// tests that exercise DPoP proof-of-possession verification.
+pub async fn spawn_app_with_dpop() -> TestApp {
+    spawn_app_with(None, None, None, None, false, false, true, false, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::session_encryption_key`] set,
+/// for tests that exercise encrypted session tokens.
+pub async fn spawn_app_with_session_encryption() -> TestApp {
+    spawn_app_with(None, None, None, None, false, false, false, true, vec![], None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::mfa_required_roles`] set to
+/// `roles`, for tests that exercise the admin-forced MFA enrollment
+/// policy.
+pub async fn spawn_app_with_mfa_required_roles(roles: Vec<Role>) -> TestApp {
+    spawn_app_with(None, None, None, None, false, false, false, false, roles, None, None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::risk_policy`] set to `policy`,
+/// for tests that exercise risk-based conditional access at login.
+pub async fn spawn_app_with_risk_policy(policy: RiskPolicy) -> TestApp {
+    spawn_app_with(None, None, None, None, false, false, false, false, vec![], Some(policy), None, None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::session_idle_timeout_secs`]
+/// set to `idle_timeout_secs`, for tests that exercise session inactivity
+/// timeouts.
+pub async fn spawn_app_with_session_idle_timeout(idle_timeout_secs: u64) -> TestApp {
+    spawn_app_with(None, None, None, None, false, false, false, false, vec![], None, Some(idle_timeout_secs), None, None, false, 5).await
+}
+
+/// Like [`spawn_app`], but with [`AppState::oidc`] configured with
+/// `issuer`, for tests that exercise the OIDC discovery document.
+pub async fn spawn_app_with_oidc_issuer(issuer: &str) -> TestApp {
+    spawn_app_with(
+        None,
+        None,
+        None,
+        None,
+        false,
+        false,
+        false,
+        false,
+        vec![],
+        None,
+        None,
+        Some(Arc::new(OidcProviderConfig { issuer: issuer.to_string() })),
+        None,
+        false,
+        5,
+    )
+    .await
+}
+
+/// Like [`spawn_app`], but with both [`AppState::oidc`] and
+/// [`AppState::oidc_clients`] configured, for tests that exercise the
+/// authorization code flow end to end.
+pub async fn spawn_app_with_oidc_clients(issuer: &str, clients: Arc<dyn OidcClientRegistry>) -> TestApp {
+    spawn_app_with(
+        None,
+        None,
+        None,
+        None,
+        false,
+        false,
+        false,
+        false,
+        vec![],
+        None,
+        None,
+        Some(Arc::new(OidcProviderConfig { issuer: issuer.to_string() })),
+        Some(clients),
+        false,
+        5,
+    )
+    .await
+}
+
+/// Like [`spawn_app`], but with [`AppState::cookie_sessions_enabled`] and
+/// [`with_csrf_protection`] both turned on, for tests that exercise
+/// cookie-based session delivery and the CSRF protection it requires.
+pub async fn spawn_app_with_cookie_sessions() -> TestApp {
+    spawn_app_with(
+        None, None, None, None, false, false, false, false, vec![], None, None, None, None, true,
+        5,
+    )
+    .await
+}
+
+/// Like [`spawn_app_with_session_verification_cache`], but with no leeway
+/// on session expiry, for tests that need a session's expiry to land at a
+/// precise, controllable instant relative to the verification cache's TTL.
+pub async fn spawn_app_with_session_verification_cache_and_no_leeway() -> TestApp {
+    spawn_app_with(
+        None, None, None, None, true, false, false, false, vec![], None, None, None, None, false,
+        0,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn spawn_app_with(
+    saml: Option<Arc<SamlSpConfig>>,
+    service_accounts: Option<Arc<dyn ServiceAccountRegistry>>,
+    usage_quota_per_day: Option<u32>,
+    search_index: Option<Arc<dyn SearchIndex>>,
+    session_verification_cache_enabled: bool,
+    require_cert_binding: bool,
+    require_dpop: bool,
+    encrypt_session_tokens: bool,
+    mfa_required_roles: Vec<Role>,
+    risk_policy: Option<RiskPolicy>,
+    session_idle_timeout_secs: Option<u64>,
+    oidc: Option<Arc<OidcProviderConfig>>,
+    oidc_clients: Option<Arc<dyn OidcClientRegistry>>,
+    cookie_sessions_enabled: bool,
+    session_validation_leeway_secs: u64,
+) -> TestApp {
+    let session_key = Arc::new(KeyRing::new(Key::generate()));
+    let metrics = Arc::new(PrometheusMetrics::new());
+    let sessions = Arc::new(SessionManager::with_metrics(metrics.clone()));
+    let audit_log = Arc::new(MemoryAuditLog::new());
+    let mailer = Arc::new(LoggingMailer::new());
+    let security_events = Arc::new(RetainingSecuritySignal::new(
+        Arc::new(LoggingSecuritySignal::new()),
+        libsvc::security_signal::DEFAULT_SECURITY_EVENT_LOG_CAPACITY,
+    ));
+    let preferences_repository = Arc::new(MemoryUserPreferencesRepository::new());
+    let clock: Arc<dyn foundation::clock::Clock> = Arc::new(SystemClock);
+    let ids = Arc::new(DefaultIdGenerator::default());
+    let notifications = Arc::new(NotificationMailer::new(
+        mailer.clone(),
+        preferences_repository.clone(),
+        Arc::new(MemoryDigestQueueRepository::new()),
+        Arc::new(MemoryNotificationRepository::new()),
+        Arc::new(MemoryDeviceRepository::new()),
+        Arc::new(LoggingPusher::new()),
+        clock.clone(),
+        ids.clone(),
+    ));
+    let user_logic: Arc<dyn UserLogic> = Arc::new(UserLogicImpl::with_sessions(
+        Arc::new(MemoryUserRepository::new()),
+        Arc::new(MemoryCredentialsRepository::new()),
+        audit_log.clone(),
+        mailer.clone(),
+        Arc::new(NoopUnitOfWorkFactory),
+        RateLimiterConfig::default(),
+        preferences_repository,
+        security_events.clone(),
+        search_index.unwrap_or_else(|| Arc::new(libsvc::search_index::NoopSearchIndex)),
+        clock,
+        ids,
+        metrics.clone(),
+        Arc::new(CustomAttributesSchemaStore::new()),
+        notifications,
+        Arc::new(LoggingSmsSender::new()),
+        Arc::new(MemorySmsOtpRepository::new()),
+        Arc::new(MemoryBackupCodesRepository::new()),
+        sessions.clone(),
+    ));
+
+    let avatars_dir = tempfile::tempdir().expect("failed to create avatars dir");
+    let avatars = Arc::new(
+        FilesystemBlobStorage::open(avatars_dir.path()).expect("failed to open avatar storage"),
+    );
+
+    let telemetry_config = TelemetryConfig {
+        service_name: "users".to_string(),
+        service_version: "0.0.0".to_string(),
+        environment: "test".to_string(),
+        default_directives: "info".to_string(),
+        format: LogFormat::Pretty,
+    };
+
+    let state = AppState {
+        user_logic: user_logic.clone(),
+        sessions: sessions.clone(),
+        session_key: session_key.clone(),
+        session_validation: test_session_validation(session_validation_leeway_secs),
+        issue_refresh_tokens: true,
+        audit_log,
+        avatars,
+        log_level: libsvc::telemetry::init(&telemetry_config),
+        security_signal: security_events.clone(),
+        captcha: Arc::new(DisabledCaptchaVerifier::new()),
+        require_captcha_for_registration: false,
+        captcha_after_failed_logins: 3,
+        failed_logins: Arc::new(FailedLoginTracker::new()),
+        mailer,
+        saml,
+        service_accounts,
+        usage: Arc::new(MemoryUsageRepository::new()),
+        usage_quota_per_day,
+        clock: Arc::new(SystemClock),
+        metrics,
+        session_verification_cache: Arc::new(VerificationCache::new(VerificationCacheConfig {
+            enabled: session_verification_cache_enabled,
+            ..VerificationCacheConfig::default()
+        })),
+        security_events,
+        require_cert_binding,
+        require_dpop,
+        dpop_keys: Arc::new(InMemoryDPoPKeyStore::new()),
+        dpop_nonces: Arc::new(InMemoryDPoPNonceStore::new(Duration::from_secs(60))),
+        session_encryption_key: encrypt_session_tokens
+            .then(|| Arc::new(foundation::crypto::EncryptionKey::generate())),
+        action_token_key: Arc::new(Key::generate()),
+        feature_flags: Arc::new(FeatureFlags::new(Arc::new(
+            MemoryFeatureOverridesRepository::new(),
+        ))),
+        mfa_required_roles: mfa_required_roles.into_iter().collect(),
+        geoip: Arc::new(NoopGeoIpLookup),
+        risk_policy: risk_policy.map(Arc::new),
+        known_devices: Arc::new(KnownDeviceTracker::new()),
+        session_idle_timeout_secs,
+        oidc,
+        oidc_clients,
+        oidc_codes: Arc::new(libsvc::oidc::InMemoryAuthorizationCodeStore::new()),
+        oidc_consents: Arc::new(libsvc::oidc::InMemoryConsentStore::new()),
+        oidc_signing_key: Arc::new(Key::generate()),
+        cookie_sessions_enabled,
+    };
+    let session_verification_cache = state.session_verification_cache.clone();
+    let security_events = state.security_events.clone();
+    let feature_flags = state.feature_flags.clone();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind listener");
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    let router = with_locale_negotiation(router(state));
+    let router = if cookie_sessions_enabled { with_csrf_protection(router) } else { router };
+    let router = with_request_timeout(router, TEST_REQUEST_TIMEOUT);
+    let app = with_request_tracing(router, telemetry_config);
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .await
+            .unwrap();
+    });
+
+    TestApp {
+        address,
+        client: reqwest::Client::new(),
+        user_logic,
+        sessions,
+        session_key,
+        session_verification_cache,
+        security_events,
+        feature_flags,
+        _avatars_dir: avatars_dir,
+    }
+}